@@ -7,7 +7,7 @@ echo "=== ProductionDeck Multi-Device Build Script ==="
 echo
 
 # List of devices to build
-devices=("mini" "revised-mini" "original" "original-v2" "xl" "plus")
+devices=("mini" "revised-mini" "original" "original-v2" "xl" "plus" "studio")
 
 echo "Available device targets:"
 for device in "${devices[@]}"; do
@@ -12,6 +12,7 @@ use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::process::Command;
 
 fn main() {
     // Put `memory.x` in our output directory and ensure it's
@@ -30,4 +31,41 @@ fn main() {
     println!("cargo:rerun-if-changed=memory.x");
 
     // Linker arguments are handled in .cargo/config.toml
+
+    emit_version();
+}
+
+/// Export the version constants used by `types::APP_VERSION`
+fn emit_version() {
+    // Short commit hash, "-dirty" with uncommitted changes; "unknown" when
+    // building from a source archive without git
+    let git_hash = Command::new("git")
+        .args([
+            "describe",
+            "--always",
+            "--dirty",
+            "--abbrev=8",
+            "--exclude=*",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=PRODUCTIONDECK_GIT_HASH={}", git_hash);
+
+    // Elgato reports firmware as "M.mm.ppp"
+    let part = |name: &str| -> u32 { env::var(name).unwrap().parse().unwrap() };
+    println!(
+        "cargo:rustc-env=PRODUCTIONDECK_FIRMWARE_VERSION={}.{:02}.{:03}",
+        part("CARGO_PKG_VERSION_MAJOR"),
+        part("CARGO_PKG_VERSION_MINOR"),
+        part("CARGO_PKG_VERSION_PATCH")
+    );
+
+    // Pick up new commits and checkouts
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
 }
@@ -0,0 +1,311 @@
+//! Capture replay tool
+//!
+//! Feeds HID reports recorded from a real StreamDeck session through the
+//! protocol handlers, as the firmware's USB handler would, and writes every
+//! completed key and touch strip image as PNG. Use it to reproduce a
+//! reassembly bug from a capture without flashing a board.
+//!
+//! ```text
+//! cargo run -p productiondeck-protocol --features replay --target x86_64-unknown-linux-gnu -- \
+//!     --pid 0x0080 capture.txt out/
+//! ```
+//!
+//! The capture holds one report per line, in hex, report ID first. Colons and
+//! whitespace between bytes are ignored, so Wireshark's field export works as
+//! is (`tshark -r session.pcapng -T fields -e usbhid.data`). A line is an
+//! output report unless it starts with `feature` (a SET_REPORT on a feature
+//! report); `#` starts a comment.
+//!
+//! Images are written as the host encoded them, before any panel rotation:
+//! `<report line>-key<key>.png` and `<report line>-lcd-<x>x<y>.png`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::OnceLock;
+use std::task::Waker;
+use std::time::Instant;
+
+use productiondeck_protocol::device::{Device, ImageFormat};
+use productiondeck_protocol::protocol::image_check;
+use productiondeck_protocol::protocol::module::ModuleSetCommand;
+use productiondeck_protocol::protocol::{OutputReportResult, ProtocolHandler};
+
+/// embassy-time clock for image timestamps. Nothing here waits on a timer.
+struct HostClock;
+
+impl embassy_time_driver::Driver for HostClock {
+    fn now(&self) -> u64 {
+        static START: OnceLock<Instant> = OnceLock::new();
+        let elapsed = START.get_or_init(Instant::now).elapsed();
+        (elapsed.as_nanos() * embassy_time_driver::TICK_HZ as u128 / 1_000_000_000) as u64
+    }
+
+    fn schedule_wake(&self, _at: u64, waker: &Waker) {
+        waker.wake_by_ref();
+    }
+}
+
+embassy_time_driver::time_driver_impl!(static CLOCK: HostClock = HostClock);
+
+const USAGE: &str = "usage: replay --pid <usb pid> <capture> <output dir>";
+
+/// One recorded report
+enum Report {
+    Output(Vec<u8>),
+    Feature(Vec<u8>),
+}
+
+/// Decoded image, 8-bit RGB or grayscale
+struct Pixels {
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    data: Vec<u8>,
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("replay: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let (pid, capture, out_dir) = parse_args(&args)?;
+    let device = Device::from_pid(pid).ok_or(format!("no device with PID {pid:#06x}"))?;
+    let text = fs::read_to_string(&capture).map_err(|e| format!("{}: {e}", capture.display()))?;
+    fs::create_dir_all(&out_dir).map_err(|e| format!("{}: {e}", out_dir.display()))?;
+
+    let display = device.display();
+    let mut protocol = ProtocolHandler::for_device(device);
+    // Chunks of images passed on unassembled (`streaming-decode`), per key
+    let mut streamed: Vec<Vec<u8>> = vec![Vec::new(); 256];
+    let mut written = 0;
+
+    println!("replaying {} as {device:?}", capture.display());
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let report = match parse_line(line) {
+            Ok(Some(report)) => report,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("{}:{line_no}: {e}", capture.display())),
+        };
+        let data = match report {
+            Report::Feature(data) => {
+                let Some(&report_id) = data.first() else {
+                    continue;
+                };
+                let command = protocol.handle_feature_report(report_id, &data);
+                if command == Some(ModuleSetCommand::Reset) {
+                    protocol.reset();
+                }
+                println!("{line_no}: feature {report_id:#04x} -> {command:?}");
+                continue;
+            }
+            Report::Output(data) => data,
+        };
+
+        let (name, format, image) = match protocol.parse_output_report(&data) {
+            OutputReportResult::KeyImageComplete { key_id, image } => {
+                if let Err(e) = image_check::validate_key_image(&display, &image) {
+                    println!("{line_no}: key {key_id} image rejected: {e:?}");
+                    continue;
+                }
+                (
+                    format!("{line_no:05}-key{key_id:02}"),
+                    display.format,
+                    image.to_vec(),
+                )
+            }
+            OutputReportResult::KeyImageChunk {
+                key_id,
+                offset,
+                payload,
+                last,
+            } => {
+                let image = &mut streamed[key_id as usize];
+                image.truncate(offset);
+                image.extend_from_slice(&data[payload]);
+                if !last {
+                    continue;
+                }
+                let image = core::mem::take(image);
+                if let Err(e) = image_check::validate_key_image(&display, &image) {
+                    println!("{line_no}: key {key_id} image rejected: {e:?}");
+                    continue;
+                }
+                (
+                    format!("{line_no:05}-key{key_id:02}"),
+                    display.format,
+                    image,
+                )
+            }
+            OutputReportResult::LcdImageComplete { region, image } => (
+                format!("{line_no:05}-lcd-{}x{}", region.x, region.y),
+                ImageFormat::Jpeg,
+                image.to_vec(),
+            ),
+            OutputReportResult::Error(e) => {
+                println!("{line_no}: {e:?}");
+                continue;
+            }
+            _ => continue,
+        };
+
+        let pixels = match format {
+            ImageFormat::Bmp => decode_bmp(&image),
+            ImageFormat::Jpeg => decode_jpeg(&image),
+        };
+        let pixels = match pixels {
+            Ok(pixels) => pixels,
+            Err(e) => {
+                println!("{line_no}: {name} not decoded: {e}");
+                continue;
+            }
+        };
+        let path = out_dir.join(format!("{name}.png"));
+        write_png(&path, &pixels).map_err(|e| format!("{}: {e}", path.display()))?;
+        println!("{line_no}: {}", path.display());
+        written += 1;
+    }
+
+    println!("{written} images written to {}", out_dir.display());
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<(u16, PathBuf, PathBuf), String> {
+    let mut pid = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pid" => {
+                let value = args.next().ok_or(USAGE)?;
+                pid = Some(parse_pid(value).ok_or(format!("bad PID {value}"))?);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    match (pid, paths.as_slice()) {
+        (Some(pid), [capture, out_dir]) => Ok((pid, capture.clone(), out_dir.clone())),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn parse_pid(value: &str) -> Option<u16> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parse one capture line; None for blank and comment lines
+fn parse_line(line: &str) -> Result<Option<Report>, String> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let (feature, hex) = match line.strip_prefix("feature") {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix("output").unwrap_or(line)),
+    };
+    let digits: Vec<u8> = hex
+        .bytes()
+        .filter(|b| *b != b':' && !b.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".into());
+    }
+    let bytes = digits
+        .chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(format!("bad hex byte {}", String::from_utf8_lossy(pair)))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(Some(if feature {
+        Report::Feature(bytes)
+    } else {
+        Report::Output(bytes)
+    }))
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// 24-bit uncompressed BMP (validated by `image_check`) to RGB rows, top first
+fn decode_bmp(data: &[u8]) -> Result<Pixels, String> {
+    if data.len() < 54 {
+        return Err("BMP header truncated".into());
+    }
+    let offset = le_u32(data, 10) as usize;
+    let width = le_u32(data, 18) as i32;
+    let height = le_u32(data, 22) as i32;
+    let (w, h) = (
+        width.unsigned_abs() as usize,
+        height.unsigned_abs() as usize,
+    );
+    let stride = (w * 3).div_ceil(4) * 4;
+    let rows = data
+        .get(offset..offset + stride * h)
+        .ok_or("BMP pixel data truncated")?;
+
+    let mut rgb = Vec::with_capacity(w * h * 3);
+    for y in 0..h {
+        // Positive heights are stored bottom row first
+        let row = if height > 0 { h - 1 - y } else { y };
+        for bgr in rows[row * stride..][..w * 3].chunks_exact(3) {
+            rgb.extend_from_slice(&[bgr[2], bgr[1], bgr[0]]);
+        }
+    }
+    Ok(Pixels {
+        width: w as u32,
+        height: h as u32,
+        color: png::ColorType::Rgb,
+        data: rgb,
+    })
+}
+
+fn decode_jpeg(data: &[u8]) -> Result<Pixels, String> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let pixels = decoder.decode().map_err(|e| e.to_string())?;
+    let info = decoder.info().ok_or("JPEG without a frame")?;
+    let color = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => png::ColorType::Rgb,
+        jpeg_decoder::PixelFormat::L8 => png::ColorType::Grayscale,
+        format => return Err(format!("unsupported JPEG pixel format {format:?}")),
+    };
+    Ok(Pixels {
+        width: info.width.into(),
+        height: info.height.into(),
+        color,
+        data: pixels,
+    })
+}
+
+fn write_png(path: &Path, pixels: &Pixels) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), pixels.width, pixels.height);
+    encoder.set_color(pixels.color);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&pixels.data)
+        .map_err(|e| e.to_string())
+}
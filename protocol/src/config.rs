@@ -0,0 +1,120 @@
+//! Protocol constants: report IDs and commands, report size limits and the
+//! sizing of the key image pool. The firmware's `config` re-exports all of
+//! them next to its hardware configuration.
+
+use crate::device::Device;
+
+/// Maximum number of keys supported by any device (XL has 32)
+pub const MAX_KEYS: usize = 32;
+
+// USB Configuration
+/// Default HID polling interval; device profiles may override it (`UsbConfig::poll_ms`)
+pub const USB_POLL_RATE_MS: u8 = 1; // 1ms USB polling (1000Hz)
+/// Largest input report of any device profile (Module 15/32)
+pub const USB_MAX_INPUT_REPORT: usize = 512;
+/// Largest output report of any device profile (V2: report ID + 1024)
+pub const USB_MAX_OUTPUT_REPORT: usize = 1025;
+pub const IMAGE_BUFFER_SIZE: usize = 1024; // 1KB buffer size
+
+/// StreamDeck Plus touch strip LCD size in pixels
+pub const PLUS_LCD_WIDTH: u16 = 800;
+pub const PLUS_LCD_HEIGHT: u16 = 100;
+
+// Key image pool (see image_pool.rs)
+/// Largest complete key image of any device (the Mini BMP), from the device
+/// tables; the RAM it takes is checked in the firmware's `memory`
+pub const MAX_IMAGE_SIZE: usize = largest_key_image();
+/// Bytes per pool slab. When every key image is streamed to the panel
+/// (`streaming-decode` with only the V1 protocol and no bulk uploads) no
+/// path buffers a whole image, and the slabs take no RAM.
+#[cfg(not(all(
+    feature = "streaming-decode",
+    not(any(
+        feature = "proto-v2",
+        feature = "proto-module",
+        feature = "vendor-bulk"
+    ))
+)))]
+pub const IMAGE_SLAB_SIZE: usize = MAX_IMAGE_SIZE;
+#[cfg(all(
+    feature = "streaming-decode",
+    not(any(
+        feature = "proto-v2",
+        feature = "proto-module",
+        feature = "vendor-bulk"
+    ))
+))]
+pub const IMAGE_SLAB_SIZE: usize = 0;
+/// Slabs in flight at once: one filling from USB, one queued, one on the panel
+pub const IMAGE_SLAB_COUNT: usize = 3;
+/// Key images that may be reassembled at once (see protocol/assembly.rs)
+pub const IMAGE_ASSEMBLY_CONTEXTS: usize = 2;
+
+const fn largest_key_image() -> usize {
+    let mut largest = 0;
+    let mut i = 0;
+    while i < Device::ALL.len() {
+        let size = Device::ALL[i].display().max_image_size();
+        if size > largest {
+            largest = size;
+        }
+        i += 1;
+    }
+    largest
+}
+
+// ===================================================================
+// USB HID Report IDs and Commands
+// ===================================================================
+
+// Report types
+pub const OUTPUT_REPORT_IMAGE: u8 = 0x02;
+pub const IMAGE_COMMAND_V2: u8 = 0x07;
+
+// Feature report IDs and commands
+pub const FEATURE_REPORT_VERSION_V1: u8 = 0x04;
+pub const FEATURE_REPORT_VERSION_V2: u8 = 0x05;
+pub const FEATURE_REPORT_SERIAL_NUMBER: u8 = 0x03;
+pub const FEATURE_REPORT_FIRMWARE_INFO: u8 = 0xA1;
+pub const FEATURE_REPORT_RESET_V1: u8 = 0x0B;
+pub const FEATURE_REPORT_BRIGHTNESS_V1: u8 = 0x05;
+pub const FEATURE_REPORT_V2_COMMANDS: u8 = 0x03; // V2 command container
+
+// V2 sub-commands (used with FEATURE_REPORT_V2_COMMANDS)
+pub const V2_COMMAND_RESET: u8 = 0x02;
+pub const V2_COMMAND_BRIGHTNESS: u8 = 0x08;
+/// Plus/Neo screen sleep: `[0x03, 0x0A, screen, 1 = sleep / 0 = wake]`
+pub const V2_COMMAND_SCREEN_SLEEP: u8 = 0x0A;
+/// Plus/Neo per-screen brightness: `[0x03, 0x0B, screen, percent]`
+pub const V2_COMMAND_SCREEN_BRIGHTNESS: u8 = 0x0B;
+
+// Vendor feature report (outside the Elgato report ID range)
+pub const FEATURE_REPORT_VENDOR: u8 = 0xF0;
+pub const VENDOR_REPORT_SIZE: usize = 32;
+
+// Settings feature reports for WebHID pages, one per setting (see the
+// firmware's protocol::vendor)
+pub const FEATURE_REPORT_SETTING_BRIGHTNESS: u8 = 0xF1;
+pub const FEATURE_REPORT_SETTING_PERSONALITY: u8 = 0xF2;
+pub const FEATURE_REPORT_SETTING_REMAP: u8 = 0xF3;
+pub const FEATURE_REPORT_SETTING_ROTATION: u8 = 0xF4;
+
+/// Settings reports and their lengths after the report ID
+pub const SETTINGS_REPORTS: [(u8, usize); 4] = [
+    (FEATURE_REPORT_SETTING_BRIGHTNESS, 1),
+    (FEATURE_REPORT_SETTING_PERSONALITY, 4),
+    (FEATURE_REPORT_SETTING_REMAP, MAX_KEYS),
+    (FEATURE_REPORT_SETTING_ROTATION, 2),
+];
+
+// Idle time feature report constants
+pub const FEATURE_REPORT_IDLE_TIME: u8 = 0x0B;
+pub const IDLE_TIME_COMMAND: u8 = 0xA2;
+pub const FEATURE_REPORT_GET_IDLE_TIME: u8 = 0xA3;
+
+// StreamDeck protocol magic bytes
+pub const STREAMDECK_MAGIC_1: u8 = 0x55;
+pub const STREAMDECK_MAGIC_2: u8 = 0xAA;
+pub const STREAMDECK_MAGIC_3: u8 = 0xD1;
+pub const STREAMDECK_RESET_MAGIC: u8 = 0x63;
+pub const STREAMDECK_BRIGHTNESS_RESET_MAGIC: u8 = 0x3E;
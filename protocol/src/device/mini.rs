@@ -0,0 +1,94 @@
+//! StreamDeck Mini device configurations
+//!
+//! Supports both the original Mini (PID 0x0063) and Revised Mini (PID 0x0080)
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// StreamDeck Mini configuration (PID: 0x0063)
+pub struct MiniConfig;
+
+impl MiniConfig {
+    pub const PID: u16 = 0x0063;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::V1;
+    /// 3x2 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(3, 2, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 80,
+        image_height: 80,
+        format: ImageFormat::Bmp,
+        rotation: Rotation::Deg270,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for MiniConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Mini"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Mini",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
+
+/// StreamDeck Revised Mini configuration (PID: 0x0080)
+pub struct RevisedMiniConfig;
+
+impl RevisedMiniConfig {
+    pub const PID: u16 = 0x0080;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::V1;
+    /// 3x2 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(3, 2, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 80,
+        image_height: 80,
+        format: ImageFormat::Bmp,
+        rotation: Rotation::Deg270,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for RevisedMiniConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Mini (Revised)"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Mini",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! Device abstraction layer for StreamDeck compatible devices
+//!
+//! This module provides a unified interface for different StreamDeck models,
+//! abstracting away device-specific configurations, protocols, and capabilities.
+
+#[cfg(feature = "proto-v1")]
+pub mod mini;
+#[cfg(feature = "proto-module")]
+pub mod module;
+#[cfg(feature = "proto-v1")]
+pub mod original;
+#[cfg(feature = "proto-v2")]
+pub mod original_v2;
+#[cfg(feature = "proto-v2")]
+pub mod plus;
+#[cfg(feature = "proto-v2")]
+pub mod studio;
+#[cfg(feature = "proto-v2")]
+pub mod xl;
+
+pub use crate::types::Rotation;
+
+use crate::config::{
+    IMAGE_SLAB_SIZE, MAX_IMAGE_SIZE, MAX_KEYS, USB_MAX_INPUT_REPORT, USB_MAX_OUTPUT_REPORT,
+};
+use crate::types::LcdRegion;
+
+/// Image format supported by StreamDeck devices
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ImageFormat {
+    /// BMP format (used by V1 protocol devices)
+    Bmp,
+    /// JPEG format (used by V2 protocol devices)
+    Jpeg,
+}
+
+/// Protocol version used by StreamDeck devices
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProtocolVersion {
+    /// V1 protocol (Original, Mini, Revised Mini)
+    #[cfg(feature = "proto-v1")]
+    V1,
+    /// V2 protocol (Original V2, XL, MK2)
+    #[cfg(feature = "proto-v2")]
+    V2,
+    /// V2 protocol with touch strip LCD and event-typed input reports (Plus, Studio)
+    #[cfg(feature = "proto-v2")]
+    Plus,
+    /// Module HID protocol(6Keys)
+    #[cfg(feature = "proto-module")]
+    Module6Keys,
+    /// Module HID protocol (15/32 Keys)
+    #[cfg(feature = "proto-module")]
+    Module15_32Keys,
+}
+
+impl ProtocolVersion {
+    /// Input report size (button states) for `total_keys` keys
+    #[cfg_attr(
+        not(any(feature = "proto-v1", feature = "proto-v2")),
+        allow(unused_variables)
+    )]
+    pub const fn input_report_size(self, total_keys: usize) -> usize {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolVersion::V1 => total_keys + 1, // +1 for report ID
+            #[cfg(feature = "proto-v2")]
+            ProtocolVersion::V2 => total_keys + 4, // +4 for V2 header
+            #[cfg(feature = "proto-v2")]
+            ProtocolVersion::Plus => total_keys + 4, // +4 for event header
+            #[cfg(feature = "proto-module")]
+            ProtocolVersion::Module6Keys => 64,
+            #[cfg(feature = "proto-module")]
+            ProtocolVersion::Module15_32Keys => 512,
+        }
+    }
+
+    /// Output report size (image data), report ID included
+    pub const fn output_report_size(self) -> usize {
+        match self {
+            // Report ID + 1024 bytes, as declared in the V2 descriptor
+            #[cfg(feature = "proto-v2")]
+            ProtocolVersion::V2 | ProtocolVersion::Plus => 1025,
+            // Standard 1KB output report size
+            #[cfg(feature = "proto-v1")]
+            ProtocolVersion::V1 => 1024,
+            #[cfg(feature = "proto-module")]
+            ProtocolVersion::Module6Keys | ProtocolVersion::Module15_32Keys => 1024,
+        }
+    }
+}
+
+/// Button layout configuration
+#[derive(Debug, Clone, Copy)]
+pub struct ButtonLayout {
+    /// Number of button columns
+    pub cols: usize,
+    /// Number of button rows  
+    pub rows: usize,
+    /// Total number of buttons (cols * rows)
+    pub total_keys: usize,
+    /// Button mapping order (true = left-to-right, false = right-to-left)
+    pub left_to_right: bool,
+}
+
+impl ButtonLayout {
+    pub const fn new(cols: usize, rows: usize, left_to_right: bool) -> Self {
+        Self {
+            cols,
+            rows,
+            total_keys: cols * rows,
+            left_to_right,
+        }
+    }
+
+    /// Rows and columns the key matrix is wired as. Rows wider than the
+    /// 8 matrix columns are folded: the Studio's 16x2 keys scan as 8x4,
+    /// which gives every key the same index as in the 16x2 layout.
+    pub const fn matrix(&self) -> (usize, usize) {
+        if self.cols > 8 && self.cols.is_multiple_of(8) {
+            (self.total_keys / 8, 8)
+        } else {
+            (self.rows, self.cols)
+        }
+    }
+}
+
+/// Display configuration for StreamDeck devices
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayConfig {
+    /// Image width in pixels per key
+    pub image_width: usize,
+    /// Image height in pixels per key
+    pub image_height: usize,
+    /// Image format (BMP or JPEG)
+    pub format: ImageFormat,
+    /// Clockwise rotation the host's key images need (Mini: 270°, Module 6: 90°,
+    /// Module 15/32: 180°)
+    pub rotation: Rotation,
+    /// Whether image needs horizontal flip
+    pub flip_horizontal: bool,
+    /// Whether image needs vertical flip
+    pub flip_vertical: bool,
+}
+
+impl DisplayConfig {
+    /// Largest complete key image in bytes
+    pub const fn max_image_size(&self) -> usize {
+        match self.format {
+            ImageFormat::Bmp => {
+                // BMP: header (54 bytes) + RGB data (width * height * 3)
+                54 + (self.image_width * self.image_height * 3)
+            }
+            ImageFormat::Jpeg => {
+                // JPEG: Variable size, use conservative estimate
+                self.image_width * self.image_height / 2
+            }
+        }
+    }
+}
+
+/// USB configuration for StreamDeck devices
+#[derive(Debug, Clone, Copy)]
+pub struct UsbConfig {
+    /// USB Vendor ID (always 0x0fd9 for Elgato)
+    pub vid: u16,
+    /// USB Product ID (device-specific)
+    pub pid: u16,
+    /// USB product name
+    pub product_name: &'static str,
+    /// USB manufacturer name
+    pub manufacturer: &'static str,
+    /// Protocol version
+    pub protocol: ProtocolVersion,
+    /// HID interrupt endpoint polling interval in ms (bInterval). Some hosts
+    /// behave better at 4-8 ms than at the 1 ms default.
+    pub poll_ms: u8,
+}
+
+/// Complete device configuration trait
+pub trait DeviceConfig {
+    /// Get device name for identification
+    fn device_name(&self) -> &'static str;
+
+    /// Get button layout configuration
+    fn button_layout(&self) -> ButtonLayout;
+
+    /// Get display configuration
+    fn display_config(&self) -> DisplayConfig;
+
+    /// Get USB configuration
+    fn usb_config(&self) -> UsbConfig;
+
+    /// Get maximum image data size in bytes
+    fn max_image_size(&self) -> usize {
+        self.display_config().max_image_size()
+    }
+
+    /// Get HID report descriptor size
+    fn hid_descriptor_size(&self) -> usize {
+        173 // Standard StreamDeck HID descriptor size
+    }
+
+    /// Get input report size (button states)
+    fn input_report_size(&self) -> usize {
+        self.usb_config()
+            .protocol
+            .input_report_size(self.button_layout().total_keys)
+    }
+
+    /// Number of dials reported to the host (Plus: 4, Studio: 2)
+    fn dial_count(&self) -> usize {
+        0
+    }
+
+    /// Panel area below the keys for the local status strip shown in
+    /// standalone mode (Plus: the touch strip), in strip pixels
+    fn info_bar(&self) -> Option<LcdRegion> {
+        None
+    }
+
+    /// Get feature report size
+    fn feature_report_size(&self) -> usize {
+        32 // Standard feature report size
+    }
+
+    /// Get output report size (image data), report ID included. The OUT
+    /// endpoint reassembles packets into reports of this size.
+    fn output_report_size(&self) -> usize {
+        self.usb_config().protocol.output_report_size()
+    }
+}
+
+#[cfg(not(any(feature = "proto-v1", feature = "proto-v2", feature = "proto-module")))]
+compile_error!("enable at least one protocol feature: proto-v1, proto-v2 or proto-module");
+
+/// Enum-based device configuration for no_std environment.
+/// Only models whose protocol feature is enabled exist.
+#[derive(Debug, Clone, Copy)]
+pub enum Device {
+    #[cfg(feature = "proto-v1")]
+    Mini,
+    #[cfg(feature = "proto-v1")]
+    RevisedMini,
+    #[cfg(feature = "proto-v1")]
+    Original,
+    #[cfg(feature = "proto-v2")]
+    OriginalV2,
+    #[cfg(feature = "proto-v2")]
+    Xl,
+    #[cfg(feature = "proto-v2")]
+    Plus,
+    #[cfg(feature = "proto-v2")]
+    Studio,
+    #[cfg(feature = "proto-module")]
+    Module6Keys,
+    #[cfg(feature = "proto-module")]
+    Module15Keys,
+    #[cfg(feature = "proto-module")]
+    Module32Keys,
+}
+
+/// Evaluate `$body` with `$model` naming the per-model configuration struct
+/// of `$device`
+macro_rules! with_model {
+    ($device:expr, $model:ident => $body:expr) => {
+        match $device {
+            #[cfg(feature = "proto-v1")]
+            Device::Mini => {
+                use mini::MiniConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v1")]
+            Device::RevisedMini => {
+                use mini::RevisedMiniConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v1")]
+            Device::Original => {
+                use original::OriginalConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v2")]
+            Device::OriginalV2 => {
+                use original_v2::OriginalV2Config as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v2")]
+            Device::Xl => {
+                use xl::XlConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v2")]
+            Device::Plus => {
+                use plus::PlusConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-v2")]
+            Device::Studio => {
+                use studio::StudioConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-module")]
+            Device::Module6Keys => {
+                use module::Module6KeysConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-module")]
+            Device::Module15Keys => {
+                use module::Module15KeysConfig as $model;
+                $body
+            }
+            #[cfg(feature = "proto-module")]
+            Device::Module32Keys => {
+                use module::Module32KeysConfig as $model;
+                $body
+            }
+        }
+    };
+}
+
+impl Device {
+    /// Every model in this build, in declaration order
+    pub const ALL: &'static [Device] = &[
+        #[cfg(feature = "proto-v1")]
+        Device::Mini,
+        #[cfg(feature = "proto-v1")]
+        Device::RevisedMini,
+        #[cfg(feature = "proto-v1")]
+        Device::Original,
+        #[cfg(feature = "proto-v2")]
+        Device::OriginalV2,
+        #[cfg(feature = "proto-v2")]
+        Device::Xl,
+        #[cfg(feature = "proto-v2")]
+        Device::Plus,
+        #[cfg(feature = "proto-v2")]
+        Device::Studio,
+        #[cfg(feature = "proto-module")]
+        Device::Module6Keys,
+        #[cfg(feature = "proto-module")]
+        Device::Module15Keys,
+        #[cfg(feature = "proto-module")]
+        Device::Module32Keys,
+    ];
+
+    /// Get device by USB PID
+    pub const fn from_pid(pid: u16) -> Option<Self> {
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            if Self::ALL[i].pid() == pid {
+                return Some(Self::ALL[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Get all supported device PIDs
+    pub fn supported_pids() -> &'static [u16] {
+        &SUPPORTED_PIDS
+    }
+
+    /// Model used when the configured PID names no model in this build:
+    /// the first one whose protocol is linked
+    #[cfg(feature = "proto-v1")]
+    pub const DEFAULT: Device = Device::Mini;
+    #[cfg(all(not(feature = "proto-v1"), feature = "proto-v2"))]
+    pub const DEFAULT: Device = Device::OriginalV2;
+    #[cfg(all(not(feature = "proto-v1"), not(feature = "proto-v2")))]
+    pub const DEFAULT: Device = Device::Module15Keys;
+
+    /// Mini or Revised Mini, which wire their six keys directly
+    pub fn is_mini(&self) -> bool {
+        #[cfg(feature = "proto-v1")]
+        return matches!(self, Device::Mini | Device::RevisedMini);
+        #[cfg(not(feature = "proto-v1"))]
+        false
+    }
+
+    /// StreamDeck Plus, the only model with a touch strip
+    pub fn is_plus(&self) -> bool {
+        #[cfg(feature = "proto-v2")]
+        return matches!(self, Device::Plus);
+        #[cfg(not(feature = "proto-v2"))]
+        false
+    }
+
+    /// Get PID for this device
+    pub const fn pid(&self) -> u16 {
+        with_model!(self, Model => Model::PID)
+    }
+
+    /// Key layout, usable in const context
+    pub const fn layout(&self) -> ButtonLayout {
+        with_model!(self, Model => Model::LAYOUT)
+    }
+
+    /// Key image format and orientation, usable in const context
+    pub const fn display(&self) -> DisplayConfig {
+        with_model!(self, Model => Model::DISPLAY)
+    }
+
+    /// USB protocol, usable in const context
+    pub const fn protocol(&self) -> ProtocolVersion {
+        with_model!(self, Model => Model::PROTOCOL)
+    }
+
+    /// The per-model configuration this variant stands for
+    fn config(&self) -> &'static dyn DeviceConfig {
+        with_model!(self, Model => &Model)
+    }
+}
+
+/// PIDs of `Device::ALL`, in the same order
+const SUPPORTED_PIDS: [u16; Device::ALL.len()] = {
+    let mut pids = [0; Device::ALL.len()];
+    let mut i = 0;
+    while i < pids.len() {
+        pids[i] = Device::ALL[i].pid();
+        i += 1;
+    }
+    pids
+};
+
+// Compile-time validation of the device tables. For every model in `ALL`:
+// - `ALL` follows the declaration order with no gaps or repeats
+// - no other model answers to its PID, and `from_pid` finds it again
+// - `supported_pids` lists its PID at the same position
+// - its keys fit `MAX_KEYS` and its reports fit the USB buffers
+// - its largest image fits the image pool and the stored image slots
+#[allow(clippy::absurd_extreme_comparisons)]
+const _: () = {
+    let all = Device::ALL;
+    assert!(SUPPORTED_PIDS.len() == all.len());
+    let mut i = 0;
+    while i < all.len() {
+        let device = all[i];
+        assert!(
+            device as usize == i,
+            "Device::ALL is out of step with Device"
+        );
+        let mut j = i + 1;
+        while j < all.len() {
+            assert!(
+                device.pid() != all[j].pid(),
+                "two device models share a PID"
+            );
+            j += 1;
+        }
+        match Device::from_pid(device.pid()) {
+            Some(found) => assert!(found as usize == i, "from_pid does not round-trip"),
+            None => panic!("from_pid does not round-trip"),
+        }
+        assert!(SUPPORTED_PIDS[i] == device.pid());
+
+        let layout = device.layout();
+        let protocol = device.protocol();
+        assert!(layout.total_keys <= MAX_KEYS, "more keys than MAX_KEYS");
+        assert!(
+            protocol.input_report_size(layout.total_keys) <= USB_MAX_INPUT_REPORT,
+            "input report larger than USB_MAX_INPUT_REPORT"
+        );
+        assert!(
+            protocol.output_report_size() <= USB_MAX_OUTPUT_REPORT,
+            "output report larger than USB_MAX_OUTPUT_REPORT"
+        );
+
+        let image = device.display().max_image_size();
+        assert!(
+            image <= MAX_IMAGE_SIZE,
+            "key image larger than MAX_IMAGE_SIZE"
+        );
+        // Streaming-only builds have no slabs (IMAGE_SLAB_SIZE == 0)
+        assert!(
+            IMAGE_SLAB_SIZE == 0 || image <= IMAGE_SLAB_SIZE,
+            "key image larger than an image pool slab"
+        );
+        i += 1;
+    }
+};
+
+/// Every model delegates to its per-model configuration struct
+impl DeviceConfig for Device {
+    fn device_name(&self) -> &'static str {
+        self.config().device_name()
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        self.config().button_layout()
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        self.config().display_config()
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        self.config().usb_config()
+    }
+
+    fn dial_count(&self) -> usize {
+        self.config().dial_count()
+    }
+
+    fn info_bar(&self) -> Option<LcdRegion> {
+        self.config().info_bar()
+    }
+}
@@ -0,0 +1,139 @@
+//! Stream Deck Module device configurations
+//!
+//! The panel-mount modules with 6, 15 and 32 keys (PIDs 0x00B8, 0x00B9,
+//! 0x00BA). Module 6 takes BMP images rotated 90°, Module 15/32 take JPEG
+//! rotated 180°.
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// Stream Deck Module 6 Keys configuration (PID: 0x00B8)
+pub struct Module6KeysConfig;
+
+impl Module6KeysConfig {
+    pub const PID: u16 = 0x00B8;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::Module6Keys;
+    /// 3x2 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(3, 2, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 80,
+        image_height: 80,
+        format: ImageFormat::Bmp,
+        rotation: Rotation::Deg90,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for Module6KeysConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Module 6 Keys"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Module 6 Keys",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
+
+/// Stream Deck Module 15 Keys configuration (PID: 0x00B9)
+pub struct Module15KeysConfig;
+
+impl Module15KeysConfig {
+    pub const PID: u16 = 0x00B9;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::Module15_32Keys;
+    /// 5x3 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(5, 3, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 72,
+        image_height: 72,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg180,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for Module15KeysConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Module 15 Keys"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Module 15 Keys",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
+
+/// Stream Deck Module 32 Keys configuration (PID: 0x00BA)
+pub struct Module32KeysConfig;
+
+impl Module32KeysConfig {
+    pub const PID: u16 = 0x00BA;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::Module15_32Keys;
+    /// 8x4 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(8, 4, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 96,
+        image_height: 96,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg180,
+        flip_horizontal: false,
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for Module32KeysConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Module 32 Keys"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Module 32 Keys",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
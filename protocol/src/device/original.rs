@@ -0,0 +1,51 @@
+//! StreamDeck Original device configuration
+//!
+//! The original StreamDeck with 15 keys (PID: 0x0060)
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// StreamDeck Original configuration (PID: 0x0060)
+pub struct OriginalConfig;
+
+impl OriginalConfig {
+    pub const PID: u16 = 0x0060;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::V1;
+    /// 5x3 layout, right-to-left mapping
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(5, 3, false);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 72,
+        image_height: 72,
+        format: ImageFormat::Bmp,
+        rotation: Rotation::Deg0,
+        flip_horizontal: true, // Original needs horizontal flip
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for OriginalConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Original"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
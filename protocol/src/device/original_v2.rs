@@ -0,0 +1,51 @@
+//! StreamDeck Original V2 device configuration
+//!
+//! The second generation original StreamDeck with 15 keys and JPEG support (PID: 0x006d)
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// StreamDeck Original V2 configuration (PID: 0x006d)
+pub struct OriginalV2Config;
+
+impl OriginalV2Config {
+    pub const PID: u16 = 0x006d;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::V2;
+    /// 5x3 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(5, 3, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 72,
+        image_height: 72,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg0,
+        flip_horizontal: true, // V2 needs both horizontal and vertical flip
+        flip_vertical: true,
+    };
+}
+
+impl DeviceConfig for OriginalV2Config {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Original V2"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! StreamDeck Plus device configuration
+//!
+//! The StreamDeck Plus with 8 keys and additional controls (PID: 0x0084)
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::{PLUS_LCD_HEIGHT, PLUS_LCD_WIDTH, USB_POLL_RATE_MS};
+use crate::types::{LcdRegion, PLUS_DIAL_COUNT};
+
+/// The whole touch strip serves as the standalone info bar
+pub const STRIP_INFO_BAR: LcdRegion = LcdRegion {
+    x: 0,
+    y: 0,
+    width: PLUS_LCD_WIDTH,
+    height: PLUS_LCD_HEIGHT,
+};
+
+/// StreamDeck Plus configuration (PID: 0x0084)
+pub struct PlusConfig;
+
+impl PlusConfig {
+    pub const PID: u16 = 0x0084;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::Plus;
+    /// 4x2 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(4, 2, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 120,
+        image_height: 120,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg0,
+        flip_horizontal: false, // Plus needs no transformation
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for PlusConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Plus"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Plus",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+
+    fn dial_count(&self) -> usize {
+        PLUS_DIAL_COUNT
+    }
+
+    fn info_bar(&self) -> Option<LcdRegion> {
+        Some(STRIP_INFO_BAR)
+    }
+}
@@ -0,0 +1,60 @@
+//! StreamDeck Studio device configuration
+//!
+//! The rack-mount StreamDeck with 32 keys in two rows of 16 and a dial at
+//! each end (PID: 0x00aa). Key images follow V2; keys and dials are reported
+//! with the Plus event-typed input reports.
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// Dials on the Studio: left and right of the key rows
+pub const STUDIO_DIAL_COUNT: usize = 2;
+
+/// StreamDeck Studio configuration (PID: 0x00aa)
+pub struct StudioConfig;
+
+impl StudioConfig {
+    pub const PID: u16 = 0x00aa;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::Plus;
+    /// 16x2 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(16, 2, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 144,
+        image_height: 112,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg0,
+        flip_horizontal: false, // Studio needs no transformation
+        flip_vertical: false,
+    };
+}
+
+impl DeviceConfig for StudioConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck Studio"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck Studio",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+
+    fn dial_count(&self) -> usize {
+        STUDIO_DIAL_COUNT
+    }
+}
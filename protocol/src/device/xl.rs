@@ -0,0 +1,51 @@
+//! StreamDeck XL device configuration
+//!
+//! The large StreamDeck with 32 keys (PID: 0x006c)
+
+use super::{
+    ButtonLayout, DeviceConfig, DisplayConfig, ImageFormat, ProtocolVersion, Rotation, UsbConfig,
+};
+use crate::config::USB_POLL_RATE_MS;
+
+/// StreamDeck XL configuration (PID: 0x006c)
+pub struct XlConfig;
+
+impl XlConfig {
+    pub const PID: u16 = 0x006c;
+    pub const PROTOCOL: ProtocolVersion = ProtocolVersion::V2;
+    /// 8x4 layout, left-to-right
+    pub const LAYOUT: ButtonLayout = ButtonLayout::new(8, 4, true);
+    pub const DISPLAY: DisplayConfig = DisplayConfig {
+        image_width: 96,
+        image_height: 96,
+        format: ImageFormat::Jpeg,
+        rotation: Rotation::Deg0,
+        flip_horizontal: true, // XL needs both horizontal and vertical flip
+        flip_vertical: true,
+    };
+}
+
+impl DeviceConfig for XlConfig {
+    fn device_name(&self) -> &'static str {
+        "StreamDeck XL"
+    }
+
+    fn button_layout(&self) -> ButtonLayout {
+        Self::LAYOUT
+    }
+
+    fn display_config(&self) -> DisplayConfig {
+        Self::DISPLAY
+    }
+
+    fn usb_config(&self) -> UsbConfig {
+        UsbConfig {
+            vid: 0x0fd9,
+            pid: Self::PID,
+            product_name: "Stream Deck XL",
+            manufacturer: "Elgato Systems",
+            protocol: Self::PROTOCOL,
+            poll_ms: USB_POLL_RATE_MS,
+        }
+    }
+}
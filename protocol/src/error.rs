@@ -0,0 +1,23 @@
+//! Protocol error type
+//!
+//! The codes are part of the firmware's error code space (category 0x1_),
+//! reported to the host in the metrics report.
+
+/// Errors raised while parsing host reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ProtocolError {
+    /// Report shorter than its header
+    PacketTooShort = 0x10,
+    /// Image chunk arrived out of order or for a different key
+    SequenceError = 0x11,
+    /// Image does not fit the reassembly buffer
+    ImageTooLarge = 0x12,
+    /// Key index outside the device's key count
+    InvalidKey = 0x13,
+    /// Command recognized but its payload is malformed
+    InvalidPayload = 0x14,
+    /// All image buffers are in use
+    NoImageBuffer = 0x15,
+}
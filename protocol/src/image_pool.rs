@@ -0,0 +1,144 @@
+//! Pool of key image buffers
+//!
+//! A full key image is up to `IMAGE_SLAB_SIZE` bytes (a Mini BMP is ~19 KB),
+//! too large to move through channels by value. Images are instead
+//! reassembled into one of a few statically allocated slabs, and the
+//! `ImageSlab` handle travels USB -> display. Dropping the handle returns the
+//! slab to the pool.
+
+use core::cell::UnsafeCell;
+use core::ops::Deref;
+use embassy_time::Instant;
+// portable-atomic: thumbv6m has no native compare-exchange
+use portable_atomic::{AtomicU8, Ordering};
+
+use crate::config::{IMAGE_SLAB_COUNT, IMAGE_SLAB_SIZE};
+use crate::error::ProtocolError;
+
+struct Slab(UnsafeCell<[u8; IMAGE_SLAB_SIZE]>);
+
+// SAFETY: a slab is only accessed through the `ImageSlab` that owns its
+// `IN_USE` bit, so there is never more than one accessor.
+unsafe impl Sync for Slab {}
+
+static SLABS: [Slab; IMAGE_SLAB_COUNT] =
+    [const { Slab(UnsafeCell::new([0; IMAGE_SLAB_SIZE])) }; IMAGE_SLAB_COUNT];
+
+/// Bit per slab: set while an `ImageSlab` owns it
+static IN_USE: AtomicU8 = AtomicU8::new(0);
+
+/// Most slabs taken at once (see `peak_in_use`)
+static PEAK_IN_USE: AtomicU8 = AtomicU8::new(0);
+
+/// Exclusive handle to one pooled image buffer
+#[derive(Debug)]
+pub struct ImageSlab {
+    index: u8,
+    len: usize,
+    /// When the first page arrived (the slab was taken)
+    received_at: Instant,
+    /// When the last page arrived; `received_at` until then
+    assembled_at: Instant,
+}
+
+impl ImageSlab {
+    /// Take a free slab, or None when all are in flight
+    pub fn alloc() -> Option<Self> {
+        let mut used = IN_USE.load(Ordering::Acquire);
+        loop {
+            let index = (0..IMAGE_SLAB_COUNT as u8).find(|i| used & (1 << i) == 0)?;
+            match IN_USE.compare_exchange_weak(
+                used,
+                used | (1 << index),
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    let taken = (used | (1 << index)).count_ones() as u8;
+                    PEAK_IN_USE.fetch_max(taken, Ordering::Relaxed);
+                    let now = Instant::now();
+                    return Some(Self {
+                        index,
+                        len: 0,
+                        received_at: now,
+                        assembled_at: now,
+                    });
+                }
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    /// Bytes available in a slab
+    pub const fn capacity() -> usize {
+        IMAGE_SLAB_SIZE
+    }
+
+    /// Number of free slabs
+    pub fn free_count() -> usize {
+        IMAGE_SLAB_COUNT - IN_USE.load(Ordering::Relaxed).count_ones() as usize
+    }
+
+    /// Most slabs taken at once since the last `reset_peak`
+    pub fn peak_in_use() -> usize {
+        PEAK_IN_USE.load(Ordering::Relaxed) as usize
+    }
+
+    /// Zero `peak_in_use`
+    pub fn reset_peak() {
+        PEAK_IN_USE.store(0, Ordering::Relaxed);
+    }
+
+    /// Append bytes; fails without copying if they don't fit
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), ProtocolError> {
+        let end = self.len + data.len();
+        if end > IMAGE_SLAB_SIZE {
+            return Err(ProtocolError::ImageTooLarge);
+        }
+        // SAFETY: this handle owns the slab (see `Slab`)
+        let buf = unsafe { &mut *SLABS[self.index as usize].0.get() };
+        buf[self.len..end].copy_from_slice(data);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Shorten the image to `len` bytes
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Record that the image's last page has arrived
+    pub fn mark_assembled(&mut self) {
+        self.assembled_at = Instant::now();
+    }
+
+    /// When the first page arrived
+    pub fn received_at(&self) -> Instant {
+        self.received_at
+    }
+
+    /// When the last page arrived (see `mark_assembled`)
+    pub fn assembled_at(&self) -> Instant {
+        self.assembled_at
+    }
+}
+
+impl Deref for ImageSlab {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: this handle owns the slab (see `Slab`)
+        let buf = unsafe { &*SLABS[self.index as usize].0.get() };
+        &buf[..self.len]
+    }
+}
+
+impl Drop for ImageSlab {
+    fn drop(&mut self) {
+        IN_USE.fetch_and(!(1 << self.index), Ordering::Release);
+    }
+}
@@ -0,0 +1,23 @@
+//! ProductionDeck protocol crate
+//!
+//! The StreamDeck device tables and the host-facing USB HID protocol: output
+//! and feature report parsing, key image reassembly, input report formatting
+//! and report descriptor generation. Nothing here touches RP2040 hardware,
+//! so the crate builds for the host as well as for the firmware, which
+//! re-exports these modules under the same paths.
+//!
+//! Host builds must name the host target, as the workspace defaults to
+//! `thumbv6m-none-eabi`:
+//!
+//! ```text
+//! cargo run -p productiondeck-protocol --features replay --target x86_64-unknown-linux-gnu -- ...
+//! ```
+
+#![no_std]
+
+pub mod config;
+pub mod device;
+pub mod error;
+pub mod image_pool;
+pub mod protocol;
+pub mod types;
@@ -0,0 +1,198 @@
+//! Key image reassembly contexts
+//!
+//! Images arrive as numbered pages. Each key being uploaded gets its own
+//! small context, so a lost or out-of-order page only costs that key's
+//! image, and the next first page of a key always starts it cleanly, even in
+//! the middle of another transfer. Contexts are few (`IMAGE_ASSEMBLY_CONTEXTS`)
+//! and each holds an image slab; when a new image needs room, the context
+//! that has waited longest for its next page is abandoned.
+//!
+//! Abandoned images are counted (`counters`) for the firmware's metrics.
+
+// portable-atomic: thumbv6m has no native read-modify-write atomics
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::config::IMAGE_ASSEMBLY_CONTEXTS;
+use crate::error::ProtocolError;
+use crate::image_pool::ImageSlab;
+
+static RESYNCS: AtomicU32 = AtomicU32::new(0);
+static EVICTIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Abandoned images: (resyncs, evictions). A resync is a partly received
+/// image dropped after a lost or out-of-order page, or restarted by a new
+/// first page; an eviction is a stalled image dropped to make room.
+pub fn counters() -> (u32, u32) {
+    (
+        RESYNCS.load(Ordering::Relaxed),
+        EVICTIONS.load(Ordering::Relaxed),
+    )
+}
+
+/// Zero the `counters`
+pub fn reset_counters() {
+    RESYNCS.store(0, Ordering::Relaxed);
+    EVICTIONS.store(0, Ordering::Relaxed);
+}
+
+/// One key image being reassembled
+#[derive(Debug)]
+struct Assembly {
+    key_id: u8,
+    /// Page number the next page must carry
+    next_page: u16,
+    image: ImageSlab,
+    /// Value of the context clock when the last page arrived
+    last_used: u32,
+}
+
+/// Bounded set of in-flight key images, at most one per key
+#[derive(Debug)]
+pub struct AssemblyContexts {
+    slots: [Option<Assembly>; IMAGE_ASSEMBLY_CONTEXTS],
+    clock: u32,
+}
+
+impl AssemblyContexts {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { None }; IMAGE_ASSEMBLY_CONTEXTS],
+            clock: 0,
+        }
+    }
+
+    /// Start a new image for `key_id` whose next page is `next_page`. An
+    /// unfinished image for the same key is abandoned.
+    pub fn start(&mut self, key_id: u8, next_page: u16) -> Result<(), ProtocolError> {
+        if self.discard(key_id) {
+            RESYNCS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let image = match ImageSlab::alloc() {
+            Some(image) => image,
+            // Every slab is busy: reclaim one from a stalled transfer
+            None if self.evict_oldest() => {
+                ImageSlab::alloc().ok_or(ProtocolError::NoImageBuffer)?
+            }
+            None => return Err(ProtocolError::NoImageBuffer),
+        };
+
+        let slot = match self.slots.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => {
+                self.evict_oldest();
+                self.slots
+                    .iter()
+                    .position(Option::is_none)
+                    .ok_or(ProtocolError::NoImageBuffer)?
+            }
+        };
+
+        self.clock = self.clock.wrapping_add(1);
+        self.slots[slot] = Some(Assembly {
+            key_id,
+            next_page,
+            image,
+            last_used: self.clock,
+        });
+        Ok(())
+    }
+
+    /// Append a page to `key_id`'s image. If it is not the expected page,
+    /// the key's image is abandoned and the key waits for its next first page.
+    pub fn continue_with(
+        &mut self,
+        key_id: u8,
+        page: u16,
+        data: &[u8],
+    ) -> Result<(), ProtocolError> {
+        let slot = self.position(key_id).ok_or(ProtocolError::SequenceError)?;
+        self.clock = self.clock.wrapping_add(1);
+        let clock = self.clock;
+        let entry = &mut self.slots[slot];
+        let result = match entry {
+            Some(assembly) if assembly.next_page == page => {
+                assembly.next_page = page.wrapping_add(1);
+                assembly.last_used = clock;
+                assembly.image.extend_from_slice(data)
+            }
+            _ => {
+                RESYNCS.fetch_add(1, Ordering::Relaxed);
+                Err(ProtocolError::SequenceError)
+            }
+        };
+        if result.is_err() {
+            *entry = None;
+        }
+        result
+    }
+
+    /// True if `key_id` has an image waiting for `page`
+    pub fn expects(&self, key_id: u8, page: u16) -> bool {
+        self.position(key_id)
+            .and_then(|slot| self.slots[slot].as_ref())
+            .is_some_and(|assembly| assembly.next_page == page)
+    }
+
+    /// Bytes received so far for `key_id`'s image
+    pub fn received(&self, key_id: u8) -> Option<&[u8]> {
+        let slot = self.position(key_id)?;
+        self.slots[slot]
+            .as_ref()
+            .map(|assembly| &assembly.image[..])
+    }
+
+    /// Remove and return `key_id`'s image
+    pub fn finish(&mut self, key_id: u8) -> Option<ImageSlab> {
+        let slot = self.position(key_id)?;
+        self.slots[slot].take().map(|assembly| assembly.image)
+    }
+
+    /// Drop `key_id`'s image, if any; true if one was in progress
+    pub fn discard(&mut self, key_id: u8) -> bool {
+        match self.position(key_id) {
+            Some(slot) => {
+                self.slots[slot] = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop every image in progress
+    pub fn clear(&mut self) {
+        self.slots = [const { None }; IMAGE_ASSEMBLY_CONTEXTS];
+    }
+
+    fn position(&self, key_id: u8) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.as_ref().is_some_and(|a| a.key_id == key_id))
+    }
+
+    /// Abandon the context idle the longest; false if none is in use
+    fn evict_oldest(&mut self) -> bool {
+        let clock = self.clock;
+        let oldest = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().map(|a| (i, clock.wrapping_sub(a.last_used))))
+            .max_by_key(|&(_, age)| age)
+            .map(|(i, _)| i);
+        match oldest {
+            Some(slot) => {
+                self.slots[slot] = None;
+                EVICTIONS.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for AssemblyContexts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,175 @@
+//! Vendor bulk image transfer protocol
+//!
+//! Companion host tools that don't need Elgato compatibility can push key
+//! images over the optional vendor bulk interface at full-speed bulk rates.
+//!
+//! Each transfer starts with an 8-byte header followed by the image bytes,
+//! which may span any number of bulk packets:
+//!
+//! `[b'P', b'D', command, key_id, length (u32 LE)]`
+//!
+//! After each transfer the device answers on the bulk IN endpoint with
+//! `[b'P', b'D', status, key_id]`.
+//!
+//! With `firmware-update`, `BULK_CMD_FIRMWARE` transfers (key_id 0) carry a
+//! firmware image started with `VENDOR_CMD_BEGIN_FIRMWARE`; each transfer
+//! continues where the last one ended.
+
+use crate::image_pool::ImageSlab;
+
+/// Frame magic
+pub const BULK_MAGIC: [u8; 2] = *b"PD";
+
+/// Bulk header size
+pub const BULK_HEADER_SIZE: usize = 8;
+
+/// Command: upload a key image (same format the HID path uses for the device)
+pub const BULK_CMD_KEY_IMAGE: u8 = 0x01;
+
+/// Command: the next part of a firmware upload (`firmware-update`)
+pub const BULK_CMD_FIRMWARE: u8 = 0x02;
+
+/// Transfer accepted
+pub const BULK_STATUS_OK: u8 = 0x00;
+/// Header not recognized
+pub const BULK_STATUS_BAD_HEADER: u8 = 0x01;
+/// Image does not fit the image buffer
+pub const BULK_STATUS_TOO_LARGE: u8 = 0x02;
+/// No image buffer free; retry once earlier images are displayed
+pub const BULK_STATUS_BUSY: u8 = 0x03;
+/// Firmware data not expected or not stored; read the update status (vendor report)
+pub const BULK_STATUS_FIRMWARE_REJECTED: u8 = 0x04;
+
+/// Outcome of feeding one bulk packet to the assembler
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum BulkResult {
+    /// More data expected
+    Pending,
+    /// Image complete
+    KeyImageComplete { key_id: u8, image: ImageSlab },
+    /// Firmware bytes at `range` of the packet; `complete` on the transfer's last packet
+    #[cfg(feature = "firmware-update")]
+    FirmwareData {
+        range: core::ops::Range<usize>,
+        complete: bool,
+    },
+    /// Transfer rejected; the status should be reported to the host
+    Rejected { key_id: u8, status: u8 },
+}
+
+/// Reassembles bulk transfers into key images
+#[derive(Debug)]
+pub struct BulkAssembler {
+    image: Option<ImageSlab>,
+    key_id: u8,
+    remaining: usize,
+    /// Bytes of an oversized transfer still to be discarded
+    discarding: usize,
+}
+
+impl BulkAssembler {
+    pub const fn new() -> Self {
+        Self {
+            image: None,
+            key_id: 0,
+            remaining: 0,
+            discarding: 0,
+        }
+    }
+
+    /// Feed one bulk OUT packet
+    pub fn feed(&mut self, mut packet: &[u8]) -> BulkResult {
+        #[cfg(feature = "firmware-update")]
+        let fed = packet.len();
+        // Drop the tail of a rejected transfer
+        if self.discarding > 0 {
+            let skip = self.discarding.min(packet.len());
+            self.discarding -= skip;
+            packet = &packet[skip..];
+            if packet.is_empty() {
+                return BulkResult::Pending;
+            }
+        }
+
+        if self.remaining == 0 {
+            let firmware =
+                cfg!(feature = "firmware-update") && packet.get(2) == Some(&BULK_CMD_FIRMWARE);
+            if packet.len() < BULK_HEADER_SIZE
+                || packet[..2] != BULK_MAGIC
+                || (packet[2] != BULK_CMD_KEY_IMAGE && !firmware)
+            {
+                return BulkResult::Rejected {
+                    key_id: packet.get(3).copied().unwrap_or(0),
+                    status: BULK_STATUS_BAD_HEADER,
+                };
+            }
+
+            let key_id = packet[3];
+            let length = u32::from_le_bytes([packet[4], packet[5], packet[6], packet[7]]) as usize;
+            packet = &packet[BULK_HEADER_SIZE..];
+
+            if firmware {
+                self.key_id = key_id;
+                self.remaining = length;
+            } else if length > ImageSlab::capacity() {
+                self.discarding = length.saturating_sub(packet.len());
+                return BulkResult::Rejected {
+                    key_id,
+                    status: BULK_STATUS_TOO_LARGE,
+                };
+            } else {
+                let Some(image) = ImageSlab::alloc() else {
+                    self.discarding = length.saturating_sub(packet.len());
+                    return BulkResult::Rejected {
+                        key_id,
+                        status: BULK_STATUS_BUSY,
+                    };
+                };
+
+                self.image = Some(image);
+                self.key_id = key_id;
+                self.remaining = length;
+            }
+        }
+
+        let take = self.remaining.min(packet.len());
+        // Firmware transfers have no image buffer: the data goes straight to flash
+        #[cfg(feature = "firmware-update")]
+        if self.image.is_none() {
+            self.remaining -= take;
+            let start = fed - packet.len();
+            return BulkResult::FirmwareData {
+                range: start..start + take,
+                complete: self.remaining == 0,
+            };
+        }
+        if let Some(image) = self.image.as_mut() {
+            // Capacity was checked against the declared length above
+            let _ = image.extend_from_slice(&packet[..take]);
+        }
+        self.remaining -= take;
+
+        match self.image.take() {
+            Some(image) if self.remaining == 0 => BulkResult::KeyImageComplete {
+                key_id: self.key_id,
+                image,
+            },
+            image => {
+                self.image = image;
+                BulkResult::Pending
+            }
+        }
+    }
+}
+
+impl Default for BulkAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the status reply sent on the bulk IN endpoint
+pub fn status_reply(status: u8, key_id: u8) -> [u8; 4] {
+    [BULK_MAGIC[0], BULK_MAGIC[1], status, key_id]
+}
@@ -0,0 +1,185 @@
+//! HID report descriptor generation
+//!
+//! Protocol handlers describe their reports with a `ReportLayout` and the
+//! descriptor is generated from it and the device's key count at USB init,
+//! so key counts and report sizes can't drift from what the firmware sends.
+
+use crate::config::{FEATURE_REPORT_VENDOR, SETTINGS_REPORTS, VENDOR_REPORT_SIZE};
+use crate::protocol::feature::FeatureLayout;
+use heapless::Vec;
+
+/// Largest generated descriptor
+pub const MAX_DESCRIPTOR_SIZE: usize = 320;
+
+/// Generated HID report descriptor
+pub type ReportDescriptor = Vec<u8, MAX_DESCRIPTOR_SIZE>;
+
+/// Input report ID carrying key states
+pub const INPUT_REPORT_ID: u8 = 0x01;
+/// Output report ID carrying image data
+pub const OUTPUT_REPORT_ID: u8 = 0x02;
+
+// Main item data: Data,Var,Abs and Data,Array,Rel
+const DATA_VAR_ABS: u8 = 0x02;
+const DATA_ARRAY_REL: u8 = 0x04;
+
+/// Report structure of one protocol
+#[derive(Clone, Copy, Debug)]
+pub struct ReportLayout {
+    /// Number of keys (Button usages 1..=key_count)
+    pub key_count: usize,
+    /// Input report length after the report ID
+    pub input_count: usize,
+    /// Output report length after the report ID
+    pub output_count: usize,
+    /// Feature report IDs used by the protocol
+    pub feature_ids: &'static [u8],
+    /// Length after the report ID of feature reports the protocol only
+    /// accepts; reports it answers take their reply's length
+    pub feature_count: usize,
+}
+
+/// Appends HID short items
+struct DescriptorBuilder {
+    buf: ReportDescriptor,
+    /// Logged with `defmt`; host builds see the truncated descriptor
+    #[cfg_attr(not(feature = "defmt"), allow(dead_code))]
+    overflow: bool,
+}
+
+impl DescriptorBuilder {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            overflow: false,
+        }
+    }
+
+    fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        if self.buf.extend_from_slice(bytes).is_err() {
+            self.overflow = true;
+        }
+        self
+    }
+
+    /// Emit an item with the smallest data size that holds `value`
+    fn item(&mut self, tag: u8, value: u32) -> &mut Self {
+        let bytes = value.to_le_bytes();
+        let (size_code, len) = match value {
+            0..=0xFF => (1, 1),
+            0x100..=0xFFFF => (2, 2),
+            _ => (3, 4),
+        };
+        self.raw(&[tag | size_code]).raw(&bytes[..len])
+    }
+
+    fn usage_page(&mut self, page: u16) -> &mut Self {
+        self.item(0x04, page as u32)
+    }
+
+    fn usage(&mut self, usage: u16) -> &mut Self {
+        self.item(0x08, usage as u32)
+    }
+
+    fn usage_range(&mut self, min: u16, max: u16) -> &mut Self {
+        self.item(0x18, min as u32).item(0x28, max as u32)
+    }
+
+    fn report_id(&mut self, id: u8) -> &mut Self {
+        self.item(0x84, id as u32)
+    }
+
+    /// Byte-sized fields (0..=255), `count` of them
+    fn bytes(&mut self, count: usize) -> &mut Self {
+        // Logical Maximum 255 needs the 2-byte form to stay positive
+        self.raw(&[0x15, 0x00, 0x26, 0xFF, 0x00])
+            .item(0x74, 8)
+            .item(0x94, count as u32)
+    }
+
+    fn input(&mut self, flags: u8) -> &mut Self {
+        self.item(0x80, flags as u32)
+    }
+
+    fn output(&mut self, flags: u8) -> &mut Self {
+        self.item(0x90, flags as u32)
+    }
+
+    fn feature(&mut self, flags: u8) -> &mut Self {
+        self.item(0xB0, flags as u32)
+    }
+
+    fn collection(&mut self, kind: u8) -> &mut Self {
+        self.item(0xA0, kind as u32)
+    }
+
+    fn end_collection(&mut self) -> &mut Self {
+        self.raw(&[0xC0])
+    }
+}
+
+/// Generate the report descriptor for a layout. Feature reports the
+/// protocol answers (`replies`) are declared too, so hosts that only read
+/// declared reports can reach them, and each is declared at its reply's
+/// length so hosts that size reads from the descriptor get the whole reply.
+pub fn build(layout: &ReportLayout, replies: &[FeatureLayout]) -> ReportDescriptor {
+    let mut b = DescriptorBuilder::new();
+
+    b.usage_page(0x0C) // Consumer
+        .usage(0x01) // Consumer Control
+        .collection(0x01); // Application
+
+    // Key states
+    b.usage(0x01)
+        .usage_page(0x09) // Button
+        .usage_range(1, layout.key_count as u16)
+        .bytes(layout.input_count)
+        .report_id(INPUT_REPORT_ID)
+        .input(DATA_VAR_ABS);
+
+    // Image data
+    b.usage(0xFF00)
+        .bytes(layout.output_count)
+        .report_id(OUTPUT_REPORT_ID)
+        .output(DATA_VAR_ABS);
+
+    let reply_ids = replies
+        .iter()
+        .map(|reply| reply.report_id)
+        .filter(|id| !layout.feature_ids.contains(id));
+    for id in layout.feature_ids.iter().copied().chain(reply_ids) {
+        let len = replies
+            .iter()
+            .find(|reply| reply.report_id == id)
+            .map_or(layout.feature_count, |reply| reply.report_len - 1);
+        b.usage(0xFF00)
+            .bytes(len)
+            .report_id(id)
+            .feature(DATA_ARRAY_REL);
+    }
+
+    // ProductionDeck vendor report
+    b.usage(0xFF00)
+        .bytes(VENDOR_REPORT_SIZE - 1)
+        .report_id(FEATURE_REPORT_VENDOR)
+        .feature(DATA_VAR_ABS);
+
+    // Settings reports for WebHID pages
+    for (id, len) in SETTINGS_REPORTS {
+        b.usage(0xFF00)
+            .bytes(len)
+            .report_id(id)
+            .feature(DATA_VAR_ABS);
+    }
+
+    b.end_collection();
+
+    #[cfg(feature = "defmt")]
+    if b.overflow {
+        defmt::error!(
+            "HID report descriptor exceeds {} bytes",
+            MAX_DESCRIPTOR_SIZE
+        );
+    }
+    b.buf
+}
@@ -0,0 +1,105 @@
+//! Byte-exact Elgato HID report descriptors
+//!
+//! Some host libraries check the report descriptor's length or contents
+//! before accepting a device. With the `elgato-exact-descriptors` feature the
+//! USB interface uses the descriptor of the real hardware instead of the
+//! generated one (see `descriptor`).
+//!
+//! Only descriptors verified against real hardware belong here; devices
+//! without a table fall back to the generated descriptor. The real
+//! descriptors have no ProductionDeck vendor report (0xF0), so vendor
+//! configuration commands are unavailable in this mode.
+
+use crate::device::Device;
+
+/// StreamDeck Mini / Revised Mini (173 bytes)
+#[cfg(feature = "proto-v1")]
+const MINI: [u8; 173] = [
+    0x05, 0x0c, // Usage Page (Consumer)
+    0x09, 0x01, // Usage (Consumer Control)
+    0xa1, 0x01, // Collection (Application)
+    0x09, 0x01, // Usage (Consumer Control)
+    0x05, 0x09, // Usage Page (Button)
+    0x19, 0x01, // Usage Minimum (0x01)
+    0x29, 0x06, // Usage Maximum (0x06)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x06, // Report Count (6)
+    0x85, 0x01, // Report ID (0x01)
+    0x81, 0x02, // Input (Data,Var,Abs)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x96, 0xff, 0x03, // Report Count (1023)
+    0x85, 0x02, // Report ID (0x02)
+    0x91, 0x02, // Output (Data,Var,Abs)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0x03, // Report ID (0x03)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0x04, // Report ID (0x04)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0x05, // Report ID (0x05)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0x07, // Report ID (0x07)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0x0b, // Report ID (0x0b)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0xa0, // Report ID (0xa0)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0xa1, // Report ID (0xa1)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0x0a, 0x00, 0xff, // Usage (Button 255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x10, // Report Count (16)
+    0x85, 0xa2, // Report ID (0xa2)
+    0xb1, 0x04, // Feature (Data,Array,Rel)
+    0xc0, // End Collection
+];
+
+/// Real hardware descriptor for `device`, if one has been captured.
+/// OriginalV2, XL, Plus and Studio still need captures and use the generated descriptor.
+pub fn exact_descriptor(device: Device) -> Option<&'static [u8]> {
+    match device {
+        #[cfg(feature = "proto-v1")]
+        Device::Mini | Device::RevisedMini => Some(&MINI),
+        _ => None,
+    }
+}
@@ -0,0 +1,28 @@
+//! Expansion bus wire format
+//!
+//! A satellite board answers a plain I2C read of `SATELLITE_REPORT_SIZE`
+//! bytes with `[EXPANSION_PROTOCOL_VERSION, keys, pressed mask u32 LE]`, bit
+//! n of the mask being the board's key n (1 = pressed). The main unit reads
+//! it (`expansion`, `i2c-expansion`); a board built with `i2c-satellite`
+//! answers it (`satellite`).
+
+/// Protocol version a satellite reports in the first byte of its answer
+pub const EXPANSION_PROTOCOL_VERSION: u8 = 1;
+
+/// Satellite answer: version, key count, pressed mask (u32 LE)
+pub const SATELLITE_REPORT_SIZE: usize = 6;
+
+/// A satellite's answer for `keys` keys with `pressed` held
+pub fn encode_satellite_report(keys: u8, pressed: u32) -> [u8; SATELLITE_REPORT_SIZE] {
+    let mut answer = [0u8; SATELLITE_REPORT_SIZE];
+    answer[0] = EXPANSION_PROTOCOL_VERSION;
+    answer[1] = keys;
+    answer[2..6].copy_from_slice(&pressed.to_le_bytes());
+    answer
+}
+
+/// Pressed mask of a satellite's answer; None if it speaks another version
+pub fn decode_satellite_report(answer: &[u8; SATELLITE_REPORT_SIZE]) -> Option<u32> {
+    (answer[0] == EXPANSION_PROTOCOL_VERSION)
+        .then(|| u32::from_le_bytes([answer[2], answer[3], answer[4], answer[5]]))
+}
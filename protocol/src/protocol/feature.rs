@@ -0,0 +1,263 @@
+//! Table-driven GET_REPORT (feature) replies
+//!
+//! Each protocol family answers host probes (firmware versions, serial number,
+//! idle time) with a fixed report layout. The layouts below mirror what real
+//! hardware returns so host libraries that slice replies at fixed offsets
+//! (python-elgato-streamdeck, node-elgato-stream-deck) read the same fields
+//! they would from an Elgato device.
+//!
+//! Where the brightness report is not taken by another reply, reading it
+//! back returns the last host level in the same layout the host writes, so
+//! a host that probes it on connect sees the level it stored. V1 answers
+//! 0x05 with firmware versions and has no such read.
+//!
+//! Values that belong to the unit rather than the protocol (version, serial,
+//! idle time, brightness) are passed in as a `UnitInfo`.
+
+use crate::device::ProtocolVersion;
+
+/// Unit state the replies report, supplied by the caller
+#[derive(Debug, Clone, Copy)]
+pub struct UnitInfo<'a> {
+    /// ASCII firmware version V1/V2 devices report
+    pub firmware_version: &'a [u8],
+    /// Serial number (same as the USB serial string descriptor)
+    pub serial: &'a [u8],
+    /// Idle time before sleep, in seconds (0 = never)
+    pub idle_time_secs: i32,
+    /// Last host brightness in percent
+    pub brightness: u8,
+}
+
+/// Content written into a feature report reply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeatureContent {
+    /// The unit's ASCII firmware version (`UnitInfo::firmware_version`)
+    FirmwareVersion,
+    /// Fixed ASCII version string, for firmware parts ProductionDeck has none of
+    FixedVersion(&'static [u8]),
+    /// Unit serial number (same as the USB serial string descriptor)
+    Serial,
+    /// Idle time in seconds as little-endian i32
+    IdleTime,
+    /// Last host brightness in percent
+    Brightness,
+    /// Report exists but carries no data (all zero)
+    Zeroed,
+}
+
+/// Byte layout of a single feature report reply
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureLayout {
+    /// Report ID (also written at byte 0 of the reply)
+    pub report_id: u8,
+    /// Total reply length including the report ID
+    pub report_len: usize,
+    /// Fixed bytes written starting at byte 1
+    pub header: &'static [u8],
+    /// Write the content length at byte 1 (overrides `header`)
+    pub length_prefixed: bool,
+    /// Offset of the content within the reply
+    pub data_offset: usize,
+    /// Content of the reply
+    pub content: FeatureContent,
+}
+
+impl FeatureLayout {
+    const fn new(report_id: u8, report_len: usize, data_offset: usize) -> Self {
+        Self {
+            report_id,
+            report_len,
+            header: &[],
+            length_prefixed: false,
+            data_offset,
+            content: FeatureContent::Zeroed,
+        }
+    }
+
+    const fn header(mut self, header: &'static [u8]) -> Self {
+        self.header = header;
+        self
+    }
+
+    const fn length_prefixed(mut self) -> Self {
+        self.length_prefixed = true;
+        self
+    }
+
+    const fn content(mut self, content: FeatureContent) -> Self {
+        self.content = content;
+        self
+    }
+}
+
+// ===================================================================
+// Per-protocol reply tables
+// ===================================================================
+
+/// V1 devices (Original, Mini, Revised Mini): 17-byte replies, data at offset 5,
+/// the 16 bytes after the report ID the Mini's descriptor declares
+pub const V1_FEATURES: &[FeatureLayout] = &[
+    FeatureLayout::new(0x03, 17, 5).content(FeatureContent::Serial),
+    FeatureLayout::new(0x04, 17, 5).content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0x05, 17, 5)
+        .header(&[0x0c, 0x31, 0x33, 0x00])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0x07, 17, 1),
+    FeatureLayout::new(0xA0, 17, 5)
+        .header(&[0x0c, 0x31, 0x33, 0x00])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0xA1, 17, 5)
+        .header(&[0x0c, 0x31, 0x33, 0x00])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0xA2, 17, 5)
+        .header(&[0x0c, 0x31, 0x33, 0x00])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0xA3, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::IdleTime),
+];
+
+/// V2 devices (Original V2, XL, Plus, Studio): 32-byte replies.
+/// Firmware: [id, 0x0C, checksum(4), version...]; serial: [0x06, len, serial...]
+pub const V2_FEATURES: &[FeatureLayout] = &[
+    FeatureLayout::new(0x03, 32, 2)
+        .header(&[0x08])
+        .content(FeatureContent::Brightness),
+    FeatureLayout::new(0x04, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0x05, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0x06, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::Serial),
+    FeatureLayout::new(0x07, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FirmwareVersion),
+    FeatureLayout::new(0xA3, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::IdleTime),
+];
+
+/// Module 6: firmware at 0xA0-0xA2, serial at 0x03 and brightness at 0x05,
+/// data at offset 5
+pub const MODULE6_FEATURES: &[FeatureLayout] = &[
+    FeatureLayout::new(0x03, 32, 5).content(FeatureContent::Serial),
+    FeatureLayout::new(0x05, 32, 5)
+        .header(&[0x55, 0xAA, 0xD1, 0x01])
+        .content(FeatureContent::Brightness),
+    FeatureLayout::new(0xA0, 32, 5).content(FeatureContent::FixedVersion(b"1.00.003")),
+    FeatureLayout::new(0xA1, 32, 5).content(FeatureContent::FixedVersion(b"1.03.000")),
+    FeatureLayout::new(0xA2, 32, 5).content(FeatureContent::FixedVersion(b"1.03.000")),
+    FeatureLayout::new(0xA3, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::IdleTime),
+];
+
+/// Module 15/32: same layouts as V2 plus idle time at 0x0A
+pub const MODULE15_32_FEATURES: &[FeatureLayout] = &[
+    FeatureLayout::new(0x03, 32, 2)
+        .header(&[0x08])
+        .content(FeatureContent::Brightness),
+    FeatureLayout::new(0x04, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FixedVersion(b"1.00.000")),
+    FeatureLayout::new(0x05, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FixedVersion(b"1.00.000")),
+    FeatureLayout::new(0x06, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::Serial),
+    FeatureLayout::new(0x07, 32, 6)
+        .header(&[0x0C])
+        .content(FeatureContent::FixedVersion(b"1.00.000")),
+    FeatureLayout::new(0x0A, 32, 2)
+        .length_prefixed()
+        .content(FeatureContent::IdleTime),
+];
+
+/// Get the feature reply table for a protocol
+pub fn table_for(version: ProtocolVersion) -> &'static [FeatureLayout] {
+    match version {
+        #[cfg(feature = "proto-v1")]
+        ProtocolVersion::V1 => V1_FEATURES,
+        #[cfg(feature = "proto-v2")]
+        ProtocolVersion::V2 | ProtocolVersion::Plus => V2_FEATURES,
+        #[cfg(feature = "proto-module")]
+        ProtocolVersion::Module6Keys => MODULE6_FEATURES,
+        #[cfg(feature = "proto-module")]
+        ProtocolVersion::Module15_32Keys => MODULE15_32_FEATURES,
+    }
+}
+
+/// Find the layout for a report ID in a table
+pub fn lookup(table: &'static [FeatureLayout], report_id: u8) -> Option<&'static FeatureLayout> {
+    table.iter().find(|layout| layout.report_id == report_id)
+}
+
+/// Whether a report identifies the unit (serial or firmware version). Host
+/// software reads these when it opens the device.
+pub fn is_identity(table: &'static [FeatureLayout], report_id: u8) -> bool {
+    lookup(table, report_id).is_some_and(|layout| {
+        matches!(
+            layout.content,
+            FeatureContent::Serial
+                | FeatureContent::FirmwareVersion
+                | FeatureContent::FixedVersion(_)
+        )
+    })
+}
+
+/// Build a feature report reply from a table. Returns the reply length, or
+/// `None` when the report ID is not part of the table.
+pub fn build(
+    table: &'static [FeatureLayout],
+    report_id: u8,
+    unit: &UnitInfo,
+    buf: &mut [u8],
+) -> Option<usize> {
+    let layout = lookup(table, report_id)?;
+    Some(write_layout(layout, unit, buf))
+}
+
+/// Write a reply for a single layout into `buf`
+pub fn write_layout(layout: &FeatureLayout, unit: &UnitInfo, buf: &mut [u8]) -> usize {
+    let total_len = layout.report_len.min(buf.len());
+    if total_len == 0 {
+        return 0;
+    }
+    buf.iter_mut().take(total_len).for_each(|b| *b = 0);
+    buf[0] = layout.report_id;
+
+    let header_end = (1 + layout.header.len()).min(total_len);
+    buf[1..header_end].copy_from_slice(&layout.header[..header_end - 1]);
+
+    let idle_bytes;
+    let brightness;
+    let content: &[u8] = match layout.content {
+        FeatureContent::FirmwareVersion => unit.firmware_version,
+        FeatureContent::FixedVersion(version) => version,
+        FeatureContent::Serial => unit.serial,
+        FeatureContent::IdleTime => {
+            idle_bytes = unit.idle_time_secs.to_le_bytes();
+            &idle_bytes
+        }
+        FeatureContent::Brightness => {
+            brightness = [unit.brightness];
+            &brightness
+        }
+        FeatureContent::Zeroed => &[],
+    };
+
+    if layout.length_prefixed && total_len > 1 {
+        buf[1] = content.len() as u8;
+    }
+
+    let start = layout.data_offset.min(total_len);
+    let end = (start + content.len()).min(total_len);
+    buf[start..end].copy_from_slice(&content[..end - start]);
+
+    total_len
+}
@@ -0,0 +1,139 @@
+//! Completed key image validation
+//!
+//! Reassembly only checks page order, so a host bug or a corrupted transfer
+//! can still produce an image the panel can't use. Before an image is queued
+//! for display its container is checked against the device's `DisplayConfig`:
+//! BMP magic, declared file size, dimensions and bit depth, or JPEG SOI/EOI
+//! markers and the frame dimensions. Pixel data itself is not inspected;
+//! `bmp_pixels` and `solid_pixel` let the panel path spot flat images,
+//! which hosts send to clear keys, and draw them without a per-pixel blit.
+
+use crate::device::{DisplayConfig, ImageFormat};
+
+/// BMP file + info header size
+const BMP_HEADER_SIZE: usize = 54;
+
+/// Why a completed image was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InvalidImage {
+    /// Missing BMP `BM` or JPEG SOI magic
+    BadMagic,
+    /// Fewer bytes than the header declares, or a JPEG without EOI
+    Truncated,
+    /// Dimensions differ from the device's key image size
+    WrongSize { width: u32, height: u32 },
+    /// BMP pixel format other than 24-bit uncompressed
+    UnsupportedFormat,
+}
+
+/// Check a completed key image against the device's display configuration
+pub fn validate_key_image(display: &DisplayConfig, data: &[u8]) -> Result<(), InvalidImage> {
+    let (width, height) = match display.format {
+        ImageFormat::Bmp => bmp_dimensions(data)?,
+        ImageFormat::Jpeg => jpeg_dimensions(data)?,
+    };
+    if width as usize != display.image_width || height as usize != display.image_height {
+        return Err(InvalidImage::WrongSize { width, height });
+    }
+    Ok(())
+}
+
+/// Pixel data of a validated BMP key image, rows in stored order. None
+/// for JPEG images and for BMP rows padded to a 4-byte multiple.
+pub fn bmp_pixels<'a>(display: &DisplayConfig, data: &'a [u8]) -> Option<&'a [u8]> {
+    if display.format != ImageFormat::Bmp || data.len() < BMP_HEADER_SIZE {
+        return None;
+    }
+    let row_bytes = display.image_width * 3;
+    if !row_bytes.is_multiple_of(4) {
+        return None;
+    }
+    let pixel_offset = le_u32(data, 10) as usize;
+    data.get(pixel_offset..pixel_offset + row_bytes * display.image_height)
+}
+
+/// The color of raw 3-byte pixels that are all the same
+pub fn solid_pixel(pixels: &[u8]) -> Option<[u8; 3]> {
+    let first = pixels.get(..3)?;
+    pixels
+        .chunks_exact(3)
+        .all(|pixel| pixel == first)
+        .then(|| [first[0], first[1], first[2]])
+}
+
+fn le_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn le_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Width and height of a 24-bit BMP whose declared size is all present
+fn bmp_dimensions(data: &[u8]) -> Result<(u32, u32), InvalidImage> {
+    if data.len() < 2 || data[0] != b'B' || data[1] != b'M' {
+        return Err(InvalidImage::BadMagic);
+    }
+    if data.len() < BMP_HEADER_SIZE {
+        return Err(InvalidImage::Truncated);
+    }
+
+    let file_size = le_u32(data, 2) as usize;
+    let pixel_offset = le_u32(data, 10) as usize;
+    let width = le_u32(data, 18) as i32;
+    // Negative height marks a top-down bitmap
+    let height = le_u32(data, 22) as i32;
+    let bits_per_pixel = le_u16(data, 28);
+    let compression = le_u32(data, 30);
+    if bits_per_pixel != 24 || compression != 0 {
+        return Err(InvalidImage::UnsupportedFormat);
+    }
+
+    let (width, height) = (width.unsigned_abs(), height.unsigned_abs());
+    // Rows are padded to a multiple of 4 bytes
+    let row_bytes = (width as usize * 3).next_multiple_of(4);
+    let pixels_end = pixel_offset.saturating_add(row_bytes.saturating_mul(height as usize));
+    if file_size > data.len() || pixels_end > file_size {
+        return Err(InvalidImage::Truncated);
+    }
+    Ok((width, height))
+}
+
+/// Width and height from a JPEG's frame header, once SOI and EOI are present
+fn jpeg_dimensions(data: &[u8]) -> Result<(u32, u32), InvalidImage> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(InvalidImage::BadMagic);
+    }
+    // The last report of an image may be zero padded after EOI
+    let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    if !data[..end].ends_with(&[0xFF, 0xD9]) {
+        return Err(InvalidImage::Truncated);
+    }
+
+    // Walk marker segments up to the frame header (SOF0..SOF15, except the
+    // DHT, JPG and DAC markers that share the range)
+    let mut at = 2;
+    while at + 4 <= end {
+        if data[at] != 0xFF {
+            return Err(InvalidImage::Truncated);
+        }
+        let marker = data[at + 1];
+        let length = u16::from_be_bytes([data[at + 2], data[at + 3]]) as usize;
+        let is_frame = (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+        if is_frame {
+            if at + 9 > end {
+                return Err(InvalidImage::Truncated);
+            }
+            let height = u16::from_be_bytes([data[at + 5], data[at + 6]]);
+            let width = u16::from_be_bytes([data[at + 7], data[at + 8]]);
+            return Ok((width as u32, height as u32));
+        }
+        if marker == 0xDA {
+            // Scan data before any frame header
+            break;
+        }
+        at += 2 + length;
+    }
+    Err(InvalidImage::Truncated)
+}
@@ -0,0 +1,452 @@
+//! StreamDeck protocol abstraction layer
+//!
+//! Handles different protocol versions (V1, V2, Plus and modules) with unified interface.
+//! Each family is linked only with its Cargo feature (`proto-v1`, `proto-v2`,
+//! `proto-module`, all on by default), so single-model builds can drop the rest.
+
+pub mod assembly;
+pub mod bulk;
+pub mod descriptor;
+pub mod elgato_descriptors;
+pub mod expansion;
+pub mod feature;
+pub mod image_check;
+pub mod module;
+#[cfg(feature = "proto-module")]
+pub mod module_15_32;
+#[cfg(feature = "proto-module")]
+pub mod module_6;
+#[cfg(feature = "proto-v2")]
+pub mod plus;
+#[cfg(feature = "proto-v1")]
+pub mod v1;
+#[cfg(feature = "proto-v2")]
+pub mod v2;
+
+use crate::config::IMAGE_BUFFER_SIZE;
+use crate::device::{Device, DeviceConfig, ProtocolVersion};
+use crate::error::ProtocolError;
+use crate::image_pool::ImageSlab;
+use crate::protocol::descriptor::{ReportDescriptor, ReportLayout};
+use crate::protocol::feature::UnitInfo;
+use crate::protocol::module::ModuleSetCommand;
+use crate::types::{LcdRegion, Rotation};
+use heapless::Vec;
+
+/// Parsed outcome of an Output Report (host -> device)
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum OutputReportResult {
+    /// Update Key Image (Module 15/32: cmd 0x07, Module 6: cmd 0x01 with ShowFlag=1)
+    KeyImageComplete { key_id: u8, image: ImageSlab },
+    /// Part of a key image to be drawn as it arrives (`streaming-decode`).
+    /// `payload` is the range of the report holding image bytes, which start
+    /// at `offset` within the image.
+    KeyImageChunk {
+        key_id: u8,
+        offset: usize,
+        payload: core::ops::Range<usize>,
+        last: bool,
+    },
+    /// Touch strip LCD image for a window of the strip (Plus: cmd 0x0c)
+    LcdImageComplete { region: LcdRegion, image: ImageSlab },
+    /// Update Full Screen Image (Module 15/32: cmd 0x08)
+    FullScreenImageChunk,
+    /// Update Boot Logo (Module 15/32: cmd 0x09, Module 6 uses Feature combo)
+    BootLogoImageChunk,
+    /// Output report not recognized/unsupported for current device
+    Unhandled,
+    /// Output report recognized but malformed or out of sequence
+    Error(ProtocolError),
+}
+
+/// Protocol-specific image processing result
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum ImageProcessResult {
+    /// Image processing complete, ready to display
+    Complete { key_id: u8, image: ImageSlab },
+    /// More packets needed to complete image
+    Incomplete,
+    /// Error processing image
+    Error(ProtocolError),
+}
+
+/// Button mapping result for different devices
+#[derive(Debug)]
+pub struct ButtonMapping {
+    pub mapped_buttons: [bool; 32], // Max buttons supported (XL has 32)
+    pub active_count: usize,
+}
+
+/// Protocol handler trait for different StreamDeck versions
+pub trait ProtocolHandlerTrait {
+    /// Get protocol version
+    fn version(&self) -> ProtocolVersion;
+
+    /// Parse an Output Report (host -> device)
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult;
+
+    /// Map physical button layout to protocol button order
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping;
+
+    /// Report structure used to generate the HID report descriptor
+    fn report_layout(&self, key_count: usize) -> ReportLayout;
+
+    /// Get input report format size
+    fn input_report_size(&self, button_count: usize) -> usize;
+
+    /// Format button state into input report
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize;
+
+    /// Process feature report commands
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand>;
+
+    /// Build feature GET report. Default is unhandled.
+    fn get_feature_report(
+        &mut self,
+        _report_id: u8,
+        _unit: &UnitInfo,
+        _buf: &mut [u8],
+    ) -> Option<usize> {
+        None
+    }
+
+    /// Drop images still being received; called on host reset. Default:
+    /// nothing is buffered.
+    fn reset(&mut self) {}
+}
+
+// Legacy ProtocolCommand has been unified into ModuleSetCommand/ModuleGetCommand.
+
+/// Enum-based protocol handler for no_std environment
+#[derive(Debug)]
+pub enum ProtocolHandler {
+    #[cfg(feature = "proto-v1")]
+    V1(v1::V1Handler),
+    #[cfg(feature = "proto-v2")]
+    V2(v2::V2Handler),
+    #[cfg(feature = "proto-v2")]
+    Plus(plus::PlusHandler),
+    #[cfg(feature = "proto-module")]
+    Module6Keys(module_6::Module6KeysHandler),
+    #[cfg(feature = "proto-module")]
+    Module15_32Keys(module_15_32::Module15_32KeysHandler),
+}
+
+impl ProtocolHandler {
+    /// Create appropriate protocol handler based on version
+    pub fn create(version: ProtocolVersion) -> Self {
+        match version {
+            #[cfg(feature = "proto-v1")]
+            ProtocolVersion::V1 => ProtocolHandler::V1(v1::V1Handler::new()),
+            #[cfg(feature = "proto-v2")]
+            ProtocolVersion::V2 => ProtocolHandler::V2(v2::V2Handler::new()),
+            #[cfg(feature = "proto-v2")]
+            ProtocolVersion::Plus => ProtocolHandler::Plus(plus::PlusHandler::new()),
+            #[cfg(feature = "proto-module")]
+            ProtocolVersion::Module6Keys => {
+                ProtocolHandler::Module6Keys(module_6::Module6KeysHandler::new())
+            }
+            #[cfg(feature = "proto-module")]
+            ProtocolVersion::Module15_32Keys => {
+                ProtocolHandler::Module15_32Keys(module_15_32::Module15_32KeysHandler::new())
+            }
+        }
+    }
+
+    /// Create the protocol handler for a device; models that share a
+    /// protocol but answer differently (Module 15 vs 32) get their own state
+    pub fn for_device(device: Device) -> Self {
+        match device {
+            #[cfg(feature = "proto-module")]
+            Device::Module32Keys => {
+                ProtocolHandler::Module15_32Keys(module_15_32::Module15_32KeysHandler::with_model(
+                    module_15_32::ModuleModel::Module32,
+                ))
+            }
+            _ => Self::create(device.usb_config().protocol),
+        }
+    }
+
+    /// Get protocol version
+    pub fn version(&self) -> ProtocolVersion {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(_) => ProtocolVersion::V1,
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(_) => ProtocolVersion::V2,
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(_) => ProtocolVersion::Plus,
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(_) => ProtocolVersion::Module6Keys,
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(_) => ProtocolVersion::Module15_32Keys,
+        }
+    }
+
+    /// Parse Output Report (host -> device)
+    pub fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.parse_output_report(data),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.parse_output_report(data),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.parse_output_report(data),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.parse_output_report(data),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => handler.parse_output_report(data),
+        }
+    }
+
+    /// Map physical button layout to protocol button order
+    pub fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => {
+                handler.map_buttons(physical_buttons, cols, rows, left_to_right)
+            }
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => {
+                handler.map_buttons(physical_buttons, cols, rows, left_to_right)
+            }
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => {
+                handler.map_buttons(physical_buttons, cols, rows, left_to_right)
+            }
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => {
+                handler.map_buttons(physical_buttons, cols, rows, left_to_right)
+            }
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => {
+                handler.map_buttons(physical_buttons, cols, rows, left_to_right)
+            }
+        }
+    }
+
+    /// Reports the protocol sends and accepts for a device with `key_count` keys
+    pub fn report_layout(&self, key_count: usize) -> ReportLayout {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.report_layout(key_count),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.report_layout(key_count),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.report_layout(key_count),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.report_layout(key_count),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => handler.report_layout(key_count),
+        }
+    }
+
+    /// Generate the HID report descriptor for a device with `key_count` keys
+    pub fn hid_descriptor(&self, key_count: usize) -> ReportDescriptor {
+        descriptor::build(
+            &self.report_layout(key_count),
+            feature::table_for(self.version()),
+        )
+    }
+
+    /// Get input report format size
+    pub fn input_report_size(&self, button_count: usize) -> usize {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.input_report_size(button_count),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.input_report_size(button_count),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.input_report_size(button_count),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.input_report_size(button_count),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => handler.input_report_size(button_count),
+        }
+    }
+
+    /// Format button state into input report
+    pub fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.format_button_report(buttons, report),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.format_button_report(buttons, report),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.format_button_report(buttons, report),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.format_button_report(buttons, report),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => {
+                handler.format_button_report(buttons, report)
+            }
+        }
+    }
+
+    /// Process feature report commands
+    pub fn handle_feature_report(
+        &mut self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Option<ModuleSetCommand> {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.handle_feature_report(report_id, data),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.handle_feature_report(report_id, data),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.handle_feature_report(report_id, data),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.handle_feature_report(report_id, data),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => {
+                handler.handle_feature_report(report_id, data)
+            }
+        }
+    }
+
+    /// Delegate feature GET report building to the specific handler
+    pub fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.get_feature_report(report_id, unit, buf),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.get_feature_report(report_id, unit, buf),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.get_feature_report(report_id, unit, buf),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => {
+                handler.get_feature_report(report_id, unit, buf)
+            }
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => {
+                handler.get_feature_report(report_id, unit, buf)
+            }
+        }
+    }
+
+    /// Drop images still being received (host reset)
+    pub fn reset(&mut self) {
+        match self {
+            #[cfg(feature = "proto-v1")]
+            ProtocolHandler::V1(handler) => handler.reset(),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::V2(handler) => handler.reset(),
+            #[cfg(feature = "proto-v2")]
+            ProtocolHandler::Plus(handler) => handler.reset(),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module6Keys(handler) => handler.reset(),
+            #[cfg(feature = "proto-module")]
+            ProtocolHandler::Module15_32Keys(handler) => handler.reset(),
+        }
+    }
+}
+
+/// Image format utilities
+pub mod image {
+    use super::*;
+
+    /// Convert RGB888 to RGB565 for display
+    pub fn rgb888_to_rgb565(rgb888: &[u8]) -> Vec<u8, 2048> {
+        let mut rgb565_data = Vec::new();
+
+        for chunk in rgb888.chunks_exact(3) {
+            if let [r, g, b] = chunk {
+                let r5 = (r >> 3) as u16;
+                let g6 = (g >> 2) as u16;
+                let b5 = (b >> 3) as u16;
+
+                let rgb565 = (r5 << 11) | (g6 << 5) | b5;
+
+                // Store as big-endian for display
+                let _ = rgb565_data.push((rgb565 >> 8) as u8);
+                let _ = rgb565_data.push((rgb565 & 0xFF) as u8);
+            }
+        }
+
+        rgb565_data
+    }
+
+    /// Where pixel (`x`, `y`) of a `size`-square key image lands once the
+    /// image is turned clockwise by `rotation`
+    pub const fn rotate_point(
+        size: usize,
+        rotation: Rotation,
+        x: usize,
+        y: usize,
+    ) -> (usize, usize) {
+        match rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (size - 1 - y, x),
+            Rotation::Deg180 => (size - 1 - x, size - 1 - y),
+            Rotation::Deg270 => (y, size - 1 - x),
+        }
+    }
+
+    /// Flip image horizontally
+    pub fn flip_horizontal(
+        image_data: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Vec<u8, IMAGE_BUFFER_SIZE> {
+        let mut flipped = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let src_x = width - 1 - x;
+                let src_idx = (y * width + src_x) * 3;
+
+                if src_idx + 2 < image_data.len() {
+                    let _ = flipped.push(image_data[src_idx]); // R
+                    let _ = flipped.push(image_data[src_idx + 1]); // G
+                    let _ = flipped.push(image_data[src_idx + 2]); // B
+                }
+            }
+        }
+
+        flipped
+    }
+
+    /// Flip image vertically  
+    pub fn flip_vertical(
+        image_data: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Vec<u8, IMAGE_BUFFER_SIZE> {
+        let mut flipped = Vec::new();
+
+        for y in 0..height {
+            let src_y = height - 1 - y;
+            for x in 0..width {
+                let src_idx = (src_y * width + x) * 3;
+
+                if src_idx + 2 < image_data.len() {
+                    let _ = flipped.push(image_data[src_idx]); // R
+                    let _ = flipped.push(image_data[src_idx + 1]); // G
+                    let _ = flipped.push(image_data[src_idx + 2]); // B
+                }
+            }
+        }
+
+        flipped
+    }
+}
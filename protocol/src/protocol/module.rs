@@ -1,11 +1,15 @@
-#[derive(Debug, Clone, Copy, PartialEq, defmt::Format)]
+use crate::types::Screen;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FirmwareType {
     LD,  // ?
     AP2, // Primary Firmware
     AP1, // Backup Firmware
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, defmt::Format)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ModuleSetCommand {
     Reset,
     ShowLogo,
@@ -14,12 +18,16 @@ pub enum ModuleSetCommand {
     SetIdleTime { seconds: i32 },
     SetKeyColor { key_index: u8, r: u8, g: u8, b: u8 }, // Module 15/32 only
     ShowBackgroundByIndex { index: u8 },                // Module 15/32 only
+    SetScreenSleep { screen: Screen, sleep: bool },     // Plus/Neo only
+    SetScreenBrightness { screen: Screen, value: u8 },  // Plus/Neo only
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, defmt::Format)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ModuleGetCommand {
     GetFirmwareVersion(FirmwareType),
     GetUnitSerialNumber,
     GetIdleTime,
+    GetBrightness,
     GetUnitInformation, // Module 15/32 only
 }
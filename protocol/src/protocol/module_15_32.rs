@@ -0,0 +1,268 @@
+//! StreamDeck Module HID Protocol Handler (15/32 keys)
+//!
+//! Unified handler for Module 15 and Module 32 per Elgato HID API.
+//! Reference: https://docs.elgato.com/streamdeck/hid/module-15_32
+
+use super::descriptor::ReportLayout;
+use super::feature::UnitInfo;
+use super::{feature, ButtonMapping, ProtocolHandlerTrait};
+use crate::device::{Device, DeviceConfig, ProtocolVersion};
+use crate::error::ProtocolError;
+use crate::protocol::module::{FirmwareType, ModuleGetCommand, ModuleSetCommand};
+use crate::protocol::OutputReportResult;
+
+/// Length of the Get Unit Information reply, report ID included
+const UNIT_INFORMATION_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModuleModel {
+    Module15,
+    Module32,
+}
+
+impl ModuleModel {
+    /// Device profile of the model
+    fn device(self) -> Device {
+        match self {
+            ModuleModel::Module15 => Device::Module15Keys,
+            ModuleModel::Module32 => Device::Module32Keys,
+        }
+    }
+
+    /// Full-screen LCD resolution behind the keys
+    fn lcd_size(self) -> (u16, u16) {
+        match self {
+            ModuleModel::Module15 => (480, 272),
+            ModuleModel::Module32 => (1024, 600),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Module15_32KeysHandler {
+    model: ModuleModel,
+}
+
+impl Module15_32KeysHandler {
+    pub fn new() -> Self {
+        Self {
+            model: ModuleModel::Module15,
+        }
+    }
+    pub fn with_model(model: ModuleModel) -> Self {
+        Self { model }
+    }
+
+    fn parse_module_set_command(&self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        match report_id {
+            // Set Backlight Brightness (Feature report ID 0x03, Command 0x08)
+            0x03 => {
+                if data.len() >= 3 && data[1] == 0x08 {
+                    Some(ModuleSetCommand::SetBrightness { value: data[2] })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_module_get_command(&self, report_id: u8) -> Option<ModuleGetCommand> {
+        Some(match report_id {
+            0x03 => ModuleGetCommand::GetBrightness,
+            0x04 => ModuleGetCommand::GetFirmwareVersion(FirmwareType::LD),
+            0x05 => ModuleGetCommand::GetFirmwareVersion(FirmwareType::AP2),
+            0x06 => ModuleGetCommand::GetUnitSerialNumber,
+            0x07 => ModuleGetCommand::GetFirmwareVersion(FirmwareType::AP1),
+            0x08 => ModuleGetCommand::GetUnitInformation,
+            0x0A => ModuleGetCommand::GetIdleTime,
+            _ => return None,
+        })
+    }
+
+    /// Feature Report - Get Unit Information (Report ID 0x08)
+    ///
+    /// `[0x08, rows, cols, key width (u16 LE), key height (u16 LE),
+    ///   LCD width (u16 LE), LCD height (u16 LE), image bpp, color scheme,
+    ///   key gallery images, LCD gallery images, demo frames, reserved]`
+    fn get_unit_information(&self, buf: &mut [u8]) -> usize {
+        let total_len = UNIT_INFORMATION_LEN.min(buf.len());
+        buf.iter_mut().take(total_len).for_each(|b| *b = 0);
+
+        let device = self.model.device();
+        let layout = device.button_layout();
+        let display = device.display_config();
+        let (lcd_w, lcd_h) = self.model.lcd_size();
+
+        let mut info = [0u8; 17];
+        info[0] = 0x08;
+        info[1] = layout.rows as u8;
+        info[2] = layout.cols as u8;
+        info[3..5].copy_from_slice(&(display.image_width as u16).to_le_bytes());
+        info[5..7].copy_from_slice(&(display.image_height as u16).to_le_bytes());
+        info[7..9].copy_from_slice(&lcd_w.to_le_bytes());
+        info[9..11].copy_from_slice(&lcd_h.to_le_bytes());
+        info[11] = 24; // Image BPP (RGB888 JPEG)
+        info[12] = 0x00; // Image color scheme: RGB
+                         // No image galleries or demo frames are stored on the device
+        info[13] = 0x00;
+        info[14] = 0x00;
+        info[15] = 0x00;
+        info[16] = 0x00; // Reserved
+
+        let len = info.len().min(total_len);
+        buf[..len].copy_from_slice(&info[..len]);
+        total_len
+    }
+}
+
+impl Default for Module15_32KeysHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolHandlerTrait for Module15_32KeysHandler {
+    fn version(&self) -> ProtocolVersion {
+        ProtocolVersion::Module15_32Keys
+    }
+
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        if data.len() < 8 {
+            return OutputReportResult::Error(ProtocolError::PacketTooShort);
+        }
+        let report_id = data[0];
+        let command = data[1];
+
+        match report_id {
+            0x02 => {
+                match command {
+                    0x07 => {
+                        // Update key Image
+                        let _key_index = data[2];
+                        let _transfer_done = data[3];
+                        let _chunk_content = u16::from_le_bytes([data[4], data[5]]);
+                        let _chunk_index = u16::from_le_bytes([data[6], data[7]]);
+                        let _chunk_data = &data[8..];
+                        OutputReportResult::Unhandled
+                    }
+                    0x08 => {
+                        // Update Full Screen Image
+                        let _key_index = data[2];
+                        let _transfer_done = data[3];
+                        let _chunk_content = u16::from_le_bytes([data[4], data[5]]);
+                        let _chunk_index = u16::from_le_bytes([data[6], data[7]]);
+                        let _chunk_data = &data[8..];
+                        OutputReportResult::Unhandled
+                    }
+                    0x09 => {
+                        // Update Boot Logo
+                        let _reserved = data[2];
+                        let _transfer_done = data[3];
+                        let _chunk_index = u16::from_le_bytes([data[4], data[5]]);
+                        let _chunk_contents_size = u16::from_le_bytes([data[6], data[7]]);
+                        let _chunk_data = &data[8..];
+                        OutputReportResult::Unhandled
+                    }
+                    0x0D => {
+                        // Update Background
+                        let _background_index = data[2];
+                        let _transfer_done = data[3];
+                        let _chunk_index = u16::from_le_bytes([data[4], data[5]]);
+                        let _chunk_contents_size = u16::from_le_bytes([data[6], data[7]]);
+                        let _chunk_data = &data[8..];
+                        OutputReportResult::Unhandled
+                    }
+                    _ => OutputReportResult::Unhandled,
+                }
+            }
+            _ => OutputReportResult::Unhandled,
+        }
+    }
+
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        let max = match self.model {
+            ModuleModel::Module15 => 15,
+            ModuleModel::Module32 => 32,
+        };
+        let mut mapped = [false; 32];
+        for y in 0..rows {
+            for x in 0..cols {
+                let src_index = if left_to_right {
+                    y * cols + x
+                } else {
+                    y * cols + (cols - 1 - x)
+                };
+                let dst_index = y * cols + x;
+                if src_index < physical_buttons.len() && dst_index < max {
+                    mapped[dst_index] = physical_buttons[src_index];
+                }
+            }
+        }
+        ButtonMapping {
+            mapped_buttons: mapped,
+            active_count: max,
+        }
+    }
+
+    fn report_layout(&self, key_count: usize) -> ReportLayout {
+        ReportLayout {
+            key_count,
+            // 512-byte input report including the report ID
+            input_count: 511,
+            output_count: 1023,
+            feature_ids: &[0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x0A],
+            feature_count: 16,
+        }
+    }
+
+    fn input_report_size(&self, _button_count: usize) -> usize {
+        512
+    }
+
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        // Report ID 0x01, Command 0x00, Length = number of keys, then states
+        let max_keys = match self.model {
+            ModuleModel::Module15 => 15,
+            ModuleModel::Module32 => 32,
+        };
+        let used = core::cmp::min(max_keys, buttons.mapped_buttons.len());
+        let needed = 4 + used;
+        if report.len() < needed {
+            return 0;
+        }
+        report[0] = 0x01; // Report ID
+        report[1] = 0x00; // Command: key state change
+        report[2] = used as u8; // length LSB (fits within u8 for 32)
+        report[3] = 0x00; // length MSB
+        for i in 0..used {
+            report[4 + i] = if buttons.mapped_buttons[i] { 1 } else { 0 };
+        }
+        needed
+    }
+
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        if let Some(cmd) = self.parse_module_set_command(report_id, data) {
+            return Some(cmd);
+        }
+        None
+    }
+
+    fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        match self.parse_module_get_command(report_id)? {
+            ModuleGetCommand::GetUnitInformation => Some(self.get_unit_information(buf)),
+            _ => feature::build(feature::MODULE15_32_FEATURES, report_id, unit, buf),
+        }
+    }
+}
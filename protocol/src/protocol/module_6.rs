@@ -0,0 +1,213 @@
+//! StreamDeck Module HID Protocol Handler (6keys)
+//!
+//! Implements the unified `ProtocolHandlerTrait` for the Elgato Stream Deck
+//! Modules per public HID API docs. Image upload parsing is stubbed until we
+//! confirm exact chunk layout from PCAPs.
+
+use super::descriptor::ReportLayout;
+use super::feature::UnitInfo;
+use super::{feature, ButtonMapping, OutputReportResult, ProtocolHandlerTrait};
+use crate::device::ProtocolVersion;
+use crate::error::ProtocolError;
+use crate::protocol::module::ModuleSetCommand;
+
+#[derive(Debug)]
+pub struct Module6KeysHandler {}
+
+impl Module6KeysHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for Module6KeysHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Module6KeysHandler {
+    fn parse_module_set_command(&self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        match report_id {
+            0x05 => {
+                // Payload excludes Report ID. Per spec:
+                // [Command=0x55, 0xAA, 0xD1, 0x01, Brightness]
+                if data.len() >= 5
+                    && data[0] == 0x55
+                    && data[1] == 0xAA
+                    && data[2] == 0xD1
+                    && data[3] == 0x01
+                {
+                    Some(ModuleSetCommand::SetBrightness { value: data[4] })
+                } else {
+                    None
+                }
+            }
+            0x0B => {
+                // Payload excludes Report ID.
+                // Commands at data[0]
+                if !data.is_empty() {
+                    match data[0] {
+                        0x63 => {
+                            // data[1]: 0x00 Show Logo, 0x02 Update Boot Logo
+                            if data.len() >= 2 {
+                                match data[1] {
+                                    0x00 => Some(ModuleSetCommand::ShowLogo),
+                                    0x02 => {
+                                        let slice = if data.len() >= 3 { data[2] } else { 0 };
+                                        Some(ModuleSetCommand::UpdateBootLogo { slice })
+                                    }
+                                    _ => None,
+                                }
+                            } else {
+                                None
+                            }
+                        }
+                        0xA2 => {
+                            // data[1..=4]: i32 seconds (LE)
+                            if data.len() >= 5 {
+                                let secs = i32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+                                Some(ModuleSetCommand::SetIdleTime { seconds: secs })
+                            } else {
+                                None
+                            }
+                        }
+                        _ => None,
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ProtocolHandlerTrait for Module6KeysHandler {
+    fn version(&self) -> ProtocolVersion {
+        ProtocolVersion::Module6Keys
+    }
+
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        if data.len() < 0x10 {
+            return OutputReportResult::Error(ProtocolError::PacketTooShort);
+        }
+        let report_id = data[0];
+        let command = data[1];
+
+        match report_id {
+            // https://docs.elgato.com/streamdeck/hid/module-6#upload-data-to-image-memory-bank
+            0x02 => {
+                if command == 0x01 {
+                    let _chunk_index = data[2];
+                    let _reserved = data[3];
+                    let _show_image_flag = data[4];
+                    let _key_index = data[5];
+                    let _reserved = &data[6..0x10];
+                    let _chunk_data = &data[0x10..];
+
+                    OutputReportResult::Unhandled
+                } else {
+                    OutputReportResult::Unhandled
+                }
+            }
+            _ => OutputReportResult::Unhandled,
+        }
+    }
+
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        let mut mapped = [false; 32];
+
+        for y in 0..rows {
+            for x in 0..cols {
+                let src_index = if left_to_right {
+                    y * cols + x
+                } else {
+                    y * cols + (cols - 1 - x)
+                };
+                let dst_index = y * cols + x;
+                if src_index < physical_buttons.len() && dst_index < 32 {
+                    mapped[dst_index] = physical_buttons[src_index];
+                }
+            }
+        }
+        ButtonMapping {
+            mapped_buttons: mapped,
+            active_count: 6,
+        }
+    }
+
+    fn report_layout(&self, key_count: usize) -> ReportLayout {
+        ReportLayout {
+            key_count,
+            // Fixed 64-byte input report including the report ID
+            input_count: 63,
+            output_count: 1023,
+            feature_ids: &[0x03, 0x04, 0x05, 0x07, 0x08, 0x0B, 0xA0, 0xA1, 0xA2, 0xA3],
+            feature_count: 16,
+        }
+    }
+
+    fn input_report_size(&self, _button_count: usize) -> usize {
+        64
+    }
+
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        // 64 bytes total per packet: Report ID (1) + 63 data bytes
+        const MAX_USB_SIZE: usize = 64;
+
+        if report.len() < MAX_USB_SIZE {
+            return 0;
+        }
+
+        // Set Report ID
+        report[0] = 0x01;
+
+        // Map up to 63 data bytes; Module 6 needs first 6
+        let button_count = core::cmp::min(6, buttons.mapped_buttons.len());
+        for i in 0..button_count {
+            report[1 + i] = if buttons.mapped_buttons[i] { 1 } else { 0 };
+        }
+
+        // Zero out remaining bytes in the USB packet
+        report
+            .iter_mut()
+            .take(MAX_USB_SIZE)
+            .skip(1 + button_count)
+            .for_each(|b| *b = 0);
+
+        MAX_USB_SIZE
+    }
+
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        if let Some(cmd) = self.parse_module_set_command(report_id, data) {
+            return Some(cmd);
+        }
+        None
+    }
+
+    fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        self.get_feature_report_bytes(report_id, unit, buf)
+    }
+}
+
+impl Module6KeysHandler {
+    pub fn get_feature_report_bytes(
+        &self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        feature::build(feature::MODULE6_FEATURES, report_id, unit, buf)
+    }
+}
@@ -0,0 +1,305 @@
+//! StreamDeck Plus protocol handler
+//!
+//! The Plus uses the V2 key image protocol, plus two additions:
+//!
+//! - Output report `0x02 0x0c` writes an image into a window of the touch
+//!   strip LCD:
+//!   `[0x02, 0x0c, x (u16 LE), y (u16 LE), width (u16 LE), height (u16 LE),
+//!     is_last, page (u16 LE), len (u16 LE), 0x00, data...]`
+//! - Input report 0x01 carries an event type after the report ID, so keys,
+//!   dials and the touch strip each get their own report layout:
+//!
+//! - keys:  `[0x01, 0x00, key_count, 0x00, key states...]`
+//! - touch: `[0x01, 0x02, 0x0E, 0x00, kind, 0x00, x (u16 LE), y (u16 LE), x_end (u16 LE), y_end (u16 LE)]`
+//! - dials: `[0x01, 0x03, 1 + dials, 0x00, 0x00 (press) | 0x01 (turn), value per dial...]`
+//!
+//! The Studio uses the same handler: V2 key images and these input reports,
+//! with two dials and no touch strip.
+//!
+//! Newer Elgato software also addresses the key grid and the strip as
+//! separate screens through V2 feature sub-commands: screen sleep/wake
+//! (`0x03 0x0A`) and per-screen brightness (`0x03 0x0B`).
+
+use super::descriptor::ReportLayout;
+use super::feature::UnitInfo;
+use super::v2::V2Handler;
+use super::{ButtonMapping, OutputReportResult, ProtocolHandlerTrait};
+use crate::config::{
+    FEATURE_REPORT_V2_COMMANDS, OUTPUT_REPORT_IMAGE, PLUS_LCD_HEIGHT, PLUS_LCD_WIDTH,
+    V2_COMMAND_SCREEN_BRIGHTNESS, V2_COMMAND_SCREEN_SLEEP,
+};
+use crate::device::ProtocolVersion;
+use crate::error::ProtocolError;
+use crate::image_pool::ImageSlab;
+use crate::protocol::module::ModuleSetCommand;
+use crate::types::{EncoderEvent, LcdRegion, Screen, TouchEvent, PLUS_DIAL_COUNT};
+
+/// Output report command: touch strip LCD image
+pub const LCD_COMMAND: u8 = 0x0C;
+/// LCD image header length after the report ID
+const LCD_HEADER_SIZE: usize = 15;
+
+/// Input report ID
+const REPORT_ID: u8 = 0x01;
+
+/// Event type: key state change
+pub const EVENT_KEYS: u8 = 0x00;
+/// Event type: touch strip
+pub const EVENT_TOUCH: u8 = 0x02;
+/// Event type: dial press or turn
+pub const EVENT_DIAL: u8 = 0x03;
+
+/// Dial event subtype: press states
+const DIAL_PRESS: u8 = 0x00;
+/// Dial event subtype: rotation deltas
+const DIAL_TURN: u8 = 0x01;
+
+/// Touch event payload length
+const TOUCH_PAYLOAD_LEN: u8 = 0x0E;
+
+/// Touch report length including the report ID
+pub const TOUCH_REPORT_SIZE: usize = 14;
+/// Longest dial report (Plus) including the report ID
+pub const DIAL_REPORT_SIZE: usize = 5 + PLUS_DIAL_COUNT;
+
+/// Format a key event report. Returns the report length.
+pub fn format_key_report(buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+    if report.len() < 4 {
+        return 0;
+    }
+    let key_count = buttons.active_count.min(report.len() - 4);
+
+    report[0] = REPORT_ID;
+    report[1] = EVENT_KEYS;
+    report[2] = key_count as u8;
+    report[3] = 0x00;
+    for (out, &pressed) in report[4..4 + key_count]
+        .iter_mut()
+        .zip(buttons.mapped_buttons.iter())
+    {
+        *out = pressed as u8;
+    }
+    4 + key_count
+}
+
+/// Format a touch strip event report. Returns the report length.
+pub fn format_touch_report(event: &TouchEvent, report: &mut [u8]) -> usize {
+    if report.len() < TOUCH_REPORT_SIZE {
+        return 0;
+    }
+    report[..TOUCH_REPORT_SIZE].fill(0);
+    report[0] = REPORT_ID;
+    report[1] = EVENT_TOUCH;
+    report[2] = TOUCH_PAYLOAD_LEN;
+    report[4] = event.kind as u8;
+    report[6..8].copy_from_slice(&event.x.to_le_bytes());
+    report[8..10].copy_from_slice(&event.y.to_le_bytes());
+    report[10..12].copy_from_slice(&event.x_end.to_le_bytes());
+    report[12..14].copy_from_slice(&event.y_end.to_le_bytes());
+    TOUCH_REPORT_SIZE
+}
+
+/// Format a dial event report for a model with `dials` dials. Returns the
+/// report length.
+pub fn format_encoder_report(event: &EncoderEvent, dials: usize, report: &mut [u8]) -> usize {
+    let dials = dials.min(PLUS_DIAL_COUNT);
+    let len = 5 + dials;
+    if report.len() < len {
+        return 0;
+    }
+    report[0] = REPORT_ID;
+    report[1] = EVENT_DIAL;
+    // Payload: subtype + one byte per dial
+    report[2] = 1 + dials as u8;
+    report[3] = 0x00;
+    let (sub_type, values) = report[4..len].split_at_mut(1);
+    match event {
+        EncoderEvent::Press(pressed) => {
+            sub_type[0] = DIAL_PRESS;
+            for (out, &p) in values.iter_mut().zip(pressed.iter()) {
+                *out = p as u8;
+            }
+        }
+        EncoderEvent::Turn(deltas) => {
+            sub_type[0] = DIAL_TURN;
+            for (out, &d) in values.iter_mut().zip(deltas.iter()) {
+                // Two's complement: negative is counter-clockwise
+                *out = d as u8;
+            }
+        }
+    }
+    len
+}
+
+/// Protocol handler for the StreamDeck Plus and Studio
+#[derive(Debug)]
+pub struct PlusHandler {
+    /// Key images, reports and feature reports follow V2
+    keys: V2Handler,
+    /// LCD image being received, if any
+    lcd_image: Option<ImageSlab>,
+    lcd_region: LcdRegion,
+    expected_page: u16,
+}
+
+impl PlusHandler {
+    pub fn new() -> Self {
+        Self {
+            keys: V2Handler::new(),
+            lcd_image: None,
+            lcd_region: LcdRegion::default(),
+            expected_page: 0,
+        }
+    }
+
+    /// Reset LCD image reception state
+    fn reset_lcd_state(&mut self) {
+        self.lcd_image = None;
+        self.expected_page = 0;
+    }
+
+    /// Parse one page of an LCD image. `header` starts after the command byte.
+    fn parse_lcd_report(&mut self, header: &[u8]) -> OutputReportResult {
+        if header.len() < LCD_HEADER_SIZE - 1 {
+            return OutputReportResult::Error(ProtocolError::PacketTooShort);
+        }
+
+        let field = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        let region = LcdRegion {
+            x: field(0),
+            y: field(2),
+            width: field(4),
+            height: field(6),
+        };
+        let is_last = header[8] != 0;
+        let page = field(9);
+        let payload_len = field(11) as usize;
+        let data = &header[LCD_HEADER_SIZE - 1..];
+
+        // First page starts image reception
+        if page == 0 {
+            self.reset_lcd_state();
+            if region.width == 0
+                || region.height == 0
+                || region.x as u32 + region.width as u32 > PLUS_LCD_WIDTH as u32
+                || region.y as u32 + region.height as u32 > PLUS_LCD_HEIGHT as u32
+            {
+                return OutputReportResult::Error(ProtocolError::InvalidPayload);
+            }
+            let Some(image) = ImageSlab::alloc() else {
+                return OutputReportResult::Error(ProtocolError::NoImageBuffer);
+            };
+            self.lcd_image = Some(image);
+            self.lcd_region = region;
+        }
+
+        let mut image = match self.lcd_image.take() {
+            Some(image) if region == self.lcd_region && page == self.expected_page => image,
+            _ => {
+                self.reset_lcd_state();
+                return OutputReportResult::Error(ProtocolError::SequenceError);
+            }
+        };
+
+        let copy_len = payload_len.min(data.len());
+        if let Err(e) = image.extend_from_slice(&data[..copy_len]) {
+            self.reset_lcd_state();
+            return OutputReportResult::Error(e);
+        }
+
+        if !is_last {
+            self.lcd_image = Some(image);
+            self.expected_page += 1;
+            return OutputReportResult::Unhandled;
+        }
+
+        self.reset_lcd_state();
+        OutputReportResult::LcdImageComplete { region, image }
+    }
+}
+
+impl Default for PlusHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolHandlerTrait for PlusHandler {
+    fn version(&self) -> ProtocolVersion {
+        ProtocolVersion::Plus
+    }
+
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        // Accept the LCD command with or without the report ID
+        match data {
+            [OUTPUT_REPORT_IMAGE, LCD_COMMAND, header @ ..] | [LCD_COMMAND, header @ ..] => {
+                self.parse_lcd_report(header)
+            }
+            _ => self.keys.parse_output_report(data),
+        }
+    }
+
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        self.keys
+            .map_buttons(physical_buttons, cols, rows, left_to_right)
+    }
+
+    fn report_layout(&self, key_count: usize) -> ReportLayout {
+        ReportLayout {
+            // The touch report is the longest input report
+            input_count: (3 + key_count).max(TOUCH_REPORT_SIZE - 1),
+            ..self.keys.report_layout(key_count)
+        }
+    }
+
+    fn input_report_size(&self, button_count: usize) -> usize {
+        // 4-byte event header + button states
+        4 + button_count
+    }
+
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        format_key_report(buttons, report)
+    }
+
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        if report_id == FEATURE_REPORT_V2_COMMANDS {
+            // Per-screen commands: [0x03, command, screen, value, ...]
+            match data {
+                [_, V2_COMMAND_SCREEN_SLEEP, screen, state, ..] => {
+                    return Some(ModuleSetCommand::SetScreenSleep {
+                        screen: Screen::from_u8(*screen)?,
+                        sleep: *state != 0,
+                    });
+                }
+                [_, V2_COMMAND_SCREEN_BRIGHTNESS, screen, value, ..] => {
+                    return Some(ModuleSetCommand::SetScreenBrightness {
+                        screen: Screen::from_u8(*screen)?,
+                        value: (*value).min(100),
+                    });
+                }
+                _ => {}
+            }
+        }
+        self.keys.handle_feature_report(report_id, data)
+    }
+
+    fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        self.keys.get_feature_report(report_id, unit, buf)
+    }
+
+    fn reset(&mut self) {
+        self.keys.reset();
+        self.reset_lcd_state();
+    }
+}
@@ -0,0 +1,308 @@
+//! StreamDeck V1 Protocol Handler
+//!
+//! Handles Original, Mini, and Revised Mini devices using BMP format
+
+use super::assembly::AssemblyContexts;
+use super::descriptor::ReportLayout;
+use super::feature::UnitInfo;
+use super::{feature, ButtonMapping, OutputReportResult, ProtocolHandlerTrait};
+use crate::config::{
+    FEATURE_REPORT_BRIGHTNESS_V1, STREAMDECK_BRIGHTNESS_RESET_MAGIC, STREAMDECK_MAGIC_1,
+    STREAMDECK_MAGIC_2, STREAMDECK_MAGIC_3, STREAMDECK_RESET_MAGIC,
+};
+use crate::device::ProtocolVersion;
+use crate::error::ProtocolError;
+use crate::protocol::module::ModuleSetCommand;
+
+/// Size of a BMP file header, which carries the total file size
+const BMP_FILE_HEADER_SIZE: usize = 6;
+
+/// V1 Protocol Handler for BMP-based StreamDeck devices
+///
+/// Images arrive as a series of pages. Page numbers are consecutive (starting
+/// at 0 on the Mini, 1 on the Original), byte 4 flags the last page, and the
+/// BMP header in the first page gives the total image length so zero padding
+/// in the final report is discarded. Uploads to different keys may be
+/// interleaved; each key is reassembled in its own context.
+///
+/// With `streaming-decode` pages are passed on as `KeyImageChunk`s instead
+/// of being buffered, one image at a time, and the display writes each one
+/// straight to the key's panel window; no whole-image buffer is needed.
+#[derive(Debug)]
+pub struct V1Handler {
+    /// Images being received, one context per key
+    assemblies: AssemblyContexts,
+    /// Image being streamed, if any
+    streaming: bool,
+    /// Image bytes streamed so far
+    received: usize,
+    /// Total image length of the streamed image, when it has a BMP header
+    file_size: Option<usize>,
+    expected_key: u8,
+    expected_packet: u8,
+}
+
+impl V1Handler {
+    pub fn new() -> Self {
+        Self {
+            assemblies: AssemblyContexts::new(),
+            streaming: false,
+            received: 0,
+            file_size: None,
+            expected_key: 0,
+            expected_packet: 0,
+        }
+    }
+
+    /// Reset streamed image state
+    fn reset_image_state(&mut self) {
+        self.streaming = false;
+        self.received = 0;
+        self.file_size = None;
+        self.expected_key = 0;
+        self.expected_packet = 0;
+    }
+
+    /// Total image length from the BMP file header, once the first page is in
+    fn bmp_file_size(image: &[u8]) -> Option<usize> {
+        let header = image.get(..BMP_FILE_HEADER_SIZE)?;
+        if header[0] != b'B' || header[1] != b'M' {
+            return None;
+        }
+        Some(u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize)
+    }
+
+    /// Pass one page on for streaming, trimmed to the image's declared size
+    fn stream_page(
+        &mut self,
+        data: &[u8],
+        data_start: usize,
+        packet_num: u8,
+        is_last: bool,
+        key_id: u8,
+    ) -> OutputReportResult {
+        let continues_image =
+            self.streaming && key_id == self.expected_key && packet_num == self.expected_packet;
+        if !continues_image {
+            self.reset_image_state();
+            if packet_num > 1 {
+                // Continuation without a matching first page
+                return OutputReportResult::Error(ProtocolError::SequenceError);
+            }
+            // First page starts image reception
+            self.streaming = true;
+            self.expected_key = key_id;
+            self.file_size = Self::bmp_file_size(&data[data_start..]);
+        }
+
+        let offset = self.received;
+        let end = match self.file_size {
+            Some(size) => data.len().min(data_start + size.saturating_sub(offset)),
+            None => data.len(),
+        };
+        self.received += end - data_start;
+
+        let last = is_last || self.file_size.is_some_and(|size| self.received >= size);
+        if last {
+            self.reset_image_state();
+        } else {
+            self.expected_packet = packet_num.wrapping_add(1);
+        }
+
+        OutputReportResult::KeyImageChunk {
+            key_id,
+            offset,
+            payload: data_start..end,
+            last,
+        }
+    }
+}
+
+impl Default for V1Handler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolHandlerTrait for V1Handler {
+    fn version(&self) -> ProtocolVersion {
+        ProtocolVersion::V1
+    }
+
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        if data.len() < 8 {
+            return OutputReportResult::Error(ProtocolError::PacketTooShort);
+        }
+
+        // V1 Protocol format primary: [0x02, 0x01, packet_num, 0x00, is_last, key_id, 0x00, 0x00, image_data...]
+        // Accept variant where report ID (0x02) is stripped by HID stack: [0x01, packet_num, 0x00, is_last, key_id, 0x00, 0x00, data...]
+        let (packet_num, is_last, key_id, data_start) = if data[0] == 0x02 {
+            (data[2], data[4] != 0, data[5], 8)
+        } else if data[0] == 0x01 {
+            (data[1], data[3] != 0, data[4], 7)
+        } else {
+            return OutputReportResult::Unhandled;
+        };
+
+        if cfg!(feature = "streaming-decode") {
+            return self.stream_page(data, data_start, packet_num, is_last, key_id);
+        }
+
+        let page = packet_num as u16;
+        if packet_num <= 1 && !self.assemblies.expects(key_id, page) {
+            // First page starts image reception
+            if let Err(e) = self.assemblies.start(key_id, page) {
+                return OutputReportResult::Error(e);
+            }
+        }
+
+        // A continuation without a matching first page is a sequence error
+        if let Err(e) = self
+            .assemblies
+            .continue_with(key_id, page, &data[data_start..])
+        {
+            return OutputReportResult::Error(e);
+        }
+
+        // Complete on the last-page flag, or once the BMP's declared size is in
+        // (hosts that leave byte 4 zero)
+        let received = self.assemblies.received(key_id).unwrap_or(&[]);
+        let file_size = Self::bmp_file_size(received);
+        let have_all = file_size.is_some_and(|size| received.len() >= size);
+        if !is_last && !have_all {
+            return OutputReportResult::Unhandled;
+        }
+
+        let Some(mut image) = self.assemblies.finish(key_id) else {
+            return OutputReportResult::Error(ProtocolError::SequenceError);
+        };
+
+        // Drop padding after the image
+        if let Some(size) = file_size {
+            image.truncate(size);
+        }
+
+        OutputReportResult::KeyImageComplete { key_id, image }
+    }
+
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        let mut mapped_buttons = [false; 32];
+        let total_keys = cols * rows;
+
+        for (physical_idx, &pressed) in physical_buttons.iter().take(total_keys).enumerate() {
+            let mapped_idx = if left_to_right {
+                physical_idx // Direct mapping for Mini and Revised Mini
+            } else {
+                // Right-to-left mapping for Original StreamDeck
+                let row = physical_idx / cols;
+                let col = physical_idx % cols;
+                let reversed_col = cols - 1 - col;
+                row * cols + reversed_col
+            };
+
+            if mapped_idx < 32 {
+                mapped_buttons[mapped_idx] = pressed;
+            }
+        }
+
+        ButtonMapping {
+            mapped_buttons,
+            active_count: total_keys,
+        }
+    }
+
+    fn report_layout(&self, key_count: usize) -> ReportLayout {
+        ReportLayout {
+            key_count,
+            // One byte per key after the report ID
+            input_count: key_count,
+            output_count: 1023,
+            feature_ids: &[0x03, 0x04, 0x05, 0x07, 0x0B, 0xA0, 0xA1, 0xA2],
+            feature_count: 16,
+        }
+    }
+
+    fn input_report_size(&self, button_count: usize) -> usize {
+        // V1 input reports: Report ID (1 byte) + button states (RP2040 USB hardware limitation)
+        1 + button_count
+    }
+
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        if report.is_empty() {
+            return 0;
+        }
+
+        // V1 format: [0x01, button_states...]
+        report[0] = 0x01; // Report ID
+
+        let button_bytes = buttons.active_count.min(report.len() - 1);
+
+        // Write actual button states
+        for i in 0..button_bytes {
+            report[i + 1] = if buttons.mapped_buttons[i] { 1 } else { 0 };
+        }
+
+        // Fill remaining bytes with 0
+        for b in report.iter_mut().skip(button_bytes + 1) {
+            *b = 0;
+        }
+
+        1 + button_bytes
+    }
+
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        match report_id {
+            FEATURE_REPORT_BRIGHTNESS_V1 => {
+                // V1 Brightness/Reset: [0x05, 0x55, 0xAA, 0xD1, 0x01, value, ...]
+                if data.len() >= 6
+                    && data[1] == STREAMDECK_MAGIC_1
+                    && data[2] == STREAMDECK_MAGIC_2
+                    && data[3] == STREAMDECK_MAGIC_3
+                    && data[4] == 0x01
+                {
+                    if data[5] == STREAMDECK_BRIGHTNESS_RESET_MAGIC {
+                        Some(ModuleSetCommand::Reset)
+                    } else {
+                        Some(ModuleSetCommand::SetBrightness { value: data[5] })
+                    }
+                } else {
+                    None
+                }
+            }
+            // Handle both V1 Reset and Module Idle Time (both use report 0x0B)
+            0x0B => {
+                if data.len() >= 6 && data[1] == crate::config::IDLE_TIME_COMMAND {
+                    // Module Idle Time: [0x0B, 0xA2, seconds_le...]
+                    let secs = i32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+                    Some(ModuleSetCommand::SetIdleTime { seconds: secs })
+                } else if data.len() >= 2 && data[1] == STREAMDECK_RESET_MAGIC {
+                    // V1 Reset: [0x0B, 0x63, ...]
+                    Some(ModuleSetCommand::Reset)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        feature::build(feature::V1_FEATURES, report_id, unit, buf)
+    }
+
+    fn reset(&mut self) {
+        self.assemblies.clear();
+        self.reset_image_state();
+    }
+}
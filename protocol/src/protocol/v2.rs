@@ -0,0 +1,225 @@
+//! StreamDeck V2 Protocol Handler
+//!
+//! Handles Original V2, XL and MK2 devices using JPEG format. The Plus
+//! builds on this handler (see `plus`).
+
+use super::assembly::AssemblyContexts;
+use super::descriptor::ReportLayout;
+use super::feature::UnitInfo;
+use super::{feature, ButtonMapping, OutputReportResult, ProtocolHandlerTrait};
+use crate::config::{
+    IMAGE_COMMAND_V2, OUTPUT_REPORT_IMAGE, V2_COMMAND_BRIGHTNESS, V2_COMMAND_RESET,
+};
+use crate::device::ProtocolVersion;
+use crate::error::ProtocolError;
+use crate::protocol::module::ModuleSetCommand;
+
+/// V2 Protocol Handler for JPEG-based StreamDeck devices
+#[derive(Debug)]
+pub struct V2Handler {
+    /// Images being received, per key
+    assemblies: AssemblyContexts,
+}
+
+impl V2Handler {
+    pub fn new() -> Self {
+        Self {
+            assemblies: AssemblyContexts::new(),
+        }
+    }
+}
+
+impl Default for V2Handler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolHandlerTrait for V2Handler {
+    fn version(&self) -> ProtocolVersion {
+        ProtocolVersion::V2
+    }
+
+    fn parse_output_report(&mut self, data: &[u8]) -> OutputReportResult {
+        if data.len() < 8 {
+            return OutputReportResult::Error(ProtocolError::PacketTooShort);
+        }
+
+        // V2 Output Report: Command 0x07 (key), 0x08 (full LCD), 0x09 (boot logo)
+        // Key image format primary: [0x02, 0x07, key_id, is_last, len_lo, len_hi, seq_lo, seq_hi, data...]
+        // Some HID stacks strip the report ID before delivering data to set_report. Accept both forms.
+        let (cmd, key_id, is_last, payload_len, sequence, data_start) =
+            if data[0] == OUTPUT_REPORT_IMAGE {
+                let cmd = data[1];
+                if cmd == IMAGE_COMMAND_V2 {
+                    (
+                        cmd,
+                        data[2],
+                        data[3] != 0,
+                        u16::from_le_bytes([data[4], data[5]]),
+                        u16::from_le_bytes([data[6], data[7]]),
+                        8,
+                    )
+                } else {
+                    (cmd, 0, false, 0, 0, 0)
+                }
+            } else if data[0] == IMAGE_COMMAND_V2 && data.len() >= 7 {
+                // Missing report ID (0x02) case for 0x07
+                (
+                    IMAGE_COMMAND_V2,
+                    data[1],
+                    data[2] != 0,
+                    u16::from_le_bytes([data[3], data[4]]),
+                    u16::from_le_bytes([data[5], data[6]]),
+                    7,
+                )
+            } else {
+                return OutputReportResult::Unhandled;
+            };
+
+        if cmd != IMAGE_COMMAND_V2 {
+            // For now, only branch key updates. Full screen / boot logo recognized but not assembled here.
+            return match cmd {
+                0x08 => OutputReportResult::FullScreenImageChunk,
+                0x09 => OutputReportResult::BootLogoImageChunk,
+                _ => OutputReportResult::Unhandled,
+            };
+        }
+
+        // A sequence-0 packet always starts the key's image, even while that
+        // key (or another) is mid-transfer
+        if sequence == 0 {
+            if let Err(e) = self.assemblies.start(key_id, 0) {
+                return OutputReportResult::Error(e);
+            }
+        }
+
+        // A lost or out-of-order page abandons only this key's image
+        let copy_len = (payload_len as usize).min(data.len() - data_start);
+        if let Err(e) = self.assemblies.continue_with(
+            key_id,
+            sequence,
+            &data[data_start..data_start + copy_len],
+        ) {
+            return OutputReportResult::Error(e);
+        }
+
+        if !is_last {
+            return OutputReportResult::Unhandled;
+        }
+
+        // Image complete
+        match self.assemblies.finish(key_id) {
+            Some(image) => OutputReportResult::KeyImageComplete { key_id, image },
+            None => OutputReportResult::Error(ProtocolError::SequenceError),
+        }
+    }
+
+    fn map_buttons(
+        &self,
+        physical_buttons: &[bool],
+        cols: usize,
+        rows: usize,
+        left_to_right: bool,
+    ) -> ButtonMapping {
+        let mut mapped_buttons = [false; 32];
+        let total_keys = cols * rows;
+
+        // V2 devices generally use left-to-right mapping
+        for (physical_idx, &pressed) in physical_buttons.iter().take(total_keys).enumerate() {
+            let mapped_idx = if left_to_right {
+                physical_idx
+            } else {
+                // Right-to-left if needed (rare for V2 devices)
+                let row = physical_idx / cols;
+                let col = physical_idx % cols;
+                let reversed_col = cols - 1 - col;
+                row * cols + reversed_col
+            };
+
+            if mapped_idx < 32 {
+                mapped_buttons[mapped_idx] = pressed;
+            }
+        }
+
+        ButtonMapping {
+            mapped_buttons,
+            active_count: total_keys,
+        }
+    }
+
+    fn report_layout(&self, key_count: usize) -> ReportLayout {
+        ReportLayout {
+            key_count,
+            // 3-byte header, then one byte per key
+            input_count: 3 + key_count,
+            output_count: 1024,
+            feature_ids: &[0x03, 0x04, 0x05],
+            feature_count: 32,
+        }
+    }
+
+    fn input_report_size(&self, button_count: usize) -> usize {
+        // V2 input reports: 3-byte header + button states
+        3 + button_count
+    }
+
+    fn format_button_report(&self, buttons: &ButtonMapping, report: &mut [u8]) -> usize {
+        if report.len() < 4 {
+            return 0;
+        }
+
+        // V2 format: [header_bytes, button_states...]
+        report[0] = 0x00; // Header byte 1
+        report[1] = 0x00; // Header byte 2
+        report[2] = 0x00; // Header byte 3
+
+        let button_bytes = (buttons.active_count).min(report.len() - 3);
+        for i in 0..button_bytes {
+            report[i + 3] = if buttons.mapped_buttons[i] { 1 } else { 0 };
+        }
+
+        // Fill remaining bytes with 0
+        for b in report.iter_mut().skip(button_bytes + 3) {
+            *b = 0;
+        }
+
+        3 + button_bytes
+    }
+
+    fn handle_feature_report(&mut self, report_id: u8, data: &[u8]) -> Option<ModuleSetCommand> {
+        if report_id == 0x03 && data.len() >= 2 {
+            // V2 commands: [0x03, command_byte, ...]
+            match data[1] {
+                V2_COMMAND_RESET => {
+                    // V2 Reset: [0x03, 0x02, ...]
+                    Some(ModuleSetCommand::Reset)
+                }
+                V2_COMMAND_BRIGHTNESS => {
+                    // V2 Brightness: [0x03, 0x08, brightness, ...]
+                    if data.len() >= 3 {
+                        Some(ModuleSetCommand::SetBrightness { value: data[2] })
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    fn get_feature_report(
+        &mut self,
+        report_id: u8,
+        unit: &UnitInfo,
+        buf: &mut [u8],
+    ) -> Option<usize> {
+        feature::build(feature::V2_FEATURES, report_id, unit, buf)
+    }
+
+    fn reset(&mut self) {
+        self.assemblies.clear();
+    }
+}
@@ -0,0 +1,104 @@
+//! Types shared by the protocol handlers and the firmware tasks that act on
+//! their results. The firmware's `types` re-exports them.
+
+/// Clockwise rotation in quarter turns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Rotation {
+    #[default]
+    Deg0 = 0,
+    Deg90 = 1,
+    Deg180 = 2,
+    Deg270 = 3,
+}
+
+impl Rotation {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Rotation::Deg0,
+            1 => Rotation::Deg90,
+            2 => Rotation::Deg180,
+            3 => Rotation::Deg270,
+            _ => return None,
+        })
+    }
+
+    /// The rotation that undoes this one
+    pub const fn inverse(self) -> Self {
+        match self {
+            Rotation::Deg0 => Rotation::Deg0,
+            Rotation::Deg90 => Rotation::Deg270,
+            Rotation::Deg180 => Rotation::Deg180,
+            Rotation::Deg270 => Rotation::Deg90,
+        }
+    }
+}
+
+/// Screen of a device with more than one display surface
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Screen {
+    /// The key grid
+    Keys = 0,
+    /// The touch strip LCD (Plus) or info bar (Neo)
+    Lcd = 1,
+}
+
+impl Screen {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Screen::Keys,
+            1 => Screen::Lcd,
+            _ => return None,
+        })
+    }
+}
+
+/// Number of dials on the StreamDeck Plus, the most of any model
+pub const PLUS_DIAL_COUNT: usize = 4;
+
+/// Dial input from the StreamDeck Plus or Studio encoders. Models with
+/// fewer dials only report the first ones.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EncoderEvent {
+    /// Press state of every dial
+    Press([bool; PLUS_DIAL_COUNT]),
+    /// Detents turned per dial since the last event (positive is clockwise)
+    Turn([i8; PLUS_DIAL_COUNT]),
+}
+
+/// Touch strip gesture kind, as reported to the host
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TouchKind {
+    Short = 1,
+    Long = 2,
+    Drag = 3,
+}
+
+/// Touch strip input from the StreamDeck Plus
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TouchEvent {
+    pub kind: TouchKind,
+    /// Touch position in strip pixels
+    pub x: u16,
+    pub y: u16,
+    /// Drag end position (equal to `x`/`y` for taps)
+    pub x_end: u16,
+    pub y_end: u16,
+}
+
+/// Window of the StreamDeck Plus touch strip LCD, in strip pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LcdRegion {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
@@ -0,0 +1,149 @@
+//! HID report descriptor conformance
+//!
+//! The descriptor the host parses and the reports the handlers send and
+//! accept are defined in different places (`ReportLayout`, the feature
+//! reply tables, the byte-exact Elgato captures, the device profiles), and
+//! a host that trusts the descriptor drops or truncates anything that
+//! disagrees with it. Every descriptor the firmware can enumerate with is
+//! parsed here and each report the handlers depend on is checked against it:
+//!
+//! - the longest input report sent (keys, and dials/touch on the Plus)
+//!   fits the declared input report
+//! - the declared output report is the size reassembly gathers
+//! - every feature report ID the handlers accept or answer is declared
+//!   (accept only, for captured descriptors), and every reply is the
+//!   length declared for its report ID
+//! - the vendor and settings reports have their fixed sizes (generated
+//!   descriptors only)
+//!
+//! Run on the host: `cargo test -p productiondeck-protocol --target x86_64-unknown-linux-gnu`
+
+use hidreport::{Report, ReportDescriptor};
+
+use productiondeck_protocol::config::{
+    FEATURE_REPORT_VENDOR, SETTINGS_REPORTS, VENDOR_REPORT_SIZE,
+};
+use productiondeck_protocol::device::{Device, DeviceConfig};
+use productiondeck_protocol::protocol::descriptor::{INPUT_REPORT_ID, OUTPUT_REPORT_ID};
+use productiondeck_protocol::protocol::elgato_descriptors::exact_descriptor;
+use productiondeck_protocol::protocol::{feature, ProtocolHandler};
+
+/// Length in bytes of report `id` in `reports`, report ID included
+fn declared_len(reports: &[impl Report], id: u8) -> Option<usize> {
+    reports
+        .iter()
+        .find(|report| report.report_id().map(u8::from) == Some(id))
+        .map(|report| report.size_in_bytes())
+}
+
+/// Mismatches between `descriptor` and the reports the handlers send and
+/// accept for `device`. `exact` is set for byte-exact Elgato captures,
+/// which carry no vendor report.
+fn mismatches(descriptor: &[u8], device: Device, exact: bool) -> Vec<String> {
+    let name = device.device_name();
+    let parsed = match ReportDescriptor::try_from(descriptor) {
+        Ok(parsed) => parsed,
+        Err(e) => return vec![format!("{name}: descriptor does not parse: {e}")],
+    };
+    let protocol = ProtocolHandler::for_device(device);
+    let keys = device.button_layout().total_keys;
+    let layout = protocol.report_layout(keys);
+    let mut found = Vec::new();
+
+    // Input: key reports, padded to the profile's size on some hosts
+    let keys_input = protocol
+        .input_report_size(keys)
+        .max(device.input_report_size());
+    // Plus: dial and touch events share the input report ID
+    #[cfg(feature = "proto-v2")]
+    let input = if protocol.version() == productiondeck_protocol::device::ProtocolVersion::Plus {
+        use productiondeck_protocol::protocol::plus::{DIAL_REPORT_SIZE, TOUCH_REPORT_SIZE};
+        keys_input.max(TOUCH_REPORT_SIZE).max(DIAL_REPORT_SIZE)
+    } else {
+        keys_input
+    };
+    #[cfg(not(feature = "proto-v2"))]
+    let input = keys_input;
+    let declared = declared_len(parsed.input_reports(), INPUT_REPORT_ID);
+    if declared.is_none_or(|declared| declared < input) {
+        found.push(format!(
+            "{name}: input report {INPUT_REPORT_ID:#04x} declared {declared:?} bytes, handlers send {input}"
+        ));
+    }
+
+    // Output: reassembly gathers exactly this many bytes per report
+    let output = device.output_report_size();
+    let declared = declared_len(parsed.output_reports(), OUTPUT_REPORT_ID);
+    if declared != Some(output) {
+        found.push(format!(
+            "{name}: output report {OUTPUT_REPORT_ID:#04x} declared {declared:?} bytes, handlers gather {output}"
+        ));
+    }
+
+    // Feature reports the handlers accept or answer. Captured descriptors
+    // are what the real device declares; replies past them (V1 idle time at
+    // 0xA3) only reach hosts that skip the descriptor.
+    let replies = feature::table_for(protocol.version())
+        .iter()
+        .map(|reply| reply.report_id)
+        .filter(|_| !exact);
+    for id in layout.feature_ids.iter().copied().chain(replies) {
+        if declared_len(parsed.feature_reports(), id).is_none() {
+            found.push(format!("{name}: feature report {id:#04x} not declared"));
+        }
+    }
+
+    // Reply lengths: hosts read as many bytes as the descriptor declares
+    for reply in feature::table_for(protocol.version()) {
+        let id = reply.report_id;
+        let declared = declared_len(parsed.feature_reports(), id);
+        if declared.is_some_and(|declared| declared != reply.report_len) {
+            found.push(format!(
+                "{name}: feature report {id:#04x} declared {declared:?} bytes, handlers reply {}",
+                reply.report_len
+            ));
+        }
+    }
+
+    if !exact {
+        let declared = declared_len(parsed.feature_reports(), FEATURE_REPORT_VENDOR);
+        if declared != Some(VENDOR_REPORT_SIZE) {
+            found.push(format!(
+                "{name}: vendor report declared {declared:?} bytes, expected {VENDOR_REPORT_SIZE}"
+            ));
+        }
+        for (id, len) in SETTINGS_REPORTS {
+            let declared = declared_len(parsed.feature_reports(), id);
+            if declared != Some(1 + len) {
+                found.push(format!(
+                    "{name}: settings report {id:#04x} declared {declared:?} bytes, expected {}",
+                    1 + len
+                ));
+            }
+        }
+    }
+    found
+}
+
+#[test]
+fn generated_descriptors_match_handlers() {
+    let found: Vec<String> = Device::ALL
+        .iter()
+        .flat_map(|&device| {
+            let keys = device.button_layout().total_keys;
+            let descriptor = ProtocolHandler::for_device(device).hid_descriptor(keys);
+            mismatches(&descriptor, device, false)
+        })
+        .collect();
+    assert!(found.is_empty(), "{}", found.join("\n"));
+}
+
+#[test]
+fn elgato_descriptors_match_handlers() {
+    let found: Vec<String> = Device::ALL
+        .iter()
+        .filter_map(|&device| Some((device, exact_descriptor(device)?)))
+        .flat_map(|(device, descriptor)| mismatches(descriptor, device, true))
+        .collect();
+    assert!(found.is_empty(), "{}", found.join("\n"));
+}
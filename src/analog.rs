@@ -0,0 +1,195 @@
+//! Analog inputs (faders, T-bars) on the RP2040 ADC
+//!
+//! The ADC is shared with the supply and temperature monitor in `power`, so
+//! this module owns it and hands out readings. Fader positions are smoothed, reported to the
+//! host through the vendor report, and can optionally be translated into
+//! StreamDeck Plus dial turns so stock host software sees them.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use defmt::*;
+use embassy_rp::adc::{Adc, Blocking, Channel, Config as AdcConfig};
+use embassy_rp::gpio::Pull;
+use embassy_rp::{peripherals, Peri};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Timer};
+
+use crate::channels::ENCODER_CHANNEL;
+use crate::config::{
+    FADER_COUNT, FADER_DEADBAND, FADER_DETENT_STEP, FADER_FILTER_SHIFT, FADER_POLL_MS,
+};
+use crate::types::{EncoderEvent, PLUS_DIAL_COUNT};
+
+/// ADC with the fader channels (GPIO26-28), the VSYS channel (GPIO29) and
+/// the on-die temperature sensor
+struct AnalogInputs {
+    adc: Adc<'static, Blocking>,
+    faders: [Channel<'static>; FADER_COUNT],
+    vsys: Channel<'static>,
+    temp: Channel<'static>,
+}
+
+static ANALOG: Mutex<CriticalSectionRawMutex, RefCell<Option<AnalogInputs>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Filtered fader positions (0-255)
+static FADER_POSITIONS: [AtomicU8; FADER_COUNT] = [const { AtomicU8::new(0) }; FADER_COUNT];
+
+/// Whether fader movement is also reported as Plus dial turns
+static DIAL_MAPPING: AtomicBool = AtomicBool::new(false);
+
+/// Take ownership of the ADC and its input pins.
+/// Must be called before `analog_task` or `power::voltage_monitor_task` run.
+pub fn init(
+    adc: Peri<'static, peripherals::ADC>,
+    fader0: Peri<'static, peripherals::PIN_26>,
+    fader1: Peri<'static, peripherals::PIN_27>,
+    fader2: Peri<'static, peripherals::PIN_28>,
+    vsys: Peri<'static, peripherals::PIN_29>,
+    temp: Peri<'static, peripherals::ADC_TEMP_SENSOR>,
+) {
+    let inputs = AnalogInputs {
+        adc: Adc::new_blocking(adc, AdcConfig::default()),
+        faders: [
+            Channel::new_pin(fader0, Pull::None),
+            Channel::new_pin(fader1, Pull::None),
+            Channel::new_pin(fader2, Pull::None),
+        ],
+        vsys: Channel::new_pin(vsys, Pull::None),
+        temp: Channel::new_temp_sensor(temp),
+    };
+    ANALOG.lock(|a| *a.borrow_mut() = Some(inputs));
+}
+
+/// Read the raw 12-bit sample of the VSYS/3 divider
+pub fn read_vsys() -> Option<u16> {
+    ANALOG.lock(|a| {
+        let mut a = a.borrow_mut();
+        let inputs = a.as_mut()?;
+        match inputs.adc.blocking_read(&mut inputs.vsys) {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                warn!("VSYS read failed: {:?}", e);
+                None
+            }
+        }
+    })
+}
+
+/// Read the raw 12-bit sample of the on-die temperature sensor
+pub fn read_temperature() -> Option<u16> {
+    ANALOG.lock(|a| {
+        let mut a = a.borrow_mut();
+        let inputs = a.as_mut()?;
+        match inputs.adc.blocking_read(&mut inputs.temp) {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                warn!("Temperature read failed: {:?}", e);
+                None
+            }
+        }
+    })
+}
+
+/// Read the raw 12-bit sample of one fader
+fn read_fader(index: usize) -> Option<u16> {
+    ANALOG.lock(|a| {
+        let mut a = a.borrow_mut();
+        let inputs = a.as_mut()?;
+        inputs.adc.blocking_read(&mut inputs.faders[index]).ok()
+    })
+}
+
+/// Current filtered fader positions (0-255)
+pub fn fader_positions() -> [u8; FADER_COUNT] {
+    core::array::from_fn(|i| FADER_POSITIONS[i].load(Ordering::Relaxed))
+}
+
+/// Enable or disable reporting fader movement as Plus dial turns
+pub fn set_dial_mapping(enabled: bool) {
+    DIAL_MAPPING.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether fader movement is reported as Plus dial turns
+pub fn dial_mapping() -> bool {
+    DIAL_MAPPING.load(Ordering::Relaxed)
+}
+
+/// Exponential moving average with a deadband on the reported position
+#[derive(Clone, Copy, Debug, Default)]
+struct FaderFilter {
+    /// Smoothed 12-bit value scaled by `1 << FADER_FILTER_SHIFT`
+    acc: u32,
+    /// Last reported position (0-255)
+    reported: u8,
+    /// Position the last dial turn was reported from
+    dial_origin: u8,
+    primed: bool,
+}
+
+impl FaderFilter {
+    /// Feed a sample; returns the new position when it moved past the deadband
+    fn update(&mut self, sample: u16) -> Option<u8> {
+        let sample = (sample as u32) << FADER_FILTER_SHIFT;
+        if !self.primed {
+            self.acc = sample;
+            self.primed = true;
+        } else {
+            self.acc = self.acc - (self.acc >> FADER_FILTER_SHIFT) + (sample >> FADER_FILTER_SHIFT);
+        }
+
+        // 12-bit -> 8-bit
+        let position = (self.acc >> (FADER_FILTER_SHIFT + 4)) as u8;
+        if position.abs_diff(self.reported) < FADER_DEADBAND {
+            return None;
+        }
+        self.reported = position;
+        Some(position)
+    }
+
+    /// Whole dial detents moved since the last reported turn
+    fn take_detents(&mut self) -> i8 {
+        let delta = self.reported as i16 - self.dial_origin as i16;
+        let detents = delta / FADER_DETENT_STEP as i16;
+        self.dial_origin = (self.dial_origin as i16 + detents * FADER_DETENT_STEP as i16) as u8;
+        detents.clamp(i8::MIN as i16, i8::MAX as i16) as i8
+    }
+}
+
+/// Poll the faders, update their positions and emit dial turns when mapped
+#[embassy_executor::task]
+pub async fn analog_task() {
+    info!("Analog input task started ({} faders)", FADER_COUNT);
+    let mut filters = [FaderFilter::default(); FADER_COUNT];
+
+    loop {
+        Timer::after(Duration::from_millis(FADER_POLL_MS)).await;
+
+        let mut moved = false;
+        for (index, filter) in filters.iter_mut().enumerate() {
+            let Some(sample) = read_fader(index) else {
+                continue;
+            };
+            if let Some(position) = filter.update(sample) {
+                FADER_POSITIONS[index].store(position, Ordering::Relaxed);
+                moved = true;
+            }
+        }
+
+        if !moved || !dial_mapping() {
+            continue;
+        }
+
+        // Fader N drives dial N
+        let mut turns = [0i8; PLUS_DIAL_COUNT];
+        for (turn, filter) in turns.iter_mut().zip(filters.iter_mut()) {
+            *turn = filter.take_detents();
+        }
+        if turns.iter().any(|&t| t != 0)
+            && ENCODER_CHANNEL.try_send(EncoderEvent::Turn(turns)).is_err()
+        {
+            crate::stats::command_dropped();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Standalone key animations (`config-drive`)
+//!
+//! An animation is a file of key images back to back on the configuration
+//! drive, `KEYnnA.BMP` for key `nn`. Every frame is a complete BMP exactly
+//! like `KEYnn.BMP`, so concatenating the frame files makes one. Frames are
+//! never copied out of the volume: the display task streams each frame from
+//! the memory-mapped flash a cluster at a time, through the same decoder as
+//! streamed host images, so playback needs no image buffer.
+//!
+//! Animations play while the device is in standalone mode (no StreamDeck
+//! software seen, `standalone::is_active`) and page 0 is shown, one frame
+//! every `ANIMATION_FRAME_MS`; each loops on its own length. Only BMP
+//! devices animate, on the TFT.
+
+use defmt::*;
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+use crate::config::{ANIMATION_FRAME_MS, ANIMATION_IDLE_POLL_MS, LOCAL_IMAGE_KEYS};
+use crate::config_drive::{FileChunks, StoredFile};
+use crate::device::{DeviceConfig, ImageFormat};
+
+/// BMP file + info header size
+const BMP_HEADER_SIZE: usize = 54;
+
+/// One key's animation file
+pub struct Animation {
+    pub key_id: u8,
+    file: StoredFile,
+    frame_size: usize,
+    frames: usize,
+}
+
+impl Animation {
+    /// Check a key's animation file: every frame must be a full-size key BMP
+    fn load(key_id: u8, file: StoredFile) -> Option<Self> {
+        let image_size = crate::config::key_image_size();
+        let frame_size = BMP_HEADER_SIZE + image_size * image_size * 3;
+        let mut header = [0u8; 6];
+        let mut len = 0;
+        for chunk in file.chunks(0, header.len()) {
+            header[len..len + chunk.len()].copy_from_slice(chunk);
+            len += chunk.len();
+        }
+        let declared = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+        if header[0..2] != *b"BM" || declared != frame_size {
+            warn!(
+                "Key {} animation is not {}x{} BMP frames",
+                key_id, image_size, image_size
+            );
+            return None;
+        }
+        let frames = file.size() / frame_size;
+        (frames > 0).then_some(Self {
+            key_id,
+            file,
+            frame_size,
+            frames,
+        })
+    }
+
+    /// Frame `index` (wrapping), a cluster at a time
+    pub fn frame(&self, index: usize) -> FileChunks {
+        let start = (index % self.frames) * self.frame_size;
+        self.file.chunks(start, self.frame_size)
+    }
+}
+
+/// Frame schedule for the display task
+pub struct Player {
+    animations: Vec<Animation, LOCAL_IMAGE_KEYS>,
+    /// Frames shown since playback last started
+    frame: usize,
+    playing: bool,
+    next_at: Instant,
+}
+
+impl Player {
+    /// Find the animation files on the configuration drive
+    pub fn load() -> Self {
+        let mut animations = Vec::new();
+        let device = crate::config::get_current_device();
+        if device.display_config().format == ImageFormat::Bmp {
+            let keys = device.button_layout().total_keys.min(LOCAL_IMAGE_KEYS);
+            for key_id in 0..keys as u8 {
+                let mut name = *b"KEY00A  BMP";
+                name[3] = b'0' + key_id / 10;
+                name[4] = b'0' + key_id % 10;
+                let Some(file) = StoredFile::find(&name) else {
+                    continue;
+                };
+                if let Some(animation) = Animation::load(key_id, file) {
+                    info!("Key {} animation: {} frames", key_id, animation.frames);
+                    // Bounded: one per key
+                    let _ = animations.push(animation);
+                }
+            }
+        }
+        Self {
+            animations,
+            frame: 0,
+            playing: false,
+            next_at: Instant::now(),
+        }
+    }
+
+    /// When the player next needs the display task; None without animations
+    pub fn next_due(&self) -> Option<Instant> {
+        (!self.animations.is_empty()).then_some(self.next_at)
+    }
+
+    /// The frame to draw now, if one is due and the device is standalone
+    pub fn take_due(&mut self) -> Option<usize> {
+        let now = Instant::now();
+        if self.animations.is_empty() || now < self.next_at {
+            return None;
+        }
+        if !crate::standalone::is_active() || crate::pages::current_page() != 0 {
+            self.playing = false;
+            self.next_at = now + Duration::from_millis(ANIMATION_IDLE_POLL_MS);
+            return None;
+        }
+        if !self.playing {
+            self.playing = true;
+            self.frame = 0;
+        }
+        // A late frame does not make the next ones hurry
+        self.next_at = (self.next_at + Duration::from_millis(ANIMATION_FRAME_MS)).max(now);
+        let frame = self.frame;
+        self.frame = self.frame.wrapping_add(1);
+        Some(frame)
+    }
+
+    pub fn animations(&self) -> &[Animation] {
+        &self.animations
+    }
+}
@@ -0,0 +1,13 @@
+//! ProductionDeck - StreamDeck Studio Compatible Firmware
+//!
+//! This binary builds firmware specifically for StreamDeck Studio compatibility:
+//! - 32 keys in 16x2 layout (wired as an 8x4 matrix)
+//! - 144x112 pixel images per key
+//! - Two dials, reported like the Plus dials
+//! - USB VID:PID 0x0fd9:0x00aa
+//! - V2 JPEG protocol with Plus input reports
+
+#![no_std]
+#![no_main]
+
+productiondeck::firmware_main!(Device::Studio, single_core);
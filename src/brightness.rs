@@ -0,0 +1,226 @@
+//! Brightness subsystem
+//!
+//! Converts host brightness percentages (0-100%) into backlight duty values
+//! using a perceptual curve with a configurable minimum floor, and keeps
+//! optional per-key brightness overrides for shared-panel and multi-panel builds.
+//!
+//! The last host level is kept in settings so the backlight comes up at it
+//! after a power cycle and the host reads back the level it stored, instead
+//! of full brightness until it happens to send one.
+
+use crate::config::{BRIGHTNESS_MIN_FLOOR_PERCENT, BRIGHTNESS_SAVE_DELAY_MS, MAX_KEYS};
+use crate::settings;
+use core::cell::Cell;
+use defmt::*;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant};
+// portable-atomic: thumbv6m has no native read-modify-write atomics
+use portable_atomic::{AtomicU8, Ordering};
+
+/// Marker for "no per-key override, follow global brightness"
+const NO_OVERRIDE: u8 = 0xFF;
+
+/// Perceptual brightness curve (approximate gamma 2.0), indexed by percent (0-100)
+/// and yielding an 8-bit duty value.
+const CURVE: [u8; 101] = build_curve();
+
+const fn build_curve() -> [u8; 101] {
+    let mut table = [0u8; 101];
+    let mut i = 0;
+    while i <= 100 {
+        // duty = 255 * (i / 100)^2, rounded
+        table[i] = ((i * i * 255 + 5000) / 10000) as u8;
+        i += 1;
+    }
+    table
+}
+
+/// Apply the minimum floor to a host brightness percentage.
+/// Zero is preserved so the host can still turn the backlight fully off.
+pub fn apply_floor(percent: u8) -> u8 {
+    let percent = percent.min(100);
+    if percent == 0 {
+        0
+    } else {
+        percent.max(BRIGHTNESS_MIN_FLOOR_PERCENT)
+    }
+}
+
+/// Convert a brightness percentage into an 8-bit backlight duty value
+pub fn percent_to_duty(percent: u8) -> u8 {
+    CURVE[apply_floor(percent) as usize]
+}
+
+/// Last global brightness requested by the host (0-100%)
+static HOST_PERCENT: AtomicU8 = AtomicU8::new(100);
+
+/// Last global brightness requested by the host (0-100%)
+pub fn host_percent() -> u8 {
+    HOST_PERCENT.load(Ordering::Relaxed)
+}
+
+/// When the host level last changed without being saved
+static UNSAVED_SINCE: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+
+/// Record a host brightness request; outputs apply it from the device bus
+pub fn set_host_percent(percent: u8) {
+    let percent = percent.min(100);
+    if HOST_PERCENT.swap(percent, Ordering::Relaxed) != percent {
+        UNSAVED_SINCE.lock(|since| since.set(Some(Instant::now())));
+    }
+}
+
+/// Take the stored host level; call after settings are loaded
+pub fn sync_from_settings() {
+    let percent = settings::with(|s| s.brightness).unwrap_or(crate::config::display_brightness());
+    HOST_PERCENT.store(percent, Ordering::Relaxed);
+}
+
+/// Write the host level to flash once it has stood for
+/// `BRIGHTNESS_SAVE_DELAY_MS`; call periodically
+pub fn save_if_settled() {
+    let settled = UNSAVED_SINCE.lock(|since| match since.get() {
+        Some(at) if at.elapsed() >= Duration::from_millis(BRIGHTNESS_SAVE_DELAY_MS) => {
+            since.set(None);
+            true
+        }
+        _ => false,
+    });
+    if !settled {
+        return;
+    }
+
+    let percent = host_percent();
+    if settings::with(|s| s.brightness) == Some(percent) {
+        return;
+    }
+    settings::update(|s| s.brightness = Some(percent));
+    match crate::storage::save_settings() {
+        Ok(()) => info!("Host brightness {}% saved", percent),
+        Err(e) => warn!("Failed to save host brightness: {}", e),
+    }
+}
+
+/// Brightness state for one display subsystem
+#[derive(Clone, Copy, Debug)]
+pub struct Brightness {
+    /// Global brightness in percent (after floor)
+    global: u8,
+    /// Per-key brightness overrides in percent (`NO_OVERRIDE` = follow global)
+    per_key: [u8; MAX_KEYS],
+    /// Upper bound on the backlight in percent (supply sag protection)
+    limit: u8,
+    /// Touch strip LCD brightness in percent (`NO_OVERRIDE` = follow global)
+    lcd: u8,
+}
+
+impl Brightness {
+    /// Create brightness state with the given global level and no overrides
+    pub const fn new(global: u8) -> Self {
+        Self {
+            global: if global > 100 { 100 } else { global },
+            per_key: [NO_OVERRIDE; MAX_KEYS],
+            limit: 100,
+            lcd: NO_OVERRIDE,
+        }
+    }
+
+    /// Set global brightness (0-100%)
+    pub fn set_global(&mut self, percent: u8) {
+        self.global = apply_floor(percent);
+    }
+
+    /// Get global brightness (0-100%)
+    pub fn global(&self) -> u8 {
+        self.global
+    }
+
+    /// Get global backlight duty value (0-255), within the limit
+    pub fn global_duty(&self) -> u8 {
+        percent_to_duty(self.global.min(self.limit))
+    }
+
+    /// Cap the backlight at `percent` (100 = no cap) without changing the
+    /// requested global brightness
+    pub fn set_limit(&mut self, percent: u8) {
+        self.limit = percent.min(100);
+    }
+
+    /// Set a per-key override (0-100%). Values above 100 clear the override.
+    pub fn set_key(&mut self, key_id: u8, percent: u8) {
+        if let Some(slot) = self.per_key.get_mut(key_id as usize) {
+            *slot = if percent > 100 {
+                NO_OVERRIDE
+            } else {
+                apply_floor(percent)
+            };
+        }
+    }
+
+    /// Remove all per-key overrides
+    pub fn clear_overrides(&mut self) {
+        self.per_key = [NO_OVERRIDE; MAX_KEYS];
+    }
+
+    /// Effective brightness for a key (0-100%)
+    pub fn key(&self, key_id: u8) -> u8 {
+        match self.per_key.get(key_id as usize) {
+            Some(&value) if value != NO_OVERRIDE => value,
+            _ => self.global,
+        }
+    }
+
+    /// Scale factor (0-255) to apply to a key's pixels so that it appears at its
+    /// own brightness while the shared backlight runs at the global level.
+    /// Returns 255 when no software dimming is needed.
+    pub fn key_pixel_scale(&self, key_id: u8) -> u8 {
+        self.pixel_scale(self.key(key_id))
+    }
+
+    /// Set the touch strip LCD brightness (0-100%)
+    pub fn set_lcd(&mut self, percent: u8) {
+        self.lcd = apply_floor(percent);
+    }
+
+    /// Effective touch strip LCD brightness (0-100%)
+    pub fn lcd(&self) -> u8 {
+        if self.lcd == NO_OVERRIDE {
+            self.global
+        } else {
+            self.lcd
+        }
+    }
+
+    /// Like `key_pixel_scale`, for the touch strip LCD
+    pub fn lcd_pixel_scale(&self) -> u8 {
+        self.pixel_scale(self.lcd())
+    }
+
+    fn pixel_scale(&self, percent: u8) -> u8 {
+        let duty = percent_to_duty(percent) as u16;
+        let global_duty = self.global_duty() as u16;
+        if global_duty == 0 || duty >= global_duty {
+            255
+        } else {
+            ((duty * 255) / global_duty) as u8
+        }
+    }
+}
+
+impl Default for Brightness {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+/// Scale an 8-bit color channel by a 0-255 factor
+#[inline]
+pub fn scale_channel(value: u8, scale: u8) -> u8 {
+    if scale == 255 {
+        value
+    } else {
+        ((value as u16 * scale as u16) / 255) as u8
+    }
+}
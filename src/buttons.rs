@@ -2,20 +2,97 @@
 //! Button matrix scanning implementation
 //!
 //! This module handles the 3x2 button matrix scanning with debouncing
-//! and sends button state changes to the USB task.
+//! and sends button state changes to the USB task. Debounced states pass
+//! through the key remap and the local gesture layer (`gestures`) first.
+//! Gestures work on the key edges of each scan, timestamped `KeyEvent`s,
+//! which are also published on `KEY_EVENTS` for local layers such as the
+//! press feedback (`key_feedback_task`).
+//!
+//! A key held down longer than the stuck-key timeout is taken for a wiring
+//! fault (a shorted matrix node reads as a key that never lets go). It is
+//! logged, counted as `InputError::StuckKey` and keeps the error LED on until
+//! it reads open again; with the policy's mask set it is also reported as
+//! released, so the host stops acting on it.
+//!
+//! With `i2c-expansion`, keys read from expansion boards (`expansion`) are
+//! merged into each raw scan, so they pass the same debounce, remap and
+//! gestures as the local keys.
+
+use core::sync::atomic::{AtomicU32, Ordering};
 
 use defmt::*;
 use embassy_rp::gpio::{Input, Output};
 use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
 
-use crate::channels::BUTTON_CHANNEL;
+use crate::channels::{subscribe_key_events, try_send_display, BUTTON_CHANNEL, KEY_EVENTS};
 use crate::config::*;
-use crate::types::ButtonState;
+use crate::error::InputError;
+use crate::gestures::GestureProcessor;
+use crate::hardware::{HardwareConfig, MatrixScanConfig};
+use crate::types::{ButtonState, DisplayCommand, KeyEvent, KeyImageVariant};
 
 // ===================================================================
 // Button Debouncing State
 // ===================================================================
 
+/// Debounce timing for one key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyDebounce {
+    /// Time the contact must read closed before a press is reported
+    pub press_ms: u8,
+    /// Time the contact must read open before a release is reported
+    /// (at most `BUTTON_MAX_RELEASE_DEBOUNCE_MS`)
+    pub release_ms: u8,
+    /// Report presses on the first closed reading; only releases are debounced
+    pub eager: bool,
+}
+
+impl KeyDebounce {
+    pub const DEFAULT: Self = Self {
+        press_ms: BUTTON_DEBOUNCE_MS,
+        release_ms: BUTTON_DEBOUNCE_MS,
+        eager: false,
+    };
+
+    /// Whether the timings fit the stored format
+    pub fn is_valid(&self) -> bool {
+        self.release_ms <= BUTTON_MAX_RELEASE_DEBOUNCE_MS
+    }
+}
+
+/// Default debounce for every key
+pub const DEFAULT_DEBOUNCE: [KeyDebounce; MAX_KEYS] = [KeyDebounce::DEFAULT; MAX_KEYS];
+
+/// When a held key is taken as stuck
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StuckKeyPolicy {
+    /// Seconds a key may stay pressed (0 = never stuck, at most `STUCK_KEY_MAX_SECS`)
+    pub timeout_secs: u8,
+    /// Report stuck keys as released
+    pub mask: bool,
+}
+
+impl StuckKeyPolicy {
+    pub const DEFAULT: Self = Self {
+        timeout_secs: STUCK_KEY_DEFAULT_SECS,
+        mask: false,
+    };
+
+    /// Whether the policy fits the stored format
+    pub fn is_valid(&self) -> bool {
+        self.timeout_secs <= STUCK_KEY_MAX_SECS
+    }
+}
+
+/// Physical keys currently considered stuck, one bit per key
+static STUCK_KEYS: AtomicU32 = AtomicU32::new(0);
+
+/// Physical keys currently considered stuck, one bit per key
+pub fn stuck_keys() -> u32 {
+    STUCK_KEYS.load(Ordering::Relaxed)
+}
+
 struct ButtonDebouncer {
     buttons: [ButtonDebounceState; 32], // Max keys for any device
 }
@@ -25,42 +102,374 @@ struct ButtonDebounceState {
     current: bool,
     raw: bool,
     last_change: Instant,
+    /// When `current` last changed
+    last_commit: Instant,
 }
 
 impl ButtonDebouncer {
     fn new() -> Self {
+        let now = Instant::now();
         Self {
             buttons: [ButtonDebounceState {
                 current: false,
                 raw: false,
-                last_change: Instant::now(),
+                last_change: now,
+                last_commit: now,
             }; 32], // Max keys for any device
         }
     }
 
-    fn update(&mut self, key: usize, raw_state: bool) -> bool {
+    fn update(&mut self, key: usize, raw_state: bool, debounce: KeyDebounce) -> bool {
         let now = Instant::now();
         let state = &mut self.buttons[key];
 
         if raw_state != state.raw {
             state.raw = raw_state;
             state.last_change = now;
+
+            // Eager press: report at once, unless this is release bounce
+            // right after a committed release
+            if debounce.eager
+                && raw_state
+                && !state.current
+                && now.duration_since(state.last_commit)
+                    >= Duration::from_millis(debounce.release_ms as u64)
+            {
+                state.current = true;
+                state.last_commit = now;
+                return true;
+            }
         }
 
-        if now.duration_since(state.last_change) >= Duration::from_millis(BUTTON_DEBOUNCE_MS) {
-            let changed = state.current != state.raw;
+        let settle_ms = if state.raw {
+            debounce.press_ms
+        } else {
+            debounce.release_ms
+        };
+        if state.current != state.raw
+            && now.duration_since(state.last_change) >= Duration::from_millis(settle_ms as u64)
+        {
             state.current = state.raw;
-            changed
+            state.last_commit = now;
+            true
         } else {
             false
         }
     }
 
+    /// How long a key has been in its debounced state
+    fn held_for(&self, key: usize, now: Instant) -> Duration {
+        now.duration_since(self.buttons[key].last_commit)
+    }
+
     fn get_state(&self, key: usize) -> bool {
         self.buttons[key].current
     }
 }
 
+// ===================================================================
+// Key Remapping
+// ===================================================================
+
+/// Apply the user remap table (physical key index -> protocol key index)
+/// so wiring that differs from the expected matrix order can be fixed at runtime
+pub fn remap_buttons(state: &mut ButtonState) {
+    let remap = crate::settings::with(|s| s.key_remap);
+    if remap == crate::settings::IDENTITY_REMAP {
+        return;
+    }
+
+    let mut remapped = 0u32;
+    for physical in state.pressed_keys() {
+        let logical = remap[physical] as usize;
+        if logical < state.active_count() {
+            remapped |= 1 << logical;
+        }
+    }
+    state.set_pressed_mask(remapped);
+}
+
+// ===================================================================
+// Scan Processing Pipeline
+// ===================================================================
+
+/// Raw scan -> debounce -> remap -> gestures, shared by all button tasks
+struct ButtonPipeline {
+    debouncer: ButtonDebouncer,
+    gestures: GestureProcessor,
+    active_keys: usize,
+    /// Keys held before gestures, as of the last published edges
+    last_keys: u32,
+    last_reported: u32,
+    /// Startup time, used for the hold-a-key-at-boot triggers (self-test,
+    /// configuration drive)
+    started: Instant,
+    boot_check_done: bool,
+    /// Encoder pushes in the matrix of a hybrid board
+    #[cfg(feature = "hybrid-dials")]
+    slots: crate::hybrid::SlotSplitter,
+}
+
+impl ButtonPipeline {
+    fn new(active_keys: usize) -> Self {
+        // Expansion boards may carry keys past the local scan
+        #[cfg(feature = "i2c-expansion")]
+        let active_keys = active_keys.max(crate::expansion::key_span());
+        Self {
+            debouncer: ButtonDebouncer::new(),
+            gestures: GestureProcessor::new(),
+            active_keys: active_keys.min(MAX_KEYS),
+            last_keys: 0,
+            last_reported: 0,
+            started: Instant::now(),
+            boot_check_done: false,
+            #[cfg(feature = "hybrid-dials")]
+            slots: crate::hybrid::SlotSplitter::new(),
+        }
+    }
+
+    /// Process one raw scan. Returns the state to report when it differs from
+    /// the last report.
+    fn process(&mut self, raw_states: &[bool; MAX_KEYS]) -> Option<ButtonState> {
+        #[cfg(feature = "i2c-expansion")]
+        let raw_states = &crate::expansion::merge(raw_states);
+        #[cfg(feature = "hybrid-dials")]
+        let raw_states = &self.slots.split(raw_states);
+        let mut debounced = ButtonState::new(self.active_keys);
+        let debounce = crate::settings::with(|s| s.debounce);
+
+        for (i, state) in raw_states
+            .iter()
+            .copied()
+            .enumerate()
+            .take(self.active_keys)
+        {
+            if self.debouncer.update(i, state, debounce[i]) {
+                let pressed = self.debouncer.get_state(i);
+                debug!(
+                    "Button {} {}",
+                    i,
+                    if pressed { "pressed" } else { "released" }
+                );
+            }
+            debounced.set_button(i, self.debouncer.get_state(i));
+        }
+        // A main unit polling this board gets the physical keys
+        #[cfg(feature = "i2c-satellite")]
+        crate::satellite::set_local_keys(debounced.pressed_mask());
+
+        let now = Instant::now();
+        let stuck = self.check_stuck(&debounced, now);
+
+        if !self.boot_check_done {
+            let elapsed = now.duration_since(self.started);
+            if crate::config::FACTORY_RESET_BOOT_KEYS
+                .iter()
+                .all(|&key| debounced.is_pressed(key))
+            {
+                info!(
+                    "Keys {} held at boot",
+                    crate::config::FACTORY_RESET_BOOT_KEYS
+                );
+                if let Err(e) = crate::storage::factory_reset() {
+                    warn!("Factory reset failed: {}", e);
+                }
+                self.boot_check_done = true;
+            } else if debounced.is_pressed(0) {
+                info!("Key 0 held at boot");
+                crate::selftest::request();
+                self.boot_check_done = true;
+            } else if cfg!(feature = "config-drive")
+                && debounced.is_pressed(crate::config::CONFIG_DRIVE_BOOT_KEY)
+            {
+                info!("Key {} held at boot", crate::config::CONFIG_DRIVE_BOOT_KEY);
+                self.boot_check_done = true;
+                #[cfg(feature = "config-drive")]
+                crate::config_drive::finish_boot_check(true);
+            } else if elapsed >= Duration::from_millis(crate::selftest::BOOT_TRIGGER_WINDOW_MS) {
+                self.boot_check_done = true;
+            }
+            #[cfg(feature = "config-drive")]
+            if self.boot_check_done && !debounced.is_pressed(crate::config::CONFIG_DRIVE_BOOT_KEY) {
+                crate::config_drive::finish_boot_check(false);
+            }
+        }
+
+        // The self-test checks physical wiring: skip the stuck-key mask and
+        // remap, and report the keys as scanned rather than through gestures
+        let selftest = crate::selftest::is_active();
+        if !selftest {
+            if crate::settings::with(|s| s.stuck_key.mask) {
+                for key in (0..self.active_keys).filter(|&key| stuck & (1 << key) != 0) {
+                    debounced.set_button(key, false);
+                }
+            }
+            remap_buttons(&mut debounced);
+        }
+
+        let edges = self.publish_edges(&debounced, now);
+        let gestures = crate::settings::with(|s| s.gestures);
+        let resolved = self
+            .gestures
+            .process(&edges, &gestures, self.active_keys, now);
+        let mut reported = if selftest { debounced } else { resolved };
+
+        if reported.pressed_mask() == self.last_reported {
+            return None;
+        }
+        self.last_reported = reported.pressed_mask();
+        reported.changed = true;
+        crate::stats::button_event();
+        Some(reported)
+    }
+
+    /// Publish the keys that changed since the last scan on `KEY_EVENTS`;
+    /// returns them for the gesture layer
+    fn publish_edges(&mut self, keys: &ButtonState, now: Instant) -> Vec<KeyEvent, MAX_KEYS> {
+        let changed = keys.pressed_mask() ^ self.last_keys;
+        self.last_keys = keys.pressed_mask();
+
+        let mut edges = Vec::new();
+        let events = KEY_EVENTS.immediate_publisher();
+        for key in (0..self.active_keys).filter(|&key| changed & (1 << key) != 0) {
+            let event = KeyEvent {
+                key: key as u8,
+                pressed: keys.is_pressed(key),
+                timestamp: now,
+            };
+            events.publish_immediate(event);
+            // Bounded: one edge per key
+            let _ = edges.push(event);
+        }
+        edges
+    }
+
+    /// Update the stuck-key set from the debounced physical state; returns it
+    fn check_stuck(&mut self, debounced: &ButtonState, now: Instant) -> u32 {
+        let timeout = crate::settings::with(|s| s.stuck_key.timeout_secs);
+        let mut stuck = 0u32;
+        if timeout > 0 {
+            let limit = Duration::from_secs(timeout as u64);
+            for key in 0..self.active_keys {
+                if debounced.is_pressed(key) && self.debouncer.held_for(key, now) >= limit {
+                    stuck |= 1 << key;
+                }
+            }
+        }
+
+        let previous = STUCK_KEYS.load(Ordering::Relaxed);
+        if stuck == previous {
+            return stuck;
+        }
+        for key in 0..self.active_keys {
+            let bit = 1 << key;
+            if stuck & bit != 0 && previous & bit == 0 {
+                warn!("Key {} held for over {} s, taken as stuck", key, timeout);
+                crate::stats::record_error(InputError::StuckKey);
+            } else if stuck & bit == 0 && previous & bit != 0 {
+                info!("Key {} no longer stuck", key);
+            }
+        }
+        STUCK_KEYS.store(stuck, Ordering::Relaxed);
+        crate::status_led::notify();
+        stuck
+    }
+}
+
+/// Immediate on-device response to key edges from `KEY_EVENTS`: the pressed
+/// outline and stored pressed images. Cosmetic: skipped rather than stalling
+/// when the display is busy, and a burst the task falls behind on loses its
+/// oldest edges.
+#[embassy_executor::task]
+pub async fn key_feedback_task() {
+    let Some(mut events) = subscribe_key_events() else {
+        return;
+    };
+    loop {
+        let event = events.next_message_pure().await;
+        if cfg!(feature = "key-feedback") && event.pressed {
+            try_send_display(DisplayCommand::HighlightKey(event.key));
+        }
+        if crate::key_images::has_pair(event.key) {
+            let variant = if event.pressed {
+                KeyImageVariant::Pressed
+            } else {
+                KeyImageVariant::Icon
+            };
+            try_send_display(DisplayCommand::ShowStoredImage {
+                key_id: event.key,
+                variant,
+            });
+        }
+    }
+}
+
+// ===================================================================
+// Ghost Suppression
+// ===================================================================
+
+/// Holds back ambiguous readings from a matrix without diodes.
+///
+/// With three keys of a rectangle held, current flows back through them and
+/// the fourth corner reads as pressed too. Whenever two rows share two or
+/// more pressed columns the reading can't be trusted, so those rows keep
+/// their last accepted state until the combination clears.
+struct GhostFilter {
+    rows: usize,
+    cols: usize,
+    accepted: [bool; MAX_KEYS],
+    ghosting: bool,
+}
+
+impl GhostFilter {
+    fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            accepted: [false; MAX_KEYS],
+            ghosting: false,
+        }
+    }
+
+    fn filter(&mut self, raw: [bool; MAX_KEYS]) -> [bool; MAX_KEYS] {
+        if MATRIX_HAS_DIODES {
+            return raw;
+        }
+
+        let mut row_bits = [0u32; 4]; // Up to 4 rows
+        for (row, bits) in row_bits.iter_mut().enumerate().take(self.rows) {
+            for col in 0..self.cols {
+                if raw[row * self.cols + col] {
+                    *bits |= 1 << col;
+                }
+            }
+        }
+
+        let mut ambiguous = [false; 4];
+        for a in 0..self.rows.min(4) {
+            for b in a + 1..self.rows.min(4) {
+                if (row_bits[a] & row_bits[b]).count_ones() >= 2 {
+                    ambiguous[a] = true;
+                    ambiguous[b] = true;
+                }
+            }
+        }
+
+        let ghosting = ambiguous.iter().any(|&a| a);
+        if ghosting && !self.ghosting {
+            warn!("Ambiguous key combination (ghosting), holding affected rows");
+            crate::stats::record_error(InputError::Ghosting);
+        }
+        self.ghosting = ghosting;
+
+        for (row, _) in ambiguous.iter().enumerate().filter(|(_, &a)| !a) {
+            let keys = row * self.cols..((row + 1) * self.cols).min(MAX_KEYS);
+            self.accepted[keys.clone()].copy_from_slice(&raw[keys]);
+        }
+        self.accepted
+    }
+}
+
 // ===================================================================
 // Button Matrix Scanning
 // ===================================================================
@@ -68,28 +477,43 @@ impl ButtonDebouncer {
 struct ButtonMatrix<const ROWS: usize, const COLS: usize> {
     rows: [Output<'static>; ROWS],
     cols: [Input<'static>; COLS],
+    timing: MatrixScanConfig,
 }
 
 impl<const ROWS: usize, const COLS: usize> ButtonMatrix<ROWS, COLS> {
     fn new(rows: [Output<'static>; ROWS], cols: [Input<'static>; COLS]) -> Self {
-        Self { rows, cols }
+        let timing = HardwareConfig::for_current_device().matrix;
+        if timing.majority_vote {
+            info!("Matrix: {}us settle, majority vote", timing.settle_us);
+        }
+        Self { rows, cols, timing }
     }
 
     async fn scan(&mut self) -> [bool; 32] {
         let mut button_states = [false; 32]; // Max keys for any device
+        let settle = Duration::from_micros(self.timing.settle_us as u64);
 
         for row_idx in 0..ROWS {
             // Pull current row low
             self.rows[row_idx].set_low();
 
-            // Small settling time
-            Timer::after(Duration::from_micros(10)).await;
+            // Let the row settle
+            Timer::after(settle).await;
+
+            let first = self.read_cols();
+            let pressed = if self.timing.majority_vote {
+                // Samples a settle time apart; a key needs two of three
+                Timer::after(settle).await;
+                let second = self.read_cols();
+                Timer::after(settle).await;
+                let third = self.read_cols();
+                (first & second) | (first & third) | (second & third)
+            } else {
+                first
+            };
 
             for col_idx in 0..COLS {
-                let key_index = row_idx * COLS + col_idx;
-
-                // Read column pin (low = button pressed due to pull-up)
-                button_states[key_index] = !self.cols[col_idx].is_high();
+                button_states[row_idx * COLS + col_idx] = pressed & (1 << col_idx) != 0;
             }
 
             // Return row to high
@@ -98,51 +522,94 @@ impl<const ROWS: usize, const COLS: usize> ButtonMatrix<ROWS, COLS> {
 
         button_states
     }
+
+    /// Column bits of the driven row (low = button pressed due to pull-up)
+    fn read_cols(&self) -> u32 {
+        self.cols
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| col.is_low())
+            .fold(0, |bits, (col_idx, _)| bits | 1 << col_idx)
+    }
 }
 
 async fn run_matrix_task<const ROWS: usize, const COLS: usize>(
     mut matrix: ButtonMatrix<ROWS, COLS>,
     active_keys: usize,
 ) {
-    let mut debouncer = ButtonDebouncer::new();
-    let mut _last_button_state = ButtonState {
-        buttons: [false; 32],
-        changed: false,
-        active_count: active_keys,
-    };
+    let mut pipeline = ButtonPipeline::new(active_keys);
+    let mut ghosts = GhostFilter::new(ROWS, COLS);
 
     let scan_interval = Duration::from_millis(1000 / BUTTON_SCAN_RATE_HZ);
     let sender = BUTTON_CHANNEL.sender();
 
     loop {
+        // Stop scanning while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
         // Scan button matrix
-        let raw_states = matrix.scan().await;
+        let raw = matrix.scan().await;
+        let testing = crate::rollover::record(&raw, ROWS, COLS);
+        let raw_states = ghosts.filter(raw);
+
+        // Send state if the reported keys changed
+        if let Some(state) = pipeline.process(&raw_states) {
+            if !testing && !crate::selftest::capture(&state) {
+                sender.send(state).await;
+            }
+        }
 
-        // Update debouncer and check for changes
-        let mut changed = false;
-        let mut new_state = ButtonState::new(active_keys);
+        // Wait for next scan
+        Timer::after(scan_interval).await;
+    }
+}
 
-        for (i, state) in raw_states.iter().copied().enumerate().take(active_keys) {
-            if debouncer.update(i, state) {
-                changed = true;
-                let pressed = debouncer.get_state(i);
-                debug!(
-                    "Button {} {}",
-                    i,
-                    if pressed { "pressed" } else { "released" }
-                );
-            }
-            new_state.set_button(i, debouncer.get_state(i));
+// ===================================================================
+// PIO Matrix Task Implementation
+// ===================================================================
+
+/// Matrix task backed by the PIO scanner: each tick only reads the latest
+/// snapshot, so there is no per-row settling delay on the CPU
+#[cfg(feature = "pio-matrix")]
+#[embassy_executor::task]
+pub async fn button_task_pio_matrix(
+    rows: heapless::Vec<Output<'static>, 4>,
+    cols: heapless::Vec<Input<'static>, 32>,
+    row_pins: &'static [u8],
+    col_pins: &'static [u8],
+) {
+    info!("Button task (PIO matrix) started");
+    let (rows_len, cols_len) = (rows.len(), cols.len());
+    let active_keys = rows_len * cols_len;
+    let mut ghosts = GhostFilter::new(rows_len, cols_len);
+    let Some(mut matrix) = crate::pio_matrix::PioMatrix::new(rows, cols, row_pins, col_pins) else {
+        error!("PIO matrix resources not initialized");
+        return;
+    };
+    let mut pipeline = ButtonPipeline::new(active_keys);
+
+    let scan_interval = Duration::from_millis(1000 / BUTTON_SCAN_RATE_HZ);
+    let sender = BUTTON_CHANNEL.sender();
+
+    loop {
+        // Stop scanning while the host is asleep
+        if crate::power::usb_suspended() {
+            matrix.set_enabled(false);
+            crate::power::wait_for_resume().await;
+            matrix.set_enabled(true);
         }
 
-        // Send state if changed
-        if changed {
-            new_state.changed = true;
-            sender.send(new_state).await;
-            _last_button_state = new_state;
+        let raw = matrix.snapshot();
+        let testing = crate::rollover::record(&raw, rows_len, cols_len);
+        let raw_states = ghosts.filter(raw);
+        if let Some(state) = pipeline.process(&raw_states) {
+            if !testing && !crate::selftest::capture(&state) {
+                sender.send(state).await;
+            }
         }
 
-        // Wait for next scan
         Timer::after(scan_interval).await;
     }
 }
@@ -164,6 +631,21 @@ pub async fn button_task_matrix_3x2(
     run_matrix_task::<2, 3>(matrix, 6).await;
 }
 
+#[embassy_executor::task]
+#[allow(clippy::too_many_arguments)]
+pub async fn button_task_matrix_4x2(
+    row0: Output<'static>,
+    row1: Output<'static>,
+    col0: Input<'static>,
+    col1: Input<'static>,
+    col2: Input<'static>,
+    col3: Input<'static>,
+) {
+    info!("Button task (matrix 4x2) started");
+    let matrix = ButtonMatrix::<2, 4>::new([row0, row1], [col0, col1, col2, col3]);
+    run_matrix_task::<2, 4>(matrix, 8).await;
+}
+
 #[embassy_executor::task]
 #[allow(clippy::too_many_arguments)]
 pub async fn button_task_matrix_5x3(
@@ -205,6 +687,45 @@ pub async fn button_task_matrix_8x4(
     run_matrix_task::<4, 8>(matrix, 32).await;
 }
 
+// ===================================================================
+// Touch Button Task Implementation
+// ===================================================================
+
+/// Keys from the regions touched on a touch panel (see `touch`)
+#[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+#[embassy_executor::task]
+pub async fn button_task_touch() {
+    let Some(mut touch) = crate::touch::take() else {
+        error!("Touch controller not initialized");
+        return;
+    };
+    info!("Button task (touch) started");
+
+    let active_keys = streamdeck_keys();
+    let mut pipeline = ButtonPipeline::new(active_keys);
+
+    let scan_interval = Duration::from_millis(1000 / BUTTON_SCAN_RATE_HZ);
+    let sender = BUTTON_CHANNEL.sender();
+
+    loop {
+        // Stop scanning while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
+        let raw_states = crate::touch::scan(&mut touch);
+        // Every combination of touched regions is representable
+        let testing = crate::rollover::record(&raw_states, 1, active_keys);
+        if let Some(state) = pipeline.process(&raw_states) {
+            if !testing && !crate::selftest::capture(&state) {
+                sender.send(state).await;
+            }
+        }
+
+        Timer::after(scan_interval).await;
+    }
+}
+
 // ===================================================================
 // Direct Button Task Implementation
 // ===================================================================
@@ -213,45 +734,28 @@ pub async fn button_task_matrix_8x4(
 pub async fn button_task_direct(inputs: heapless::Vec<Input<'static>, 32>) {
     info!("Button task (direct) started");
 
-    let mut debouncer = ButtonDebouncer::new();
-    let mut _last_button_state = ButtonState {
-        buttons: [false; 32],
-        changed: false,
-        active_count: inputs.len(),
-    };
+    let mut pipeline = ButtonPipeline::new(inputs.len());
 
     let scan_interval = Duration::from_millis(1000 / BUTTON_SCAN_RATE_HZ);
     let sender = BUTTON_CHANNEL.sender();
 
     loop {
+        // Stop scanning while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
         // Read all inputs directly (active-low with pull-ups)
         let mut raw_states = [false; 32];
         for (i, pin) in inputs.iter().enumerate() {
             raw_states[i] = !pin.is_high();
         }
 
-        // Debounce and check for changes
-        let mut changed = false;
-        let active_keys = inputs.len();
-        let mut new_state = ButtonState::new(active_keys);
-
-        for (i, state) in raw_states.iter().copied().enumerate().take(active_keys) {
-            if debouncer.update(i, state) {
-                changed = true;
-                let pressed = debouncer.get_state(i);
-                debug!(
-                    "Button {} {}",
-                    i,
-                    if pressed { "pressed" } else { "released" }
-                );
+        let testing = crate::rollover::record(&raw_states, 1, inputs.len());
+        if let Some(state) = pipeline.process(&raw_states) {
+            if !testing && !crate::selftest::capture(&state) {
+                sender.send(state).await;
             }
-            new_state.set_button(i, debouncer.get_state(i));
-        }
-
-        if changed {
-            new_state.changed = true;
-            sender.send(new_state).await;
-            _last_button_state = new_state;
         }
 
         Timer::after(scan_interval).await;
@@ -2,19 +2,151 @@
 //!
 //! This module defines all the Embassy channels used for communication
 //! between different tasks in the ProductionDeck application.
+//!
+//! The multicore binaries run the tasks on both cores, so every channel
+//! uses `CriticalSectionRawMutex`, which on the RP2040 takes a hardware
+//! spinlock and is safe across cores (`ThreadModeRawMutex` only guards
+//! against tasks on the same core). Who sends and receives where:
+//!
+//! | Channel                    | Senders (core 0)                        | Receiver                     |
+//! |----------------------------|-----------------------------------------|------------------------------|
+//! | `BUTTON_CHANNEL`           | key scan                                | USB input loop (core 0)      |
+//! | `KEY_EVENTS`               | key scan                                | key feedback task (core 0)   |
+//! | `USB_COMMAND_CHANNEL`      | USB handlers                            | command router (core 0)      |
+//! | `DISPLAY_CONTROL_CHANNEL`  | via `send_display`                      | display task, first (core 1) |
+//! | `DISPLAY_CHANNEL`          | router, vendor, pager, supervisor, keys | display task (core 1)        |
+//! | `DEVICE_BUS`               | command router, vendor report           | display, LEDs (either core)  |
+//! | `ENCODER_CHANNEL`          | dial sampling                           | USB input loop (core 0)      |
+//! | `TOUCH_CHANNEL`            | touch strip driver (not yet present)    | USB input loop (core 0)      |
+//! | `SECONDARY_BUTTON_CHANNEL` | secondary deck scan                     | secondary deck USB loop      |
+//!
+//! Single-core builds run everything on core 0; the critical section costs
+//! them a few cycles per message.
+//!
+//! Builds or profiles without a usable display have nothing draining the
+//! display channels, so `send_display` only waits for queue space once
+//! display hardware has been claimed (`attach_display`).
 
-use crate::types::{ButtonState, DisplayCommand, UsbCommand};
-use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use crate::types::{
+    ButtonState, DeviceCommand, DisplayCommand, EncoderEvent, KeyEvent, TouchEvent, UsbCommand,
+};
+use core::sync::atomic::{AtomicBool, Ordering};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::channel::Channel;
+use embassy_sync::pubsub::{DynSubscriber, PubSubChannel};
 
 /// Channel for button state communication from button task to USB task
 /// Buffer size: 1 (latest state only)
-pub static BUTTON_CHANNEL: Channel<ThreadModeRawMutex, ButtonState, 1> = Channel::new();
+pub static BUTTON_CHANNEL: Channel<CriticalSectionRawMutex, ButtonState, 1> = Channel::new();
+
+/// Key transitions with timestamps, before gestures, for local layers that
+/// need edges rather than `BUTTON_CHANNEL`'s full-state snapshots. A
+/// subscriber that falls behind loses the oldest events. Buffer size: 8, up
+/// to 4 subscribers
+pub static KEY_EVENTS: PubSubChannel<CriticalSectionRawMutex, KeyEvent, 8, 4, 0> =
+    PubSubChannel::new();
+
+/// Subscribe a local layer to key transitions.
+/// None if all subscriber slots are taken.
+pub fn subscribe_key_events() -> Option<DynSubscriber<'static, KeyEvent>> {
+    match KEY_EVENTS.dyn_subscriber() {
+        Ok(subscriber) => Some(subscriber),
+        Err(_) => {
+            defmt::warn!("Key event channel has no free subscriber slot");
+            None
+        }
+    }
+}
 
 /// Channel for USB commands from HID handler to other tasks
 /// Buffer size: 4 (allows some buffering of commands)
-pub static USB_COMMAND_CHANNEL: Channel<ThreadModeRawMutex, UsbCommand, 4> = Channel::new();
+pub static USB_COMMAND_CHANNEL: Channel<CriticalSectionRawMutex, UsbCommand, 4> = Channel::new();
 
 /// Channel for display commands to the display task
 /// Buffer size: 8 (allows buffering of multiple display operations)
-pub static DISPLAY_CHANNEL: Channel<ThreadModeRawMutex, DisplayCommand, 8> = Channel::new();
+pub static DISPLAY_CHANNEL: Channel<CriticalSectionRawMutex, DisplayCommand, 8> = Channel::new();
+
+/// Control-priority display commands (`DisplayCommand::is_control`). The
+/// display task drains this before `DISPLAY_CHANNEL`, so brightness and
+/// clears take effect within a frame even when images fill the main queue.
+/// Buffer size: 4 (control commands are rare and quick)
+pub static DISPLAY_CONTROL_CHANNEL: Channel<CriticalSectionRawMutex, DisplayCommand, 4> =
+    Channel::new();
+
+/// Set once display hardware is claimed and a display task will drain the queues
+static DISPLAY_ATTACHED: AtomicBool = AtomicBool::new(false);
+
+/// Mark the display as present; called when its hardware is claimed
+pub fn attach_display() {
+    DISPLAY_ATTACHED.store(true, Ordering::Relaxed);
+}
+
+/// Whether a display task drains the display queues
+pub fn display_attached() -> bool {
+    DISPLAY_ATTACHED.load(Ordering::Relaxed)
+}
+
+/// Queue a display command on the channel for its priority. Waits for queue
+/// space while a display is attached; without one a command that does not
+/// fit is dropped, so senders on the USB OUT path never stall.
+pub async fn send_display(command: DisplayCommand) {
+    if !display_attached() {
+        let _ = try_send_display(command);
+    } else if command.is_control() {
+        DISPLAY_CONTROL_CHANNEL.send(command).await
+    } else {
+        DISPLAY_CHANNEL.send(command).await
+    }
+}
+
+/// Queue a display command without waiting; false if its channel is full
+pub fn try_send_display(command: DisplayCommand) -> bool {
+    let queued = if command.is_control() {
+        DISPLAY_CONTROL_CHANNEL.try_send(command)
+    } else {
+        DISPLAY_CHANNEL.try_send(command)
+    };
+    queued.is_ok()
+}
+
+/// Broadcast bus for device-wide commands (reset, brightness, sleep)
+/// Every output subsystem in the build subscribes, so commands still reach
+/// LEDs on display-less Module builds. Buffer size: 4, up to 4 subscribers
+pub static DEVICE_BUS: PubSubChannel<CriticalSectionRawMutex, DeviceCommand, 4, 4, 0> =
+    PubSubChannel::new();
+
+/// Subscribe an output subsystem to the device bus.
+/// None if all subscriber slots are taken.
+pub fn subscribe_device_bus() -> Option<DynSubscriber<'static, DeviceCommand>> {
+    match DEVICE_BUS.dyn_subscriber() {
+        Ok(subscriber) => Some(subscriber),
+        Err(_) => {
+            defmt::warn!("Device bus has no free subscriber slot");
+            None
+        }
+    }
+}
+
+/// Next device command, or never if not subscribed
+pub async fn next_device_command(
+    subscriber: &mut Option<DynSubscriber<'static, DeviceCommand>>,
+) -> DeviceCommand {
+    match subscriber {
+        Some(subscriber) => subscriber.next_message_pure().await,
+        None => core::future::pending().await,
+    }
+}
+
+/// Channel for StreamDeck Plus dial events to the USB task
+/// Buffer size: 4 (turns arrive in bursts)
+pub static ENCODER_CHANNEL: Channel<CriticalSectionRawMutex, EncoderEvent, 4> = Channel::new();
+
+/// Channel for StreamDeck Plus touch strip events to the USB task
+/// Buffer size: 4 (allows buffering of quick taps)
+pub static TOUCH_CHANNEL: Channel<CriticalSectionRawMutex, TouchEvent, 4> = Channel::new();
+
+/// Key states of the secondary deck (`secondary_deck`) to its own HID
+/// interface, kept apart from `BUTTON_CHANNEL` so its keys never reach the
+/// main deck's reports. Buffer size: 1 (latest state only)
+pub static SECONDARY_BUTTON_CHANNEL: Channel<CriticalSectionRawMutex, ButtonState, 1> =
+    Channel::new();
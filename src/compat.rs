@@ -0,0 +1,110 @@
+//! Host library compatibility matrix
+//!
+//! Open-source host libraries (python-elgato-streamdeck and streamdeck-ui on
+//! top of it, node-elgato-stream-deck) don't drive the device the way the
+//! Elgato app does: they read serial and firmware reports at fixed offsets,
+//! some only write images on the interrupt OUT pipe, some poll key state
+//! with GET_REPORT. To check a library against a build, start a matrix run
+//! (`VENDOR_CMD_START_COMPAT_MATRIX`), exercise the library, and read back
+//! which request kinds it made and which of them failed
+//! (`VENDOR_CMD_GET_COMPAT_MATRIX`). Feature report IDs nothing answered are
+//! kept too, as those are the read and write paths still missing.
+
+// portable-atomic: thumbv6m has no native read-modify-write atomics
+use portable_atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+/// Kinds of host request tracked by the matrix, by bit number
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum Probe {
+    /// GET_REPORT of the serial number
+    SerialRead = 0,
+    /// GET_REPORT of a firmware version
+    FirmwareRead = 1,
+    /// GET_REPORT of the idle time
+    IdleTimeRead = 2,
+    /// Any other GET_REPORT (feature)
+    OtherFeatureRead = 3,
+    /// Reset feature report
+    Reset = 4,
+    /// Brightness feature report
+    Brightness = 5,
+    /// Any other SET_REPORT (feature)
+    OtherFeatureWrite = 6,
+    /// Image written with SET_REPORT on the control pipe
+    ImageControl = 7,
+    /// Image written on the interrupt OUT endpoint
+    ImageInterrupt = 8,
+    /// Key report sent on the interrupt IN endpoint
+    KeyReport = 9,
+    /// Key state polled with GET_REPORT (input)
+    InputReportRead = 10,
+}
+
+/// Size of a serialized matrix
+pub const MATRIX_SIZE: usize = 7;
+
+/// No unanswered report recorded
+const NO_REPORT: u8 = 0x00;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static SEEN: AtomicU32 = AtomicU32::new(0);
+static FAILED: AtomicU32 = AtomicU32::new(0);
+static UNANSWERED_READ: AtomicU8 = AtomicU8::new(NO_REPORT);
+static UNANSWERED_WRITE: AtomicU8 = AtomicU8::new(NO_REPORT);
+
+/// Clear the matrix and start recording
+pub fn start() {
+    SEEN.store(0, Ordering::Relaxed);
+    FAILED.store(0, Ordering::Relaxed);
+    UNANSWERED_READ.store(NO_REPORT, Ordering::Relaxed);
+    UNANSWERED_WRITE.store(NO_REPORT, Ordering::Relaxed);
+    RECORDING.store(true, Ordering::Relaxed);
+}
+
+/// The host made a request of this kind and it was served
+pub fn note(probe: Probe) {
+    if RECORDING.load(Ordering::Relaxed) {
+        SEEN.fetch_or(1 << probe as u32, Ordering::Relaxed);
+    }
+}
+
+/// The host made a request of this kind and it could not be served
+pub fn fail(probe: Probe) {
+    if RECORDING.load(Ordering::Relaxed) {
+        SEEN.fetch_or(1 << probe as u32, Ordering::Relaxed);
+        FAILED.fetch_or(1 << probe as u32, Ordering::Relaxed);
+    }
+}
+
+/// Nothing answered a GET_REPORT for this feature report ID
+pub fn unanswered_read(report_id: u8) {
+    fail(Probe::OtherFeatureRead);
+    if RECORDING.load(Ordering::Relaxed) {
+        UNANSWERED_READ.store(report_id, Ordering::Relaxed);
+    }
+}
+
+/// Nothing acted on a SET_REPORT for this feature report ID
+pub fn unanswered_write(report_id: u8) {
+    fail(Probe::OtherFeatureWrite);
+    if RECORDING.load(Ordering::Relaxed) {
+        UNANSWERED_WRITE.store(report_id, Ordering::Relaxed);
+    }
+}
+
+/// Serialize the matrix: `[recording, seen (u16 LE), failed (u16 LE),
+/// last unanswered read ID, last unanswered write ID]` (0 = none)
+pub fn snapshot() -> [u8; MATRIX_SIZE] {
+    let seen = (SEEN.load(Ordering::Relaxed) as u16).to_le_bytes();
+    let failed = (FAILED.load(Ordering::Relaxed) as u16).to_le_bytes();
+    [
+        RECORDING.load(Ordering::Relaxed) as u8,
+        seen[0],
+        seen[1],
+        failed[0],
+        failed[1],
+        UNANSWERED_READ.load(Ordering::Relaxed),
+        UNANSWERED_WRITE.load(Ordering::Relaxed),
+    ]
+}
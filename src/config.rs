@@ -2,8 +2,13 @@
 //! RP2040-based StreamDeck compatible device with multi-device support
 
 use crate::device::{Device, DeviceConfig};
+use crate::settings::SerialString;
 use core::sync::atomic::{AtomicI32, AtomicU16, AtomicU8, Ordering};
 
+// Report IDs, report size limits and image pool sizing shared with the
+// protocol handlers
+pub use productiondeck_protocol::config::*;
+
 // ===================================================================
 // Device Selection Configuration
 // ===================================================================
@@ -31,9 +36,10 @@ pub fn get_device_pid() -> u16 {
 pub fn get_current_device() -> Device {
     let pid = get_device_pid();
     Device::from_pid(pid).unwrap_or_else(|| {
-        // Fallback to Mini if current PID is invalid
-        CURRENT_DEVICE_PID.store(0x0063, Ordering::Relaxed);
-        Device::Mini
+        // Fall back to the default model (Mini when V1 is built) if the
+        // current PID is invalid
+        CURRENT_DEVICE_PID.store(Device::DEFAULT.pid(), Ordering::Relaxed);
+        Device::DEFAULT
     })
 }
 
@@ -48,6 +54,8 @@ pub enum ButtonInputMode {
     Matrix = 0,
     /// Direct pin reading (one GPIO per key)
     Direct = 1,
+    /// Touch panel key regions (`touch-xpt2046` / `touch-ft6236` builds)
+    Touch = 2,
 }
 
 /// Current button input mode (defaults to Matrix)
@@ -62,6 +70,7 @@ pub fn set_button_input_mode(mode: ButtonInputMode) {
 pub fn button_input_mode() -> ButtonInputMode {
     match BUTTON_INPUT_MODE.load(Ordering::Relaxed) {
         1 => ButtonInputMode::Direct,
+        2 => ButtonInputMode::Touch,
         _ => ButtonInputMode::Matrix,
     }
 }
@@ -86,12 +95,34 @@ pub fn usb_product() -> &'static str {
     get_current_device().usb_config().product_name
 }
 
-/// Serial number (static for all devices)
+/// Fallback serial number, used until flash storage has been initialized
 pub const USB_SERIAL: &str = "PRODUCTIONDK"; // 12 chars
 
+/// Current USB serial number: user override from settings, otherwise derived
+/// from the flash unique ID so multiple units on one host don't collide
+pub fn usb_serial() -> SerialString {
+    if let Some(serial) = crate::settings::with(|s| s.serial_override.clone()) {
+        return serial;
+    }
+    crate::storage::unique_serial().unwrap_or_else(|| {
+        let mut serial = SerialString::new();
+        let _ = serial.push_str(USB_SERIAL);
+        serial
+    })
+}
+
 /// USB version settings
 pub const USB_BCD_DEVICE: u16 = 0x0200; // Device version 2.0
 
+/// Quiet time after which a serial or firmware version read is taken as host
+/// software opening the device again (see usb/handler.rs)
+pub const HOST_REOPEN_GAP_MS: u64 = 2000;
+
+/// USB identity of the configuration drive, which is not a StreamDeck
+pub const CONFIG_DRIVE_VID: u16 = 0x1209; // pid.codes
+pub const CONFIG_DRIVE_PID: u16 = 0x0001; // pid.codes test PID
+pub const CONFIG_DRIVE_PRODUCT: &str = "ProductionDeck Config Drive";
+
 // ===================================================================
 // Device Specifications - Dynamic based on current device
 // ===================================================================
@@ -113,6 +144,11 @@ pub fn key_image_size() -> usize {
     display.image_width // Assume square images
 }
 
+/// Clockwise turn that shows the host's key images upright
+pub fn key_image_rotation() -> crate::types::Rotation {
+    get_current_device().display_config().rotation
+}
+
 pub fn key_image_bytes() -> usize {
     let display = get_current_device().display_config();
     display.image_width * display.image_height * 3 // RGB
@@ -183,39 +219,159 @@ pub const DISPLAY_DC_PIN: u8 = 14; // Data/Command select
 pub const DISPLAY_RST_PIN: u8 = 15; // Reset
 pub const DISPLAY_BL_PIN: u8 = 17; // Backlight control (PWM)
 
+// Panel variations (clone ST7735 modules differ; see hardware::PanelConfig)
+pub const PANEL_BGR: bool = false; // Red and blue show swapped
+pub const PANEL_INVERT: bool = false; // Colors show inverted with INVOFF
+pub const PANEL_MADCTL: u8 = 0x00; // Extra MADCTL bits (e.g. mirroring), XOR'd over the rotation
+
 // Status LEDs
 pub const LED_STATUS_PIN: u8 = 25; // Built-in LED on Pico
 pub const LED_USB_PIN: u8 = 20; // USB status LED
 pub const LED_ERROR_PIN: u8 = 21; // Error indication LED
+pub const STATUS_ERROR_HOLD_MS: u32 = 10_000; // How long the error LED keeps blinking after an error
+
+// Accent LED ring around the panel (`led-ring`; see effects.rs)
+pub const LED_RING_PIN: u8 = 22; // WS2812 data in
+pub const LED_RING_LEDS: usize = 16; // LEDs on the ring or strip
+pub const RING_FRAME_MS: u64 = 20; // Frame time while an effect is moving
+pub const RING_BREATHE_MS: u64 = 4000; // One idle breath, dim to bright and back
+pub const RING_ACTIVITY_FLASH_MS: u64 = 80; // Flash after a key image arrives
+pub const RING_ERROR_FLASH_MS: u64 = 1200; // Red blinking after an error
+pub const RING_ERROR_BLINK_MS: u64 = 200; // Red on, then off, for this long each
+
+// Night mode (see night.rs)
+pub const NIGHT_POLL_MS: u64 = 30_000; // How often a night schedule is checked against the clock
+
+// Key-to-report latency self-test (`latency-test`; see latency_test.rs)
+pub const LATENCY_TEST_PIN: u8 = 1; // Pulls a jumpered key input low
+pub const LATENCY_TEST_TIMEOUT_MS: u64 = 300; // A sample without a key report by then is missed
+pub const LATENCY_TEST_GAP_MS: u64 = 150; // Released time between samples, past the longest release debounce
+
+// I2C expansion boards (`i2c-expansion`; see expansion.rs)
+pub const EXPANSION_SDA_PIN: u8 = 0; // I2C0 SDA, shared by every board on the chain
+pub const EXPANSION_SCL_PIN: u8 = 1; // I2C0 SCL
+pub const EXPANSION_I2C_HZ: u32 = 400_000; // Fast mode; drop to 100 kHz for long cables between panels
+pub const EXPANSION_POLL_MS: u64 = 5; // Time between reads of every board, within one local scan
+pub const EXPANSION_RETRY_MS: u64 = 1000; // How often an offline board is tried again
+pub const SATELLITE_I2C_ADDRESS: u8 = 0x40; // Address this board answers at (`i2c-satellite`); matches EXPANSION_LOWER_PANEL
 
 // ===================================================================
 // Hardware Configuration Options
 // ===================================================================
 
-pub const BUTTON_DEBOUNCE_MS: u64 = 20; // Button debounce time
+pub const BUTTON_DEBOUNCE_MS: u8 = 20; // Default press and release debounce time
+pub const BUTTON_MAX_RELEASE_DEBOUNCE_MS: u8 = 127; // Release time shares a byte with the eager flag
 pub const BUTTON_SCAN_RATE_HZ: u64 = 100; // Button scan frequency
+pub const SECONDARY_DECK_PINS: [u8; 3] = [9, 13, 16]; // Direct-wired keys of the secondary deck (`secondary-deck`; claimed in hardware.rs)
+pub const SECONDARY_DECK_KEYS: usize = SECONDARY_DECK_PINS.len();
+pub const STUCK_KEY_DEFAULT_SECS: u8 = 60; // Hold time before a key is reported stuck (0 = off)
+pub const STUCK_KEY_MAX_SECS: u8 = 120; // Stuck timeout shares a byte with the mask flag
+pub const LONG_PRESS_MS: u64 = 500; // Hold time before a long-press action fires
+pub const DOUBLE_PRESS_WINDOW_MS: u64 = 250; // Max gap between taps of a double-press
+pub const VIRTUAL_TAP_MS: u64 = 50; // How long a synthesized tap is reported as held
+pub const MATRIX_HAS_DIODES: bool = false; // Diode-less matrices get ghost suppression
+pub const PIO_MATRIX_CLOCK_HZ: u32 = 3_200_000; // PIO scanner clock: 32-cycle row settle = 10us
+
+// Matrix line noise (long ribbon cables; see hardware::MatrixScanConfig)
+pub const MATRIX_SETTLE_US: u16 = 10; // Wait after driving a row before reading the columns
+pub const MATRIX_MAJORITY_VOTE: bool = false; // Read each row three times, keep the 2-of-3 vote
+
+/// Lowest non-zero brightness the backlight is driven at (percent).
+/// Keeps panels readable when the host requests very low levels.
+pub const BRIGHTNESS_MIN_FLOOR_PERCENT: u8 = 5;
+
+/// PWM clock divider for the backlight: 125 MHz / 24 / 255 counts is about
+/// 20 kHz, above hearing for drivers with coil whine
+pub const BACKLIGHT_PWM_DIVIDER: u8 = 24;
+
+/// Time a host brightness change must stand before it is written to flash.
+/// Hosts send a burst of levels while a slider is dragged.
+pub const BRIGHTNESS_SAVE_DELAY_MS: u64 = 5000;
 
 // Display configuration - Dynamic
 pub fn display_brightness() -> u8 {
-    255 // Default brightness (0-255)
+    100 // Default brightness (0-100%)
 }
 
 pub fn display_total_width() -> usize {
+    // Touch builds lay the keys out over the whole panel (see keypad)
+    if cfg!(any(feature = "touch-xpt2046", feature = "touch-ft6236")) {
+        return KEYPAD_PANEL_WIDTH as usize;
+    }
     streamdeck_cols() * key_image_size()
 }
 
 pub fn display_total_height() -> usize {
-    streamdeck_rows() * key_image_size()
+    if cfg!(any(feature = "touch-xpt2046", feature = "touch-ft6236")) {
+        return KEYPAD_PANEL_HEIGHT as usize;
+    }
+    // Key images aren't always square (Studio: 144x112)
+    streamdeck_rows() * get_current_device().display_config().image_height
 }
 
 // USB Configuration
-pub const USB_POLL_RATE_MS: u64 = 1; // 1ms USB polling (1000Hz)
-pub const IMAGE_BUFFER_SIZE: usize = 1024; // 1KB buffer size
-
-// Image processing optimization
-pub const IMAGE_PROCESSING_BUFFER_SIZE: usize = 8192; // 8KB for image processing
-pub const DISPLAY_BUFFER_SIZE: usize = 2048; // 2KB for display operations
+/// Full-speed packet size, the RP2040 maximum for interrupt endpoints
+pub const USB_HID_PACKET_SIZE: usize = 64;
+/// Control transfer data buffer: a whole output report fits, as the control
+/// pipe rejects SET_REPORTs longer than its buffer instead of splitting them
+pub const USB_CONTROL_BUFFER_SIZE: usize = USB_MAX_OUTPUT_REPORT;
+pub const USB_CONFIG_DESCRIPTOR_SIZE: usize = 512; // Room for HID + keyboard + secondary deck + bulk interfaces
+pub const WEBUSB_LANDING_URL: &str = "https://github.com/FlowingSPDG/productiondeck"; // Page Chrome offers on plug-in (`webusb`)
+
+/// Pages the standalone page key cycles through (see pages.rs)
+pub const STANDALONE_PAGES: usize = 4;
+/// Characters in a standalone page key label
+pub const PAGE_LABEL_LEN: usize = 8;
+/// Keys that can hold a locally stored icon + pressed icon pair (see key_images.rs)
+pub const LOCAL_IMAGE_KEYS: usize = 16;
+/// Key held at power-up to start as a USB configuration drive (see config_drive.rs)
+pub const CONFIG_DRIVE_BOOT_KEY: usize = 1;
+/// Keys held together at power-up to restore factory settings (see settings.rs)
+pub const FACTORY_RESET_BOOT_KEYS: [usize; 2] = [2, 3];
+/// Time each frame of a standalone key animation stays up (see animations.rs)
+pub const ANIMATION_FRAME_MS: u64 = 100;
+/// How often a stopped animation player checks for standalone mode
+pub const ANIMATION_IDLE_POLL_MS: u64 = 1000;
+/// Bytes per streamed image chunk (`streaming-decode`)
+pub const STREAM_CHUNK_SIZE: usize = 512;
+/// Runs in a run-length encoded key image; more and the image goes as a slab
+pub const RLE_MAX_RUNS: usize = 80;
+/// Largest key row in bytes (Studio: 144 px RGB888), from the device tables
+pub const MAX_ROW_BYTES: usize = crate::memory::LARGEST_KEY_ROW;
+/// Highest image rate drawn per key (0 = unlimited); faster updates wait and coalesce
+pub const DISPLAY_MAX_KEY_FPS: u8 = 20;
+/// Longest the display task writes to the panel before yielding to the
+/// button, USB and input report tasks sharing its executor
+pub const DISPLAY_TIME_SLICE_US: u64 = 1000;
+/// Button-to-report latency the time slicing is sized for; slower reports
+/// are counted in the metrics
+pub const INPUT_LATENCY_BUDGET_US: u32 = 5000;
+/// Key border width in pixels, drawn over the image edge (`key-feedback`)
+pub const KEY_BORDER_WIDTH: u16 = 2;
+/// Key border color (RGB565, dark grey)
+pub const KEY_BORDER_COLOR: u16 = 0x4208;
+/// Key border color while the key is pressed (RGB565, white)
+pub const KEY_HIGHLIGHT_COLOR: u16 = 0xFFFF;
+/// How long a press lights up the key border
+pub const KEY_HIGHLIGHT_MS: u64 = 150;
+/// Touch panel resolution; the on-screen keypad fills it (touch builds)
+pub const KEYPAD_PANEL_WIDTH: u16 = 320;
+pub const KEYPAD_PANEL_HEIGHT: u16 = 240;
+/// Key border width at the end of the press animation (touch builds),
+/// capped to the gap around the icon
+pub const KEYPAD_PRESS_BORDER_WIDTH: u16 = 6;
+/// Press animation frames, from the plain border to the pressed one
+pub const KEYPAD_PRESS_FRAMES: u16 = 4;
+pub const KEYPAD_FRAME_MS: u64 = 20;
+/// How often the supervisor refreshes the standalone info bar
+pub const INFO_BAR_REFRESH_MS: u64 = 1000;
+/// Standalone info bar text and background colors (RGB565)
+pub const INFO_BAR_FG: u16 = 0xFFFF;
+pub const INFO_BAR_BG: u16 = 0x0000;
+
+// Multicore
 pub const MULTICORE_CHANNEL_SIZE: usize = 8; // Increased channel size for better throughput
+pub const CORE1_STACK_SIZE: usize = 16384; // Core 1 executor stack (runtime::run_dual_core); the display task runs here
 
 // ===================================================================
 // Power Management: Idle Time (Sleep Mode)
@@ -235,60 +391,138 @@ pub fn get_idle_time_seconds() -> i32 {
 }
 
 // ===================================================================
-// USB HID Report IDs and Commands
+// Power Management: Staged Startup, Supply and Temperature Monitoring
+// ===================================================================
+
+pub const POWER_UP_USB_TIMEOUT_MS: u64 = 3000; // Max wait for enumeration before starting the panel
+pub const POWER_UP_PANEL_TIMEOUT_MS: u64 = 1000; // Max wait for panel init before the backlight ramp
+pub const BACKLIGHT_RAMP_STEPS: u8 = 10; // Brightness steps from dark to full
+pub const BACKLIGHT_RAMP_STEP_MS: u64 = 30; // Delay between ramp steps
+pub const RESET_FADE_STEPS: u8 = 16; // PWM duty steps back up to the host level after a host reset
+pub const RESET_FADE_STEP_MS: u64 = 20; // Delay between reset fade steps
+
+pub const VSYS_ADC_PIN: u8 = 29; // VSYS/3 divider on the Pico
+pub const VSYS_POLL_MS: u64 = 100; // Supply voltage sampling interval
+pub const VSYS_SAG_MV: u16 = 4300; // Below this the backlight is limited
+pub const VSYS_RECOVER_MV: u16 = 4500; // Above this the limit is lifted
+pub const SAG_BRIGHTNESS_PERCENT: u8 = 25; // Backlight limit while the supply sags
+pub const THERMAL_THROTTLE_DECI_C: i16 = 600; // Die temperature (0.1 °C) above which the backlight is limited
+pub const THERMAL_RECOVER_DECI_C: i16 = 550; // Below this the thermal limit is lifted
+pub const THERMAL_BRIGHTNESS_PERCENT: u8 = 50; // Backlight limit while the die is hot
+
+// ===================================================================
+// Analog Inputs (Faders)
+// ===================================================================
+
+pub const FADER_COUNT: usize = 3; // Faders on ADC0-2 (GPIO26-28)
+pub const FADER_POLL_MS: u64 = 10; // Fader sampling interval
+pub const FADER_FILTER_SHIFT: u32 = 3; // Moving average weight 1/8 per sample
+pub const FADER_DEADBAND: u8 = 2; // Position change (of 255) needed to report movement
+pub const FADER_DETENT_STEP: u8 = 8; // Position change per emulated dial detent
+
+// ===================================================================
+// Touchscreen Keys (`touch-xpt2046` / `touch-ft6236` features)
+// ===================================================================
+
+pub const TOUCH_EDGE_MARGIN: u16 = 4; // Pixels at each key edge where touches are ignored
+pub const TOUCH_SWAP_XY: bool = false; // Touch panel axes are swapped relative to the TFT
+                                       // Resistive panel calibration: raw readings at the left/right and top/bottom
+                                       // edges of the key grid (swap min and max for a reversed axis)
+pub const TOUCH_RAW_X_MIN: u16 = 200;
+pub const TOUCH_RAW_X_MAX: u16 = 3900;
+pub const TOUCH_RAW_Y_MIN: u16 = 200;
+pub const TOUCH_RAW_Y_MAX: u16 = 3900;
+
+// ===================================================================
+// Hybrid Key/Dial Boards (`hybrid-dials` feature)
 // ===================================================================
 
-// Report types
-pub const OUTPUT_REPORT_IMAGE: u8 = 0x02;
-pub const IMAGE_COMMAND_V2: u8 = 0x07;
+pub const ENCODER_POLL_US: u64 = 500; // Quadrature sampling period; fast enough for hand-turned encoders
+pub const ENCODER_STEPS_PER_DETENT: i8 = 4; // Quadrature transitions per detent (EC11-style encoders)
 
-// Feature report IDs and commands
-pub const FEATURE_REPORT_VERSION_V1: u8 = 0x04;
-pub const FEATURE_REPORT_VERSION_V2: u8 = 0x05;
-pub const FEATURE_REPORT_SERIAL_NUMBER: u8 = 0x03;
-pub const FEATURE_REPORT_FIRMWARE_INFO: u8 = 0xA1;
-pub const FEATURE_REPORT_RESET_V1: u8 = 0x0B;
-pub const FEATURE_REPORT_BRIGHTNESS_V1: u8 = 0x05;
-pub const FEATURE_REPORT_V2_COMMANDS: u8 = 0x03; // V2 command container
+// ===================================================================
+// E-paper Configuration (`epaper-display` feature)
+// ===================================================================
 
-// V2 sub-commands (used with FEATURE_REPORT_V2_COMMANDS)
-pub const V2_COMMAND_RESET: u8 = 0x02;
-pub const V2_COMMAND_BRIGHTNESS: u8 = 0x08;
+pub const EPAPER_WIDTH: usize = 296; // Panel gate lines (landscape width)
+pub const EPAPER_HEIGHT: usize = 128; // Panel source lines (landscape height)
+pub const EPAPER_BUSY_PIN: u8 = 22; // BUSY input; SPI, CS, DC and RST are shared with the TFT pins
+pub const EPAPER_MIN_REFRESH_MS: u64 = 1000; // Minimum interval between refreshes
+pub const EPAPER_SETTLE_MS: u64 = 150; // Wait after a change so host bursts coalesce
+pub const EPAPER_FULL_REFRESH_EVERY: u8 = 20; // Partial refreshes before a full one clears ghosting
+pub const EPAPER_BUSY_TIMEOUT_MS: u64 = 5000; // Longest wait for an update to finish
 
-// Idle time feature report constants
-pub const FEATURE_REPORT_IDLE_TIME: u8 = 0x0B;
-pub const IDLE_TIME_COMMAND: u8 = 0xA2;
-pub const FEATURE_REPORT_GET_IDLE_TIME: u8 = 0xA3;
+// ===================================================================
+// Parallel Display Bus (`parallel-display` feature)
+// ===================================================================
 
-// StreamDeck protocol magic bytes
-pub const STREAMDECK_MAGIC_1: u8 = 0x55;
-pub const STREAMDECK_MAGIC_2: u8 = 0xAA;
-pub const STREAMDECK_MAGIC_3: u8 = 0xD1;
-pub const STREAMDECK_RESET_MAGIC: u8 = 0x63;
-pub const STREAMDECK_BRIGHTNESS_RESET_MAGIC: u8 = 0x3E;
+pub const PARALLEL_DATA_BASE_PIN: u8 = 0; // D0; D1-D7 follow on consecutive GPIOs
+pub const PARALLEL_WR_PIN: u8 = 8; // Write strobe
+pub const PARALLEL_CS_PIN: u8 = 18; // Chip select (GPIO8 is WR); DC, RST and backlight are the TFT's
+pub const PARALLEL_PIO_CLOCK_HZ: u32 = 40_000_000; // 3 cycles per byte = 75 ns write cycle
+
+// ===================================================================
+// Logging
+// ===================================================================
+
+pub const LOG_RATE_LIMIT_MS: u32 = 1000; // Minimum interval between rate-limited messages per call site
+pub const LOG_BUFFER_SIZE: usize = 4096; // RAM kept for encoded defmt frames with `log-buffer`
 
 // ===================================================================
 // ST7735 Display Commands
 // ===================================================================
 
 pub const ST7735_SWRESET: u8 = 0x01; // Software reset
+pub const ST7735_SLPIN: u8 = 0x10; // Sleep in
 pub const ST7735_SLPOUT: u8 = 0x11; // Sleep out
 pub const ST7735_COLMOD: u8 = 0x3A; // Color mode
 pub const ST7735_CASET: u8 = 0x2A; // Column address set
 pub const ST7735_RASET: u8 = 0x2B; // Row address set
 pub const ST7735_INVOFF: u8 = 0x20; // Display inversion off
+pub const ST7735_INVON: u8 = 0x21; // Display inversion on
 pub const ST7735_NORON: u8 = 0x13; // Normal display mode
 pub const ST7735_DISPON: u8 = 0x29; // Display on
 pub const ST7735_RAMWR: u8 = 0x2C; // Memory write
+pub const ST7735_MADCTL: u8 = 0x36; // Memory data access control
+
+// MADCTL bits (MIPI DCS): row/column order and row-column exchange
+pub const MADCTL_MY: u8 = 0x80;
+pub const MADCTL_MX: u8 = 0x40;
+pub const MADCTL_MV: u8 = 0x20;
 
 // ST7735 Color format constants
 pub const ST7735_COLOR_MODE_16BIT: u8 = 0x05; // RGB565 format
+pub const DCS_COLOR_MODE_16BIT: u8 = 0x55; // RGB565 on MIPI DCS panels (ILI9486, ST7796)
 
 // RGB565 conversion masks
 pub const RGB565_RED_MASK: u16 = 0xF8;
 pub const RGB565_GREEN_MASK: u16 = 0xFC;
 pub const RGB565_BLUE_SHIFT: u8 = 3;
 
+// ===================================================================
+// SSD1680 E-paper Commands (`epaper-display` feature)
+// ===================================================================
+
+pub const SSD1680_DRIVER_OUTPUT: u8 = 0x01; // Gate count and scan direction
+pub const SSD1680_DEEP_SLEEP: u8 = 0x10; // Deep sleep mode
+pub const SSD1680_DATA_ENTRY_MODE: u8 = 0x11; // RAM address increment direction
+pub const SSD1680_SWRESET: u8 = 0x12; // Software reset
+pub const SSD1680_TEMP_SENSOR: u8 = 0x18; // Temperature sensor select
+pub const SSD1680_MASTER_ACTIVATION: u8 = 0x20; // Run the update sequence
+pub const SSD1680_UPDATE_CONTROL_1: u8 = 0x21; // RAM content options
+pub const SSD1680_UPDATE_CONTROL_2: u8 = 0x22; // Update sequence select
+pub const SSD1680_WRITE_RAM_BW: u8 = 0x24; // Write black/white RAM (new image)
+pub const SSD1680_WRITE_RAM_OLD: u8 = 0x26; // Write red RAM (old image for partial updates)
+pub const SSD1680_BORDER_WAVEFORM: u8 = 0x3C; // Border waveform
+pub const SSD1680_RAM_X_RANGE: u8 = 0x44; // RAM X start/end (bytes)
+pub const SSD1680_RAM_Y_RANGE: u8 = 0x45; // RAM Y start/end (gate lines)
+pub const SSD1680_RAM_X_COUNTER: u8 = 0x4E; // RAM X address counter
+pub const SSD1680_RAM_Y_COUNTER: u8 = 0x4F; // RAM Y address counter
+
+// SSD1680 update sequences
+pub const SSD1680_SEQUENCE_FULL: u8 = 0xF7; // Full waveform from OTP LUT
+pub const SSD1680_SEQUENCE_PARTIAL: u8 = 0xFF; // Partial (differential) waveform
+
 // ===================================================================
 // Backward Compatibility Constants
 // ===================================================================
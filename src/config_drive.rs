@@ -0,0 +1,601 @@
+//! USB configuration drive (`config-drive`)
+//!
+//! Holding `CONFIG_DRIVE_BOOT_KEY` at power-up starts the device as a small
+//! USB mass-storage drive instead of a StreamDeck. The drive is a FAT12
+//! volume in flash; users edit `PAGES.TXT` and drop key icons on it, and
+//! when the drive is ejected the firmware imports them and restarts:
+//!
+//! - `PAGES.TXT`: standalone pages (see `pages`), one setting per line:
+//!   `pagekey <key>|none`, `pages <count>`, `key <page> <key> <shortcut> [label]`.
+//!   Lines override the current setup; `#` starts a comment.
+//! - `KEYnn.BMP` / `KEYnnP.BMP` (`.JPG` on JPEG devices): icon and pressed
+//!   icon for key `nn` (see `key_images`).
+//! - `KEYnnA.BMP`: key images back to back, played on key `nn` in standalone
+//!   mode. These stay on the drive and are read from it (see `animations`).
+//!
+//! The volume is formatted with a template `PAGES.TXT` the first time the
+//! drive is used. Hosts may reformat it; FAT12 and FAT16 are understood.
+
+use defmt::*;
+use embassy_rp::flash::ERASE_SIZE;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration};
+use heapless::Vec;
+
+use crate::config::{LOCAL_IMAGE_KEYS, MAX_KEYS, PAGE_LABEL_LEN, STANDALONE_PAGES};
+use crate::device::{DeviceConfig, ImageFormat};
+use crate::pages::{self, PageTable, NO_PAGE_KEY};
+use crate::standalone::parse_shortcut;
+use crate::storage::{self, CONFIG_DRIVE_SIZE};
+use crate::types::KeyImageVariant;
+
+/// Bytes per block as seen by the host
+pub const BLOCK_SIZE: usize = 512;
+
+/// Blocks in the volume
+pub const BLOCK_COUNT: usize = CONFIG_DRIVE_SIZE / BLOCK_SIZE;
+
+/// Blocks per flash erase sector
+const BLOCKS_PER_SECTOR: usize = ERASE_SIZE / BLOCK_SIZE;
+
+/// How long the USB task waits for the key scan to settle the boot check
+const BOOT_CHECK_TIMEOUT_MS: u64 = crate::selftest::BOOT_TRIGGER_WINDOW_MS + 200;
+
+/// Longest `PAGES.TXT` line kept; the rest of a longer line is ignored
+const MAX_LINE_LEN: usize = 96;
+
+/// Set once the key scan has decided whether the boot key was held
+static BOOT_CHECK: Signal<CriticalSectionRawMutex, bool> = Signal::new();
+
+/// Report the power-up key check (from the key scan)
+pub fn finish_boot_check(requested: bool) {
+    if requested {
+        info!("Configuration drive requested");
+    }
+    BOOT_CHECK.signal(requested);
+}
+
+/// Whether to start as a configuration drive; waits for the power-up key check
+pub async fn boot_requested() -> bool {
+    with_timeout(
+        Duration::from_millis(BOOT_CHECK_TIMEOUT_MS),
+        BOOT_CHECK.wait(),
+    )
+    .await
+    .unwrap_or(false)
+}
+
+// ===================================================================
+// Block Access
+// ===================================================================
+
+/// Block view of the volume with a one-sector write-back cache, so that the
+/// host's sequential 512-byte writes cost one flash erase per 4 KB
+pub struct Volume {
+    cache: [u8; ERASE_SIZE],
+    cached_sector: Option<usize>,
+    dirty: bool,
+}
+
+impl Volume {
+    pub const fn new() -> Self {
+        Self {
+            cache: [0; ERASE_SIZE],
+            cached_sector: None,
+            dirty: false,
+        }
+    }
+
+    /// Read one block
+    pub fn read_block(&self, lba: usize, out: &mut [u8; BLOCK_SIZE]) -> Result<(), &'static str> {
+        if lba >= BLOCK_COUNT {
+            return Err("Block out of range");
+        }
+        let (sector, index) = (lba / BLOCKS_PER_SECTOR, lba % BLOCKS_PER_SECTOR);
+        let block = if self.cached_sector == Some(sector) {
+            &self.cache[index * BLOCK_SIZE..(index + 1) * BLOCK_SIZE]
+        } else {
+            &storage::config_drive()[lba * BLOCK_SIZE..(lba + 1) * BLOCK_SIZE]
+        };
+        out.copy_from_slice(block);
+        Ok(())
+    }
+
+    /// Write one block; it reaches flash when another sector is written or on `flush`
+    pub fn write_block(&mut self, lba: usize, data: &[u8; BLOCK_SIZE]) -> Result<(), &'static str> {
+        if lba >= BLOCK_COUNT {
+            return Err("Block out of range");
+        }
+        let (sector, index) = (lba / BLOCKS_PER_SECTOR, lba % BLOCKS_PER_SECTOR);
+        if self.cached_sector != Some(sector) {
+            self.flush()?;
+            let start = sector * ERASE_SIZE;
+            self.cache
+                .copy_from_slice(&storage::config_drive()[start..start + ERASE_SIZE]);
+            self.cached_sector = Some(sector);
+        }
+        let block = &mut self.cache[index * BLOCK_SIZE..(index + 1) * BLOCK_SIZE];
+        if block != data {
+            block.copy_from_slice(data);
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Write the cached sector back to flash if it changed
+    pub fn flush(&mut self) -> Result<(), &'static str> {
+        if let (Some(sector), true) = (self.cached_sector, self.dirty) {
+            storage::write_config_drive_sector(sector, &self.cache)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ===================================================================
+// Formatting
+// ===================================================================
+
+/// Layout of the volume as formatted by the firmware
+const SECTORS_PER_CLUSTER: u8 = 8;
+/// Two reserved blocks put the data area on a flash sector boundary
+const RESERVED_BLOCKS: u16 = 2;
+const FAT_COUNT: u8 = 2;
+const FAT_BLOCKS: u16 = 1;
+const ROOT_ENTRIES: u16 = 64;
+const DIR_ENTRY_SIZE: usize = 32;
+const ROOT_BLOCKS: usize = ROOT_ENTRIES as usize * DIR_ENTRY_SIZE / BLOCK_SIZE;
+const DATA_START: usize =
+    RESERVED_BLOCKS as usize + (FAT_COUNT as u16 * FAT_BLOCKS) as usize + ROOT_BLOCKS;
+const _: () = core::assert!(DATA_START.is_multiple_of(BLOCKS_PER_SECTOR));
+
+const VOLUME_LABEL: &[u8; 11] = b"PRODDECK   ";
+const PAGES_FILE: &[u8; 11] = b"PAGES   TXT";
+
+const PAGES_TEMPLATE: &str = "\
+# ProductionDeck standalone pages. Eject the drive to apply.
+#
+#   pagekey <key>|none     key that cycles through the pages
+#   pages <count>          pages in use (1-4)
+#   key <page> <key> <shortcut> [label]
+#
+# Shortcuts: CTRL+SHIFT+A, ALT+F4, F13, 0x68, NONE. Labels: up to 8 characters.
+# Icons: KEY00.BMP and KEY00P.BMP (pressed) for key 0, KEY01.BMP ... (.JPG on
+# JPEG devices), sized like the host's key images. KEY00A.BMP: BMP frames
+# back to back, animated on key 0 while no StreamDeck software is running.
+#
+# pagekey 5
+# pages 2
+# key 0 0 CTRL+C Copy
+# key 1 0 F13 Scene1
+";
+
+/// Format the volume unless it already holds a FAT file system
+pub fn ensure_formatted(volume: &mut Volume) -> Result<(), &'static str> {
+    let mut block = [0u8; BLOCK_SIZE];
+    volume.read_block(0, &mut block)?;
+    if Fat::parse(&block).is_some() {
+        return Ok(());
+    }
+    info!("Formatting configuration drive");
+
+    // Boot sector with the FAT12 BIOS parameter block
+    block.fill(0);
+    block[0..3].copy_from_slice(&[0xEB, 0x3C, 0x90]);
+    block[3..11].copy_from_slice(b"PRODDECK");
+    block[11..13].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+    block[13] = SECTORS_PER_CLUSTER;
+    block[14..16].copy_from_slice(&RESERVED_BLOCKS.to_le_bytes());
+    block[16] = FAT_COUNT;
+    block[17..19].copy_from_slice(&ROOT_ENTRIES.to_le_bytes());
+    block[19..21].copy_from_slice(&(BLOCK_COUNT as u16).to_le_bytes());
+    block[21] = 0xF8; // Fixed disk
+    block[22..24].copy_from_slice(&FAT_BLOCKS.to_le_bytes());
+    block[24..26].copy_from_slice(&1u16.to_le_bytes()); // Sectors per track
+    block[26..28].copy_from_slice(&1u16.to_le_bytes()); // Heads
+    block[36] = 0x80; // Drive number
+    block[38] = 0x29; // Extended boot signature
+    block[39..43].copy_from_slice(&0x5044_4B31u32.to_le_bytes()); // Volume ID
+    block[43..54].copy_from_slice(VOLUME_LABEL);
+    block[54..62].copy_from_slice(b"FAT12   ");
+    block[510..512].copy_from_slice(&[0x55, 0xAA]);
+    volume.write_block(0, &block)?;
+
+    // Clear the rest of the metadata
+    block.fill(0);
+    for lba in 1..DATA_START {
+        volume.write_block(lba, &block)?;
+    }
+
+    // FATs: media descriptor, then cluster 2 (the template) ends its chain
+    block[0..5].copy_from_slice(&[0xF8, 0xFF, 0xFF, 0xFF, 0x0F]);
+    for fat in 0..FAT_COUNT as usize {
+        volume.write_block(RESERVED_BLOCKS as usize + fat * FAT_BLOCKS as usize, &block)?;
+    }
+
+    // Root directory: volume label and the template
+    block.fill(0);
+    block[0..11].copy_from_slice(VOLUME_LABEL);
+    block[11] = 0x08; // Volume label attribute
+    let entry = &mut block[DIR_ENTRY_SIZE..2 * DIR_ENTRY_SIZE];
+    entry[0..11].copy_from_slice(PAGES_FILE);
+    entry[11] = 0x20; // Archive attribute
+    entry[26..28].copy_from_slice(&2u16.to_le_bytes());
+    entry[28..32].copy_from_slice(&(PAGES_TEMPLATE.len() as u32).to_le_bytes());
+    volume.write_block(
+        RESERVED_BLOCKS as usize + (FAT_COUNT as u16 * FAT_BLOCKS) as usize,
+        &block,
+    )?;
+
+    // Template contents in cluster 2
+    for (i, chunk) in PAGES_TEMPLATE.as_bytes().chunks(BLOCK_SIZE).enumerate() {
+        block.fill(0);
+        block[..chunk.len()].copy_from_slice(chunk);
+        volume.write_block(DATA_START + i, &block)?;
+    }
+
+    volume.flush()
+}
+
+// ===================================================================
+// FAT Reading
+// ===================================================================
+
+/// Geometry of a FAT12/FAT16 volume, from its BIOS parameter block
+#[derive(Clone, Copy)]
+struct Fat {
+    sectors_per_cluster: usize,
+    fat_start: usize,
+    root_start: usize,
+    root_entries: usize,
+    data_start: usize,
+    cluster_count: usize,
+    fat16: bool,
+}
+
+/// A file found in the root directory
+struct FileEntry {
+    first_cluster: usize,
+    size: usize,
+}
+
+impl Fat {
+    fn parse(boot: &[u8]) -> Option<Self> {
+        if boot[510..512] != [0x55, 0xAA] {
+            return None;
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([boot[at], boot[at + 1]]) as usize;
+        if u16_at(11) != BLOCK_SIZE {
+            return None;
+        }
+        let sectors_per_cluster = boot[13] as usize;
+        let reserved = u16_at(14);
+        let fats = boot[16] as usize;
+        let root_entries = u16_at(17);
+        let total = match u16_at(19) {
+            0 => u32::from_le_bytes([boot[32], boot[33], boot[34], boot[35]]) as usize,
+            n => n,
+        };
+        let fat_size = u16_at(22);
+        if sectors_per_cluster == 0 || fats == 0 || fat_size == 0 || total > BLOCK_COUNT {
+            return None;
+        }
+
+        let root_start = reserved + fats * fat_size;
+        let data_start = root_start + (root_entries * DIR_ENTRY_SIZE).div_ceil(BLOCK_SIZE);
+        let cluster_count = total.checked_sub(data_start)? / sectors_per_cluster;
+        Some(Self {
+            sectors_per_cluster,
+            fat_start: reserved,
+            root_start,
+            root_entries,
+            data_start,
+            cluster_count,
+            fat16: cluster_count >= 4085,
+        })
+    }
+
+    /// Look up a file in the root directory by its 8.3 name
+    fn find(&self, volume: &[u8], name: &[u8; 11]) -> Option<FileEntry> {
+        let root = volume.get(self.root_start * BLOCK_SIZE..)?;
+        for entry in root.chunks_exact(DIR_ENTRY_SIZE).take(self.root_entries) {
+            match entry[0] {
+                0x00 => break,    // End of directory
+                0xE5 => continue, // Deleted
+                _ => {}
+            }
+            // Skip volume labels, directories and long name entries
+            if entry[11] & 0x18 != 0 || !entry[..11].eq_ignore_ascii_case(name) {
+                continue;
+            }
+            return Some(FileEntry {
+                first_cluster: u16::from_le_bytes([entry[26], entry[27]]) as usize,
+                size: u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]) as usize,
+            });
+        }
+        None
+    }
+
+    /// Next cluster of a chain, None at its end
+    fn next_cluster(&self, volume: &[u8], cluster: usize) -> Option<usize> {
+        let fat = &volume[self.fat_start * BLOCK_SIZE..];
+        let next = if self.fat16 {
+            let at = cluster * 2;
+            let value = u16::from_le_bytes([*fat.get(at)?, *fat.get(at + 1)?]) as usize;
+            (value < 0xFFF8).then_some(value)?
+        } else {
+            let at = cluster * 3 / 2;
+            let pair = u16::from_le_bytes([*fat.get(at)?, *fat.get(at + 1)?]) as usize;
+            let value = if cluster % 2 == 1 {
+                pair >> 4
+            } else {
+                pair & 0xFFF
+            };
+            (value < 0xFF8).then_some(value)?
+        };
+        (2..self.cluster_count + 2).contains(&next).then_some(next)
+    }
+
+    /// Pass a file's contents to `f` one cluster at a time
+    fn read_file(&self, volume: &'static [u8], file: &FileEntry, mut f: impl FnMut(&[u8])) {
+        for data in self.chunks(volume, file, 0, file.size) {
+            f(data);
+        }
+    }
+
+    /// `len` bytes of a file from `start`, in pieces that end at cluster boundaries
+    fn chunks(
+        &self,
+        volume: &'static [u8],
+        file: &FileEntry,
+        start: usize,
+        len: usize,
+    ) -> FileChunks {
+        FileChunks {
+            fat: *self,
+            volume,
+            cluster: file.first_cluster,
+            skip: start,
+            remaining: len.min(file.size.saturating_sub(start)),
+            // Bounded by the cluster count, in case of a looped chain
+            steps: self.cluster_count,
+        }
+    }
+}
+
+/// Pieces of a file read in place from the volume (`Fat::chunks`)
+pub struct FileChunks {
+    fat: Fat,
+    volume: &'static [u8],
+    cluster: usize,
+    /// Bytes still to skip before the range starts
+    skip: usize,
+    remaining: usize,
+    steps: usize,
+}
+
+impl Iterator for FileChunks {
+    type Item = &'static [u8];
+
+    fn next(&mut self) -> Option<&'static [u8]> {
+        let cluster_bytes = self.fat.sectors_per_cluster * BLOCK_SIZE;
+        while self.remaining > 0 && self.steps > 0 {
+            self.steps -= 1;
+            if !(2..self.fat.cluster_count + 2).contains(&self.cluster) {
+                return None;
+            }
+            let cluster = self.cluster;
+            let next = self.fat.next_cluster(self.volume, cluster);
+            if self.skip >= cluster_bytes {
+                self.skip -= cluster_bytes;
+                self.cluster = next?;
+                continue;
+            }
+            let start = (self.fat.data_start + (cluster - 2) * self.fat.sectors_per_cluster)
+                * BLOCK_SIZE
+                + self.skip;
+            let len = self.remaining.min(cluster_bytes - self.skip);
+            let data = self.volume.get(start..start + len)?;
+            self.skip = 0;
+            self.remaining -= len;
+            // A chain that ends early leaves the rest of the range unread
+            match next {
+                Some(next) => self.cluster = next,
+                None => self.remaining = 0,
+            }
+            return Some(data);
+        }
+        None
+    }
+}
+
+/// A file in the root directory of the configuration drive, read in place from flash
+pub struct StoredFile {
+    fat: Fat,
+    entry: FileEntry,
+}
+
+impl StoredFile {
+    /// Look up a file by its 8.3 name (`b"KEY00A  BMP"`); None without a FAT volume
+    pub fn find(name: &[u8; 11]) -> Option<Self> {
+        let volume = storage::config_drive();
+        let fat = Fat::parse(&volume[..BLOCK_SIZE])?;
+        let entry = fat.find(volume, name)?;
+        Some(Self { fat, entry })
+    }
+
+    pub fn size(&self) -> usize {
+        self.entry.size
+    }
+
+    /// `len` bytes from `start`, a cluster at a time
+    pub fn chunks(&self, start: usize, len: usize) -> FileChunks {
+        self.fat
+            .chunks(storage::config_drive(), &self.entry, start, len)
+    }
+}
+
+// ===================================================================
+// Import
+// ===================================================================
+
+/// Apply `PAGES.TXT` and the key icons from the volume; call after `flush`
+pub fn import() {
+    let volume = storage::config_drive();
+    let Some(fat) = Fat::parse(&volume[..BLOCK_SIZE]) else {
+        warn!("Configuration drive has no FAT volume, nothing to import");
+        return;
+    };
+
+    if let Some(file) = fat.find(volume, PAGES_FILE) {
+        import_pages(&fat, volume, &file);
+    }
+
+    let device = crate::config::get_current_device();
+    let extension: &[u8; 3] = match device.display_config().format {
+        ImageFormat::Bmp => b"BMP",
+        ImageFormat::Jpeg => b"JPG",
+    };
+    let keys = device.button_layout().total_keys.min(LOCAL_IMAGE_KEYS);
+    for key in 0..keys as u8 {
+        for variant in [KeyImageVariant::Icon, KeyImageVariant::Pressed] {
+            let mut name = *b"KEY00      ";
+            name[3] = b'0' + key / 10;
+            name[4] = b'0' + key % 10;
+            if variant == KeyImageVariant::Pressed {
+                name[5] = b'P';
+            }
+            name[8..11].copy_from_slice(extension);
+            if let Some(file) = fat.find(volume, &name) {
+                import_icon(&fat, volume, &file, key, variant);
+            }
+        }
+    }
+}
+
+fn import_pages(fat: &Fat, volume: &'static [u8], file: &FileEntry) {
+    let mut table = pages::with(|t| t.clone());
+    let mut shortcuts = crate::settings::with(|s| s.shortcuts);
+    let mut line: Vec<u8, MAX_LINE_LEN> = Vec::new();
+    let (mut number, mut errors) = (0, 0);
+
+    let mut finish_line = |line: &mut Vec<u8, MAX_LINE_LEN>| {
+        number += 1;
+        let text = core::str::from_utf8(line).unwrap_or("\u{0}");
+        if !apply_line(text, &mut table, &mut shortcuts) {
+            warn!("PAGES.TXT line {}: not understood", number);
+            errors += 1;
+        }
+        line.clear();
+    };
+    fat.read_file(volume, file, |data| {
+        for &b in data {
+            if b == b'\n' {
+                finish_line(&mut line);
+            } else {
+                let _ = line.push(b);
+            }
+        }
+    });
+    if !line.is_empty() {
+        finish_line(&mut line);
+    }
+
+    pages::replace(table);
+    crate::settings::update(|s| s.shortcuts = shortcuts);
+    if let Err(e) = storage::save_pages().and_then(|()| storage::save_settings()) {
+        warn!("Failed to persist imported pages: {}", e);
+    }
+    info!(
+        "Imported PAGES.TXT ({} lines, {} not understood)",
+        number, errors
+    );
+}
+
+/// Apply one line of `PAGES.TXT`; false if it is malformed
+fn apply_line(
+    line: &str,
+    table: &mut PageTable,
+    shortcuts: &mut [crate::standalone::KeyShortcut; MAX_KEYS],
+) -> bool {
+    let line = line.split('#').next().unwrap_or("").trim();
+    let mut words = line.split_ascii_whitespace();
+    let Some(command) = words.next() else {
+        return true; // Blank or comment
+    };
+    let number = |word: Option<&str>| word.and_then(|w| w.parse::<usize>().ok());
+
+    if command.eq_ignore_ascii_case("pagekey") {
+        match words.next() {
+            Some(w) if w.eq_ignore_ascii_case("none") => table.page_key = NO_PAGE_KEY,
+            w => match number(w) {
+                Some(key) if key < MAX_KEYS => table.page_key = key as u8,
+                _ => return false,
+            },
+        }
+    } else if command.eq_ignore_ascii_case("pages") {
+        match number(words.next()) {
+            Some(count @ 1..=STANDALONE_PAGES) => table.page_count = count as u8,
+            _ => return false,
+        }
+    } else if command.eq_ignore_ascii_case("key") {
+        let (Some(page), Some(key)) = (number(words.next()), number(words.next())) else {
+            return false;
+        };
+        let Some(shortcut) = words.next().and_then(parse_shortcut) else {
+            return false;
+        };
+        let label = words.next().unwrap_or("").as_bytes();
+        if page >= STANDALONE_PAGES || key >= MAX_KEYS || label.len() > PAGE_LABEL_LEN {
+            return false;
+        }
+        let entry = &mut table.entries[page][key];
+        entry.shortcut = shortcut;
+        entry.label = [0; PAGE_LABEL_LEN];
+        entry.label[..label.len()].copy_from_slice(label);
+        if page == 0 {
+            shortcuts[key] = shortcut;
+        }
+    } else {
+        return false;
+    }
+    words.next().is_none()
+}
+
+fn import_icon(
+    fat: &Fat,
+    volume: &'static [u8],
+    file: &FileEntry,
+    key: u8,
+    variant: KeyImageVariant,
+) {
+    if let Err(e) = crate::key_images::begin(key, variant) {
+        warn!("Key {} {} icon not imported: {}", key, variant, e);
+        return;
+    }
+    // Flash can't be programmed from data that is itself in flash
+    let mut buf = [0u8; 256];
+    let mut offset = 0;
+    let mut result = Ok(());
+    fat.read_file(volume, file, |data| {
+        for chunk in data.chunks(buf.len()) {
+            if result.is_err() {
+                return;
+            }
+            buf[..chunk.len()].copy_from_slice(chunk);
+            result = crate::key_images::write(key, variant, offset, &buf[..chunk.len()]);
+            offset += chunk.len();
+        }
+    });
+    match result.and_then(|()| crate::key_images::commit(key, variant, offset)) {
+        Ok(()) => info!("Imported key {} {} icon ({} bytes)", key, variant, offset),
+        Err(e) => warn!("Key {} {} icon not imported: {}", key, variant, e),
+    }
+}
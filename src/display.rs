@@ -2,51 +2,400 @@
 //!
 //! This module manages a single 216x144 display divided into 6 regions (72x72 each)
 //! to simulate individual key displays like the StreamDeck Mini.
+//!
+//! The controller speaks MIPI DCS commands through a `PanelDriver`, so the same
+//! drawing code runs over the ST7735's SPI bus or, with the `parallel-display`
+//! feature, a PIO-driven 8080 parallel bus (see `parallel_bus`).
+//!
+//! Panels mounted rotated are handled by the controller's MADCTL: every key
+//! region is computed in the mounted orientation by `key_region` and the
+//! controller maps it onto its own memory. Key images the host sends turned
+//! (the Mini and the Modules, see `DisplayConfig::rotation`) are turned back
+//! as they are drawn.
 
 #![allow(dead_code)]
 
+use core::cell::RefCell;
 use defmt::*;
+use embassy_futures::select::{select4, Either4};
 use embassy_rp::gpio::Output;
 use embassy_rp::peripherals;
-use embassy_rp::spi::Spi;
-use embassy_time::{Duration, Timer};
+use embassy_rp::pwm::{self, Pwm};
+use embassy_rp::spi::{self, Spi};
+use embassy_rp::Peri;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::spi::SpiDevice;
+use embedded_hal_bus::spi::{NoDelay, RefCellDevice};
 use heapless::Vec;
+use static_cell::StaticCell;
 
-use crate::channels::DISPLAY_CHANNEL;
+use crate::brightness::{self, Brightness};
+use crate::channels::{
+    next_device_command, subscribe_device_bus, DISPLAY_CHANNEL, DISPLAY_CONTROL_CHANNEL,
+};
 use crate::config::*;
-use crate::types::DisplayCommand;
+use crate::error::DisplayError;
+use crate::gamma;
+use crate::gfx;
+use crate::hardware::{ColorOrder, HardwareConfig, PanelConfig};
+use crate::image_pool::ImageSlab;
+use crate::logging::Subsystem;
+use crate::power::StartupStage;
+use crate::protocol::image;
+use crate::protocol::image_check::solid_pixel;
+use crate::stats::{PipelineTiming, Pool};
+use crate::types::{DeviceCommand, DisplayCommand, LcdRegion, RleImage, Rotation, Screen};
+use crate::{log_at, log_limited};
 
 // ===================================================================
-// Display Controller Structure
+// Panel Bus
 // ===================================================================
 
-struct DisplayController {
-    spi: Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+/// Bus to a DCS-style panel controller (command/data select plus chip select)
+pub(crate) trait PanelDriver {
+    /// COLMOD parameter selecting 16-bit RGB565 on this panel's controller
+    const COLOR_MODE_16BIT: u8;
+
+    /// Assert chip select
+    fn select(&mut self);
+
+    /// Release chip select once all queued bytes are out
+    async fn deselect(&mut self);
+
+    /// Send a command byte
+    async fn command(&mut self, command: u8);
+
+    /// Send parameter or pixel bytes
+    async fn data(&mut self, data: &[u8]);
+}
+
+/// ST7735 on SPI0 or SPI1 with GPIO chip select and data/command lines
+pub(crate) struct SpiPanel<T: spi::Instance + 'static> {
+    spi: Spi<'static, T, spi::Blocking>,
     cs: Output<'static>,
     dc: Output<'static>,
-    rst: Output<'static>,
-    // backlight: Pwm<'static, PWM0>,
-    current_brightness: u8,
 }
 
-impl DisplayController {
-    async fn new(
-        spi: Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+impl<T: spi::Instance> PanelDriver for SpiPanel<T> {
+    const COLOR_MODE_16BIT: u8 = ST7735_COLOR_MODE_16BIT;
+
+    fn select(&mut self) {
+        self.cs.set_low();
+    }
+
+    async fn deselect(&mut self) {
+        self.cs.set_high();
+    }
+
+    async fn command(&mut self, command: u8) {
+        // Set DC pin low for command mode
+        self.dc.set_low();
+
+        // Send command byte
+        let _ = self.spi.blocking_write(&[command]);
+    }
+
+    async fn data(&mut self, data: &[u8]) {
+        // Set DC pin high for data mode
+        self.dc.set_high();
+
+        // Send data
+        let _ = self.spi.blocking_write(data);
+    }
+}
+
+// ===================================================================
+// Multi-Panel Bus
+// ===================================================================
+
+type SharedSpi = Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>;
+type PanelDevice = RefCellDevice<'static, SharedSpi, Output<'static>, NoDelay>;
+
+/// One panel per key on a shared SPI bus
+///
+/// Presents the panels to `DisplayController` as one virtual panel laid out
+/// like the key grid. Setup commands go to every panel. A drawing window is
+/// split at RAMWR: each panel it touches gets its clipped, panel-local window,
+/// and pixel data is then routed run by run to the panel under the cursor.
+/// The controllers keep their memory write going across chip-select gaps,
+/// so interleaved rows land correctly.
+pub(crate) struct MultiPanelBus {
+    /// Chip select is driven by the `RefCellDevice` around each transfer
+    panels: Vec<(PanelDevice, Output<'static>), MAX_KEYS>,
+    pending: PendingCommand,
+    window: (u16, u16, u16, u16),
+    /// Next pixel position within the window during a memory write
+    cursor: (u16, u16),
+}
+
+/// What the next data bytes belong to
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PendingCommand {
+    Broadcast,
+    ColumnRange,
+    RowRange,
+    MemoryWrite,
+}
+
+impl MultiPanelBus {
+    /// `panels` holds (chip select, data/command) per key, in key order
+    pub fn new(spi: SharedSpi, panels: Vec<(Output<'static>, Output<'static>), MAX_KEYS>) -> Self {
+        static BUS: StaticCell<RefCell<SharedSpi>> = StaticCell::new();
+        let bus: &'static RefCell<SharedSpi> = BUS.init(RefCell::new(spi));
+
+        let mut devices = Vec::new();
+        for (cs, dc) in panels {
+            let Ok(device) = RefCellDevice::new_no_delay(bus, cs);
+            let _ = devices.push((device, dc));
+        }
+        info!("Multi-panel bus with {} panels", devices.len());
+
+        Self {
+            panels: devices,
+            pending: PendingCommand::Broadcast,
+            window: (0, 0, 0, 0),
+            cursor: (0, 0),
+        }
+    }
+
+    fn send(&mut self, index: usize, command: bool, bytes: &[u8]) {
+        let Some((device, dc)) = self.panels.get_mut(index) else {
+            return;
+        };
+        if command {
+            dc.set_low();
+        } else {
+            dc.set_high();
+        }
+        let _ = device.write(bytes);
+    }
+
+    /// Panel owning a virtual pixel, or None outside the key grid
+    fn panel_at(x: u16, y: u16) -> Option<usize> {
+        let size = crate::config::key_image_size() as u16;
+        let (col, row) = ((x / size) as usize, (y / size) as usize);
+        let cols = crate::config::streamdeck_cols();
+        (col < cols && row < crate::config::streamdeck_rows()).then_some(row * cols + col)
+    }
+
+    /// Give every panel under the window its part of it, in panel coordinates
+    fn start_memory_write(&mut self) {
+        let (x0, y0, x1, y1) = self.window;
+        let size = crate::config::key_image_size() as u16;
+        let cols = crate::config::streamdeck_cols();
+        for index in 0..self.panels.len() {
+            let (px, py) = ((index % cols) as u16 * size, (index / cols) as u16 * size);
+            let (left, right) = (x0.max(px), x1.min(px + size - 1));
+            let (top, bottom) = (y0.max(py), y1.min(py + size - 1));
+            if left > right || top > bottom {
+                continue;
+            }
+            let [l0, l1] = (left - px).to_be_bytes();
+            let [r0, r1] = (right - px).to_be_bytes();
+            let [t0, t1] = (top - py).to_be_bytes();
+            let [b0, b1] = (bottom - py).to_be_bytes();
+            self.send(index, true, &[ST7735_CASET]);
+            self.send(index, false, &[l0, l1, r0, r1]);
+            self.send(index, true, &[ST7735_RASET]);
+            self.send(index, false, &[t0, t1, b0, b1]);
+            self.send(index, true, &[ST7735_RAMWR]);
+        }
+        self.cursor = (x0, y0);
+    }
+
+    /// Route RGB565 pixel data to the panels in runs along each window row
+    fn write_pixels(&mut self, mut data: &[u8]) {
+        let (x0, _, x1, _) = self.window;
+        let size = crate::config::key_image_size() as u16;
+        while data.len() >= 2 {
+            let (x, y) = self.cursor;
+            // Up to the end of the window row or the panel edge
+            let run = (x1 + 1 - x).min(size - x % size) as usize;
+            let run = run.min(data.len() / 2);
+            if let Some(index) = Self::panel_at(x, y) {
+                self.send(index, false, &data[..run * 2]);
+            }
+            data = &data[run * 2..];
+
+            let x = x + run as u16;
+            self.cursor = if x > x1 { (x0, y + 1) } else { (x, y) };
+        }
+    }
+}
+
+impl PanelDriver for MultiPanelBus {
+    const COLOR_MODE_16BIT: u8 = ST7735_COLOR_MODE_16BIT;
+
+    fn select(&mut self) {}
+
+    async fn deselect(&mut self) {}
+
+    async fn command(&mut self, command: u8) {
+        self.pending = match command {
+            ST7735_CASET => PendingCommand::ColumnRange,
+            ST7735_RASET => PendingCommand::RowRange,
+            ST7735_RAMWR => {
+                self.start_memory_write();
+                PendingCommand::MemoryWrite
+            }
+            _ => {
+                for index in 0..self.panels.len() {
+                    self.send(index, true, &[command]);
+                }
+                PendingCommand::Broadcast
+            }
+        };
+    }
+
+    async fn data(&mut self, data: &[u8]) {
+        let range = |d: &[u8]| match *d {
+            [s0, s1, e0, e1, ..] => {
+                Some((u16::from_be_bytes([s0, s1]), u16::from_be_bytes([e0, e1])))
+            }
+            _ => None,
+        };
+        match self.pending {
+            PendingCommand::Broadcast => {
+                for index in 0..self.panels.len() {
+                    self.send(index, false, data);
+                }
+            }
+            PendingCommand::ColumnRange => {
+                if let Some((start, end)) = range(data) {
+                    (self.window.0, self.window.2) = (start, end);
+                }
+            }
+            PendingCommand::RowRange => {
+                if let Some((start, end)) = range(data) {
+                    (self.window.1, self.window.3) = (start, end);
+                }
+            }
+            PendingCommand::MemoryWrite => self.write_pixels(data),
+        }
+    }
+}
+
+/// Display peripheral chosen by the board profile (`HardwareConfig::resolve_display_bus`)
+pub enum DisplayLink {
+    Spi0 {
+        spi: Spi<'static, peripherals::SPI0, spi::Blocking>,
+        cs: Output<'static>,
+        dc: Output<'static>,
+    },
+    Spi1 {
+        spi: Spi<'static, peripherals::SPI1, spi::Blocking>,
         cs: Output<'static>,
         dc: Output<'static>,
-        rst: Output<'static>,
-        _bl: Output<'static>,
+    },
+}
+
+/// Pixels buffered per bus transfer when streaming RGB565
+const PIXEL_BATCH: usize = 64;
+
+/// RGB565 color as bus bytes (big-endian) in the panel's channel order
+fn encode_pixel(color: u16, order: ColorOrder) -> [u8; 2] {
+    let color = match order {
+        ColorOrder::Rgb => color,
+        // Swap the 5-bit red and blue fields
+        ColorOrder::Bgr => (color << 11) | (color & 0x07E0) | (color >> 11),
+    };
+    color.to_be_bytes()
+}
+
+// ===================================================================
+// Backlight
+// ===================================================================
+
+/// Backlight LED on PWM slice 0 channel B (GPIO17), 256 duty steps
+pub struct Backlight {
+    pwm: Pwm<'static>,
+    config: pwm::Config,
+}
+
+impl Backlight {
+    /// Take the backlight pin; it starts dark
+    pub fn new(
+        slice: Peri<'static, peripherals::PWM_SLICE0>,
+        pin: Peri<'static, peripherals::PIN_17>,
     ) -> Self {
+        let mut config = pwm::Config::default();
+        config.divider = BACKLIGHT_PWM_DIVIDER.into();
+        // compare_b = top + 1 holds the output high: duty 255 is fully on
+        config.top = 254;
+        config.compare_b = 0;
+        Self {
+            pwm: Pwm::new_output_b(slice, pin, config.clone()),
+            config,
+        }
+    }
+
+    /// Drive the backlight at `duty` (0 = off, 255 = fully on)
+    pub fn set_duty(&mut self, duty: u8) {
+        self.config.compare_b = duty as u16;
+        self.pwm.set_config(&self.config);
+    }
+}
+
+// ===================================================================
+// Display Controller Structure
+// ===================================================================
+
+struct DisplayController<P: PanelDriver> {
+    panel: P,
+    rst: Output<'static>,
+    bl: Backlight,
+    brightness: Brightness,
+    /// Backlight switched on: set after the startup ramp's first level,
+    /// cleared while asleep. A cap of 0% darkens it but leaves this set, so
+    /// the level comes back when the cap lifts.
+    backlight_on: bool,
+    /// Color order, inversion and MADCTL quirks from the board profile
+    quirks: PanelConfig,
+    /// When each key's press highlight ends (`key-feedback`)
+    highlight_until: [Option<Instant>; MAX_KEYS],
+    /// Press animation frame shown on each key, 0 = none (touch builds)
+    press_frame: [u16; MAX_KEYS],
+    /// When the press animation next advances
+    next_press_frame: Option<Instant>,
+    /// Screens the host has put to sleep, indexed by `Screen`
+    screen_asleep: [bool; 2],
+    /// Start of the current run of panel writes (see `yield_slice`)
+    slice_started: Instant,
+    /// This core's interpolators and the tables they convert through
+    #[cfg(feature = "interp-convert")]
+    interp: crate::interp::Interp,
+    #[cfg(feature = "interp-convert")]
+    pixel_lut: crate::interp::PixelLut,
+}
+
+impl<P: PanelDriver> DisplayController<P> {
+    async fn new(panel: P, rst: Output<'static>, bl: Backlight) -> Self {
         info!("Initializing display controller");
 
         let mut controller = Self {
-            spi,
-            cs,
-            dc,
+            panel,
             rst,
-            current_brightness: crate::config::display_brightness(),
+            bl,
+            brightness: Brightness::new(crate::brightness::host_percent()),
+            backlight_on: false,
+            quirks: HardwareConfig::for_current_device().panel,
+            highlight_until: [None; MAX_KEYS],
+            press_frame: [0; MAX_KEYS],
+            next_press_frame: None,
+            screen_asleep: [false; 2],
+            slice_started: Instant::now(),
+            #[cfg(feature = "interp-convert")]
+            interp: crate::interp::Interp::claim(),
+            #[cfg(feature = "interp-convert")]
+            pixel_lut: crate::interp::PixelLut::new(),
         };
 
+        #[cfg(feature = "interp-convert")]
+        crate::interp::benchmark(&controller.interp, Self::rgb888_to_565);
+
+        // Backlight stays dark until the supervisor ramps it up
+        controller.bl.set_duty(0);
+
         // Initialize the display
         controller.init_display().await;
 
@@ -61,7 +410,7 @@ impl DisplayController {
         );
 
         // Select the display
-        self.cs.set_low();
+        self.panel.select();
 
         // Reset the display
         self.rst.set_low();
@@ -78,7 +427,12 @@ impl DisplayController {
 
         // Color mode - 16 bit RGB565
         self.send_command(ST7735_COLMOD).await;
-        self.send_data(&[ST7735_COLOR_MODE_16BIT]).await;
+        self.send_data(&[P::COLOR_MODE_16BIT]).await;
+
+        // Memory access order for the mounting orientation
+        let rotation = crate::hardware::display_rotation();
+        self.send_command(ST7735_MADCTL).await;
+        self.send_data(&[self.madctl(rotation)]).await;
 
         // Column address set (0 to display_total_width-1)
         self.send_command(ST7735_CASET).await;
@@ -102,8 +456,12 @@ impl DisplayController {
         ])
         .await;
 
-        // Display inversion off
-        self.send_command(ST7735_INVOFF).await;
+        // Inversion as the panel module needs it
+        if self.quirks.invert {
+            self.send_command(ST7735_INVON).await;
+        } else {
+            self.send_command(ST7735_INVOFF).await;
+        }
 
         // Normal display mode
         self.send_command(ST7735_NORON).await;
@@ -113,7 +471,7 @@ impl DisplayController {
         Timer::after(Duration::from_millis(10)).await;
 
         // Deselect display
-        self.cs.set_high();
+        self.panel.deselect().await;
 
         info!("Shared display initialization complete");
 
@@ -121,20 +479,60 @@ impl DisplayController {
         self.clear_all().await;
     }
 
-    async fn send_command(&mut self, command: u8) {
-        // Set DC pin low for command mode
-        self.dc.set_low();
+    /// MADCTL value that shows the key grid upright on a panel mounted at `rotation`
+    fn madctl(&self, rotation: Rotation) -> u8 {
+        let orientation = match rotation {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => MADCTL_MX | MADCTL_MV,
+            Rotation::Deg180 => MADCTL_MX | MADCTL_MY,
+            Rotation::Deg270 => MADCTL_MY | MADCTL_MV,
+        };
+        orientation ^ self.quirks.madctl
+    }
 
-        // Send command byte
-        let _ = self.spi.blocking_write(&[command]);
+    /// Switch orientation at runtime; the old content would show rotated, so clear it
+    async fn set_rotation(&mut self, rotation: Rotation) {
+        self.panel.select();
+        self.send_command(ST7735_MADCTL).await;
+        self.send_data(&[self.madctl(rotation)]).await;
+        self.panel.deselect().await;
+        info!("Display rotation set to {}", rotation);
+        self.clear_all().await;
+    }
+
+    async fn send_command(&mut self, command: u8) {
+        self.panel.command(command).await;
     }
 
     async fn send_data(&mut self, data: &[u8]) {
-        // Set DC pin high for data mode
-        self.dc.set_high();
+        self.panel.data(data).await;
+    }
 
-        // Send data
-        let _ = self.spi.blocking_write(data);
+    /// Send `count` copies of one RGB565 pixel (big-endian) into the current window
+    async fn send_repeated(&mut self, pixel: [u8; 2], count: usize) {
+        let mut batch = [0u8; PIXEL_BATCH * 2];
+        for slot in batch.chunks_exact_mut(2) {
+            slot.copy_from_slice(&pixel);
+        }
+        let mut remaining = count;
+        while remaining > 0 {
+            let n = remaining.min(PIXEL_BATCH);
+            self.panel.data(&batch[..n * 2]).await;
+            remaining -= n;
+            self.yield_slice().await;
+        }
+    }
+
+    /// Let the other tasks on this executor run once the panel writes have
+    /// held it for `DISPLAY_TIME_SLICE_US`. SPI writes are blocking, so a
+    /// full-screen blit would otherwise keep key reports waiting for tens of
+    /// milliseconds. The panel window stays open across the yield; nothing
+    /// else drives this bus.
+    async fn yield_slice(&mut self) {
+        if self.slice_started.elapsed() >= Duration::from_micros(DISPLAY_TIME_SLICE_US) {
+            embassy_futures::yield_now().await;
+            self.slice_started = Instant::now();
+        }
     }
 
     async fn set_window(&mut self, x_start: u16, y_start: u16, x_end: u16, y_end: u16) {
@@ -162,39 +560,51 @@ impl DisplayController {
         self.send_command(ST7735_RAMWR).await;
     }
 
-    async fn display_image(&mut self, key_id: u8, image_data: &[u8]) {
+    /// Draw a complete key image; returns the microseconds spent decoding and
+    /// writing to the bus
+    async fn display_image(
+        &mut self,
+        key_id: u8,
+        image_data: &[u8],
+    ) -> Result<(u32, u32), DisplayError> {
         if key_id >= crate::config::streamdeck_keys() as u8 {
-            warn!("Invalid key_id: {}", key_id);
-            return;
+            return Err(DisplayError::InvalidKey);
         }
+        let Some((x_start, y_start, x_end, y_end)) = Self::key_region(key_id) else {
+            return Err(DisplayError::InvalidKey);
+        };
 
-        info!("Displaying image on key {} region", key_id);
+        log_at!(
+            debug,
+            Subsystem::Display,
+            "Displaying image on key {} region",
+            key_id
+        );
+        let started = Instant::now();
 
-        // Calculate position on shared display
-        let cols = crate::config::streamdeck_cols();
-        let col = (key_id as usize) % cols;
-        let row = (key_id as usize) / cols;
         let image_size = crate::config::key_image_size();
-        let x_start = (col * image_size) as u16;
-        let y_start = (row * image_size) as u16;
-        let x_end = x_start + image_size as u16 - 1;
-        let y_end = y_start + image_size as u16 - 1;
 
-        debug!(
+        log_at!(
+            trace,
+            Subsystem::Display,
             "Key {} maps to region: ({},{}) to ({},{})",
-            key_id, x_start, y_start, x_end, y_end
+            key_id,
+            x_start,
+            y_start,
+            x_end,
+            y_end
         );
 
         // Select the display
-        self.cs.set_low();
+        self.panel.select();
 
         // Set window to key region
         self.set_window(x_start, y_start, x_end, y_end).await;
 
         // Process image data - skip BMP header if present
         let mut data_offset = 0;
-        if image_data.len() > 54 && image_data[0] == 0x42 && image_data[1] == 0x4D {
-            data_offset = 54; // Skip BMP header
+        if image_data.len() > BMP_HEADER_SIZE && image_data[0] == 0x42 && image_data[1] == 0x4D {
+            data_offset = BMP_HEADER_SIZE; // Skip BMP header
             debug!("Skipped BMP header");
         }
 
@@ -207,83 +617,521 @@ impl DisplayController {
                 rgb_data.len(),
                 expected_size
             );
-            self.cs.set_high();
-            return;
+            self.panel.deselect().await;
+            return Err(DisplayError::ImageTooSmall);
         }
 
         // Convert RGB888 to RGB565 and send to display
         let pixel_count = image_size * image_size;
-        let mut buffer = [0u8; 2]; // Buffer for one RGB565 pixel
+        let scale = self.brightness.key_pixel_scale(key_id);
+        let factor = Self::icon_scale();
+        let rotation = crate::config::key_image_rotation();
+        let (decode_us, blit_us) = if let Some(color) = solid_pixel(&rgb_data[..expected_size]) {
+            // Solid color: convert once and fill the region
+            let writing = Instant::now();
+            let pixel = encode_pixel(Self::rgb888_to_565(scale, color), self.quirks.color_order);
+            let region = (x_end - x_start + 1) as usize * (y_end - y_start + 1) as usize;
+            self.send_repeated(pixel, region).await;
+            (0, writing.elapsed().as_micros() as u32)
+        } else if rotation != Rotation::Deg0 {
+            // Gather each row of the turned image, then draw it like a source row
+            let (mut decode_us, mut blit_us) = (0, 0);
+            let mut row: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+            for y in 0..image_size {
+                row.clear();
+                for x in 0..image_size {
+                    let (sx, sy) = image::rotate_point(image_size, rotation.inverse(), x, y);
+                    let at = (sy * image_size + sx) * 3;
+                    let _ = row.extend_from_slice(&rgb_data[at..at + 3]);
+                }
+                for _ in 0..factor {
+                    let (decode, blit) = self.write_rgb888_repeated(scale, &row, factor).await;
+                    decode_us += decode;
+                    blit_us += blit;
+                }
+            }
+            (decode_us, blit_us)
+        } else if factor == 1 {
+            self.write_rgb888(scale, &rgb_data[..expected_size]).await
+        } else {
+            // Each source row becomes `factor` rows of `factor`-wide pixels
+            let (mut decode_us, mut blit_us) = (0, 0);
+            for row in rgb_data[..expected_size].chunks(image_size * 3) {
+                for _ in 0..factor {
+                    let (decode, blit) = self.write_rgb888_repeated(scale, row, factor).await;
+                    decode_us += decode;
+                    blit_us += blit;
+                }
+            }
+            (decode_us, blit_us)
+        };
 
-        for i in 0..pixel_count {
-            let rgb_offset = i * 3;
-            if rgb_offset + 2 < rgb_data.len() {
-                let r = rgb_data[rgb_offset];
-                let g = rgb_data[rgb_offset + 1];
-                let b = rgb_data[rgb_offset + 2];
+        // Deselect display
+        self.panel.deselect().await;
+        self.finish_key(key_id).await;
+
+        crate::stats::image_displayed(started.elapsed().as_micros() as u32);
+        log_limited!(
+            info,
+            Subsystem::Display,
+            "Image displayed on key {} region: {} pixels",
+            key_id,
+            pixel_count
+        );
+        Ok((decode_us, blit_us))
+    }
 
-                // Convert to RGB565
-                let rgb565 = ((r as u16 & RGB565_RED_MASK) << 8)
-                    | ((g as u16 & RGB565_GREEN_MASK) << 3)
-                    | (b as u16 >> RGB565_BLUE_SHIFT);
+    /// Convert RGB888 pixels to RGB565 and send them into the current window,
+    /// dimmed by `scale` (255 = unchanged). The display must be selected.
+    /// Returns the microseconds spent converting and writing to the bus.
+    async fn write_rgb888(&mut self, scale: u8, rgb_data: &[u8]) -> (u32, u32) {
+        self.write_rgb888_repeated(scale, rgb_data, 1).await
+    }
 
-                // Send as big-endian
-                buffer[0] = (rgb565 >> 8) as u8;
-                buffer[1] = (rgb565 & 0xFF) as u8;
-                let _ = self.spi.blocking_write(&buffer);
+    /// `write_rgb888` with every pixel sent `repeat` times, for icons drawn
+    /// at a whole-number scale
+    async fn write_rgb888_repeated(
+        &mut self,
+        scale: u8,
+        rgb_data: &[u8],
+        repeat: usize,
+    ) -> (u32, u32) {
+        let mut batch = [0u8; PIXEL_BATCH * 2]; // RGB565 pixels per bus transfer
+        let order = self.quirks.color_order;
+        let (mut convert, mut write) = (Duration::from_ticks(0), Duration::from_ticks(0));
+        let mut converting = Instant::now();
+        let mut len = 0;
+
+        #[cfg(feature = "interp-convert")]
+        {
+            self.pixel_lut.prepare(scale);
+            self.interp.load(&self.pixel_lut);
+        }
+        for pixel in rgb_data.chunks_exact(3) {
+            let pixel = [pixel[0], pixel[1], pixel[2]];
+            #[cfg(feature = "interp-convert")]
+            let rgb565 = self.interp.rgb565(&self.pixel_lut, pixel);
+            #[cfg(not(feature = "interp-convert"))]
+            let rgb565 = Self::rgb888_to_565(scale, pixel);
+            let bytes = encode_pixel(rgb565, order);
+
+            for _ in 0..repeat {
+                if len == batch.len() {
+                    let writing = Instant::now();
+                    convert += writing - converting;
+                    self.panel.data(&batch).await;
+                    write += writing.elapsed();
+                    self.yield_slice().await;
+                    converting = Instant::now();
+                    len = 0;
+                }
+                batch[len..len + 2].copy_from_slice(&bytes);
+                len += 2;
             }
         }
+        if len > 0 {
+            let writing = Instant::now();
+            convert += writing - converting;
+            self.panel.data(&batch[..len]).await;
+            write += writing.elapsed();
+        }
+        (convert.as_micros() as u32, write.as_micros() as u32)
+    }
 
-        // Deselect display
-        self.cs.set_high();
+    /// Draw a run-length encoded key image; each run is converted once
+    async fn draw_rle(&mut self, key_id: u8, image: &RleImage) -> Result<(), DisplayError> {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            return Err(DisplayError::InvalidKey);
+        }
+        let Some((x_start, y_start, x_end, y_end)) = Self::key_region(key_id) else {
+            return Err(DisplayError::InvalidKey);
+        };
+        let image_size = crate::config::key_image_size();
+        if image.pixel_count() < image_size * image_size {
+            return Err(DisplayError::ImageTooSmall);
+        }
+        let started = Instant::now();
+        let scale = self.brightness.key_pixel_scale(key_id);
+        let factor = Self::icon_scale();
 
-        info!(
-            "Image displayed on key {} region: {} pixels",
-            key_id, pixel_count
+        self.panel.select();
+        self.set_window(x_start, y_start, x_end, y_end).await;
+
+        if factor == 1 {
+            let order = self.quirks.color_order;
+            let mut batch = [0u8; PIXEL_BATCH * 2];
+            let mut len = 0;
+            let mut remaining = image_size * image_size;
+            for run in image.runs() {
+                let bytes = encode_pixel(Self::rgb888_to_565(scale, run.color), order);
+                for _ in 0..(run.len as usize).min(remaining) {
+                    if len == batch.len() {
+                        self.panel.data(&batch).await;
+                        self.yield_slice().await;
+                        len = 0;
+                    }
+                    batch[len..len + 2].copy_from_slice(&bytes);
+                    len += 2;
+                }
+                remaining = remaining.saturating_sub(run.len as usize);
+            }
+            if len > 0 {
+                self.panel.data(&batch[..len]).await;
+            }
+        } else {
+            // Scaled icons: expand a source row at a time
+            let mut pixels = image.pixels();
+            let mut row: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+            for _ in 0..image_size {
+                row.clear();
+                for pixel in pixels.by_ref().take(image_size) {
+                    let _ = row.extend_from_slice(&pixel);
+                }
+                for _ in 0..factor {
+                    self.write_rgb888_repeated(scale, &row, factor).await;
+                }
+            }
+        }
+
+        self.panel.deselect().await;
+        self.finish_key(key_id).await;
+        crate::stats::image_displayed(started.elapsed().as_micros() as u32);
+        Ok(())
+    }
+
+    /// Gamma-correct an RGB888 pixel, dim it by `scale` (255 = unchanged)
+    /// and pack it as RGB565
+    fn rgb888_to_565(scale: u8, pixel: [u8; 3]) -> u16 {
+        let r = brightness::scale_channel(gamma::correct(pixel[0]), scale);
+        let g = brightness::scale_channel(gamma::correct(pixel[1]), scale);
+        let b = brightness::scale_channel(gamma::correct(pixel[2]), scale);
+        ((r as u16 & RGB565_RED_MASK) << 8)
+            | ((g as u16 & RGB565_GREEN_MASK) << 3)
+            | (b as u16 >> RGB565_BLUE_SHIFT)
+    }
+
+    /// Draw an image into a window of the touch strip LCD, which sits below
+    /// the key grid
+    async fn display_lcd_image(
+        &mut self,
+        region: LcdRegion,
+        image_data: &[u8],
+    ) -> Result<(), DisplayError> {
+        let started = Instant::now();
+        let expected_size = region.width as usize * region.height as usize * 3;
+        if image_data.len() < expected_size {
+            warn!(
+                "LCD image data too small: {} bytes, expected: {}",
+                image_data.len(),
+                expected_size
+            );
+            return Err(DisplayError::ImageTooSmall);
+        }
+
+        let x_start = region.x;
+        let y_start = crate::config::display_total_height() as u16 + region.y;
+        self.panel.select();
+        self.set_window(
+            x_start,
+            y_start,
+            x_start + region.width - 1,
+            y_start + region.height - 1,
+        )
+        .await;
+        // Key brightness overrides don't apply to the strip, its own level does
+        let scale = self.brightness.lcd_pixel_scale();
+        self.write_rgb888(scale, &image_data[..expected_size]).await;
+        self.panel.deselect().await;
+
+        crate::stats::image_displayed(started.elapsed().as_micros() as u32);
+        log_at!(
+            debug,
+            Subsystem::Display,
+            "LCD image displayed at {}",
+            region
         );
+        Ok(())
     }
 
-    async fn clear_key(&mut self, key_id: u8) {
-        if key_id >= crate::config::streamdeck_keys() as u8 {
-            warn!("Invalid key_id: {}", key_id);
+    /// Render centered text into a window of the touch strip LCD
+    async fn draw_lcd_text(&mut self, region: LcdRegion, text: &str, fg: u16, bg: u16) {
+        let (width, height) = (region.width as usize, region.height as usize);
+        if width == 0 || height == 0 {
             return;
         }
+        let block = gfx::TextBlock::centered(text, width, height);
+        let order = self.quirks.color_order;
+        let (fg, bg) = (encode_pixel(fg, order), encode_pixel(bg, order));
 
-        debug!("Clearing key {} region", key_id);
+        let x_start = region.x;
+        let y_start = crate::config::display_total_height() as u16 + region.y;
+        self.panel.select();
+        self.set_window(
+            x_start,
+            y_start,
+            x_start + region.width - 1,
+            y_start + region.height - 1,
+        )
+        .await;
 
-        // Calculate position on shared display
-        let cols = crate::config::streamdeck_cols();
-        let col = (key_id as usize) % cols;
-        let row = (key_id as usize) / cols;
+        // Strip rows are wider than key rows: send each in pieces
+        let mut line: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = if block.pixel(x, y) { &fg } else { &bg };
+                if line.extend_from_slice(pixel).is_err() {
+                    self.panel.data(&line).await;
+                    line.clear();
+                    let _ = line.extend_from_slice(pixel);
+                }
+            }
+            self.panel.data(&line).await;
+            line.clear();
+            self.yield_slice().await;
+        }
+        self.panel.deselect().await;
+    }
+
+    /// Draw RGB888 pixels of a key image starting at pixel index `start`
+    /// (row-major). The window is computed per call, so pixels can go out
+    /// as they arrive and other drawing may happen in between.
+    async fn write_image_pixels(&mut self, key_id: u8, start: usize, mut rgb_data: &[u8]) {
+        let Some((x_start, y_start, x_end, _)) = Self::key_region(key_id) else {
+            return;
+        };
+        let image_size = crate::config::key_image_size();
+        let upright = crate::config::key_image_rotation() == Rotation::Deg0;
+        let factor = Self::icon_scale();
+        let scale = self.brightness.key_pixel_scale(key_id);
+        let mut pixel = start;
+
+        self.panel.select();
+        while rgb_data.len() >= 3 && pixel < image_size * image_size {
+            let (row, col) = (pixel / image_size, pixel % image_size);
+            if upright && factor == 1 && col == 0 && rgb_data.len() >= image_size * 3 {
+                // Whole rows: one window for all of them
+                let y = y_start + row as u16;
+                let rows = (rgb_data.len() / (image_size * 3)).min(image_size - row);
+                self.set_window(x_start, y, x_end, y + rows as u16 - 1)
+                    .await;
+                let (rows_data, rest) = rgb_data.split_at(rows * image_size * 3);
+                self.write_rgb888(scale, rows_data).await;
+                rgb_data = rest;
+                pixel += rows * image_size;
+            } else {
+                // Part of a row, a scaled row, or a row of a turned image
+                let count = (image_size - col).min(rgb_data.len() / 3);
+                let (segment, rest) = rgb_data.split_at(count * 3);
+                self.write_run(key_id, row, col, segment).await;
+                rgb_data = rest;
+                pixel += count;
+            }
+        }
+        self.panel.deselect().await;
+
+        if pixel == image_size * image_size {
+            self.finish_key(key_id).await;
+        }
+    }
+
+    /// Draw `segment`, the pixels of source row `row` from column `col` on,
+    /// where turning the key image upright puts them: along a row, or down a
+    /// column for quarter turns. The display must be selected.
+    async fn write_run(&mut self, key_id: u8, row: usize, col: usize, segment: &[u8]) {
+        let Some((x_start, y_start, _, _)) = Self::key_region(key_id) else {
+            return;
+        };
+        let count = segment.len() / 3;
+        if count == 0 {
+            return;
+        }
         let image_size = crate::config::key_image_size();
-        let x_start = (col * image_size) as u16;
-        let y_start = (row * image_size) as u16;
-        let x_end = x_start + image_size as u16 - 1;
-        let y_end = y_start + image_size as u16 - 1;
+        let rotation = crate::config::key_image_rotation();
+        let factor = Self::icon_scale();
+        let scale = self.brightness.key_pixel_scale(key_id);
+
+        let first = image::rotate_point(image_size, rotation, col, row);
+        let last = image::rotate_point(image_size, rotation, col + count - 1, row);
+        let x = x_start + (first.0.min(last.0) * factor) as u16;
+        let y = y_start + (first.1.min(last.1) * factor) as u16;
+        let (f, len) = (factor as u16, (count * factor) as u16);
+
+        // Runs that end left of or above their start go out back to front
+        let mut reversed: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+        let pixels = if last.0 < first.0 || last.1 < first.1 {
+            for pixel in segment.chunks_exact(3).rev() {
+                let _ = reversed.extend_from_slice(pixel);
+            }
+            &reversed[..]
+        } else {
+            segment
+        };
+
+        if matches!(rotation, Rotation::Deg90 | Rotation::Deg270) {
+            // A `factor` wide column: each pixel's block is `factor` squared
+            // pixels in a row
+            self.set_window(x, y, x + f - 1, y + len - 1).await;
+            self.write_rgb888_repeated(scale, pixels, factor * factor)
+                .await;
+        } else {
+            self.set_window(x, y, x + len - 1, y + f - 1).await;
+            for _ in 0..factor {
+                self.write_rgb888_repeated(scale, pixels, factor).await;
+            }
+        }
+    }
+
+    /// Display window of a key region, or None for an invalid key
+    fn key_region(key_id: u8) -> Option<(u16, u16, u16, u16)> {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            warn!("Invalid key_id: {}", key_id);
+            crate::stats::record_error(DisplayError::InvalidKey);
+            return None;
+        }
+
+        // Touch builds center scaled icons in the on-screen keypad's cells
+        #[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+        return crate::keypad::icon_region(key_id);
+
+        #[cfg(not(any(feature = "touch-xpt2046", feature = "touch-ft6236")))]
+        {
+            let cols = crate::config::streamdeck_cols();
+            let col = (key_id as usize) % cols;
+            let row = (key_id as usize) / cols;
+            let image_size = crate::config::key_image_size();
+            let x_start = (col * image_size) as u16;
+            let y_start = (row * image_size) as u16;
+            Some((
+                x_start,
+                y_start,
+                x_start + image_size as u16 - 1,
+                y_start + image_size as u16 - 1,
+            ))
+        }
+    }
+
+    /// Area a key's border frames: its image region, or its whole keypad
+    /// cell in touch builds
+    fn border_region(key_id: u8) -> Option<(u16, u16, u16, u16)> {
+        #[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+        return crate::keypad::cell(key_id);
+        #[cfg(not(any(feature = "touch-xpt2046", feature = "touch-ft6236")))]
+        Self::key_region(key_id)
+    }
+
+    /// Whole-number factor key images are drawn at (touch builds scale them
+    /// to the keypad)
+    fn icon_scale() -> usize {
+        #[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+        return crate::keypad::icon_scale();
+        #[cfg(not(any(feature = "touch-xpt2046", feature = "touch-ft6236")))]
+        1
+    }
+
+    async fn clear_key(&mut self, key_id: u8) {
+        log_at!(debug, Subsystem::Display, "Clearing key {} region", key_id);
+        self.fill_key(key_id, 0x0000).await;
+    }
+
+    async fn fill_key(&mut self, key_id: u8, color: u16) {
+        let Some((x_start, y_start, x_end, y_end)) = Self::key_region(key_id) else {
+            return;
+        };
+        let pixels = (x_end - x_start + 1) as usize * (y_end - y_start + 1) as usize;
 
         // Select the display
-        self.cs.set_low();
+        self.panel.select();
 
         // Set window to key region
         self.set_window(x_start, y_start, x_end, y_end).await;
 
-        // Fill region with black (RGB565: 0x0000)
-        let black_pixel = [0x00, 0x00];
-        for _ in 0..(image_size * image_size) {
-            let _ = self.spi.blocking_write(&black_pixel);
-        }
+        // Fill region with the color (RGB565, big-endian)
+        let pixel = encode_pixel(color, self.quirks.color_order);
+        self.send_repeated(pixel, pixels).await;
 
         // Deselect display
-        self.cs.set_high();
+        self.panel.deselect().await;
+        self.finish_key(key_id).await;
+    }
 
-        debug!("Key {} region cleared", key_id);
+    /// Draw the classic eight vertical color bars across a key region
+    async fn color_bars(&mut self, key_id: u8) {
+        const BARS: [u16; 8] = [
+            0xFFFF, // White
+            0xFFE0, // Yellow
+            0x07FF, // Cyan
+            0x07E0, // Green
+            0xF81F, // Magenta
+            0xF800, // Red
+            0x001F, // Blue
+            0x0000, // Black
+        ];
+
+        let Some((x_start, y_start, x_end, y_end)) = Self::key_region(key_id) else {
+            return;
+        };
+        let (width, height) = (
+            (x_end - x_start + 1) as usize,
+            (y_end - y_start + 1) as usize,
+        );
+
+        self.panel.select();
+        self.set_window(x_start, y_start, x_end, y_end).await;
+
+        for _ in 0..height {
+            for (i, &bar) in BARS.iter().enumerate() {
+                // Columns of bar i: x * BARS.len() / width == i
+                let start = (i * width).div_ceil(BARS.len());
+                let end = ((i + 1) * width).div_ceil(BARS.len());
+                let pixel = encode_pixel(bar, self.quirks.color_order);
+                self.send_repeated(pixel, end - start).await;
+            }
+        }
+
+        self.panel.deselect().await;
+        self.finish_key(key_id).await;
+    }
+
+    /// Render centered text in a key region
+    async fn draw_text(&mut self, key_id: u8, text: &str, fg: u16, bg: u16) {
+        let Some((x_start, y_start, x_end, y_end)) = Self::key_region(key_id) else {
+            return;
+        };
+        let (width, height) = (
+            (x_end - x_start + 1) as usize,
+            (y_end - y_start + 1) as usize,
+        );
+        let block = gfx::TextBlock::centered(text, width, height);
+        let order = self.quirks.color_order;
+        let (fg, bg) = (encode_pixel(fg, order), encode_pixel(bg, order));
+
+        self.panel.select();
+        self.set_window(x_start, y_start, x_end, y_end).await;
+
+        // Scaled keypad icons can be wider than a key row: send in pieces
+        let mut line: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = if block.pixel(x, y) { &fg } else { &bg };
+                if line.extend_from_slice(pixel).is_err() {
+                    self.panel.data(&line).await;
+                    line.clear();
+                    let _ = line.extend_from_slice(pixel);
+                }
+            }
+            self.panel.data(&line).await;
+            line.clear();
+        }
+
+        self.panel.deselect().await;
+        self.finish_key(key_id).await;
     }
 
     async fn clear_all(&mut self) {
         info!("Clearing entire display");
 
         // Select the display
-        self.cs.set_low();
+        self.panel.select();
 
         // Set window to entire display
         self.set_window(
@@ -295,120 +1143,521 @@ impl DisplayController {
         .await;
 
         // Fill entire display with black
-        let black_pixel = [0x00, 0x00];
-        for _ in 0..(crate::config::display_total_width() * crate::config::display_total_height()) {
-            let _ = self.spi.blocking_write(&black_pixel);
-        }
+        let pixels = crate::config::display_total_width() * crate::config::display_total_height();
+        self.send_repeated([0x00, 0x00], pixels).await;
 
         // Deselect display
-        self.cs.set_high();
+        self.panel.deselect().await;
+
+        for key_id in 0..crate::config::streamdeck_keys() as u8 {
+            self.finish_key(key_id).await;
+        }
 
         info!("Display cleared");
     }
 
+    /// Redraw a key's border after its region was overwritten (`key-feedback`)
+    async fn finish_key(&mut self, key_id: u8) {
+        if !cfg!(feature = "key-feedback") {
+            return;
+        }
+        let highlighted = matches!(self.highlight_until.get(key_id as usize), Some(Some(_)));
+        let color = if highlighted {
+            KEY_HIGHLIGHT_COLOR
+        } else {
+            KEY_BORDER_COLOR
+        };
+        let width = self.border_width(key_id);
+        self.draw_frame(key_id, color, width).await;
+    }
+
+    /// Draw a `KEY_BORDER_WIDTH` frame just inside a key region
+    async fn draw_border(&mut self, key_id: u8, color: u16) {
+        self.draw_frame(key_id, color, KEY_BORDER_WIDTH).await;
+    }
+
+    /// Draw a frame `width` pixels wide just inside a key's border region
+    async fn draw_frame(&mut self, key_id: u8, color: u16, width: u16) {
+        let Some((x_start, y_start, x_end, y_end)) = Self::border_region(key_id) else {
+            return;
+        };
+        let width = width.min((x_end - x_start + 1).min(y_end - y_start + 1) / 2);
+        if width == 0 {
+            return;
+        }
+
+        // Top and bottom bands span the region; the sides fill in between
+        let mut bands: Vec<(u16, u16, u16, u16), 4> = Vec::new();
+        let _ = bands.push((x_start, y_start, x_end, y_start + width - 1));
+        let _ = bands.push((x_start, y_end + 1 - width, x_end, y_end));
+        if y_start + width <= y_end - width {
+            let _ = bands.push((x_start, y_start + width, x_start + width - 1, y_end - width));
+            let _ = bands.push((x_end + 1 - width, y_start + width, x_end, y_end - width));
+        }
+
+        self.panel.select();
+        for (x0, y0, x1, y1) in bands {
+            self.set_window(x0, y0, x1, y1).await;
+            let pixels = (x1 - x0 + 1) as usize * (y1 - y0 + 1) as usize;
+            let pixel = encode_pixel(color, self.quirks.color_order);
+            self.send_repeated(pixel, pixels).await;
+        }
+        self.panel.deselect().await;
+    }
+
+    /// Light up a key's border for `KEY_HIGHLIGHT_MS` (`key-feedback`)
+    async fn highlight_key(&mut self, key_id: u8) {
+        if !cfg!(feature = "key-feedback") {
+            return;
+        }
+        let Some(until) = self.highlight_until.get_mut(key_id as usize) else {
+            return;
+        };
+        let now = Instant::now();
+        *until = Some(now + Duration::from_millis(KEY_HIGHLIGHT_MS));
+        // Touch keys press in: the border thickens over a few frames
+        if cfg!(any(feature = "touch-xpt2046", feature = "touch-ft6236")) {
+            self.press_frame[key_id as usize] = 1;
+            self.next_press_frame = Some(now + Duration::from_millis(KEYPAD_FRAME_MS));
+        }
+        let width = self.border_width(key_id);
+        self.draw_frame(key_id, KEY_HIGHLIGHT_COLOR, width).await;
+    }
+
+    /// Border width for a key's press animation frame
+    fn border_width(&self, key_id: u8) -> u16 {
+        let frame = self.press_frame.get(key_id as usize).copied().unwrap_or(0);
+        if frame == 0 {
+            return KEY_BORDER_WIDTH;
+        }
+        // The pressed border stays in the gap around the icon
+        #[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+        let pressed = KEYPAD_PRESS_BORDER_WIDTH.min(crate::keypad::gap());
+        #[cfg(not(any(feature = "touch-xpt2046", feature = "touch-ft6236")))]
+        let pressed = KEYPAD_PRESS_BORDER_WIDTH;
+        let pressed = pressed.max(KEY_BORDER_WIDTH);
+        KEY_BORDER_WIDTH + (pressed - KEY_BORDER_WIDTH) * frame / KEYPAD_PRESS_FRAMES
+    }
+
+    /// When the next press highlight ends or animation frame is due, or None
+    /// if none is lit
+    fn next_highlight_end(&self) -> Option<Instant> {
+        self.highlight_until
+            .iter()
+            .flatten()
+            .chain(self.next_press_frame.iter())
+            .min()
+            .copied()
+    }
+
+    /// Advance press animations, and restore the plain border of keys whose
+    /// highlight has run out
+    async fn expire_highlights(&mut self) {
+        let now = Instant::now();
+        if self.next_press_frame.is_some_and(|at| at <= now) {
+            self.next_press_frame = None;
+            for key_id in 0..MAX_KEYS {
+                let frame = self.press_frame[key_id];
+                if frame == 0 || frame >= KEYPAD_PRESS_FRAMES {
+                    continue;
+                }
+                self.press_frame[key_id] = frame + 1;
+                let width = self.border_width(key_id as u8);
+                self.draw_frame(key_id as u8, KEY_HIGHLIGHT_COLOR, width)
+                    .await;
+                if frame + 1 < KEYPAD_PRESS_FRAMES {
+                    self.next_press_frame = Some(now + Duration::from_millis(KEYPAD_FRAME_MS));
+                }
+            }
+        }
+
+        for key_id in 0..MAX_KEYS {
+            if self.highlight_until[key_id].is_some_and(|until| until <= now) {
+                self.highlight_until[key_id] = None;
+                if self.press_frame[key_id] > 0 {
+                    // Clear the pressed border back to the cell background
+                    let width = self.border_width(key_id as u8);
+                    self.press_frame[key_id] = 0;
+                    self.draw_frame(key_id as u8, 0x0000, width).await;
+                }
+                self.draw_border(key_id as u8, KEY_BORDER_COLOR).await;
+            }
+        }
+    }
+
     async fn set_brightness(&mut self, brightness: u8) {
-        let brightness = brightness.min(100);
-        self.current_brightness = brightness;
+        self.brightness.set_global(brightness);
+        let duty = self.brightness.global_duty();
+        self.bl.set_duty(duty);
+        self.backlight_on = true;
+
+        info!(
+            "Brightness set to {}% (duty {}/255)",
+            self.brightness.global(),
+            duty
+        );
+    }
+
+    /// Drive the backlight at `scale`/255 of the current level's duty, for
+    /// fades that leave the requested level alone
+    fn scale_backlight(&mut self, scale: u8) {
+        let duty = self.brightness.global_duty() as u16 * scale as u16 / 255;
+        self.bl.set_duty(duty as u8);
+    }
+
+    /// Apply a backlight cap (supply sag, heat, night mode) to the PWM duty.
+    /// A backlight that is off, before the startup ramp or asleep, stays off
+    /// and picks the cap up when it is next lit.
+    fn limit_brightness(&mut self, percent: u8) {
+        self.brightness.set_limit(percent);
+        if self.backlight_on {
+            let duty = self.brightness.global_duty();
+            self.bl.set_duty(duty);
+            info!("Backlight limited to {}% (duty {}/255)", percent, duty);
+        }
+    }
+
+    async fn sleep(&mut self) {
+        info!("Display entering sleep");
+        self.bl.set_duty(0);
+        self.backlight_on = false;
+        self.panel.select();
+        self.send_command(ST7735_SLPIN).await;
+        self.panel.deselect().await;
+    }
+
+    async fn wake(&mut self) {
+        info!("Display waking from sleep");
+        self.panel.select();
+        self.send_command(ST7735_SLPOUT).await;
+        self.panel.deselect().await;
+        Timer::after(Duration::from_millis(120)).await;
+        let brightness = self.brightness.global();
+        self.set_brightness(brightness).await;
+    }
+
+    /// Blank or restore one screen. The keys and the touch strip share the
+    /// panel and backlight, so the panel itself only sleeps once both do;
+    /// a woken screen stays blank until the host redraws it.
+    async fn set_screen_sleep(&mut self, screen: Screen, sleep: bool) {
+        if self.screen_asleep[screen as usize] == sleep {
+            return;
+        }
+        let panel_asleep = self.screen_asleep.iter().all(|&asleep| asleep);
+        self.screen_asleep[screen as usize] = sleep;
+        info!(
+            "Screen {} {}",
+            screen,
+            if sleep { "sleeping" } else { "awake" }
+        );
+
+        if sleep && self.screen_asleep.iter().all(|&asleep| asleep) {
+            self.sleep().await;
+        } else if sleep {
+            match screen {
+                Screen::Keys => self.clear_all().await,
+                Screen::Lcd => self.fill_lcd(0x0000).await,
+            }
+        } else if panel_asleep {
+            self.wake().await;
+        }
+    }
+
+    /// Set one screen's brightness; the key grid level drives the backlight
+    async fn set_screen_brightness(&mut self, screen: Screen, value: u8) {
+        match screen {
+            Screen::Keys => self.set_brightness(value).await,
+            Screen::Lcd => {
+                self.brightness.set_lcd(value);
+                info!("Touch strip brightness set to {}%", self.brightness.lcd());
+            }
+        }
+    }
+
+    /// Fill the whole touch strip LCD with one RGB565 color
+    async fn fill_lcd(&mut self, color: u16) {
+        let y_start = crate::config::display_total_height() as u16;
+        self.panel.select();
+        self.set_window(
+            0,
+            y_start,
+            PLUS_LCD_WIDTH - 1,
+            y_start + PLUS_LCD_HEIGHT - 1,
+        )
+        .await;
+        let pixel = encode_pixel(color, self.quirks.color_order);
+        self.send_repeated(pixel, PLUS_LCD_WIDTH as usize * PLUS_LCD_HEIGHT as usize)
+            .await;
+        self.panel.deselect().await;
+    }
 
-        // TODO: Implement PWM brightness control
+    async fn set_key_brightness(&mut self, key_id: u8, value: u8) {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            warn!("Invalid key_id: {}", key_id);
+            return;
+        }
+
+        self.brightness.set_key(key_id, value);
         info!(
-            "Brightness set to {}% (PWM not implemented yet)",
-            brightness
+            "Key {} brightness set to {}%",
+            key_id,
+            self.brightness.key(key_id)
         );
     }
 }
 
 // ===================================================================
-// Image Buffer Management
+// Streaming Image Decode
 // ===================================================================
 
-struct ImageBuffer {
-    data: Vec<u8, IMAGE_BUFFER_SIZE>,
-    receiving: bool,
-    complete: bool,
-    expected_sequence: u16,
+/// Decoder for a streamed BMP key image.
+///
+/// Each chunk's pixels are written straight to their place on the panel as
+/// the chunk arrives; only a pixel split between two chunks is held back, so
+/// RAM use does not grow with the image size.
+struct ImageStream {
+    key_id: u8,
+    /// Image offset the next chunk must start at
+    next_offset: u32,
+    /// BMP header bytes still to skip
+    header_remaining: usize,
+    /// Bytes of a pixel cut off at the end of the last chunk
+    partial: Vec<u8, 3>,
+    /// Pixels drawn so far
+    pixels_written: usize,
+    started: Instant,
 }
 
-impl ImageBuffer {
-    fn new() -> Self {
+impl ImageStream {
+    fn new(key_id: u8, first_chunk: &[u8]) -> Self {
+        let has_header = first_chunk.len() >= 2 && first_chunk[0] == 0x42 && first_chunk[1] == 0x4D;
         Self {
-            data: Vec::new(),
-            receiving: false,
-            complete: false,
-            expected_sequence: 0,
+            key_id,
+            next_offset: 0,
+            header_remaining: if has_header { BMP_HEADER_SIZE } else { 0 },
+            partial: Vec::new(),
+            pixels_written: 0,
+            started: Instant::now(),
         }
     }
 
-    fn reset(&mut self) {
-        self.data.clear();
-        self.receiving = false;
-        self.complete = false;
-        self.expected_sequence = 0;
-    }
+    /// Feed the next chunk, drawing every pixel it completes
+    async fn feed<P: PanelDriver>(
+        &mut self,
+        controller: &mut DisplayController<P>,
+        mut data: &[u8],
+    ) {
+        self.next_offset += data.len() as u32;
 
-    fn add_packet(&mut self, packet_data: &[u8]) -> Result<bool, &'static str> {
-        if packet_data.len() < 8 {
-            return Err("Packet too small");
+        let skip = self.header_remaining.min(data.len());
+        self.header_remaining -= skip;
+        data = &data[skip..];
+
+        let image_size = crate::config::key_image_size();
+        let total = image_size * image_size;
+        if self.pixels_written >= total {
+            return;
         }
 
-        let key_id = packet_data[2];
-        let is_last = packet_data[3] != 0;
-        let payload_len = u16::from_le_bytes([packet_data[4], packet_data[5]]);
-        let sequence = u16::from_le_bytes([packet_data[6], packet_data[7]]);
+        // Finish the pixel the previous chunk cut off
+        if !self.partial.is_empty() {
+            let take = (3 - self.partial.len()).min(data.len());
+            // Bounded: at most 3 bytes
+            let _ = self.partial.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.partial.len() < 3 {
+                return;
+            }
+            controller
+                .write_image_pixels(self.key_id, self.pixels_written, &self.partial)
+                .await;
+            self.partial.clear();
+            self.pixels_written += 1;
+        }
 
-        // Reset buffer on first packet
-        if sequence == 0 {
-            self.reset();
-            self.receiving = true;
-            debug!("Starting image reception for key {}", key_id);
+        let whole = (data.len() / 3).min(total - self.pixels_written);
+        if whole > 0 {
+            controller
+                .write_image_pixels(self.key_id, self.pixels_written, &data[..whole * 3])
+                .await;
+            self.pixels_written += whole;
+        }
+        if self.pixels_written < total {
+            // Bounded: fewer than 3 bytes remain
+            let _ = self.partial.extend_from_slice(&data[whole * 3..]);
         }
+    }
 
-        // Validate sequence
-        if !self.receiving || sequence != self.expected_sequence {
-            error!(
-                "Image packet sequence error: expected {}, got {}",
-                self.expected_sequence, sequence
+    /// Check the image was complete once the last chunk is in
+    fn finish(&self) -> Result<(), DisplayError> {
+        let image_size = crate::config::key_image_size();
+        if self.pixels_written < image_size * image_size {
+            return Err(DisplayError::ImageTooSmall);
+        }
+        crate::stats::image_displayed(self.started.elapsed().as_micros() as u32);
+        Ok(())
+    }
+}
+
+/// Draw frame `frame` of every key animation, streamed from flash
+#[cfg(feature = "config-drive")]
+async fn draw_animation_frame<P: PanelDriver>(
+    controller: &mut DisplayController<P>,
+    player: &crate::animations::Player,
+    frame: usize,
+) {
+    for animation in player.animations() {
+        let mut stream = ImageStream::new(animation.key_id, b"BM");
+        for chunk in animation.frame(frame) {
+            stream.feed(controller, chunk).await;
+        }
+        if let Err(e) = stream.finish() {
+            warn!(
+                "Animation frame for key {} not displayed: {}",
+                animation.key_id, e
             );
-            self.reset();
-            return Err("Sequence error");
+            crate::stats::record_error(e);
         }
+    }
+}
 
-        // Copy payload data
-        let data_offset = 8;
-        let copy_len = (payload_len as usize).min(packet_data.len() - data_offset);
+/// BMP file + info header size
+const BMP_HEADER_SIZE: usize = 54;
 
-        if self
-            .data
-            .extend_from_slice(&packet_data[data_offset..data_offset + copy_len])
-            .is_err()
-        {
-            error!("Image buffer overflow");
-            self.reset();
-            return Err("Buffer overflow");
+// ===================================================================
+// Per-Key Frame Pacing
+// ===================================================================
+
+/// Frames held back at once; leaves one slab filling from USB and one queued
+const MAX_PENDING_FRAMES: usize = IMAGE_SLAB_COUNT - 2;
+
+/// Caps each key at `DISPLAY_MAX_KEY_FPS`. A key image that arrives early
+/// waits in its key's slot, where a newer image replaces it; waiting images
+/// are drawn round-robin across keys so one animated key can't starve the
+/// others.
+struct FramePacer {
+    last_drawn: [Option<Instant>; MAX_KEYS],
+    pending: [Option<ImageSlab>; MAX_KEYS],
+    pending_count: usize,
+    /// Key after the one drawn last; the round-robin scan starts here
+    cursor: usize,
+}
+
+impl FramePacer {
+    const fn new() -> Self {
+        Self {
+            last_drawn: [None; MAX_KEYS],
+            pending: [const { None }; MAX_KEYS],
+            pending_count: 0,
+            cursor: 0,
         }
+    }
 
-        self.expected_sequence += 1;
+    fn interval() -> Duration {
+        match DISPLAY_MAX_KEY_FPS {
+            0 => Duration::from_ticks(0),
+            fps => Duration::from_micros(1_000_000 / fps as u64),
+        }
+    }
 
-        debug!(
-            "Image packet key={} seq={} len={} total={}",
-            key_id,
-            sequence,
-            copy_len,
-            self.data.len()
-        );
+    fn due_at(&self, key: usize) -> Instant {
+        match self.last_drawn[key] {
+            Some(last) => last + Self::interval(),
+            None => Instant::from_ticks(0),
+        }
+    }
 
-        if is_last {
-            self.complete = true;
-            self.receiving = false;
-            info!(
-                "Image complete for key {} ({} bytes)",
-                key_id,
-                self.data.len()
-            );
-            return Ok(true);
+    /// Queue an image for its key (< `MAX_KEYS`), replacing any image still
+    /// waiting there
+    fn push(&mut self, key_id: u8, data: ImageSlab) {
+        let key = key_id as usize;
+        if self.due_at(key) > Instant::now() {
+            crate::stats::frame_deferred();
+        }
+        match self.pending[key].replace(data) {
+            Some(_) => crate::stats::frame_coalesced(),
+            None => self.pending_count += 1,
+        }
+    }
+
+    /// When the next waiting image may be drawn, or None if nothing waits.
+    /// With every spare slot in use the next image is due at once, so held
+    /// frames never stop USB from receiving new ones.
+    fn next_due(&self) -> Option<Instant> {
+        if self.pending_count > MAX_PENDING_FRAMES {
+            return Some(Instant::from_ticks(0));
         }
+        (0..MAX_KEYS)
+            .filter(|&key| self.pending[key].is_some())
+            .map(|key| self.due_at(key))
+            .min()
+    }
+
+    /// Take the next image to draw, scanning keys round-robin
+    fn take_due(&mut self) -> Option<(u8, ImageSlab)> {
+        let now = Instant::now();
+        let force = self.pending_count > MAX_PENDING_FRAMES;
+        let key = (0..MAX_KEYS)
+            .map(|i| (self.cursor + i) % MAX_KEYS)
+            .find(|&key| self.pending[key].is_some() && (force || self.due_at(key) <= now))?;
+        let data = self.pending[key].take()?;
+        self.pending_count -= 1;
+        self.last_drawn[key] = Some(now);
+        self.cursor = (key + 1) % MAX_KEYS;
+        Some((key as u8, data))
+    }
+
+    /// Forget waiting images for a key that is about to be redrawn otherwise
+    fn cancel(&mut self, key_id: u8) {
+        if let Some(slot) = self.pending.get_mut(key_id as usize) {
+            if slot.take().is_some() {
+                self.pending_count -= 1;
+            }
+        }
+    }
+
+    fn cancel_all(&mut self) {
+        for key in 0..MAX_KEYS {
+            self.cancel(key as u8);
+        }
+    }
+}
+
+// ===================================================================
+// Reset Fade-In
+// ===================================================================
+
+/// Backlight fade back in after a host reset, the way real decks fade
+/// their keys in. Scales the PWM duty of the current level up from dark on
+/// a square-law curve, which looks even to the eye where a linear duty
+/// ramp jumps at the start. Runs from the display loop's timer so images
+/// keep drawing during it.
+struct BacklightFade {
+    step: u8,
+    next_at: Instant,
+}
+
+impl BacklightFade {
+    fn start() -> Self {
+        Self {
+            step: 0,
+            next_at: Instant::now(),
+        }
+    }
+
+    /// Duty scale (0-255) for the next step once it is due; None when finished
+    fn take_due(&mut self) -> Option<u8> {
+        if self.step >= RESET_FADE_STEPS || Instant::now() < self.next_at {
+            return None;
+        }
+        self.step += 1;
+        self.next_at += Duration::from_millis(RESET_FADE_STEP_MS);
+        let (step, steps) = (self.step as u32, RESET_FADE_STEPS as u32);
+        Some((255 * step * step / (steps * steps)) as u8)
+    }
 
-        Ok(false)
+    fn done(&self) -> bool {
+        self.step >= RESET_FADE_STEPS
     }
 }
 
@@ -417,30 +1666,147 @@ impl ImageBuffer {
 // ===================================================================
 
 #[embassy_executor::task]
-pub async fn display_task(
-    spi: embassy_rp::spi::Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
-    cs: Output<'static>,
-    dc: Output<'static>,
-    rst: Output<'static>,
-    bl: Output<'static>,
-) {
+pub async fn display_task(link: DisplayLink, rst: Output<'static>, bl: Backlight) {
     info!("Display task started");
+    match link {
+        DisplayLink::Spi0 { spi, cs, dc } => run_display(SpiPanel { spi, cs, dc }, rst, bl).await,
+        DisplayLink::Spi1 { spi, cs, dc } => run_display(SpiPanel { spi, cs, dc }, rst, bl).await,
+    }
+}
 
-    let mut controller = DisplayController::new(spi, cs, dc, rst, bl).await;
+/// Draw a complete key image; the slab returns to the pool when the caller drops it
+async fn draw_image<P: PanelDriver>(
+    controller: &mut DisplayController<P>,
+    key_id: u8,
+    data: &ImageSlab,
+) {
+    let queue_us = data.assembled_at().elapsed().as_micros() as u32;
+    match controller.display_image(key_id, data).await {
+        Ok((decode_us, blit_us)) => crate::stats::image_pipeline(&PipelineTiming {
+            assemble_us: (data.assembled_at() - data.received_at()).as_micros() as u32,
+            queue_us,
+            decode_us,
+            blit_us,
+        }),
+        Err(e) => {
+            warn!("Image for key {} not displayed: {}", key_id, e);
+            crate::stats::record_error(e);
+        }
+    }
+}
 
-    let mut image_buffers: [ImageBuffer; 32] = Default::default(); // Max keys for any device
+/// Display task for per-key panels sharing SPI0; reset and backlight are common
+#[embassy_executor::task]
+pub async fn display_task_multi(
+    spi: embassy_rp::spi::Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+    panels: Vec<(Output<'static>, Output<'static>), MAX_KEYS>,
+    rst: Output<'static>,
+    bl: Backlight,
+) {
+    info!("Display task started (per-key panels)");
+    run_display(MultiPanelBus::new(spi, panels), rst, bl).await
+}
 
-    // Initialize image buffers
-    for buffer in &mut image_buffers {
-        *buffer = ImageBuffer::new();
-    }
+/// Display command loop shared by every panel bus
+pub(crate) async fn run_display<P: PanelDriver>(
+    panel: P,
+    rst: Output<'static>,
+    bl: Backlight,
+) -> ! {
+    // Staged power-up: the panel starts once USB has enumerated
+    crate::power::wait_for_stage(StartupStage::Panel).await;
+    let mut controller = DisplayController::new(panel, rst, bl).await;
 
+    let control = DISPLAY_CONTROL_CHANNEL.receiver();
     let receiver = DISPLAY_CHANNEL.receiver();
+    let mut device_bus = subscribe_device_bus();
+    let mut stream: Option<ImageStream> = None;
+    let mut pacer = FramePacer::new();
+    let mut fade: Option<BacklightFade> = None;
+    #[cfg(feature = "config-drive")]
+    let mut animations = crate::animations::Player::load();
 
     info!("Display controller ready");
+    crate::power::advance_startup(StartupStage::Backlight);
 
     loop {
-        match receiver.receive().await {
+        // Control commands and the device bus come first, then queued
+        // drawing, then a due frame; frames go out one per pass, so commands
+        // keep flowing between them
+        let next_timer = [
+            pacer.next_due(),
+            controller.next_highlight_end(),
+            fade.as_ref().map(|fade| fade.next_at),
+            #[cfg(feature = "config-drive")]
+            animations.next_due(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let timer_due = async {
+            match next_timer {
+                Some(at) => Timer::at(at).await,
+                None => core::future::pending().await,
+            }
+        };
+        let command = match select4(
+            control.receive(),
+            next_device_command(&mut device_bus),
+            receiver.receive(),
+            timer_due,
+        )
+        .await
+        {
+            Either4::First(command) => {
+                crate::stats::pool_used(Pool::DisplayControl, control.len() + 1);
+                command
+            }
+            Either4::Third(command) => {
+                crate::stats::pool_used(Pool::DisplayCommands, receiver.len() + 1);
+                command
+            }
+            Either4::Second(DeviceCommand::Reset) => {
+                // Full host reset: half-drawn images and per-key levels go,
+                // the keys clear, then the backlight fades back in
+                stream = None;
+                controller.brightness.clear_overrides();
+                controller.brightness.set_global(brightness::host_percent());
+                controller.scale_backlight(0);
+                fade = Some(BacklightFade::start());
+                DisplayCommand::ClearAll
+            }
+            Either4::Second(command) => command.into(),
+            Either4::Fourth(()) => {
+                if let Some(scale) = fade.as_mut().and_then(BacklightFade::take_due) {
+                    controller.scale_backlight(scale);
+                }
+                if fade.as_ref().is_some_and(BacklightFade::done) {
+                    fade = None;
+                }
+                controller.expire_highlights().await;
+                if let Some((key_id, data)) = pacer.take_due() {
+                    draw_image(&mut controller, key_id, &data).await;
+                }
+                #[cfg(feature = "config-drive")]
+                if let Some(frame) = animations.take_due() {
+                    draw_animation_frame(&mut controller, &animations, frame).await;
+                }
+                continue;
+            }
+        };
+        // Anything else drawn to a key supersedes the image waiting for it
+        match &command {
+            DisplayCommand::Clear(key_id)
+            | DisplayCommand::FillKey { key_id, .. }
+            | DisplayCommand::DrawRle { key_id, .. }
+            | DisplayCommand::ColorBars(key_id)
+            | DisplayCommand::DrawText { key_id, .. }
+            | DisplayCommand::DisplayImageChunk { key_id, .. }
+            | DisplayCommand::ShowStoredImage { key_id, .. } => pacer.cancel(*key_id),
+            DisplayCommand::ClearAll => pacer.cancel_all(),
+            _ => {}
+        }
+        match command {
             DisplayCommand::Clear(key_id) => {
                 controller.clear_key(key_id).await;
             }
@@ -448,41 +1814,122 @@ pub async fn display_task(
                 controller.clear_all().await;
             }
             DisplayCommand::SetBrightness(brightness) => {
+                // The host's own level wins over a fade still running
+                fade = None;
                 controller.set_brightness(brightness).await;
             }
-            DisplayCommand::DisplayImage { key_id, data } => {
-                if key_id < 32 {
-                    // Max keys for any device
-                    let buffer = &mut image_buffers[key_id as usize];
-
-                    match buffer.add_packet(&data) {
-                        Ok(true) => {
-                            // Image complete, display it
-                            controller.display_image(key_id, &buffer.data).await;
-                            buffer.reset();
-                        }
-                        Ok(false) => {
-                            // More packets expected
-                            debug!("Partial image data received for key {}", key_id);
-                        }
-                        Err(e) => {
-                            error!("Image processing error for key {}: {}", key_id, e);
+            DisplayCommand::SetKeyBrightness { key_id, value } => {
+                controller.set_key_brightness(key_id, value).await;
+            }
+            DisplayCommand::Sleep => {
+                controller.sleep().await;
+            }
+            DisplayCommand::Wake => {
+                controller.wake().await;
+            }
+            DisplayCommand::FillKey { key_id, color } => {
+                controller.fill_key(key_id, color).await;
+            }
+            DisplayCommand::DrawRle { key_id, image } => {
+                if let Err(e) = controller.draw_rle(key_id, &image).await {
+                    warn!("RLE image for key {} not displayed: {}", key_id, e);
+                    crate::stats::record_error(e);
+                }
+            }
+            DisplayCommand::ColorBars(key_id) => {
+                controller.color_bars(key_id).await;
+            }
+            DisplayCommand::DrawText {
+                key_id,
+                text,
+                fg,
+                bg,
+            } => {
+                controller.draw_text(key_id, &text, fg, bg).await;
+            }
+            DisplayCommand::DisplayImageChunk {
+                key_id,
+                offset,
+                last,
+                data,
+            } => {
+                if offset == 0 {
+                    if key_id >= crate::config::streamdeck_keys() as u8 {
+                        warn!("Invalid key_id: {}", key_id);
+                        crate::stats::record_error(DisplayError::InvalidKey);
+                        stream = None;
+                        continue;
+                    }
+                    stream = Some(ImageStream::new(key_id, &data));
+                }
+                let result = match stream.as_mut() {
+                    Some(s) if s.key_id == key_id && s.next_offset == offset => {
+                        s.feed(&mut controller, &data).await;
+                        if last {
+                            s.finish()
+                        } else {
+                            Ok(())
                         }
                     }
+                    // Missing chunk or another key's stream interleaved
+                    _ => Err(DisplayError::SequenceError),
+                };
+                if let Err(e) = result {
+                    warn!("Streamed image for key {} dropped: {}", key_id, e);
+                    crate::stats::record_error(e);
+                    stream = None;
+                } else if last {
+                    log_limited!(
+                        debug,
+                        Subsystem::Display,
+                        "Streamed image complete for key {}",
+                        key_id
+                    );
+                    stream = None;
+                }
+            }
+            DisplayCommand::DisplayImage { key_id, data } => {
+                if (key_id as usize) < MAX_KEYS {
+                    pacer.push(key_id, data);
                 } else {
-                    error!("Invalid key_id: {}", key_id);
+                    // Drawn at once so the invalid key is reported
+                    draw_image(&mut controller, key_id, &data).await;
+                }
+            }
+            DisplayCommand::LimitBrightness(percent) => {
+                controller.limit_brightness(percent);
+            }
+            DisplayCommand::HighlightKey(key_id) => {
+                controller.highlight_key(key_id).await;
+            }
+            DisplayCommand::SetRotation(rotation) => {
+                controller.set_rotation(rotation).await;
+            }
+            DisplayCommand::SetScreenSleep { screen, sleep } => {
+                controller.set_screen_sleep(screen, sleep).await;
+            }
+            DisplayCommand::SetScreenBrightness { screen, value } => {
+                controller.set_screen_brightness(screen, value).await;
+            }
+            DisplayCommand::ShowStoredImage { key_id, variant } => {
+                if let Some(image) = crate::key_images::image(key_id, variant) {
+                    if let Err(e) = controller.display_image(key_id, image).await {
+                        warn!("Stored image for key {} not displayed: {}", key_id, e);
+                        crate::stats::record_error(e);
+                    }
+                }
+            }
+            DisplayCommand::DisplayLcdImage { region, data } => {
+                if let Err(e) = controller.display_lcd_image(region, &data).await {
+                    warn!("LCD image not displayed: {}", e);
+                    crate::stats::record_error(e);
                 }
             }
+            DisplayCommand::DrawInfoBar { region, text } => {
+                controller
+                    .draw_lcd_text(region, &text, INFO_BAR_FG, INFO_BAR_BG)
+                    .await;
+            }
         }
     }
 }
-
-// ===================================================================
-// Default trait implementation for ImageBuffer array
-// ===================================================================
-
-impl Default for ImageBuffer {
-    fn default() -> Self {
-        Self::new()
-    }
-}
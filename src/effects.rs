@@ -0,0 +1,257 @@
+//! Accent LED ring effects
+//!
+//! A WS2812 ring or strip around the panel (`led-ring`) shows ambient
+//! effects next to the key LEDs: every LED shows the same color, chosen here
+//! from the device state and the stored `RingEffects`:
+//!
+//! | Priority | Condition                                    | Ring                          |
+//! |----------|----------------------------------------------|-------------------------------|
+//! | 1        | host asleep or brightness 0                  | off                           |
+//! | 2        | error within `RING_ERROR_FLASH_MS`           | red blinks (`flash_error`)    |
+//! | 3        | key image within `RING_ACTIVITY_FLASH_MS`    | white (`flash_activity`)      |
+//! | 4        | otherwise                                    | idle: off, solid or breathe   |
+//!
+//! The ring follows the host brightness like the backlight, within the night
+//! mode cap (`night::backlight_cap`). The effect setup
+//! is stored in settings and changed through the vendor report
+//! (`VENDOR_CMD_SET_RING_EFFECTS`), so it can be set up on builds without a
+//! ring too.
+//!
+//! The ring is clocked by PIO1 state machine 0 through DMA_CH3, data on
+//! GPIO22 (`LED_RING_PIN`). The parallel display needs PIO1 as well and the
+//! e-paper panel's BUSY line is GPIO22, so builds with either do not compile
+//! with the ring (see the feature checks in `hardware`).
+
+use core::cell::Cell;
+use defmt::*;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::Instant;
+
+use crate::config::{
+    RING_ACTIVITY_FLASH_MS, RING_BREATHE_MS, RING_ERROR_BLINK_MS, RING_ERROR_FLASH_MS,
+};
+
+/// What the ring shows when nothing is flashing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum IdleEffect {
+    Off = 0,
+    Solid = 1,
+    /// Slow fade of the ring color up and down (`RING_BREATHE_MS`)
+    Breathe = 2,
+}
+
+impl IdleEffect {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => IdleEffect::Off,
+            1 => IdleEffect::Solid,
+            2 => IdleEffect::Breathe,
+            _ => return None,
+        })
+    }
+}
+
+/// Stored ring effect setup
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+pub struct RingEffects {
+    pub idle: IdleEffect,
+    /// Idle color, RGB
+    pub color: [u8; 3],
+    /// Flash white when a key image arrives
+    pub flash_activity: bool,
+    /// Blink red when an error is recorded
+    pub flash_error: bool,
+}
+
+impl RingEffects {
+    pub const DEFAULT: Self = Self {
+        idle: IdleEffect::Breathe,
+        color: [0x00, 0x60, 0xFF],
+        flash_activity: true,
+        flash_error: true,
+    };
+}
+
+const OFF: [u8; 3] = [0, 0, 0];
+const ACTIVITY_COLOR: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const ERROR_COLOR: [u8; 3] = [0xFF, 0x00, 0x00];
+
+/// Wakes the ring when a flash starts or the setup changes
+static CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+static ACTIVITY_AT: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+static ERROR_AT: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+
+/// A key image arrived from the host
+pub fn activity() {
+    ACTIVITY_AT.lock(|at| at.set(Some(Instant::now())));
+    CHANGED.signal(());
+}
+
+/// An error was recorded
+pub fn error() {
+    ERROR_AT.lock(|at| at.set(Some(Instant::now())));
+    CHANGED.signal(());
+}
+
+/// Let the ring pick up a changed effect setup now
+pub fn notify() {
+    CHANGED.signal(());
+}
+
+/// Time since a flash started, if it is still running
+fn flashing(at: &Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>>, ms: u64) -> Option<u64> {
+    let elapsed = at.lock(|at| at.get())?.elapsed().as_millis();
+    (elapsed < ms).then_some(elapsed)
+}
+
+/// Color of the ring now, before brightness, and whether it is changing
+/// (so the next frame is due after `RING_FRAME_MS`)
+pub fn frame(effects: &RingEffects) -> ([u8; 3], bool) {
+    if effects.flash_error {
+        if let Some(elapsed) = flashing(&ERROR_AT, RING_ERROR_FLASH_MS) {
+            let on = (elapsed / RING_ERROR_BLINK_MS).is_multiple_of(2);
+            return (if on { ERROR_COLOR } else { OFF }, true);
+        }
+    }
+    if effects.flash_activity && flashing(&ACTIVITY_AT, RING_ACTIVITY_FLASH_MS).is_some() {
+        return (ACTIVITY_COLOR, true);
+    }
+    match effects.idle {
+        IdleEffect::Off => (OFF, false),
+        IdleEffect::Solid => (effects.color, false),
+        IdleEffect::Breathe => {
+            // Triangle wave from a dim floor, so the ring never goes fully dark
+            let phase = Instant::now().as_millis() % RING_BREATHE_MS;
+            let half = RING_BREATHE_MS / 2;
+            let rise = if phase < half {
+                phase
+            } else {
+                RING_BREATHE_MS - phase
+            };
+            let level = (32 + rise * (255 - 32) / half) as u8;
+            (
+                effects
+                    .color
+                    .map(|c| crate::brightness::scale_channel(c, level)),
+                true,
+            )
+        }
+    }
+}
+
+/// The setup the ring runs
+pub fn current() -> RingEffects {
+    crate::settings::with(|s| s.ring_effects)
+}
+
+#[cfg(feature = "led-ring")]
+pub use ring::{effects_task, init};
+
+#[cfg(feature = "led-ring")]
+mod ring {
+    use core::cell::RefCell;
+
+    use defmt::*;
+    use embassy_futures::select::{select3, Either3};
+    use embassy_rp::peripherals::{DMA_CH3, PIN_22, PIO1};
+    use embassy_rp::pio::Pio;
+    use embassy_rp::pio_programs::ws2812::{PioWs2812, PioWs2812Program};
+    use embassy_rp::{bind_interrupts, Peri};
+    use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+    use embassy_sync::blocking_mutex::Mutex;
+    use embassy_time::{Duration, Timer};
+    use smart_leds::RGB8;
+
+    use super::{current, frame, CHANGED, OFF};
+    use crate::brightness::{percent_to_duty, scale_channel};
+    use crate::channels::{next_device_command, subscribe_device_bus};
+    use crate::config::{LED_RING_LEDS, LED_RING_PIN, RING_FRAME_MS};
+    use crate::types::DeviceCommand;
+
+    bind_interrupts!(struct PioIrqs {
+        PIO1_IRQ_0 => embassy_rp::pio::InterruptHandler<PIO1>;
+    });
+
+    /// Peripherals claimed at init, handed to the effects task
+    struct RingResources {
+        pio: Peri<'static, PIO1>,
+        dma: Peri<'static, DMA_CH3>,
+        pin: Peri<'static, PIN_22>,
+    }
+
+    static RESOURCES: Mutex<CriticalSectionRawMutex, RefCell<Option<RingResources>>> =
+        Mutex::new(RefCell::new(None));
+
+    /// Take ownership of PIO1, DMA_CH3 and the data pin.
+    /// Must be called before the effects task is spawned.
+    pub fn init(pio: Peri<'static, PIO1>, dma: Peri<'static, DMA_CH3>, pin: Peri<'static, PIN_22>) {
+        RESOURCES.lock(|r| r.replace(Some(RingResources { pio, dma, pin })));
+    }
+
+    /// Drive the accent ring; never returns once started
+    #[embassy_executor::task]
+    pub async fn effects_task() {
+        let Some(resources) = RESOURCES.lock(|r| r.take()) else {
+            error!("LED ring resources not initialized");
+            return;
+        };
+        let Pio {
+            mut common, sm0, ..
+        } = Pio::new(resources.pio, PioIrqs);
+        let program = PioWs2812Program::new(&mut common);
+        let mut ring: PioWs2812<'static, PIO1, 0, LED_RING_LEDS> =
+            PioWs2812::new(&mut common, sm0, resources.dma, resources.pin, &program);
+        info!("LED ring: {} LEDs on GPIO{}", LED_RING_LEDS, LED_RING_PIN);
+
+        let mut device_bus = subscribe_device_bus();
+        let mut percent = crate::brightness::host_percent();
+        let mut asleep = crate::power::usb_suspended();
+        let mut shown = None;
+
+        loop {
+            let (color, moving) = if asleep {
+                (OFF, false)
+            } else {
+                frame(&current())
+            };
+            let duty = percent_to_duty(percent.min(crate::night::backlight_cap()));
+            let color = color.map(|c| scale_channel(c, duty));
+            if shown != Some(color) {
+                let [r, g, b] = color;
+                ring.write(&[RGB8::new(r, g, b); LED_RING_LEDS]).await;
+                shown = Some(color);
+            }
+
+            let next_frame = async {
+                if moving {
+                    Timer::after(Duration::from_millis(RING_FRAME_MS)).await
+                } else {
+                    core::future::pending().await
+                }
+            };
+            match select3(
+                next_frame,
+                CHANGED.wait(),
+                next_device_command(&mut device_bus),
+            )
+            .await
+            {
+                Either3::First(()) | Either3::Second(()) => {}
+                Either3::Third(command) => match command {
+                    DeviceCommand::SetBrightness(level) => percent = level,
+                    DeviceCommand::Sleep => asleep = true,
+                    DeviceCommand::Wake => asleep = false,
+                    DeviceCommand::Reset => {
+                        super::ACTIVITY_AT.lock(|at| at.set(None));
+                        super::ERROR_AT.lock(|at| at.set(None));
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,713 @@
+//! E-paper display backend (`epaper-display` feature)
+//!
+//! Drives an SSD1680-class black/white panel (2.9", 296x128) in place of the
+//! ST7735. Key images are converted to grayscale, ordered-dithered to 1 bit
+//! and scaled into a per-key cell of a framebuffer. Only the region touched
+//! since the last refresh is sent, with a partial refresh, and refreshes are
+//! throttled so a burst of host updates coalesces into one. Every
+//! `EPAPER_FULL_REFRESH_EVERY` partial refreshes a full refresh clears
+//! ghosting.
+//!
+//! The panel keeps its image without power, so once a rarely-changing layout
+//! is drawn the device can sit idle at near-zero display current.
+
+use defmt::*;
+use embassy_futures::select::{select4, Either4};
+use embassy_rp::gpio::{Input, Output};
+use embassy_rp::peripherals;
+use embassy_rp::spi::Spi;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+use heapless::Vec;
+
+use crate::channels::{
+    next_device_command, subscribe_device_bus, DISPLAY_CHANNEL, DISPLAY_CONTROL_CHANNEL,
+};
+use crate::config::*;
+use crate::error::DisplayError;
+use crate::gfx;
+use crate::logging::Subsystem;
+use crate::power::StartupStage;
+use crate::protocol::image;
+use crate::stats::Pool;
+use crate::types::{DisplayCommand, RleImage, Rotation, Screen};
+use crate::{log_at, log_limited};
+
+/// Framebuffer bytes per panel row (one bit per source line)
+const ROW_BYTES: usize = EPAPER_HEIGHT / 8;
+pub(crate) const FRAMEBUFFER_SIZE: usize = ROW_BYTES * EPAPER_WIDTH;
+
+/// 4x4 ordered dither thresholds
+const BAYER_4X4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// BMP file + info header size
+const BMP_HEADER_SIZE: usize = 54;
+
+/// Perceptual luma of an RGB888 pixel
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u16 * 77 + g as u16 * 150 + b as u16 * 29) >> 8) as u8
+}
+
+/// Luma of an RGB565 color
+fn luma565(color: u16) -> u8 {
+    let r = ((color >> 8) & 0xF8) as u8;
+    let g = ((color >> 3) & 0xFC) as u8;
+    let b = ((color << 3) & 0xF8) as u8;
+    luma(r, g, b)
+}
+
+/// Ordered dither of a gray level at a framebuffer position (true = white)
+fn dither(gray: u8, x: usize, y: usize) -> bool {
+    gray as u16 > BAYER_4X4[(y % 4) * 4 + x % 4] as u16 * 16 + 8
+}
+
+/// Inclusive rectangle in logical (landscape) coordinates
+#[derive(Clone, Copy, Debug, defmt::Format)]
+struct Rect {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+impl Rect {
+    fn union(self, other: Rect) -> Rect {
+        Rect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+}
+
+// ===================================================================
+// E-paper Controller
+// ===================================================================
+
+struct EpaperController {
+    spi: Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+    cs: Output<'static>,
+    dc: Output<'static>,
+    rst: Output<'static>,
+    busy: Input<'static>,
+    /// Panel-native framebuffer: one row of `ROW_BYTES` per gate line, 1 = white
+    framebuffer: [u8; FRAMEBUFFER_SIZE],
+    /// Area drawn since the last refresh
+    dirty: Option<Rect>,
+    /// When the first change since the last refresh was drawn
+    dirty_since: Instant,
+    last_refresh: Instant,
+    partial_refreshes: u8,
+    asleep: bool,
+    /// Steps source columns when fitting key images into cells
+    #[cfg(feature = "interp-convert")]
+    interp: crate::interp::Interp,
+}
+
+impl EpaperController {
+    async fn new(
+        spi: Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+        cs: Output<'static>,
+        dc: Output<'static>,
+        rst: Output<'static>,
+        busy: Input<'static>,
+    ) -> Self {
+        info!("Initializing e-paper controller");
+
+        let mut controller = Self {
+            spi,
+            cs,
+            dc,
+            rst,
+            busy,
+            framebuffer: [0xFF; FRAMEBUFFER_SIZE],
+            dirty: None,
+            dirty_since: Instant::now(),
+            last_refresh: Instant::now(),
+            partial_refreshes: 0,
+            asleep: false,
+            #[cfg(feature = "interp-convert")]
+            interp: crate::interp::Interp::claim(),
+        };
+        controller.init_panel().await;
+        controller.full_refresh().await;
+        controller
+    }
+
+    async fn init_panel(&mut self) {
+        info!("Initializing SSD1680 ({}x{})", EPAPER_WIDTH, EPAPER_HEIGHT);
+
+        self.cs.set_low();
+
+        self.rst.set_low();
+        Timer::after(Duration::from_millis(10)).await;
+        self.rst.set_high();
+        Timer::after(Duration::from_millis(10)).await;
+        self.wait_busy().await;
+
+        self.send_command(SSD1680_SWRESET);
+        self.wait_busy().await;
+
+        // Gate lines run along the logical x axis
+        let gates = (EPAPER_WIDTH - 1) as u16;
+        self.send_command(SSD1680_DRIVER_OUTPUT);
+        self.send_data(&[gates as u8, (gates >> 8) as u8, 0x00]);
+
+        // X and Y increment, X first
+        self.send_command(SSD1680_DATA_ENTRY_MODE);
+        self.send_data(&[0x03]);
+
+        self.send_command(SSD1680_BORDER_WAVEFORM);
+        self.send_data(&[0x05]);
+
+        self.send_command(SSD1680_UPDATE_CONTROL_1);
+        self.send_data(&[0x00, 0x80]);
+
+        // Internal temperature sensor
+        self.send_command(SSD1680_TEMP_SENSOR);
+        self.send_data(&[0x80]);
+
+        self.cs.set_high();
+        self.asleep = false;
+    }
+
+    fn send_command(&mut self, command: u8) {
+        self.dc.set_low();
+        let _ = self.spi.blocking_write(&[command]);
+    }
+
+    fn send_data(&mut self, data: &[u8]) {
+        self.dc.set_high();
+        let _ = self.spi.blocking_write(data);
+    }
+
+    async fn wait_busy(&mut self) {
+        let timeout = Duration::from_millis(EPAPER_BUSY_TIMEOUT_MS);
+        if with_timeout(timeout, self.busy.wait_for_low())
+            .await
+            .is_err()
+        {
+            warn!("E-paper busy timeout");
+        }
+    }
+
+    /// Send framebuffer bytes for panel columns `xb0..=xb1` (in bytes) and
+    /// gate lines `y0..=y1` to one of the panel RAMs. The panel must be selected.
+    fn write_ram(&mut self, ram: u8, xb0: usize, xb1: usize, y0: usize, y1: usize) {
+        self.send_command(SSD1680_RAM_X_RANGE);
+        self.send_data(&[xb0 as u8, xb1 as u8]);
+        self.send_command(SSD1680_RAM_Y_RANGE);
+        self.send_data(&[y0 as u8, (y0 >> 8) as u8, y1 as u8, (y1 >> 8) as u8]);
+        self.send_command(SSD1680_RAM_X_COUNTER);
+        self.send_data(&[xb0 as u8]);
+        self.send_command(SSD1680_RAM_Y_COUNTER);
+        self.send_data(&[y0 as u8, (y0 >> 8) as u8]);
+
+        self.send_command(ram);
+        self.dc.set_high();
+        for line in y0..=y1 {
+            let start = line * ROW_BYTES;
+            let _ = self
+                .spi
+                .blocking_write(&self.framebuffer[start + xb0..=start + xb1]);
+        }
+    }
+
+    /// Trigger a display update with the given sequence and wait for it
+    async fn update(&mut self, sequence: u8) {
+        self.send_command(SSD1680_UPDATE_CONTROL_2);
+        self.send_data(&[sequence]);
+        self.send_command(SSD1680_MASTER_ACTIVATION);
+        self.wait_busy().await;
+    }
+
+    /// Redraw the whole panel with the full waveform (clears ghosting)
+    async fn full_refresh(&mut self) {
+        let started = Instant::now();
+        let (xb1, y1) = (ROW_BYTES - 1, EPAPER_WIDTH - 1);
+
+        self.cs.set_low();
+        self.write_ram(SSD1680_WRITE_RAM_BW, 0, xb1, 0, y1);
+        // Partial refreshes drive from the old image in the second RAM
+        self.write_ram(SSD1680_WRITE_RAM_OLD, 0, xb1, 0, y1);
+        self.update(SSD1680_SEQUENCE_FULL).await;
+        self.cs.set_high();
+
+        self.dirty = None;
+        self.partial_refreshes = 0;
+        self.last_refresh = Instant::now();
+        info!(
+            "E-paper full refresh in {} ms",
+            started.elapsed().as_millis()
+        );
+    }
+
+    /// Push the dirty region to the panel
+    async fn refresh(&mut self) {
+        let Some(rect) = self.dirty else {
+            return;
+        };
+        if self.partial_refreshes >= EPAPER_FULL_REFRESH_EVERY {
+            self.full_refresh().await;
+            return;
+        }
+
+        let started = Instant::now();
+        // Logical y maps to panel columns, logical x to gate lines
+        let (xb0, xb1) = (rect.y0 / 8, rect.y1 / 8);
+        let (y0, y1) = (rect.x0, rect.x1);
+
+        self.cs.set_low();
+        self.write_ram(SSD1680_WRITE_RAM_BW, xb0, xb1, y0, y1);
+        self.update(SSD1680_SEQUENCE_PARTIAL).await;
+        // Keep the old-image RAM in step for the next differential update
+        self.write_ram(SSD1680_WRITE_RAM_OLD, xb0, xb1, y0, y1);
+        self.cs.set_high();
+
+        self.dirty = None;
+        self.partial_refreshes += 1;
+        self.last_refresh = Instant::now();
+        log_limited!(
+            debug,
+            Subsystem::Display,
+            "E-paper partial refresh of {} in {} ms",
+            rect,
+            started.elapsed().as_millis()
+        );
+    }
+
+    /// When the pending changes may be refreshed, or None if nothing is pending
+    fn refresh_due(&self) -> Option<Instant> {
+        if self.dirty.is_none() || self.asleep {
+            return None;
+        }
+        let throttle = self.last_refresh + Duration::from_millis(EPAPER_MIN_REFRESH_MS);
+        let settle = self.dirty_since + Duration::from_millis(EPAPER_SETTLE_MS);
+        Some(throttle.max(settle))
+    }
+
+    fn mark_dirty(&mut self, rect: Rect) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(rect),
+            None => {
+                self.dirty_since = Instant::now();
+                rect
+            }
+        });
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, white: bool) {
+        if x >= EPAPER_WIDTH || y >= EPAPER_HEIGHT {
+            return;
+        }
+        let index = x * ROW_BYTES + y / 8;
+        let bit = 0x80 >> (y % 8);
+        if white {
+            self.framebuffer[index] |= bit;
+        } else {
+            self.framebuffer[index] &= !bit;
+        }
+    }
+
+    /// Side of the square cell each key is drawn into
+    fn cell_size() -> usize {
+        let cols = crate::config::streamdeck_cols();
+        let rows = crate::config::streamdeck_rows();
+        (EPAPER_WIDTH / cols).min(EPAPER_HEIGHT / rows)
+    }
+
+    /// Panel area of a key cell (grid centered on the panel), or None for an invalid key
+    fn key_cell(key_id: u8) -> Option<Rect> {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            warn!("Invalid key_id: {}", key_id);
+            crate::stats::record_error(DisplayError::InvalidKey);
+            return None;
+        }
+        let cols = crate::config::streamdeck_cols();
+        let rows = crate::config::streamdeck_rows();
+        let cell = Self::cell_size();
+        let x0 = (EPAPER_WIDTH - cols * cell) / 2 + (key_id as usize % cols) * cell;
+        let y0 = (EPAPER_HEIGHT - rows * cell) / 2 + (key_id as usize / cols) * cell;
+        Some(Rect {
+            x0,
+            y0,
+            x1: x0 + cell - 1,
+            y1: y0 + cell - 1,
+        })
+    }
+
+    /// Fill a key cell from a per-pixel gray level function
+    fn fill_cell(&mut self, key_id: u8, gray: impl Fn(usize, usize) -> u8) {
+        let Some(cell) = Self::key_cell(key_id) else {
+            return;
+        };
+        let size = Self::cell_size();
+        for dy in 0..size {
+            for dx in 0..size {
+                let (x, y) = (cell.x0 + dx, cell.y0 + dy);
+                self.set_pixel(x, y, dither(gray(dx, dy), x, y));
+            }
+        }
+        self.mark_dirty(cell);
+    }
+
+    /// Draw one source row of a key image (RGB888, `key_image_size` wide),
+    /// scaled to the key cell
+    fn draw_key_row(&mut self, key_id: u8, row: usize, rgb_data: &[u8]) {
+        let Some(cell) = Self::key_cell(key_id) else {
+            return;
+        };
+        let source = crate::config::key_image_size();
+        let size = Self::cell_size();
+        let rotation = crate::config::key_image_rotation();
+        if rotation != Rotation::Deg0 {
+            self.draw_turned_key_row(cell, row, rgb_data, rotation);
+            return;
+        }
+
+        // Cell rows whose nearest source row is `row`
+        let first = (row * size).div_ceil(source);
+        let end = ((row + 1) * size).div_ceil(source).min(size);
+        for dy in first..end {
+            #[cfg(feature = "interp-convert")]
+            let columns = self.interp.nearest(source, size);
+            #[cfg(not(feature = "interp-convert"))]
+            let columns = (0..size).map(|dx| dx * source / size);
+            for (dx, sx) in columns.enumerate() {
+                let Some(pixel) = rgb_data.get(sx * 3..sx * 3 + 3) else {
+                    continue;
+                };
+                let (x, y) = (cell.x0 + dx, cell.y0 + dy);
+                self.set_pixel(x, y, dither(luma(pixel[0], pixel[1], pixel[2]), x, y));
+            }
+        }
+        if first < end {
+            self.mark_dirty(Rect {
+                x0: cell.x0,
+                y0: cell.y0 + first,
+                x1: cell.x1,
+                y1: cell.y0 + end - 1,
+            });
+        }
+    }
+
+    /// `draw_key_row` for key images the host sends turned: each source
+    /// pixel fills the block of cell pixels it lands on once turned upright
+    fn draw_turned_key_row(&mut self, cell: Rect, row: usize, rgb_data: &[u8], rotation: Rotation) {
+        let source = crate::config::key_image_size();
+        let size = Self::cell_size();
+        // Cell pixels whose nearest source pixel is `at`
+        let span =
+            |at: usize| (at * size).div_ceil(source)..((at + 1) * size).div_ceil(source).min(size);
+
+        for (sx, pixel) in rgb_data.chunks_exact(3).take(source).enumerate() {
+            let (tx, ty) = image::rotate_point(source, rotation, sx, row);
+            let gray = luma(pixel[0], pixel[1], pixel[2]);
+            for dy in span(ty) {
+                for dx in span(tx) {
+                    let (x, y) = (cell.x0 + dx, cell.y0 + dy);
+                    self.set_pixel(x, y, dither(gray, x, y));
+                }
+            }
+        }
+        self.mark_dirty(cell);
+    }
+
+    fn display_image(&mut self, key_id: u8, image_data: &[u8]) -> Result<(), DisplayError> {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            return Err(DisplayError::InvalidKey);
+        }
+        let started = Instant::now();
+
+        let mut rgb_data = image_data;
+        if rgb_data.len() > BMP_HEADER_SIZE && rgb_data[0] == 0x42 && rgb_data[1] == 0x4D {
+            rgb_data = &rgb_data[BMP_HEADER_SIZE..];
+        }
+        let source = crate::config::key_image_size();
+        if rgb_data.len() < source * source * 3 {
+            return Err(DisplayError::ImageTooSmall);
+        }
+
+        for (row, data) in rgb_data.chunks_exact(source * 3).take(source).enumerate() {
+            self.draw_key_row(key_id, row, data);
+        }
+
+        crate::stats::image_displayed(started.elapsed().as_micros() as u32);
+        log_at!(
+            debug,
+            Subsystem::Display,
+            "Image for key {} drawn to e-paper framebuffer",
+            key_id
+        );
+        Ok(())
+    }
+
+    fn draw_rle(&mut self, key_id: u8, image: &RleImage) -> Result<(), DisplayError> {
+        if key_id >= crate::config::streamdeck_keys() as u8 {
+            return Err(DisplayError::InvalidKey);
+        }
+        let source = crate::config::key_image_size();
+        if image.pixel_count() < source * source {
+            return Err(DisplayError::ImageTooSmall);
+        }
+
+        let mut pixels = image.pixels();
+        let mut row: Vec<u8, MAX_ROW_BYTES> = Vec::new();
+        for y in 0..source {
+            row.clear();
+            for pixel in pixels.by_ref().take(source) {
+                let _ = row.extend_from_slice(&pixel);
+            }
+            self.draw_key_row(key_id, y, &row);
+        }
+        Ok(())
+    }
+
+    fn color_bars(&mut self, key_id: u8) {
+        // Gray ramp standing in for the TFT color bars
+        let size = Self::cell_size();
+        self.fill_cell(key_id, |dx, _| (255 - dx * 8 / size * 36) as u8);
+    }
+
+    fn draw_text(&mut self, key_id: u8, text: &str, fg: u16, bg: u16) {
+        let size = Self::cell_size();
+        let block = gfx::TextBlock::centered(text, size, size);
+        let (fg, bg) = (luma565(fg), luma565(bg));
+        self.fill_cell(key_id, |dx, dy| if block.pixel(dx, dy) { fg } else { bg });
+    }
+
+    fn clear_all(&mut self) {
+        self.framebuffer.fill(0xFF);
+        self.mark_dirty(Rect {
+            x0: 0,
+            y0: 0,
+            x1: EPAPER_WIDTH - 1,
+            y1: EPAPER_HEIGHT - 1,
+        });
+    }
+
+    async fn sleep(&mut self) {
+        if self.asleep {
+            return;
+        }
+        // Flush pending changes; the panel holds the image while powered down
+        self.refresh().await;
+        info!("E-paper entering deep sleep");
+        self.cs.set_low();
+        self.send_command(SSD1680_DEEP_SLEEP);
+        self.send_data(&[0x01]);
+        self.cs.set_high();
+        self.asleep = true;
+    }
+
+    async fn wake(&mut self) {
+        if !self.asleep {
+            return;
+        }
+        info!("E-paper waking from deep sleep");
+        // Deep sleep loses the RAM contents; only a hardware reset wakes the panel
+        self.init_panel().await;
+        self.full_refresh().await;
+    }
+}
+
+// ===================================================================
+// Streaming Image Decode
+// ===================================================================
+
+/// Row assembler for a streamed BMP key image
+struct EpaperStream {
+    key_id: u8,
+    next_offset: u32,
+    header_remaining: usize,
+    row: Vec<u8, MAX_ROW_BYTES>,
+    rows_written: usize,
+}
+
+impl EpaperStream {
+    fn new(key_id: u8, first_chunk: &[u8]) -> Self {
+        let has_header = first_chunk.len() >= 2 && first_chunk[0] == 0x42 && first_chunk[1] == 0x4D;
+        Self {
+            key_id,
+            next_offset: 0,
+            header_remaining: if has_header { BMP_HEADER_SIZE } else { 0 },
+            row: Vec::new(),
+            rows_written: 0,
+        }
+    }
+
+    fn feed(&mut self, controller: &mut EpaperController, mut data: &[u8]) {
+        self.next_offset += data.len() as u32;
+
+        let skip = self.header_remaining.min(data.len());
+        self.header_remaining -= skip;
+        data = &data[skip..];
+
+        let image_size = crate::config::key_image_size();
+        let row_bytes = (image_size * 3).min(MAX_ROW_BYTES);
+        while !data.is_empty() && self.rows_written < image_size {
+            let take = (row_bytes - self.row.len()).min(data.len());
+            // Bounded by row_bytes <= MAX_ROW_BYTES
+            let _ = self.row.extend_from_slice(&data[..take]);
+            data = &data[take..];
+
+            if self.row.len() == row_bytes {
+                controller.draw_key_row(self.key_id, self.rows_written, &self.row);
+                self.row.clear();
+                self.rows_written += 1;
+            }
+        }
+    }
+
+    fn finish(&self) -> Result<(), DisplayError> {
+        if self.rows_written < crate::config::key_image_size() {
+            return Err(DisplayError::ImageTooSmall);
+        }
+        Ok(())
+    }
+}
+
+/// Wait until `at`, or forever if None
+async fn wait_until(at: Option<Instant>) {
+    match at {
+        Some(at) => Timer::at(at).await,
+        None => core::future::pending().await,
+    }
+}
+
+// ===================================================================
+// E-paper Task Implementation
+// ===================================================================
+
+#[embassy_executor::task]
+pub async fn epaper_task(
+    spi: Spi<'static, peripherals::SPI0, embassy_rp::spi::Blocking>,
+    cs: Output<'static>,
+    dc: Output<'static>,
+    rst: Output<'static>,
+    busy: Input<'static>,
+) {
+    info!("E-paper task started");
+
+    crate::power::wait_for_stage(StartupStage::Panel).await;
+    let mut controller = EpaperController::new(spi, cs, dc, rst, busy).await;
+
+    let control = DISPLAY_CONTROL_CHANNEL.receiver();
+    let receiver = DISPLAY_CHANNEL.receiver();
+    let mut device_bus = subscribe_device_bus();
+    let mut stream: Option<EpaperStream> = None;
+
+    info!("E-paper controller ready");
+    // No backlight to ramp, but the supervisor still waits on this stage
+    crate::power::advance_startup(StartupStage::Backlight);
+
+    loop {
+        // Control commands and the device bus go ahead of queued drawing
+        let command = match select4(
+            control.receive(),
+            next_device_command(&mut device_bus),
+            receiver.receive(),
+            wait_until(controller.refresh_due()),
+        )
+        .await
+        {
+            Either4::First(command) => {
+                crate::stats::pool_used(Pool::DisplayControl, control.len() + 1);
+                command
+            }
+            Either4::Third(command) => {
+                crate::stats::pool_used(Pool::DisplayCommands, receiver.len() + 1);
+                command
+            }
+            Either4::Second(command) => command.into(),
+            Either4::Fourth(()) => {
+                controller.refresh().await;
+                continue;
+            }
+        };
+
+        match command {
+            DisplayCommand::Clear(key_id) => controller.fill_cell(key_id, |_, _| 255),
+            DisplayCommand::ClearAll => controller.clear_all(),
+            DisplayCommand::FillKey { key_id, color } => {
+                let gray = luma565(color);
+                controller.fill_cell(key_id, |_, _| gray);
+            }
+            DisplayCommand::DrawRle { key_id, image } => {
+                if let Err(e) = controller.draw_rle(key_id, &image) {
+                    warn!("RLE image for key {} not displayed: {}", key_id, e);
+                    crate::stats::record_error(e);
+                }
+            }
+            DisplayCommand::ColorBars(key_id) => controller.color_bars(key_id),
+            DisplayCommand::DrawText {
+                key_id,
+                text,
+                fg,
+                bg,
+            } => controller.draw_text(key_id, &text, fg, bg),
+            DisplayCommand::DisplayImage { key_id, data } => {
+                if let Err(e) = controller.display_image(key_id, &data) {
+                    warn!("Image for key {} not displayed: {}", key_id, e);
+                    crate::stats::record_error(e);
+                }
+            }
+            DisplayCommand::DisplayImageChunk {
+                key_id,
+                offset,
+                last,
+                data,
+            } => {
+                if offset == 0 {
+                    stream = Some(EpaperStream::new(key_id, &data));
+                }
+                let result = match stream.as_mut() {
+                    Some(s) if s.key_id == key_id && s.next_offset == offset => {
+                        s.feed(&mut controller, &data);
+                        if last {
+                            s.finish()
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    _ => Err(DisplayError::SequenceError),
+                };
+                if let Err(e) = result {
+                    warn!("Streamed image for key {} dropped: {}", key_id, e);
+                    crate::stats::record_error(e);
+                    stream = None;
+                } else if last {
+                    stream = None;
+                }
+            }
+            DisplayCommand::Sleep => controller.sleep().await,
+            DisplayCommand::Wake => controller.wake().await,
+            // Reflective panel: no backlight to dim
+            DisplayCommand::SetBrightness(_)
+            | DisplayCommand::SetKeyBrightness { .. }
+            | DisplayCommand::LimitBrightness(_)
+            | DisplayCommand::SetScreenBrightness { .. } => {}
+            // Far too slow to flash on a key press
+            DisplayCommand::HighlightKey(_) | DisplayCommand::ShowStoredImage { .. } => {}
+            DisplayCommand::SetRotation(rotation) => {
+                warn!("E-paper orientation is fixed, ignoring {}", rotation);
+            }
+            DisplayCommand::DisplayLcdImage { region, .. }
+            | DisplayCommand::DrawInfoBar { region, .. } => {
+                warn!("No touch strip LCD on e-paper builds, dropping {}", region);
+            }
+            // The image persists unpowered; only the key grid exists
+            DisplayCommand::SetScreenSleep {
+                screen: Screen::Keys,
+                sleep,
+            } => {
+                if sleep {
+                    controller.sleep().await;
+                } else {
+                    controller.wake().await;
+                }
+            }
+            DisplayCommand::SetScreenSleep { .. } => {}
+        }
+    }
+}
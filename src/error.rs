@@ -0,0 +1,104 @@
+//! Firmware error types
+//!
+//! Errors carry a stable one-byte code (category in the high nibble) so they
+//! can be reported to the host in the metrics report and counted per kind.
+
+use defmt::Format;
+
+pub use productiondeck_protocol::error::ProtocolError;
+
+/// Errors raised while drawing to the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum DisplayError {
+    /// Key index outside the device's key count
+    InvalidKey = 0x20,
+    /// Image data shorter than one full key image
+    ImageTooSmall = 0x21,
+    /// Image packet shorter than its header
+    PacketTooShort = 0x22,
+    /// Image packet arrived out of order
+    SequenceError = 0x23,
+    /// Image does not fit the image buffer
+    BufferOverflow = 0x24,
+}
+
+/// Errors raised by USB endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum UsbError {
+    /// Endpoint read failed
+    ReadFailed = 0x30,
+    /// Endpoint write failed
+    WriteFailed = 0x31,
+}
+
+/// Errors raised while reading keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum InputError {
+    /// Key combination is ambiguous in a diode-less matrix and was suppressed
+    Ghosting = 0x40,
+    /// Key held longer than the stuck-key timeout (wiring fault suspected)
+    StuckKey = 0x41,
+    /// An expansion board stopped answering on the I2C bus
+    ExpansionOffline = 0x42,
+}
+
+/// Any firmware error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub enum Error {
+    Protocol(ProtocolError),
+    Display(DisplayError),
+    Usb(UsbError),
+    Input(InputError),
+}
+
+impl Error {
+    /// Number of distinct error kinds (size of the per-kind counter table)
+    pub const KIND_COUNT: usize = 16;
+
+    /// Stable one-byte error code
+    pub fn code(&self) -> u8 {
+        match *self {
+            Error::Protocol(e) => e as u8,
+            Error::Display(e) => e as u8,
+            Error::Usb(e) => e as u8,
+            Error::Input(e) => e as u8,
+        }
+    }
+
+    /// Position in the per-kind counter table
+    pub fn kind_index(&self) -> usize {
+        match *self {
+            Error::Protocol(e) => (e as u8 - 0x10) as usize,
+            Error::Display(e) => 6 + (e as u8 - 0x20) as usize,
+            Error::Usb(e) => 11 + (e as u8 - 0x30) as usize,
+            Error::Input(e) => 13 + (e as u8 - 0x40) as usize,
+        }
+    }
+}
+
+impl From<ProtocolError> for Error {
+    fn from(e: ProtocolError) -> Self {
+        Error::Protocol(e)
+    }
+}
+
+impl From<DisplayError> for Error {
+    fn from(e: DisplayError) -> Self {
+        Error::Display(e)
+    }
+}
+
+impl From<UsbError> for Error {
+    fn from(e: UsbError) -> Self {
+        Error::Usb(e)
+    }
+}
+
+impl From<InputError> for Error {
+    fn from(e: InputError) -> Self {
+        Error::Input(e)
+    }
+}
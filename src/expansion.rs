@@ -0,0 +1,237 @@
+//! I2C expansion boards (`i2c-expansion`)
+//!
+//! XL-sized builds are easier to make from modular panels than from one big
+//! matrix. Extra panels hang off an I2C bus (`EXPANSION_SDA_PIN` /
+//! `EXPANSION_SCL_PIN`) and contribute keys to the main unit, which polls
+//! them every `EXPANSION_POLL_MS`. The boards on the bus and the physical
+//! keys they carry come from `HardwareConfig::expansion_boards`; the button
+//! pipeline ORs their keys into each raw scan (`merge`), so remote keys are
+//! debounced, remapped and reported exactly like local ones.
+//!
+//! Two kinds of board are understood:
+//!
+//! - `ExpansionKind::Satellite`: a board running the expansion protocol
+//!   (`protocol::expansion`): a plain read returns its version, key count
+//!   and pressed mask. The satellite scans its own keys and only needs to
+//!   answer reads; this firmware does so when built with `i2c-satellite`
+//!   (`satellite`).
+//! - `ExpansionKind::Pca9555`: a 16-bit port expander with the keys switching
+//!   its inputs to ground, read through input port registers 0 and 1.
+//!
+//! A board that stops answering is logged once, counted as
+//! `InputError::ExpansionOffline`, and its keys read as released until it
+//! answers again; it is retried every `EXPANSION_RETRY_MS`.
+//!
+//! The bus shares GPIO0 with the parallel
+//! display and I2C0 with the FT6236 touch panel; builds with either do not
+//! compile (see the feature checks in `hardware`).
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use defmt::*;
+use embassy_rp::bind_interrupts;
+use embassy_rp::i2c::{self, Async, I2c};
+use embassy_rp::peripherals::{I2C0, PIN_0, PIN_1};
+use embassy_rp::Peri;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::config::{
+    EXPANSION_I2C_HZ, EXPANSION_POLL_MS, EXPANSION_RETRY_MS, EXPANSION_SCL_PIN, EXPANSION_SDA_PIN,
+    MAX_KEYS,
+};
+use crate::error::InputError;
+use crate::hardware::{ExpansionBoard, ExpansionKind};
+use crate::protocol::expansion::{
+    decode_satellite_report, EXPANSION_PROTOCOL_VERSION, SATELLITE_REPORT_SIZE,
+};
+
+bind_interrupts!(struct I2cIrqs {
+    I2C0_IRQ => i2c::InterruptHandler<I2C0>;
+});
+
+/// Most boards on the bus (one bit each in the online mask)
+pub const MAX_EXPANSION_BOARDS: usize = 8;
+
+/// PCA9555 input port 0 register; port 1 follows it
+const PCA9555_INPUT_PORT0: u8 = 0x00;
+
+/// Bus claimed at init, handed to the polling task
+static BUS: Mutex<CriticalSectionRawMutex, RefCell<Option<I2c<'static, I2C0, Async>>>> =
+    Mutex::new(RefCell::new(None));
+/// Boards of the layout the bus was set up for
+static BOARDS: Mutex<CriticalSectionRawMutex, Cell<&'static [ExpansionBoard]>> =
+    Mutex::new(Cell::new(&[]));
+/// Remote keys pressed, by physical key index
+static REMOTE_KEYS: AtomicU32 = AtomicU32::new(0);
+/// Boards that answered their last poll, by position in the board table
+static ONLINE: AtomicU8 = AtomicU8::new(0);
+
+/// Set up the expansion bus for `boards`.
+/// Must be called before the expansion task is spawned.
+pub fn init(
+    i2c: Peri<'static, I2C0>,
+    sda: Peri<'static, PIN_0>,
+    scl: Peri<'static, PIN_1>,
+    boards: &'static [ExpansionBoard],
+) {
+    let boards = &boards[..boards.len().min(MAX_EXPANSION_BOARDS)];
+    BOARDS.lock(|b| b.set(boards));
+    if boards.is_empty() {
+        return;
+    }
+    let mut config = i2c::Config::default();
+    config.frequency = EXPANSION_I2C_HZ;
+    let bus = I2c::new_async(i2c, scl, sda, I2cIrqs, config);
+    BUS.lock(|b| b.replace(Some(bus)));
+}
+
+/// The boards on the bus
+pub fn boards() -> &'static [ExpansionBoard] {
+    BOARDS.lock(|b| b.get())
+}
+
+/// One past the highest physical key index an expansion board carries
+pub fn key_span() -> usize {
+    boards()
+        .iter()
+        .map(|board| board.first_key as usize + board.keys as usize)
+        .max()
+        .unwrap_or(0)
+        .min(MAX_KEYS)
+}
+
+/// Remote keys pressed, by physical key index
+pub fn remote_keys() -> u32 {
+    REMOTE_KEYS.load(Ordering::Relaxed)
+}
+
+/// Boards that answered their last poll, by position in the board table
+pub fn online_mask() -> u8 {
+    ONLINE.load(Ordering::Relaxed)
+}
+
+/// `raw` with the pressed remote keys added
+pub fn merge(raw: &[bool; MAX_KEYS]) -> [bool; MAX_KEYS] {
+    let remote = remote_keys();
+    let mut merged = *raw;
+    for (key, state) in merged.iter_mut().enumerate() {
+        *state |= remote & (1 << key) != 0;
+    }
+    merged
+}
+
+/// Serialize for the vendor report: `[boards, online mask, remote keys u32 LE]`
+pub fn to_bytes() -> [u8; 6] {
+    let mut out = [0u8; 6];
+    out[0] = boards().len() as u8;
+    out[1] = online_mask();
+    out[2..6].copy_from_slice(&remote_keys().to_le_bytes());
+    out
+}
+
+/// Read one board; its pressed keys (bit n = the board's key n)
+async fn read_board(
+    bus: &mut I2c<'static, I2C0, Async>,
+    board: &ExpansionBoard,
+) -> Result<u32, i2c::Error> {
+    let pressed = match board.kind {
+        ExpansionKind::Satellite => {
+            let mut answer = [0u8; SATELLITE_REPORT_SIZE];
+            bus.read_async(board.address, &mut answer).await?;
+            let Some(pressed) = decode_satellite_report(&answer) else {
+                warn!(
+                    "Expansion board 0x{:02X}: protocol version {}, expected {}",
+                    board.address, answer[0], EXPANSION_PROTOCOL_VERSION
+                );
+                return Ok(0);
+            };
+            pressed
+        }
+        ExpansionKind::Pca9555 => {
+            let mut ports = [0u8; 2];
+            bus.write_read_async(board.address, [PCA9555_INPUT_PORT0], &mut ports)
+                .await?;
+            // Keys pull the inputs low
+            !(u16::from_le_bytes(ports) as u32) & 0xFFFF
+        }
+    };
+    // Only the keys the table gives the board
+    let mask = match board.keys {
+        0..32 => (1u32 << board.keys) - 1,
+        _ => u32::MAX,
+    };
+    Ok(pressed & mask)
+}
+
+/// Poll the expansion boards and publish their keys; never returns once started
+#[embassy_executor::task]
+pub async fn expansion_task() {
+    let boards = boards();
+    if boards.is_empty() {
+        info!("No expansion boards for this layout");
+        return;
+    }
+    let Some(mut bus) = BUS.lock(|b| b.borrow_mut().take()) else {
+        error!("Expansion bus not initialized");
+        return;
+    };
+    info!(
+        "Expansion bus: {} boards on SDA GPIO{}, SCL GPIO{} at {} Hz",
+        boards.len(),
+        EXPANSION_SDA_PIN,
+        EXPANSION_SCL_PIN,
+        EXPANSION_I2C_HZ
+    );
+
+    let poll_interval = Duration::from_millis(EXPANSION_POLL_MS);
+    let retry_interval = Duration::from_millis(EXPANSION_RETRY_MS);
+    // When each offline board is due for another try
+    let mut retry_at = [Instant::MIN; MAX_EXPANSION_BOARDS];
+
+    loop {
+        // Stop polling while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
+        let now = Instant::now();
+        let mut online = online_mask();
+        let mut remote = 0u32;
+        for (index, board) in boards.iter().enumerate() {
+            let bit = 1 << index;
+            if online & bit == 0 && now < retry_at[index] {
+                continue;
+            }
+            match read_board(&mut bus, board).await {
+                Ok(pressed) => {
+                    if online & bit == 0 {
+                        info!(
+                            "Expansion board 0x{:02X} online: keys {}..{}",
+                            board.address,
+                            board.first_key,
+                            board.first_key as usize + board.keys as usize
+                        );
+                        online |= bit;
+                    }
+                    remote |= pressed.checked_shl(board.first_key as u32).unwrap_or(0);
+                }
+                Err(e) => {
+                    // Logged once per outage, or on the first try of a
+                    // board that never answered
+                    if online & bit != 0 || retry_at[index] == Instant::MIN {
+                        warn!("Expansion board 0x{:02X} offline: {:?}", board.address, e);
+                        crate::stats::record_error(InputError::ExpansionOffline);
+                    }
+                    online &= !bit;
+                    retry_at[index] = now + retry_interval;
+                }
+            }
+        }
+        ONLINE.store(online, Ordering::Relaxed);
+        REMOTE_KEYS.store(remote, Ordering::Relaxed);
+
+        Timer::after(poll_interval).await;
+    }
+}
@@ -0,0 +1,310 @@
+//! Firmware update from the host (`firmware-update`)
+//!
+//! A companion tool can replace the firmware without BOOTSEL access:
+//!
+//! 1. `VENDOR_CMD_BEGIN_FIRMWARE` with the image size and CRC-32 drops any
+//!    staged image and starts an upload
+//! 2. the image streams over the vendor bulk interface (`BULK_CMD_FIRMWARE`
+//!    transfers, in order) into the staging area below the configuration
+//!    drive (`storage::FIRMWARE_STAGING_OFFSET`), erased a sector at a time
+//!    as the data reaches it
+//! 3. `VENDOR_CMD_COMMIT_FIRMWARE` checks the CRC over the staged flash and
+//!    the image's vector table, writes the staging header and restarts the
+//!    device once the host has had time to read the reply
+//!
+//! The image is the flash contents from `FLASH_BASE`, boot2 included: the
+//! ELF through `objcopy -O binary`, or a UF2 file's payloads in address
+//! order. `VENDOR_CMD_GET_FIRMWARE_STATUS` reports progress.
+//!
+//! At the next boot `apply_staged` finds the header before any other task
+//! runs, checks the CRC again and copies the image over the running firmware
+//! from a RAM routine, then restarts into it. The copy takes a few seconds;
+//! power lost during it leaves a half-written firmware, and BOOTSEL is
+//! needed after all.
+
+use core::cell::Cell;
+use defmt::*;
+use embassy_rp::flash::{ERASE_SIZE, FLASH_BASE};
+use embassy_rp::rom_data;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer};
+
+use crate::crc::crc32;
+use crate::memory::RAM_SIZE;
+use crate::storage::{self, FIRMWARE_HEADER_OFFSET, FIRMWARE_SLOT_SIZE, FIRMWARE_STAGING_OFFSET};
+
+/// Marks a staged image ready to apply
+const HEADER_MAGIC: [u8; 4] = *b"PDFW";
+
+/// Header: magic, image size (u32 LE), image CRC-32 (u32 LE)
+const HEADER_SIZE: usize = 12;
+
+/// Offset of the vector table in an image, after boot2
+const VECTOR_TABLE_OFFSET: usize = 0x100;
+
+/// Time the host gets to read the commit reply before the device restarts
+const RESTART_DELAY_MS: u64 = 500;
+
+/// RAM address range an initial stack pointer may take
+const RAM_START: u32 = 0x2000_0000;
+const RAM_END: u32 = RAM_START + RAM_SIZE as u32;
+
+/// Where an upload stands
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum UpdateState {
+    Idle = 0,
+    /// Begun; bulk data is being written to the staging area
+    Receiving = 1,
+    /// Committed; applied at the next boot
+    Staged = 2,
+    /// A write failed or the image did not check out; begin again
+    Failed = 3,
+}
+
+/// Why an update step was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub enum UpdateError {
+    /// Out of order, out of range, or the image failed its checks
+    Invalid,
+    /// Flash could not be erased or written
+    Storage,
+}
+
+#[derive(Clone, Copy)]
+struct Upload {
+    state: UpdateState,
+    size: usize,
+    crc: u32,
+    received: usize,
+}
+
+static UPLOAD: Mutex<CriticalSectionRawMutex, Cell<Upload>> = Mutex::new(Cell::new(Upload {
+    state: UpdateState::Idle,
+    size: 0,
+    crc: 0,
+    received: 0,
+}));
+
+static RESTART: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Upload progress: state, bytes received, image size
+pub fn status() -> (UpdateState, usize, usize) {
+    let upload = UPLOAD.lock(|u| u.get());
+    (upload.state, upload.received, upload.size)
+}
+
+fn fail(error: UpdateError) -> UpdateError {
+    UPLOAD.lock(|u| {
+        let mut upload = u.get();
+        upload.state = UpdateState::Failed;
+        u.set(upload);
+    });
+    error
+}
+
+/// Start an upload of a `size` byte image with CRC-32 `crc`
+pub fn begin(size: usize, crc: u32) -> Result<(), UpdateError> {
+    if size <= VECTOR_TABLE_OFFSET + 8 || size > FIRMWARE_SLOT_SIZE {
+        return Err(UpdateError::Invalid);
+    }
+    // Staging over the running firmware would crash it
+    let running = running_image_size();
+    if running > FIRMWARE_HEADER_OFFSET as usize {
+        warn!(
+            "Running firmware ({} bytes) overlaps the update staging area",
+            running
+        );
+        return Err(UpdateError::Invalid);
+    }
+    storage::erase_firmware_sector(0).map_err(|_| fail(UpdateError::Storage))?;
+    UPLOAD.lock(|u| {
+        u.set(Upload {
+            state: UpdateState::Receiving,
+            size,
+            crc,
+            received: 0,
+        })
+    });
+    info!("Firmware upload started: {} bytes", size);
+    Ok(())
+}
+
+/// Append bulk data to the upload
+pub fn write(data: &[u8]) -> Result<(), UpdateError> {
+    let upload = UPLOAD.lock(|u| u.get());
+    if upload.state != UpdateState::Receiving || upload.received + data.len() > upload.size {
+        return Err(fail(UpdateError::Invalid));
+    }
+    let offset = upload.received;
+    let end = offset + data.len();
+    // Erase each image sector as the data first reaches it
+    for sector in offset.div_ceil(ERASE_SIZE)..end.div_ceil(ERASE_SIZE) {
+        storage::erase_firmware_sector(1 + sector).map_err(|_| fail(UpdateError::Storage))?;
+    }
+    storage::write_firmware(ERASE_SIZE + offset, data).map_err(|_| fail(UpdateError::Storage))?;
+    UPLOAD.lock(|u| {
+        u.set(Upload {
+            received: end,
+            ..upload
+        })
+    });
+    Ok(())
+}
+
+/// Check the uploaded image, stage it and restart into it
+pub fn commit() -> Result<(), UpdateError> {
+    let upload = UPLOAD.lock(|u| u.get());
+    if upload.state != UpdateState::Receiving || upload.received != upload.size {
+        return Err(UpdateError::Invalid);
+    }
+    let image = &storage::firmware_staging()[ERASE_SIZE..ERASE_SIZE + upload.size];
+    let crc = crc32(image);
+    if crc != upload.crc {
+        warn!("Firmware CRC 0x{:08X}, expected 0x{:08X}", crc, upload.crc);
+        return Err(fail(UpdateError::Invalid));
+    }
+    if !plausible(image) {
+        warn!("Firmware image has no valid vector table");
+        return Err(fail(UpdateError::Invalid));
+    }
+
+    let mut header = [0u8; HEADER_SIZE];
+    header[0..4].copy_from_slice(&HEADER_MAGIC);
+    header[4..8].copy_from_slice(&(upload.size as u32).to_le_bytes());
+    header[8..12].copy_from_slice(&crc.to_le_bytes());
+    storage::write_firmware(0, &header).map_err(|_| fail(UpdateError::Storage))?;
+
+    UPLOAD.lock(|u| {
+        u.set(Upload {
+            state: UpdateState::Staged,
+            ..upload
+        })
+    });
+    info!("Firmware staged, restarting to apply it");
+    RESTART.signal(());
+    Ok(())
+}
+
+/// Wait until a committed update wants the device restarted
+pub async fn wait_restart() {
+    RESTART.wait().await;
+    Timer::after(Duration::from_millis(RESTART_DELAY_MS)).await;
+}
+
+/// Whether `image` starts like firmware for this flash layout: after boot2,
+/// a stack pointer in RAM and a reset vector inside the image
+fn plausible(image: &[u8]) -> bool {
+    let word = |offset: usize| {
+        image
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let (Some(stack), Some(reset)) = (word(VECTOR_TABLE_OFFSET), word(VECTOR_TABLE_OFFSET + 4))
+    else {
+        return false;
+    };
+    let code =
+        FLASH_BASE as u32 + VECTOR_TABLE_OFFSET as u32..FLASH_BASE as u32 + image.len() as u32;
+    (RAM_START..=RAM_END).contains(&stack) && code.contains(&(reset & !1))
+}
+
+/// Flash the running firmware takes, boot2 included
+fn running_image_size() -> usize {
+    extern "C" {
+        static __sidata: u32;
+        static __sdata: u32;
+        static __edata: u32;
+    }
+    // Linker symbols; only their addresses mean anything
+    let (load, start, end) = (
+        core::ptr::addr_of!(__sidata) as usize,
+        core::ptr::addr_of!(__sdata) as usize,
+        core::ptr::addr_of!(__edata) as usize,
+    );
+    load + (end - start) - FLASH_BASE as usize
+}
+
+/// Apply a staged update, if there is a good one. Call first thing at boot,
+/// before core 1 or any DMA starts; does not return when it applies one.
+pub fn apply_staged() {
+    let staging = storage::firmware_staging();
+    if staging[0..4] != HEADER_MAGIC {
+        return;
+    }
+    let size = u32::from_le_bytes([staging[4], staging[5], staging[6], staging[7]]) as usize;
+    let crc = u32::from_le_bytes([staging[8], staging[9], staging[10], staging[11]]);
+    if size > FIRMWARE_SLOT_SIZE || crc32(&staging[ERASE_SIZE..ERASE_SIZE + size]) != crc {
+        warn!("Staged firmware is damaged, keeping the running firmware");
+        return;
+    }
+
+    info!("Applying staged firmware update ({} bytes)", size);
+    let rom = RomFlash {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        flash_range_erase: rom_data::flash_range_erase::ptr(),
+        flash_range_program: rom_data::flash_range_program::ptr(),
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        flash_enter_cmd_xip: rom_data::flash_enter_cmd_xip::ptr(),
+    };
+    cortex_m::interrupt::disable();
+    // SAFETY: interrupts are off and only this core runs, so nothing else
+    // touches flash; the routine lives in RAM and never returns into flash
+    unsafe { copy_staged(&rom, size.next_multiple_of(ERASE_SIZE) as u32) }
+}
+
+/// Boot ROM flash routines, looked up before XIP goes away
+struct RomFlash {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+    flash_enter_cmd_xip: unsafe extern "C" fn(),
+}
+
+/// Copy `size` bytes (whole sectors) of the staged image to the start of
+/// flash a sector at a time through a stack buffer, drop the staging header
+/// and restart. Runs from RAM and calls only the boot ROM: after the first
+/// erase no flash code may run.
+///
+/// # Safety
+///
+/// Interrupts must be off, core 1 and DMA idle.
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn copy_staged(rom: &RomFlash, size: u32) -> ! {
+    const WORDS: usize = ERASE_SIZE / 4;
+    let mut sector = core::mem::MaybeUninit::<[u32; WORDS]>::uninit();
+    let buffer = sector.as_mut_ptr() as *mut u32;
+
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    let mut offset = 0u32;
+    while offset < size {
+        // Slow serial XIP is enough to read the staged sector
+        (rom.flash_flush_cache)();
+        (rom.flash_enter_cmd_xip)();
+        let source = (FLASH_BASE as u32 + FIRMWARE_STAGING_OFFSET + offset) as *const u32;
+        let mut i = 0;
+        while i < WORDS {
+            core::ptr::write_volatile(buffer.add(i), core::ptr::read_volatile(source.add(i)));
+            i += 1;
+        }
+        (rom.flash_exit_xip)();
+        (rom.flash_range_erase)(offset, ERASE_SIZE, 1 << 31, 0);
+        (rom.flash_range_program)(offset, buffer as *const u8, ERASE_SIZE);
+        offset = offset.wrapping_add(ERASE_SIZE as u32);
+    }
+    (rom.flash_range_erase)(FIRMWARE_HEADER_OFFSET, ERASE_SIZE, 1 << 31, 0);
+    (rom.flash_flush_cache)();
+
+    // SYSRESETREQ through AIRCR
+    core::ptr::write_volatile(0xE000_ED0C as *mut u32, 0x05FA_0004);
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,71 @@
+//! Panel gamma correction
+//!
+//! Cheap panels often look washed out or crush dark tones. The user uploads
+//! a 16-point curve through the vendor report; it is stored in settings and
+//! expanded here into a 256-entry lookup table that the display applies to
+//! every channel while converting RGB888 images.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::settings;
+
+/// Points in the stored curve, for inputs 0, 17, 34 ... 255
+pub const GAMMA_POINTS: usize = 16;
+
+/// Input step between curve points
+const STEP: usize = 255 / (GAMMA_POINTS - 1);
+
+/// Straight-line curve (no correction)
+pub const IDENTITY_GAMMA: [u8; GAMMA_POINTS] = identity();
+
+const fn identity() -> [u8; GAMMA_POINTS] {
+    let mut points = [0u8; GAMMA_POINTS];
+    let mut i = 0;
+    while i < GAMMA_POINTS {
+        points[i] = (i * STEP) as u8;
+        i += 1;
+    }
+    points
+}
+
+/// Expanded curve, read without a critical section for every pixel channel
+static LUT: [AtomicU8; 256] = lut_identity();
+
+const fn lut_identity() -> [AtomicU8; 256] {
+    let mut lut = [const { AtomicU8::new(0) }; 256];
+    let mut i = 0;
+    while i < 256 {
+        lut[i] = AtomicU8::new(i as u8);
+        i += 1;
+    }
+    lut
+}
+
+/// Corrected value of one color channel
+#[inline]
+pub fn correct(value: u8) -> u8 {
+    LUT[value as usize].load(Ordering::Relaxed)
+}
+
+/// Bumped whenever the table changes, so cached conversions can rebuild
+static GENERATION: AtomicU8 = AtomicU8::new(0);
+
+/// Current table generation
+pub fn generation() -> u8 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Expand the curve from settings; call after settings are loaded or changed
+pub fn sync_from_settings() {
+    let points = settings::with(|s| s.gamma).unwrap_or(IDENTITY_GAMMA);
+    for (input, slot) in LUT.iter().enumerate() {
+        // Linear interpolation between the two surrounding points
+        let segment = (input / STEP).min(GAMMA_POINTS - 2);
+        let (low, high) = (points[segment] as i32, points[segment + 1] as i32);
+        let t = (input - segment * STEP) as i32;
+        let value = low + ((high - low) * t + STEP as i32 / 2) / STEP as i32;
+        slot.store(value.clamp(0, 255) as u8, Ordering::Relaxed);
+    }
+    // Only the settings path writes the table, so load + store cannot race
+    GENERATION.store(generation().wrapping_add(1), Ordering::Relaxed);
+}
@@ -0,0 +1,218 @@
+//! Local long-press / double-press processing
+//!
+//! Sits between the key edges of the scan (debounced and remapped, see
+//! `KeyEvent`) and the USB report path.
+//! Keys with a gesture configured report a different virtual key index when
+//! held past `LONG_PRESS_MS` or pressed twice within `DOUBLE_PRESS_WINDOW_MS`,
+//! giving more actions than physical keys. The host only ever sees ordinary
+//! key presses within the device's key count, so the Elgato protocols are
+//! unaffected.
+//!
+//! Keys without a gesture pass through unchanged and without added latency.
+//! A plain tap on a gesture key is reported once the gesture is resolved, as
+//! a short press of `VIRTUAL_TAP_MS`.
+
+use embassy_time::{Duration, Instant};
+
+use crate::config::{DOUBLE_PRESS_WINDOW_MS, LONG_PRESS_MS, MAX_KEYS, VIRTUAL_TAP_MS};
+use crate::types::{ButtonState, KeyEvent};
+
+/// Marker for "no virtual key assigned"
+pub const GESTURE_NONE: u8 = 0xFF;
+
+/// Virtual key assignment for one key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyGesture {
+    /// Key reported while the key is held past the long-press threshold
+    pub long_press: u8,
+    /// Key reported for the second press of a double-press
+    pub double_press: u8,
+}
+
+impl KeyGesture {
+    pub const NONE: Self = Self {
+        long_press: GESTURE_NONE,
+        double_press: GESTURE_NONE,
+    };
+
+    /// Whether any gesture is configured for this key
+    pub fn is_active(&self) -> bool {
+        self.long_press != GESTURE_NONE || self.double_press != GESTURE_NONE
+    }
+
+    /// Whether every assigned virtual key is below `key_count`
+    pub fn is_valid(&self, key_count: usize) -> bool {
+        [self.long_press, self.double_press]
+            .iter()
+            .all(|&k| k == GESTURE_NONE || (k as usize) < key_count)
+    }
+
+    fn long_key(&self) -> Option<usize> {
+        (self.long_press != GESTURE_NONE).then_some(self.long_press as usize)
+    }
+
+    fn double_key(&self) -> Option<usize> {
+        (self.double_press != GESTURE_NONE).then_some(self.double_press as usize)
+    }
+}
+
+/// Gesture table with nothing configured
+pub const NO_GESTURES: [KeyGesture; MAX_KEYS] = [KeyGesture::NONE; MAX_KEYS];
+
+#[derive(Clone, Copy, Debug)]
+enum GestureState {
+    Idle,
+    /// Pressed, waiting to see if this becomes a long press
+    Pressed {
+        since: Instant,
+    },
+    /// Held past the threshold without a long-press key: report the key itself
+    Held,
+    /// Reporting the long-press key until release
+    LongPress {
+        key: usize,
+    },
+    /// Released after a short press, waiting for a second press
+    WaitSecond {
+        released: Instant,
+    },
+    /// Reporting the double-press key until release
+    DoublePress {
+        key: usize,
+    },
+    /// Reporting a synthesized tap of `key` until `until`
+    Tap {
+        key: usize,
+        until: Instant,
+    },
+}
+
+/// Per-key gesture state machines
+pub struct GestureProcessor {
+    states: [GestureState; MAX_KEYS],
+    /// Keys held, as of the last edge of each
+    held: u32,
+}
+
+impl GestureProcessor {
+    pub const fn new() -> Self {
+        Self {
+            states: [GestureState::Idle; MAX_KEYS],
+            held: 0,
+        }
+    }
+
+    /// Apply this scan's key edges and return the key states reported to
+    /// the host for the first `active_keys` keys. Must be called on every
+    /// scan, with or without edges, so pending gestures time out.
+    pub fn process(
+        &mut self,
+        edges: &[KeyEvent],
+        gestures: &[KeyGesture; MAX_KEYS],
+        active_keys: usize,
+        now: Instant,
+    ) -> ButtonState {
+        let long_press = Duration::from_millis(LONG_PRESS_MS);
+        let double_window = Duration::from_millis(DOUBLE_PRESS_WINDOW_MS);
+        let tap = Duration::from_millis(VIRTUAL_TAP_MS);
+
+        for edge in edges.iter().filter(|edge| (edge.key as usize) < MAX_KEYS) {
+            if edge.pressed {
+                self.held |= 1 << edge.key;
+            } else {
+                self.held &= !(1 << edge.key);
+            }
+        }
+        let mut output = ButtonState::new(active_keys);
+
+        let keys = self
+            .states
+            .iter_mut()
+            .zip(gestures.iter().copied())
+            .enumerate()
+            .take(active_keys);
+
+        for (key, (state, gesture)) in keys {
+            let pressed = self.held & (1 << key) != 0;
+            // Press and release times come from the edge when there is one
+            let at = edges
+                .iter()
+                .find(|edge| edge.key as usize == key)
+                .map_or(now, |edge| edge.timestamp);
+            if !gesture.is_active() {
+                *state = GestureState::Idle;
+                if pressed {
+                    output.set_button(key, true);
+                }
+                continue;
+            }
+
+            *state = match *state {
+                GestureState::Idle if pressed => GestureState::Pressed { since: at },
+                GestureState::Idle => GestureState::Idle,
+                GestureState::Pressed { since } if pressed => {
+                    if now.duration_since(since) < long_press {
+                        GestureState::Pressed { since }
+                    } else if let Some(long_key) = gesture.long_key() {
+                        GestureState::LongPress { key: long_key }
+                    } else {
+                        GestureState::Held
+                    }
+                }
+                GestureState::Pressed { .. } => {
+                    if gesture.double_key().is_some() {
+                        GestureState::WaitSecond { released: at }
+                    } else {
+                        GestureState::Tap {
+                            key,
+                            until: now + tap,
+                        }
+                    }
+                }
+                GestureState::Held if pressed => GestureState::Held,
+                GestureState::LongPress { key } if pressed => GestureState::LongPress { key },
+                GestureState::Held | GestureState::LongPress { .. } => GestureState::Idle,
+                GestureState::WaitSecond { .. } if pressed => match gesture.double_key() {
+                    Some(double_key) => GestureState::DoublePress { key: double_key },
+                    None => GestureState::Pressed { since: at },
+                },
+                GestureState::WaitSecond { released } => {
+                    if now.duration_since(released) < double_window {
+                        GestureState::WaitSecond { released }
+                    } else {
+                        GestureState::Tap {
+                            key,
+                            until: now + tap,
+                        }
+                    }
+                }
+                GestureState::DoublePress { key } if pressed => GestureState::DoublePress { key },
+                GestureState::DoublePress { .. } => GestureState::Idle,
+                // A new press cuts a pending tap short so it is not lost
+                GestureState::Tap { .. } if pressed => GestureState::Pressed { since: at },
+                GestureState::Tap { key, until } if now < until => GestureState::Tap { key, until },
+                GestureState::Tap { .. } => GestureState::Idle,
+            };
+
+            let reported = match *state {
+                GestureState::Held => Some(key),
+                GestureState::LongPress { key }
+                | GestureState::DoublePress { key }
+                | GestureState::Tap { key, .. } => Some(key),
+                _ => None,
+            };
+            // Virtual keys outside this device's key count are dropped
+            if let Some(reported) = reported.filter(|&k| k < active_keys) {
+                output.set_button(reported, true);
+            }
+        }
+
+        output
+    }
+}
+
+impl Default for GestureProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -5,24 +5,89 @@
 
 use defmt::*;
 use embassy_executor::{SpawnError, Spawner};
-use embassy_rp::gpio::{Input, Level, Output, Pull};
+use embassy_rp::gpio::{AnyPin, Input, Level, Output, Pull};
+#[cfg(not(feature = "parallel-display"))]
+use embassy_rp::spi::{self, Spi};
 use embassy_rp::usb::Driver;
-use embassy_rp::{peripherals, Peripherals};
+use embassy_rp::{peripherals, Peri, Peripherals};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
 use heapless::Vec;
 
+use crate::buttons::button_task_direct;
+#[cfg(not(feature = "pio-matrix"))]
 use crate::buttons::{
-    button_task_direct, button_task_matrix_3x2, button_task_matrix_5x3, button_task_matrix_8x4,
+    button_task_matrix_3x2, button_task_matrix_4x2, button_task_matrix_5x3, button_task_matrix_8x4,
 };
 use crate::config;
 use crate::device::{Device, DeviceConfig};
+#[cfg(not(feature = "epaper-display"))]
+use crate::display::Backlight;
+#[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+use crate::display::DisplayLink;
+use crate::types::Rotation;
 use crate::usb::usb_task_for_device;
 
+// Feature combinations that would claim the same pins or peripherals
+#[cfg(all(feature = "epaper-display", feature = "parallel-display"))]
+compile_error!("enable at most one of epaper-display and parallel-display");
+#[cfg(all(
+    feature = "parallel-display",
+    not(any(feature = "touch-xpt2046", feature = "touch-ft6236"))
+))]
+compile_error!("parallel-display puts its data bus on the key matrix pins: build it with touch-xpt2046 or touch-ft6236");
+#[cfg(all(
+    feature = "led-ring",
+    any(feature = "parallel-display", feature = "epaper-display")
+))]
+compile_error!(
+    "led-ring needs PIO1 and GPIO22: it cannot be combined with parallel-display or epaper-display"
+);
+#[cfg(all(
+    feature = "i2c-expansion",
+    any(feature = "parallel-display", feature = "touch-ft6236")
+))]
+compile_error!("i2c-expansion needs I2C0 on GPIO0/1: it cannot be combined with parallel-display or touch-ft6236");
+#[cfg(all(
+    feature = "latency-test",
+    any(feature = "parallel-display", feature = "i2c-expansion")
+))]
+compile_error!(
+    "latency-test drives GPIO1: it cannot be combined with parallel-display or i2c-expansion"
+);
+#[cfg(all(
+    feature = "i2c-satellite",
+    any(
+        feature = "i2c-expansion",
+        feature = "parallel-display",
+        feature = "touch-ft6236",
+        feature = "latency-test"
+    )
+))]
+compile_error!("i2c-satellite needs I2C0 on GPIO0/1: it cannot be combined with i2c-expansion, parallel-display, touch-ft6236 or latency-test");
+#[cfg(all(
+    feature = "secondary-deck",
+    any(feature = "touch-xpt2046", feature = "touch-ft6236")
+))]
+compile_error!("secondary-deck uses GPIO13 and 16, which the touch panels take");
+
 /// Hardware configuration for a specific StreamDeck device
 pub struct HardwareConfig {
     pub device: Device,
     pub button_pins: ButtonPins,
     pub display_pins: DisplayPins,
+    /// Per-key panels sharing the display SPI bus, in key order (empty = one shared panel)
+    pub key_panels: &'static [KeyPanelPins],
     pub led_pins: LedPins,
+    /// How the panel is mounted; a stored setting overrides it
+    pub display_rotation: Rotation,
+    pub panel: PanelConfig,
+    pub matrix: MatrixScanConfig,
+    /// What sits in each key matrix slot of a hybrid key/dial board, in scan
+    /// order (empty = keys only, reported in scan order)
+    pub hybrid_slots: &'static [MatrixSlot],
+    /// Expansion boards on the I2C expansion bus (`i2c-expansion`)
+    pub expansion_boards: &'static [ExpansionBoard],
 }
 
 /// Pin assignments for button matrix
@@ -33,14 +98,144 @@ pub struct ButtonPins {
 
 /// Pin assignments for display interface
 pub struct DisplayPins {
-    pub spi_mosi: u8,
-    pub spi_sck: u8,
+    /// Peripheral the panel is wired to
+    pub bus: DisplayBus,
+    /// SPI TX (MOSI)
+    pub data: u8,
+    /// SPI SCK
+    pub clock: u8,
+    /// Chip select, data/command
     pub cs: u8,
     pub dc: u8,
     pub rst: u8,
     pub backlight: u8,
 }
 
+/// Peripheral carrying the display link
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum DisplayBus {
+    Spi0,
+    Spi1,
+}
+
+impl DisplayBus {
+    /// Identifier reported in the vendor pin map
+    pub fn id(&self) -> u8 {
+        match self {
+            DisplayBus::Spi0 => 0,
+            DisplayBus::Spi1 => 1,
+        }
+    }
+
+    /// Whether the peripheral can be routed to these GPIOs (RP2040 function table)
+    pub fn supports_pins(&self, data: u8, clock: u8) -> bool {
+        match self {
+            DisplayBus::Spi0 => matches!(data, 3 | 7 | 19 | 23) && matches!(clock, 2 | 6 | 18 | 22),
+            DisplayBus::Spi1 => matches!(data, 11 | 15 | 27) && matches!(clock, 10 | 14 | 26),
+        }
+    }
+
+    /// Data and clock GPIOs the firmware routes the peripheral to
+    pub fn wired_pins(&self) -> (u8, u8) {
+        match self {
+            DisplayBus::Spi0 => (config::SPI_MOSI_PIN, config::SPI_SCK_PIN),
+            DisplayBus::Spi1 => (15, 14),
+        }
+    }
+}
+
+/// Chip select and data/command pins of one per-key panel
+pub struct KeyPanelPins {
+    pub cs: u8,
+    pub dc: u8,
+}
+
+/// Channel order of a panel's pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ColorOrder {
+    Rgb,
+    Bgr,
+}
+
+/// Quirks of the fitted panel module
+#[derive(Clone, Copy, Debug)]
+pub struct PanelConfig {
+    /// BGR panels get red and blue swapped during pixel conversion
+    pub color_order: ColorOrder,
+    /// Send INVON instead of INVOFF at init
+    pub invert: bool,
+    /// MADCTL bits XOR'd over the rotation's
+    pub madctl: u8,
+}
+
+/// How the GPIO key matrix is read (the PIO scanner has its own fixed timing)
+#[derive(Clone, Copy, Debug)]
+pub struct MatrixScanConfig {
+    /// Wait after driving a row low before reading the columns; long cables
+    /// need longer for the line to settle
+    pub settle_us: u16,
+    /// Read every row three times and take each key's 2-of-3 majority, so a
+    /// single noise spike never becomes a press
+    pub majority_vote: bool,
+}
+
+/// What sits in one key matrix slot of a hybrid board (see `hybrid`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum MatrixSlot {
+    /// A key, reported as this protocol key index
+    Key(u8),
+    /// An encoder's push switch; the encoder is reported as this Plus dial
+    Dial(u8),
+    /// Nothing fitted
+    Empty,
+}
+
+/// Six keys and a dial on the Plus's 4x2 matrix. The dial takes the top
+/// right slot (quadrature A/B on GPIO11/12), the bottom right one is left
+/// empty; keys keep their Plus key positions.
+pub const HYBRID_6KEY_DIAL: &[MatrixSlot] = &[
+    MatrixSlot::Key(0),
+    MatrixSlot::Key(1),
+    MatrixSlot::Key(2),
+    MatrixSlot::Dial(0),
+    MatrixSlot::Key(4),
+    MatrixSlot::Key(5),
+    MatrixSlot::Key(6),
+    MatrixSlot::Empty,
+];
+
+/// What answers at an expansion board's address (see `expansion`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ExpansionKind {
+    /// A board running the expansion protocol, e.g. an RP2040 scanning its
+    /// own keys
+    Satellite,
+    /// A PCA9555 (or TCA9555) 16-bit port expander, keys switching inputs to
+    /// ground; bit n of the input ports is key n
+    Pca9555,
+}
+
+/// One board on the I2C expansion bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct ExpansionBoard {
+    /// 7-bit I2C address
+    pub address: u8,
+    pub kind: ExpansionKind,
+    /// Physical key index of the board's first key; its keys follow in order
+    pub first_key: u8,
+    /// Keys the board contributes (at most 32 for a satellite, 16 for a PCA9555)
+    pub keys: u8,
+}
+
+/// A 32-key deck built from two 16-key panels: the main unit wires the top
+/// two rows of its 8x4 matrix, a satellite panel at 0x40 scans the bottom two.
+pub const EXPANSION_LOWER_PANEL: &[ExpansionBoard] = &[ExpansionBoard {
+    address: 0x40,
+    kind: ExpansionKind::Satellite,
+    first_key: 16,
+    keys: 16,
+}];
+
 /// Pin assignments for status LEDs
 pub struct LedPins {
     pub status: u8,
@@ -60,10 +255,10 @@ impl HardwareConfig {
         let layout = device.button_layout();
 
         // Get pin assignments based on device layout
-        let (row_pins, col_pins) = match (layout.rows, layout.cols) {
+        let (row_pins, col_pins) = match layout.matrix() {
             (2, 3) => (&[2u8, 3][..], &[4u8, 5, 6][..]), // Mini
             (3, 5) => (&[2u8, 3, 7][..], &[4u8, 5, 6, 10, 11][..]), // Original
-            (4, 8) => (&[2u8, 3, 7, 9][..], &[4u8, 5, 6, 10, 11, 12, 13, 16][..]), // XL
+            (4, 8) => (&[2u8, 3, 7, 9][..], &[4u8, 5, 6, 10, 11, 12, 13, 16][..]), // XL, Studio
             (2, 4) => (&[2u8, 3][..], &[4u8, 5, 6, 10][..]), // Plus
             _ => (&[2u8, 3][..], &[4u8, 5, 6][..]),      // Fallback to Mini
         };
@@ -72,26 +267,114 @@ impl HardwareConfig {
             device,
             button_pins: ButtonPins { row_pins, col_pins },
             display_pins: DisplayPins {
-                spi_mosi: 19,
-                spi_sck: 18,
+                bus: DisplayBus::Spi0,
+                data: 19,
+                clock: 18,
                 cs: 8,
                 dc: 14,
                 rst: 15,
                 backlight: 17,
             },
+            key_panels: &[],
             led_pins: LedPins {
                 status: 25,
                 usb: 20,
                 error: 21,
             },
+            display_rotation: Rotation::Deg0,
+            panel: PanelConfig {
+                color_order: if config::PANEL_BGR {
+                    ColorOrder::Bgr
+                } else {
+                    ColorOrder::Rgb
+                },
+                invert: config::PANEL_INVERT,
+                madctl: config::PANEL_MADCTL,
+            },
+            matrix: MatrixScanConfig {
+                settle_us: config::MATRIX_SETTLE_US,
+                majority_vote: config::MATRIX_MAJORITY_VOTE,
+            },
+            hybrid_slots: if cfg!(feature = "hybrid-dials") && device.is_plus() {
+                HYBRID_6KEY_DIAL
+            } else {
+                &[]
+            },
+            expansion_boards: if cfg!(feature = "i2c-expansion") && layout.total_keys == 32 {
+                EXPANSION_LOWER_PANEL
+            } else {
+                &[]
+            },
         }
     }
+
+    /// Check the display link against the pin function table and the key
+    /// matrix. Returns the bus to bring up, or None if the profile is unusable.
+    pub fn resolve_display_bus(&self) -> Option<DisplayBus> {
+        let d = &self.display_pins;
+        if !d.bus.supports_pins(d.data, d.clock) {
+            error!(
+                "{} cannot use GPIO{} / GPIO{} for the display",
+                d.bus, d.data, d.clock
+            );
+            return None;
+        }
+        let (data, clock) = d.bus.wired_pins();
+        if (d.data, d.clock) != (data, clock) {
+            error!(
+                "{} is wired to GPIO{} / GPIO{}, not GPIO{} / GPIO{}",
+                d.bus, data, clock, d.data, d.clock
+            );
+            return None;
+        }
+        if d.backlight != config::DISPLAY_BL_PIN {
+            error!(
+                "The backlight PWM drives GPIO{}, not GPIO{}",
+                config::DISPLAY_BL_PIN,
+                d.backlight
+            );
+            return None;
+        }
+        if !self.key_panels.is_empty() && d.bus != DisplayBus::Spi0 {
+            error!("Per-key panels share SPI0, not {}", d.bus);
+            return None;
+        }
+        // Peripherals other features already hold
+        if d.bus == DisplayBus::Spi1 && cfg!(feature = "touch-xpt2046") {
+            error!("{} is taken by the touch panel", d.bus);
+            return None;
+        }
+
+        let mut display: Vec<u8, { 6 + 2 * config::MAX_KEYS }> = Vec::new();
+        let _ = display.extend_from_slice(&[d.data, d.clock, d.rst, d.backlight, d.cs, d.dc]);
+        for panel in self.key_panels {
+            let _ = display.extend_from_slice(&[panel.cs, panel.dc]);
+        }
+        let keys = self
+            .button_pins
+            .row_pins
+            .iter()
+            .chain(self.button_pins.col_pins);
+        if let Some(pin) = keys.copied().find(|pin| display.contains(pin)) {
+            error!("GPIO{} is used by both the key matrix and the display", pin);
+            return None;
+        }
+
+        info!("Display on {} (GPIO{} / GPIO{})", d.bus, d.data, d.clock);
+        Some(d.bus)
+    }
+}
+
+/// Panel orientation: the stored setting, else the board profile's
+pub fn display_rotation() -> Rotation {
+    crate::settings::with(|s| s.display_rotation)
+        .unwrap_or_else(|| HardwareConfig::for_current_device().display_rotation)
 }
 
 /// Initialize and spawn all hardware tasks for the current device (runtime selection)
 pub async fn init_hardware_tasks(spawner: &Spawner, p: Peripherals) -> Result<(), SpawnError> {
-    let hw_config = HardwareConfig::for_current_device();
-    init_hardware_tasks_with_config(spawner, p, &hw_config).await
+    // Resolved once settings are loaded, so a stored personality applies
+    init_hardware_tasks_with_config(spawner, p, None, true).await
 }
 
 /// Initialize and spawn all hardware tasks for a specific device (compile-time selection)
@@ -100,66 +383,52 @@ pub async fn init_hardware_tasks_for_device(
     p: Peripherals,
     device: Device,
 ) -> Result<(), SpawnError> {
-    let hw_config = HardwareConfig::for_device(device);
-    init_hardware_tasks_with_config(spawner, p, &hw_config).await
+    init_hardware_tasks_with_config(spawner, p, Some(device), true).await
 }
 
-/// Initialize and spawn core 0 tasks (USB, buttons) for multicore setup
+/// Initialize and spawn core 0 tasks (USB, buttons) for multicore setup;
+/// the display hardware is left for `init_hardware_tasks_core1`
 pub async fn init_hardware_tasks_core0(
     spawner: &Spawner,
+    p: Peripherals,
     device: Device,
 ) -> Result<(), SpawnError> {
-    let p = embassy_rp::init(Default::default());
-    let hw_config = HardwareConfig::for_device(device);
-
-    info!(
-        "Core 0: Initializing hardware for {}",
-        hw_config.device.device_name()
-    );
-
-    // Create all pins and return them with the USB driver
-    let (driver, usb_led, status_led, error_led, row_pins, col_pins) =
-        create_all_pins_for_device(p, hw_config.device);
-
-    // Spawn USB task
-    spawner.spawn(usb_task_for_device(driver, usb_led, hw_config.device))?;
-
-    // For Mini devices, prefer Direct pin mode with 6 dedicated inputs
-    if matches!(
-        device,
-        crate::device::Device::Mini | crate::device::Device::RevisedMini
-    ) {
-        crate::config::set_button_input_mode(crate::config::ButtonInputMode::Direct);
-    }
-
-    // Spawn button task with device-specific layout
-    spawn_button_task_with_pins(spawner, row_pins, col_pins, device)?;
-
-    // Spawn status LED task
-    spawner.spawn(status_task(status_led, error_led))?;
-
-    Ok(())
+    init_hardware_tasks_with_config(spawner, p, Some(device), false).await
 }
 
 /// Initialize and spawn core 1 tasks (display, image processing) for multicore setup
-pub async fn init_hardware_tasks_core1(device: Device) -> Result<(), SpawnError> {
+pub async fn init_hardware_tasks_core1(
+    spawner: &Spawner,
+    device: Device,
+) -> Result<(), SpawnError> {
     info!(
         "Core 1: Initializing image processing tasks for {}",
         device.device_name()
     );
 
-    // TODO: Initialize display hardware and spawn display task
-    // For now, just return success as display is not yet implemented
-
-    Ok(())
+    // Core 0 claims the display hardware along with the other pins
+    match DISPLAY_HARDWARE.wait().await {
+        Some(display) => spawn_display_task(spawner, display),
+        None => {
+            warn!("Core 1: no display hardware, display task not started");
+            Ok(())
+        }
+    }
 }
 
-/// Initialize and spawn all hardware tasks with given configuration
+/// Initialize and spawn all hardware tasks for `device`
+/// (None = the current device, resolved after settings are loaded).
+/// `spawn_display` is false when core 1 runs the display task.
 async fn init_hardware_tasks_with_config(
     spawner: &Spawner,
     p: Peripherals,
-    hw_config: &HardwareConfig,
+    device: Option<Device>,
+    spawn_display: bool,
 ) -> Result<(), SpawnError> {
+    // Create all pins and return them with the USB driver
+    let (device, driver, usb_led, status_led, error_led, row_pins, col_pins) =
+        create_all_pins_for_device(p, device);
+    let hw_config = HardwareConfig::for_device(device);
     let layout = hw_config.device.button_layout();
 
     info!(
@@ -171,39 +440,76 @@ async fn init_hardware_tasks_with_config(
         layout.cols, layout.rows, layout.total_keys
     );
 
-    // Create all pins and return them with the USB driver
-    let (driver, usb_led, status_led, error_led, row_pins, col_pins) =
-        create_all_pins_for_device(p, hw_config.device);
-
     // Spawn USB task
-    spawner.spawn(usb_task_for_device(driver, usb_led, hw_config.device))?;
-
-    // For Mini devices, prefer Direct pin mode with 6 dedicated inputs
-    let device = hw_config.device;
-    if matches!(
-        device,
-        crate::device::Device::Mini | crate::device::Device::RevisedMini
-    ) {
+    spawner.spawn(usb_task_for_device(driver, usb_led, device))?;
+
+    // Touch builds read the panel; Mini devices prefer Direct pin mode
+    // with 6 dedicated inputs
+    if cfg!(any(feature = "touch-xpt2046", feature = "touch-ft6236")) {
+        crate::config::set_button_input_mode(crate::config::ButtonInputMode::Touch);
+    } else if device.is_mini() {
         crate::config::set_button_input_mode(crate::config::ButtonInputMode::Direct);
     }
 
     // Spawn button task with device-specific layout
     spawn_button_task_with_pins(spawner, row_pins, col_pins, device)?;
+    spawner.spawn(crate::buttons::key_feedback_task())?;
+
+    // Keys of the secondary deck
+    #[cfg(feature = "secondary-deck")]
+    spawner.spawn(crate::secondary_deck::secondary_deck_task())?;
 
-    // Spawn display task (commented out until hardware is ready)
-    // spawn_display_task(spawner, p, &hw_config)?;
+    // Encoders of a hybrid key/dial board
+    #[cfg(feature = "hybrid-dials")]
+    if crate::hybrid::is_enabled() {
+        spawner.spawn(crate::hybrid::encoder_task())?;
+    }
+
+    // Spawn the display task for the hardware claimed with the pins
+    if spawn_display {
+        if let Some(display) = DISPLAY_HARDWARE.try_take().flatten() {
+            spawn_display_task(spawner, display)?;
+        }
+    }
 
     // Spawn status LED task
     spawner.spawn(status_task(status_led, error_led))?;
 
+    // Spawn accent ring effects
+    #[cfg(feature = "led-ring")]
+    spawner.spawn(crate::effects::effects_task())?;
+
+    // Spawn expansion board polling
+    #[cfg(feature = "i2c-expansion")]
+    spawner.spawn(crate::expansion::expansion_task())?;
+
+    // Spawn answers to a main unit's expansion polls
+    #[cfg(feature = "i2c-satellite")]
+    spawner.spawn(crate::satellite::satellite_task())?;
+
+    // Spawn key-to-report latency self-test
+    #[cfg(feature = "latency-test")]
+    spawner.spawn(crate::latency_test::latency_test_task())?;
+
+    // Spawn supply voltage monitor
+    spawner.spawn(crate::power::voltage_monitor_task())?;
+
+    // Spawn night mode schedule
+    spawner.spawn(crate::night::night_task())?;
+
+    // Spawn fader polling
+    spawner.spawn(crate::analog::analog_task())?;
+
     Ok(())
 }
 
 /// Create all pins for specific device layout
+/// (None = the current device, which a stored personality may override)
 fn create_all_pins_for_device(
     p: Peripherals,
-    device: Device,
+    device: Option<Device>,
 ) -> (
+    Device,
     Driver<'static, peripherals::USB>,
     Output<'static>,
     Output<'static>,
@@ -211,6 +517,54 @@ fn create_all_pins_for_device(
     Vec<Output<'static>, 4>,
     Vec<Input<'static>, 32>,
 ) {
+    // Flash storage must be ready before the USB task reads the serial number
+    crate::storage::init(p.FLASH);
+    let device = device.unwrap_or_else(|| {
+        if let Some(pid) = crate::settings::with(|s| s.personality_pid) {
+            info!("Device personality override: PID 0x{:04X}", pid);
+            let _ = config::set_device_pid(pid);
+        }
+        config::get_current_device()
+    });
+    crate::analog::init(
+        p.ADC,
+        p.PIN_26,
+        p.PIN_27,
+        p.PIN_28,
+        p.PIN_29,
+        p.ADC_TEMP_SENSOR,
+    );
+    #[cfg(feature = "pio-matrix")]
+    crate::pio_matrix::init(p.PIO0, p.DMA_CH0, p.DMA_CH1);
+    #[cfg(feature = "led-ring")]
+    crate::effects::init(p.PIO1, p.DMA_CH3, p.PIN_22);
+    #[cfg(feature = "latency-test")]
+    crate::latency_test::init(p.PIN_1);
+    #[cfg(feature = "i2c-expansion")]
+    crate::expansion::init(
+        p.I2C0,
+        p.PIN_0,
+        p.PIN_1,
+        HardwareConfig::for_device(device).expansion_boards,
+    );
+    #[cfg(feature = "i2c-satellite")]
+    crate::satellite::init(p.I2C0, p.PIN_0, p.PIN_1, device.button_layout().total_keys);
+    // Faders stand in for the dials on the Plus and Studio
+    crate::analog::set_dial_mapping(device.dial_count() > 0);
+
+    // Display hardware, handed to the core that runs the display task
+    let hw_config = HardwareConfig::for_device(device);
+    #[cfg(feature = "epaper-display")]
+    let display = claim_display(&hw_config, p.PIN_22);
+    #[cfg(feature = "parallel-display")]
+    let display = claim_display(&hw_config, p.PIO1, p.DMA_CH2, p.PWM_SLICE0, p.PIN_17);
+    #[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+    let display = claim_display(&hw_config, p.PWM_SLICE0, p.PIN_17);
+    if display.is_some() {
+        crate::channels::attach_display();
+    }
+    DISPLAY_HARDWARE.signal(display);
+
     // Create USB driver and LEDs first
     let driver = Driver::new(p.USB, crate::Irqs);
     let usb_led = Output::new(p.PIN_20, Level::Low);
@@ -222,11 +576,35 @@ fn create_all_pins_for_device(
     let mut row_pins: Vec<Output<'static>, 4> = Vec::new();
     let mut col_pins: Vec<Input<'static>, 32> = Vec::new();
 
-    // If Direct mode is selected for Mini, build 6 direct input pins
-    if matches!(
+    if cfg!(any(feature = "touch-xpt2046", feature = "touch-ft6236")) {
+        // Touch builds have no key switches; the controller takes matrix pins
+        #[cfg(feature = "touch-ft6236")]
+        crate::touch::init(crate::touch::ft6236::Ft6236::new(
+            embassy_rp::i2c::I2c::new_blocking(
+                p.I2C0,
+                p.PIN_13,
+                p.PIN_12,
+                embassy_rp::i2c::Config::default(),
+            ),
+        ));
+        #[cfg(all(feature = "touch-xpt2046", not(feature = "touch-ft6236")))]
+        {
+            let mut spi_config = embassy_rp::spi::Config::default();
+            // The XPT2046 clocks at up to 2.5 MHz
+            spi_config.frequency = 2_000_000;
+            let spi = embassy_rp::spi::Spi::new_blocking(
+                p.SPI1, p.PIN_10, p.PIN_11, p.PIN_12, spi_config,
+            );
+            crate::touch::init(crate::touch::xpt2046::Xpt2046::new(
+                spi,
+                Output::new(p.PIN_13, Level::High),
+                Input::new(p.PIN_16, Pull::Up),
+            ));
+        }
+    } else if matches!(
         crate::config::button_input_mode(),
         crate::config::ButtonInputMode::Direct
-    ) && matches!(device, Device::Mini | Device::RevisedMini)
+    ) && device.is_mini()
     {
         // Build six dedicated direct-input pins for Mini to avoid partial-move issues
         let _ = col_pins.push(Input::new(p.PIN_4, Pull::Up));
@@ -235,8 +613,10 @@ fn create_all_pins_for_device(
         let _ = col_pins.push(Input::new(p.PIN_10, Pull::Up));
         let _ = col_pins.push(Input::new(p.PIN_11, Pull::Up));
         let _ = col_pins.push(Input::new(p.PIN_12, Pull::Up));
+        #[cfg(feature = "secondary-deck")]
+        init_secondary_deck(p.PIN_9, p.PIN_13, p.PIN_16);
     } else {
-        match (layout.rows, layout.cols) {
+        match layout.matrix() {
             (2, 3) => {
                 // Mini and Revised Mini (2x3 = 6 keys)
                 let _ = row_pins.push(Output::new(p.PIN_2, Level::High));
@@ -244,6 +624,8 @@ fn create_all_pins_for_device(
                 let _ = col_pins.push(Input::new(p.PIN_4, Pull::Up));
                 let _ = col_pins.push(Input::new(p.PIN_5, Pull::Up));
                 let _ = col_pins.push(Input::new(p.PIN_6, Pull::Up));
+                #[cfg(feature = "secondary-deck")]
+                init_secondary_deck(p.PIN_9, p.PIN_13, p.PIN_16);
             }
             (3, 5) => {
                 // 15 Keys Module (5x3)
@@ -256,8 +638,33 @@ fn create_all_pins_for_device(
                 let _ = col_pins.push(Input::new(p.PIN_10, Pull::Up));
                 let _ = col_pins.push(Input::new(p.PIN_11, Pull::Up));
             }
+            (2, 4) => {
+                // Plus (4x2 = 8 keys)
+                let _ = row_pins.push(Output::new(p.PIN_2, Level::High));
+                let _ = row_pins.push(Output::new(p.PIN_3, Level::High));
+                let _ = col_pins.push(Input::new(p.PIN_4, Pull::Up));
+                let _ = col_pins.push(Input::new(p.PIN_5, Pull::Up));
+                let _ = col_pins.push(Input::new(p.PIN_6, Pull::Up));
+                let _ = col_pins.push(Input::new(p.PIN_10, Pull::Up));
+                #[cfg(feature = "secondary-deck")]
+                init_secondary_deck(p.PIN_9, p.PIN_13, p.PIN_16);
+
+                // Hybrid board: quadrature outputs of the dial in slot 3
+                #[cfg(feature = "hybrid-dials")]
+                {
+                    let slots = HardwareConfig::for_device(device).hybrid_slots;
+                    if !slots.is_empty() {
+                        let mut quadrature = Vec::new();
+                        let _ = quadrature.push((
+                            Input::new(p.PIN_11, Pull::Up),
+                            Input::new(p.PIN_12, Pull::Up),
+                        ));
+                        crate::hybrid::init(slots, quadrature);
+                    }
+                }
+            }
             (4, 8) => {
-                // 32 Keys Module (8x4)
+                // XL, 32 Keys Module and Studio (8x4)
                 let _ = row_pins.push(Output::new(p.PIN_2, Level::High));
                 let _ = row_pins.push(Output::new(p.PIN_3, Level::High));
                 let _ = row_pins.push(Output::new(p.PIN_7, Level::High));
@@ -286,21 +693,273 @@ fn create_all_pins_for_device(
         }
     }
 
-    (driver, usb_led, status_led, error_led, row_pins, col_pins)
+    (
+        device, driver, usb_led, status_led, error_led, row_pins, col_pins,
+    )
+}
+
+/// Claim the secondary deck's key inputs (`SECONDARY_DECK_PINS`); only
+/// called for layouts that leave those pins free
+#[cfg(feature = "secondary-deck")]
+fn init_secondary_deck(
+    pin9: embassy_rp::Peri<'static, peripherals::PIN_9>,
+    pin13: embassy_rp::Peri<'static, peripherals::PIN_13>,
+    pin16: embassy_rp::Peri<'static, peripherals::PIN_16>,
+) {
+    crate::secondary_deck::init([
+        Input::new(pin9, Pull::Up),
+        Input::new(pin13, Pull::Up),
+        Input::new(pin16, Pull::Up),
+    ]);
+}
+
+// ===================================================================
+// Display hardware
+// ===================================================================
+
+/// Display peripherals claimed with the pins, handed to the display task
+pub enum DisplayHardware {
+    /// One panel on the profile's bus
+    #[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+    Panel {
+        link: DisplayLink,
+        rst: Output<'static>,
+        bl: Backlight,
+    },
+    /// Per-key panels sharing SPI0 (`HardwareConfig::key_panels`)
+    #[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+    KeyPanels {
+        spi: Spi<'static, peripherals::SPI0, spi::Blocking>,
+        panels: Vec<(Output<'static>, Output<'static>), { config::MAX_KEYS }>,
+        rst: Output<'static>,
+        bl: Backlight,
+    },
+    #[cfg(feature = "parallel-display")]
+    Parallel {
+        bus: crate::parallel_bus::Parallel8080Bus,
+        rst: Output<'static>,
+        bl: Backlight,
+    },
+    #[cfg(feature = "epaper-display")]
+    Epaper {
+        spi: Spi<'static, peripherals::SPI0, spi::Blocking>,
+        cs: Output<'static>,
+        dc: Output<'static>,
+        rst: Output<'static>,
+        busy: Input<'static>,
+    },
+}
+
+/// Claimed display hardware; None when the profile has no usable display
+static DISPLAY_HARDWARE: Signal<CriticalSectionRawMutex, Option<DisplayHardware>> = Signal::new();
+
+/// A display control pin by GPIO number, as output
+fn display_output(pin: u8, level: Level) -> Output<'static> {
+    // SAFETY: display pins are claimed once, after `resolve_display_bus` (or
+    // the parallel bus check) has ruled out the key matrix and each other
+    Output::new(unsafe { AnyPin::steal(pin) }, level)
+}
+
+/// SPI0 on `DisplayBus::Spi0`'s wired pins
+#[cfg(not(feature = "parallel-display"))]
+fn display_spi0() -> Spi<'static, peripherals::SPI0, spi::Blocking> {
+    let mut spi_config = spi::Config::default();
+    spi_config.frequency = config::SPI_BAUDRATE;
+    // SAFETY: only the display takes SPI0 and GPIO18/19
+    let (spi, clk, mosi) = unsafe {
+        (
+            peripherals::SPI0::steal(),
+            peripherals::PIN_18::steal(),
+            peripherals::PIN_19::steal(),
+        )
+    };
+    Spi::new_blocking_txonly(spi, clk, mosi, spi_config)
+}
+
+/// Bring up the display link `resolve_display_bus` picked
+#[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+fn claim_display(
+    hw_config: &HardwareConfig,
+    pwm: Peri<'static, peripherals::PWM_SLICE0>,
+    bl: Peri<'static, peripherals::PIN_17>,
+) -> Option<DisplayHardware> {
+    let bus = hw_config.resolve_display_bus()?;
+    let d = &hw_config.display_pins;
+    let rst = display_output(d.rst, Level::High);
+    let bl = Backlight::new(pwm, bl);
+
+    if !hw_config.key_panels.is_empty() {
+        let panels = hw_config
+            .key_panels
+            .iter()
+            .map(|panel| {
+                (
+                    display_output(panel.cs, Level::High),
+                    display_output(panel.dc, Level::Low),
+                )
+            })
+            .collect();
+        return Some(DisplayHardware::KeyPanels {
+            spi: display_spi0(),
+            panels,
+            rst,
+            bl,
+        });
+    }
+
+    let link = match bus {
+        DisplayBus::Spi0 => DisplayLink::Spi0 {
+            spi: display_spi0(),
+            cs: display_output(d.cs, Level::High),
+            dc: display_output(d.dc, Level::Low),
+        },
+        DisplayBus::Spi1 => {
+            let mut spi_config = spi::Config::default();
+            spi_config.frequency = config::SPI_BAUDRATE;
+            // SAFETY: `resolve_display_bus` rejects SPI1 when the touch panel has it
+            let (spi, clk, mosi) = unsafe {
+                (
+                    peripherals::SPI1::steal(),
+                    peripherals::PIN_14::steal(),
+                    peripherals::PIN_15::steal(),
+                )
+            };
+            DisplayLink::Spi1 {
+                spi: Spi::new_blocking_txonly(spi, clk, mosi, spi_config),
+                cs: display_output(d.cs, Level::High),
+                dc: display_output(d.dc, Level::Low),
+            }
+        }
+    };
+    Some(DisplayHardware::Panel { link, rst, bl })
+}
+
+/// Bring up the e-paper panel, which shares the TFT's SPI0 pins
+#[cfg(feature = "epaper-display")]
+fn claim_display(
+    hw_config: &HardwareConfig,
+    busy: Peri<'static, peripherals::PIN_22>,
+) -> Option<DisplayHardware> {
+    let bus = hw_config.resolve_display_bus()?;
+    if bus != DisplayBus::Spi0 {
+        error!("The e-paper panel needs SPI0, not {}", bus);
+        return None;
+    }
+    let d = &hw_config.display_pins;
+    Some(DisplayHardware::Epaper {
+        spi: display_spi0(),
+        cs: display_output(d.cs, Level::High),
+        dc: display_output(d.dc, Level::Low),
+        rst: display_output(d.rst, Level::High),
+        busy: Input::new(busy, Pull::None),
+    })
+}
+
+/// Bring up the 8080 bus: data on `PARALLEL_DATA_BASE_PIN`.., WR and CS on
+/// their own pins, DC and RST from the profile. Only touch builds leave the
+/// matrix pins under the data bus free.
+#[cfg(feature = "parallel-display")]
+fn claim_display(
+    hw_config: &HardwareConfig,
+    pio: Peri<'static, peripherals::PIO1>,
+    dma: Peri<'static, peripherals::DMA_CH2>,
+    pwm: Peri<'static, peripherals::PWM_SLICE0>,
+    bl: Peri<'static, peripherals::PIN_17>,
+) -> Option<DisplayHardware> {
+    let d = &hw_config.display_pins;
+    let bus_pin = |pin: u8| {
+        (config::PARALLEL_DATA_BASE_PIN..config::PARALLEL_DATA_BASE_PIN + 8).contains(&pin)
+            || pin == config::PARALLEL_WR_PIN
+            || pin == config::PARALLEL_CS_PIN
+    };
+    if let Some(pin) = [d.dc, d.rst].into_iter().find(|pin| bus_pin(*pin)) {
+        error!("GPIO{} is used by both the parallel bus and DC/RST", pin);
+        return None;
+    }
+    if d.backlight != config::DISPLAY_BL_PIN {
+        error!(
+            "The backlight PWM drives GPIO{}, not GPIO{}",
+            config::DISPLAY_BL_PIN,
+            d.backlight
+        );
+        return None;
+    }
+    info!(
+        "Display on the 8080 bus (D0 GPIO{}, WR GPIO{})",
+        config::PARALLEL_DATA_BASE_PIN,
+        config::PARALLEL_WR_PIN
+    );
+    let data = core::array::from_fn(|bit| {
+        display_output(config::PARALLEL_DATA_BASE_PIN + bit as u8, Level::Low)
+    });
+    let bus = crate::parallel_bus::Parallel8080Bus::new(
+        pio,
+        dma,
+        data,
+        display_output(config::PARALLEL_WR_PIN, Level::High),
+        display_output(config::PARALLEL_CS_PIN, Level::High),
+        display_output(d.dc, Level::Low),
+    );
+    Some(DisplayHardware::Parallel {
+        bus,
+        rst: display_output(d.rst, Level::High),
+        bl: Backlight::new(pwm, bl),
+    })
+}
+
+/// Spawn the task driving `display` on `spawner`'s core
+fn spawn_display_task(spawner: &Spawner, display: DisplayHardware) -> Result<(), SpawnError> {
+    match display {
+        #[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+        DisplayHardware::Panel { link, rst, bl } => {
+            spawner.spawn(crate::display::display_task(link, rst, bl))
+        }
+        #[cfg(not(any(feature = "epaper-display", feature = "parallel-display")))]
+        DisplayHardware::KeyPanels {
+            spi,
+            panels,
+            rst,
+            bl,
+        } => spawner.spawn(crate::display::display_task_multi(spi, panels, rst, bl)),
+        #[cfg(feature = "parallel-display")]
+        DisplayHardware::Parallel { bus, rst, bl } => {
+            spawner.spawn(crate::parallel_bus::display_task_8080(bus, rst, bl))
+        }
+        #[cfg(feature = "epaper-display")]
+        DisplayHardware::Epaper {
+            spi,
+            cs,
+            dc,
+            rst,
+            busy,
+        } => spawner.spawn(crate::epaper::epaper_task(spi, cs, dc, rst, busy)),
+    }
 }
 
 /// Spawn button task with specific pins
 fn spawn_button_task_with_pins(
     spawner: &Spawner,
-    mut row_pins: Vec<Output<'static>, 4>,
+    row_pins: Vec<Output<'static>, 4>,
     mut col_pins: Vec<Input<'static>, 32>,
     device: Device,
 ) -> Result<(), SpawnError> {
     match crate::config::button_input_mode() {
+        #[cfg(feature = "pio-matrix")]
         crate::config::ButtonInputMode::Matrix => {
+            let pins = HardwareConfig::for_device(device).button_pins;
+            spawner.spawn(crate::buttons::button_task_pio_matrix(
+                row_pins,
+                col_pins,
+                pins.row_pins,
+                pins.col_pins,
+            ))
+        }
+        #[cfg(not(feature = "pio-matrix"))]
+        crate::config::ButtonInputMode::Matrix => {
+            let mut row_pins = row_pins;
             // Extract pins for matrix task based on device layout
             let layout = device.button_layout();
-            match (layout.rows, layout.cols) {
+            match layout.matrix() {
                 (2, 3) => {
                     let row1 = row_pins.pop().unwrap();
                     let row0 = row_pins.pop().unwrap();
@@ -322,6 +981,15 @@ fn spawn_button_task_with_pins(
                         row0, row1, row2, col0, col1, col2, col3, col4,
                     ))
                 }
+                (2, 4) => {
+                    let row1 = row_pins.pop().unwrap();
+                    let row0 = row_pins.pop().unwrap();
+                    let col3 = col_pins.pop().unwrap();
+                    let col2 = col_pins.pop().unwrap();
+                    let col1 = col_pins.pop().unwrap();
+                    let col0 = col_pins.pop().unwrap();
+                    spawner.spawn(button_task_matrix_4x2(row0, row1, col0, col1, col2, col3))
+                }
                 (4, 8) => {
                     let row3 = row_pins.pop().unwrap();
                     let row2 = row_pins.pop().unwrap();
@@ -351,6 +1019,13 @@ fn spawn_button_task_with_pins(
                 }
             }
         }
+        #[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+        crate::config::ButtonInputMode::Touch => spawner.spawn(crate::buttons::button_task_touch()),
+        #[cfg(not(any(feature = "touch-xpt2046", feature = "touch-ft6236")))]
+        crate::config::ButtonInputMode::Touch => {
+            error!("No touch controller in this build");
+            Ok(())
+        }
         crate::config::ButtonInputMode::Direct => {
             // Use as many input pins as available up to 32
             let mut inputs: heapless::Vec<Input<'static>, 32> = heapless::Vec::new();
@@ -358,7 +1033,7 @@ fn spawn_button_task_with_pins(
                 let _ = inputs.push(pin);
             }
             // Ensure Mini has exactly 6 inputs if possible
-            if matches!(device, Device::Mini | Device::RevisedMini) && inputs.len() > 6 {
+            if device.is_mini() && inputs.len() > 6 {
                 while inputs.len() > 6 {
                     let _ = inputs.pop();
                 }
@@ -368,18 +1043,9 @@ fn spawn_button_task_with_pins(
     }
 }
 
-/// Status LED task implementation
+/// Status LED task implementation (patterns in `status_led`)
 #[embassy_executor::task]
-pub async fn status_task(mut status_led: Output<'static>, _error_led: Output<'static>) {
-    use embassy_time::{Duration, Timer};
-
+pub async fn status_task(status_led: Output<'static>, error_led: Output<'static>) {
     info!("Status LED task started");
-
-    loop {
-        // Heartbeat pattern - short blink every second
-        status_led.set_high();
-        Timer::after(Duration::from_millis(100)).await;
-        status_led.set_low();
-        Timer::after(Duration::from_millis(900)).await;
-    }
+    crate::status_led::run(status_led, error_led).await
 }
@@ -0,0 +1,103 @@
+//! Host operating system quirks
+//!
+//! The same host software behaves differently depending on the OS under it:
+//! how its HID stack treats input reports and which pipe images are written
+//! on. A host profile bundles the adjustments for one OS (`HostQuirks`).
+//!
+//! The profile is only ever applied once it is stored in the settings
+//! (vendor command `SET_HOST_PROFILE`). Some profiles drop traffic, so a
+//! wrong guess would break the device, and none of the host's control
+//! requests tells the OSes apart reliably: SET_IDLE, for one, comes from
+//! Windows' HID class driver and Linux usbhid alike. Without a stored
+//! profile the host is `Unknown`, which keeps the generic behavior.
+
+/// Host operating system a profile is tuned for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum HostOs {
+    /// Not configured: generic behavior
+    #[default]
+    Unknown = 0,
+    Windows = 1,
+    MacOs = 2,
+    Linux = 3,
+}
+
+impl HostOs {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => HostOs::Unknown,
+            1 => HostOs::Windows,
+            2 => HostOs::MacOs,
+            3 => HostOs::Linux,
+            _ => return None,
+        })
+    }
+
+    /// Adjustments for hosts running this OS
+    pub const fn quirks(self) -> HostQuirks {
+        match self {
+            HostOs::Unknown | HostOs::Linux => HostQuirks {
+                min_report_gap_ms: 0,
+                pad_input_reports: false,
+                image_path: ImagePath::Any,
+            },
+            // hidapi writes on the interrupt pipe and may retry a stalled
+            // write as SET_REPORT; only one copy may reach reassembly
+            HostOs::Windows => HostQuirks {
+                min_report_gap_ms: 0,
+                pad_input_reports: true,
+                image_path: ImagePath::Interrupt,
+            },
+            // IOHIDFamily merges key reports that arrive within one frame
+            HostOs::MacOs => HostQuirks {
+                min_report_gap_ms: 2,
+                pad_input_reports: true,
+                image_path: ImagePath::Any,
+            },
+        }
+    }
+}
+
+/// Pipe output (image) reports are accepted on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ImagePath {
+    /// Control (SET_REPORT) and interrupt OUT
+    Any,
+    /// Interrupt OUT only; SET_REPORT output reports are ignored
+    Interrupt,
+    /// Control only; interrupt OUT reports are ignored
+    Control,
+}
+
+impl ImagePath {
+    pub fn accepts_control(self) -> bool {
+        self != ImagePath::Interrupt
+    }
+
+    pub fn accepts_interrupt(self) -> bool {
+        self != ImagePath::Control
+    }
+}
+
+/// What a host profile adjusts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct HostQuirks {
+    /// Least time between two input reports (0 = back to back)
+    pub min_report_gap_ms: u8,
+    /// Send key reports at the profile's full input report size instead of
+    /// only the bytes in use
+    pub pad_input_reports: bool,
+    /// Where image reports are taken from
+    pub image_path: ImagePath,
+}
+
+/// Profile in effect: the stored one, else the generic behavior
+pub fn current() -> HostOs {
+    crate::settings::with(|s| s.host_os).unwrap_or_default()
+}
+
+/// Adjustments of the profile in effect
+pub fn quirks() -> HostQuirks {
+    current().quirks()
+}
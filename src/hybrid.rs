@@ -0,0 +1,205 @@
+//! Hybrid key/dial boards
+//!
+//! A board can fit rotary encoders in some of its key matrix slots and still
+//! work with stock Elgato software: it enumerates as a StreamDeck Plus and
+//! the encoders are reported as Plus dials. The board profile
+//! (`HardwareConfig::hybrid_slots`) declares what sits in each matrix slot:
+//!
+//! - a key, and the protocol key index it is reported as
+//! - an encoder, whose push switch closes the slot, and the dial it drives
+//! - nothing
+//!
+//! Each scan is split before debounce: key slots are moved to their protocol
+//! index, encoder pushes are debounced here and sent as dial press events.
+//! Turns come from the encoders' quadrature outputs, which need sampling far
+//! faster than the key scan and are polled by `encoder_task`.
+
+use core::cell::{Cell, RefCell};
+use defmt::*;
+use embassy_rp::gpio::Input;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
+
+use crate::channels::ENCODER_CHANNEL;
+use crate::config::{BUTTON_DEBOUNCE_MS, ENCODER_POLL_US, ENCODER_STEPS_PER_DETENT, MAX_KEYS};
+use crate::hardware::MatrixSlot;
+use crate::types::{EncoderEvent, PLUS_DIAL_COUNT};
+
+/// Quadrature outputs (A, B) of one encoder
+pub type QuadraturePins = (Input<'static>, Input<'static>);
+
+/// Slot table of the board, empty when it has no encoders
+static SLOTS: Mutex<CriticalSectionRawMutex, Cell<&'static [MatrixSlot]>> =
+    Mutex::new(Cell::new(&[]));
+
+/// Encoder outputs, dial N at index N; taken by `encoder_task`
+static QUADRATURE: Mutex<CriticalSectionRawMutex, RefCell<Vec<QuadraturePins, PLUS_DIAL_COUNT>>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+/// Set up a hybrid board: its slot table and the quadrature outputs of its
+/// encoders, dial 0 first. Must be called before the button and encoder
+/// tasks run.
+pub fn init(slots: &'static [MatrixSlot], quadrature: Vec<QuadraturePins, PLUS_DIAL_COUNT>) {
+    info!(
+        "Hybrid board: {} matrix slots, {} encoders",
+        slots.len(),
+        quadrature.len()
+    );
+    SLOTS.lock(|s| s.set(slots));
+    QUADRATURE.lock(|q| *q.borrow_mut() = quadrature);
+}
+
+/// Whether this board has encoders in its key matrix
+pub fn is_enabled() -> bool {
+    !SLOTS.lock(|s| s.get()).is_empty()
+}
+
+// ===================================================================
+// Key Matrix Split
+// ===================================================================
+
+/// Separates encoder pushes from keys in raw scans
+pub struct SlotSplitter {
+    /// Debounced press state reported for each dial
+    pressed: [bool; PLUS_DIAL_COUNT],
+    /// When each dial's raw state started differing from `pressed`
+    changing_since: [Option<Instant>; PLUS_DIAL_COUNT],
+}
+
+impl SlotSplitter {
+    pub const fn new() -> Self {
+        Self {
+            pressed: [false; PLUS_DIAL_COUNT],
+            changing_since: [None; PLUS_DIAL_COUNT],
+        }
+    }
+
+    /// Split one raw scan. Returns the key states in protocol key order and
+    /// sends a dial press event when a debounced encoder push changes. Scans
+    /// of boards without encoders pass through unchanged.
+    pub fn split(&mut self, raw: &[bool; MAX_KEYS]) -> [bool; MAX_KEYS] {
+        let slots = SLOTS.lock(|s| s.get());
+        if slots.is_empty() {
+            return *raw;
+        }
+
+        let now = Instant::now();
+        let mut keys = [false; MAX_KEYS];
+        let mut dials = self.pressed;
+        for (slot, &closed) in slots.iter().zip(raw.iter()) {
+            match *slot {
+                MatrixSlot::Key(key) => {
+                    if let Some(state) = keys.get_mut(key as usize) {
+                        *state |= closed;
+                    }
+                }
+                MatrixSlot::Dial(dial) if (dial as usize) < PLUS_DIAL_COUNT => {
+                    dials[dial as usize] = self.debounce(dial as usize, closed, now);
+                }
+                MatrixSlot::Dial(_) | MatrixSlot::Empty => {}
+            }
+        }
+
+        if dials != self.pressed {
+            self.pressed = dials;
+            debug!("Dial presses: {}", dials);
+            if ENCODER_CHANNEL
+                .try_send(EncoderEvent::Press(dials))
+                .is_err()
+            {
+                crate::stats::command_dropped();
+            }
+        }
+        keys
+    }
+
+    /// Debounced state of a dial's push switch
+    fn debounce(&mut self, dial: usize, closed: bool, now: Instant) -> bool {
+        if closed == self.pressed[dial] {
+            self.changing_since[dial] = None;
+            return closed;
+        }
+        let since = *self.changing_since[dial].get_or_insert(now);
+        if now.duration_since(since) >= Duration::from_millis(BUTTON_DEBOUNCE_MS as u64) {
+            self.changing_since[dial] = None;
+            closed
+        } else {
+            self.pressed[dial]
+        }
+    }
+}
+
+impl Default for SlotSplitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ===================================================================
+// Quadrature Decoding
+// ===================================================================
+
+/// Step for a move between Gray-code states, indexed by
+/// `previous << 2 | current`; 0 for no move or an invalid (skipped) one
+const QUADRATURE_STEPS: [i8; 16] = [0, 1, -1, 0, -1, 0, 0, 1, 1, 0, 0, -1, 0, -1, 1, 0];
+
+/// Decoder for one encoder
+#[derive(Clone, Copy, Default)]
+struct Quadrature {
+    state: u8,
+    /// Transitions since the last whole detent
+    steps: i8,
+}
+
+impl Quadrature {
+    fn read(pins: &QuadraturePins) -> u8 {
+        (pins.0.is_low() as u8) << 1 | pins.1.is_low() as u8
+    }
+
+    /// Feed a new pin state; returns whole detents turned (positive is clockwise)
+    fn update(&mut self, state: u8) -> i8 {
+        let step = QUADRATURE_STEPS[(self.state << 2 | state) as usize];
+        self.state = state;
+        self.steps += step;
+        if self.steps.abs() < ENCODER_STEPS_PER_DETENT {
+            return 0;
+        }
+        let detent = self.steps.signum();
+        self.steps = 0;
+        detent
+    }
+}
+
+/// Poll the encoders' quadrature outputs and send dial turns
+#[embassy_executor::task]
+pub async fn encoder_task() {
+    let pins = QUADRATURE.lock(|q| core::mem::take(&mut *q.borrow_mut()));
+    info!("Encoder task started ({} encoders)", pins.len());
+
+    let mut decoders = [Quadrature::default(); PLUS_DIAL_COUNT];
+    for (decoder, pins) in decoders.iter_mut().zip(pins.iter()) {
+        decoder.state = Quadrature::read(pins);
+    }
+    // Detents not yet delivered, kept when the channel is full
+    let mut turns = [0i8; PLUS_DIAL_COUNT];
+
+    loop {
+        // Stop polling while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
+        for ((decoder, pins), turn) in decoders.iter_mut().zip(pins.iter()).zip(turns.iter_mut()) {
+            *turn = turn.saturating_add(decoder.update(Quadrature::read(pins)));
+        }
+        if turns.iter().any(|&t| t != 0)
+            && ENCODER_CHANNEL.try_send(EncoderEvent::Turn(turns)).is_ok()
+        {
+            turns = [0; PLUS_DIAL_COUNT];
+        }
+
+        Timer::after(Duration::from_micros(ENCODER_POLL_US)).await;
+    }
+}
@@ -0,0 +1,295 @@
+//! Image sinks
+//!
+//! The command router hands every validated host image to an `ImageSink`
+//! instead of straight to the display channel, so a build can send images
+//! to more than one output: the key panel, an LED ring that wants one color
+//! per key, a debug dump. Sinks are chained as nested pairs and run in
+//! order; the set is fixed at compile time by Cargo features:
+//!
+//! | Sink           | Feature          | Output                                   |
+//! |----------------|------------------|------------------------------------------|
+//! | `PanelSink`    | always           | display task (`DISPLAY_CHANNEL`)         |
+//! | `KeyColorSink` | `led-key-colors` | average color per key (`key_color`)      |
+//! | `HexDumpSink`  | `image-hex-dump` | image headers over defmt (`Display` log) |
+//!
+//! Whole images travel in a pooled `ImageSlab` that only one owner can hold,
+//! so sinks get `&mut Option<ImageSlab>`: sinks that only read the image
+//! leave it in place, a sink that keeps it (the panel) takes it. The panel
+//! therefore runs last.
+
+#[cfg(feature = "led-key-colors")]
+use core::cell::Cell;
+#[cfg(feature = "led-key-colors")]
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+
+use crate::channels::{display_attached, send_display, DISPLAY_CHANNEL};
+#[cfg(feature = "led-key-colors")]
+use crate::config::MAX_KEYS;
+use crate::device::DeviceConfig;
+use crate::image_pool::ImageSlab;
+use crate::protocol::image_check::{bmp_pixels, solid_pixel};
+use crate::types::{DisplayCommand, ImageChunk, LcdRegion, RleImage};
+#[cfg(feature = "image-hex-dump")]
+use crate::{log_at, logging::Subsystem};
+
+/// Receiver of host image traffic that passed validation
+pub(crate) trait ImageSink {
+    /// A whole key image. Take it to keep it; leave it for the sinks after.
+    async fn key_image(&mut self, key_id: u8, image: &mut Option<ImageSlab>);
+
+    /// One piece of a key image streamed straight to the panel
+    async fn key_image_chunk(&mut self, key_id: u8, offset: u32, last: bool, data: &ImageChunk);
+
+    /// A whole image for a region of the touch strip LCD
+    async fn lcd_image(&mut self, region: LcdRegion, image: &mut Option<ImageSlab>);
+}
+
+/// No sink; fills the slot of a sink left out of the build
+impl ImageSink for () {
+    async fn key_image(&mut self, _key_id: u8, _image: &mut Option<ImageSlab>) {}
+
+    async fn key_image_chunk(
+        &mut self,
+        _key_id: u8,
+        _offset: u32,
+        _last: bool,
+        _data: &ImageChunk,
+    ) {
+    }
+
+    async fn lcd_image(&mut self, _region: LcdRegion, _image: &mut Option<ImageSlab>) {}
+}
+
+/// Two sinks in a row: `A` sees every image before `B`
+impl<A: ImageSink, B: ImageSink> ImageSink for (A, B) {
+    async fn key_image(&mut self, key_id: u8, image: &mut Option<ImageSlab>) {
+        self.0.key_image(key_id, image).await;
+        self.1.key_image(key_id, image).await;
+    }
+
+    async fn key_image_chunk(&mut self, key_id: u8, offset: u32, last: bool, data: &ImageChunk) {
+        self.0.key_image_chunk(key_id, offset, last, data).await;
+        self.1.key_image_chunk(key_id, offset, last, data).await;
+    }
+
+    async fn lcd_image(&mut self, region: LcdRegion, image: &mut Option<ImageSlab>) {
+        self.0.lcd_image(region, image).await;
+        self.1.lcd_image(region, image).await;
+    }
+}
+
+#[cfg(feature = "led-key-colors")]
+type ColorSink = KeyColorSink;
+#[cfg(not(feature = "led-key-colors"))]
+type ColorSink = ();
+
+#[cfg(feature = "image-hex-dump")]
+type DumpSink = HexDumpSink;
+#[cfg(not(feature = "image-hex-dump"))]
+type DumpSink = ();
+
+/// The sinks enabled in this build, panel last
+pub(crate) fn sinks() -> impl ImageSink {
+    (DumpSink::default(), (ColorSink::default(), PanelSink))
+}
+
+// ===================================================================
+// Panel
+// ===================================================================
+
+/// Key panel and touch strip LCD, through the display task
+pub(crate) struct PanelSink;
+
+impl ImageSink for PanelSink {
+    async fn key_image(&mut self, key_id: u8, image: &mut Option<ImageSlab>) {
+        let Some(data) = image.take() else {
+            return;
+        };
+        clear_waiting_screen().await;
+        // Flat images skip the slab queue and the per-pixel blit: hosts clear
+        // keys with all-black images, and simple icons fit in a few runs
+        let display = crate::config::get_current_device().display_config();
+        let command = match bmp_pixels(&display, &data) {
+            Some(pixels) if solid_pixel(pixels) == Some([0, 0, 0]) => {
+                Some(DisplayCommand::Clear(key_id))
+            }
+            Some(pixels) => {
+                RleImage::encode(pixels).map(|image| DisplayCommand::DrawRle { key_id, image })
+            }
+            None => None,
+        };
+        match command {
+            Some(command) => {
+                drop(data);
+                send_display(command).await;
+            }
+            None => send_display(DisplayCommand::DisplayImage { key_id, data }).await,
+        }
+    }
+
+    async fn key_image_chunk(&mut self, key_id: u8, offset: u32, last: bool, data: &ImageChunk) {
+        if offset == 0 {
+            clear_waiting_screen().await;
+        }
+        send_display(DisplayCommand::DisplayImageChunk {
+            key_id,
+            offset,
+            last,
+            data: data.clone(),
+        })
+        .await;
+    }
+
+    async fn lcd_image(&mut self, region: LcdRegion, image: &mut Option<ImageSlab>) {
+        if let Some(data) = image.take() {
+            send_display(DisplayCommand::DisplayLcdImage { region, data }).await;
+        }
+    }
+}
+
+/// First image from the host replaces the waiting screen; the clear stays in
+/// line behind the splash text, not ahead of it
+async fn clear_waiting_screen() {
+    if crate::splash::host_image_received() && display_attached() {
+        DISPLAY_CHANNEL.send(DisplayCommand::ClearAll).await;
+    }
+}
+
+// ===================================================================
+// Key colors
+// ===================================================================
+
+/// Pixels sampled per image: every Nth pixel is plenty for an average
+#[cfg(feature = "led-key-colors")]
+const COLOR_SAMPLE_STEP: usize = 7;
+
+/// Average color of each key's last BMP image, 0x00RRGGBB
+#[cfg(feature = "led-key-colors")]
+static KEY_COLORS: Mutex<CriticalSectionRawMutex, Cell<[Option<u32>; MAX_KEYS]>> =
+    Mutex::new(Cell::new([None; MAX_KEYS]));
+
+/// Average color of a key's last image as 0x00RRGGBB, for LEDs that follow
+/// the key images. None until a BMP image arrives for the key.
+#[cfg(feature = "led-key-colors")]
+pub fn key_color(key_id: u8) -> Option<u32> {
+    KEY_COLORS.lock(|colors| colors.get().get(key_id as usize).copied().flatten())
+}
+
+/// Averages each whole BMP key image down to one color. JPEG images and
+/// streamed chunks are skipped: neither can be averaged without decoding.
+#[cfg(feature = "led-key-colors")]
+#[derive(Default)]
+pub(crate) struct KeyColorSink;
+
+#[cfg(feature = "led-key-colors")]
+impl ImageSink for KeyColorSink {
+    async fn key_image(&mut self, key_id: u8, image: &mut Option<ImageSlab>) {
+        let Some(color) = image.as_deref().and_then(average_bmp_color) else {
+            return;
+        };
+        KEY_COLORS.lock(|colors| {
+            let mut table = colors.get();
+            if let Some(slot) = table.get_mut(key_id as usize) {
+                *slot = Some(color);
+            }
+            colors.set(table);
+        });
+    }
+
+    async fn key_image_chunk(
+        &mut self,
+        _key_id: u8,
+        _offset: u32,
+        _last: bool,
+        _data: &ImageChunk,
+    ) {
+    }
+
+    async fn lcd_image(&mut self, _region: LcdRegion, _image: &mut Option<ImageSlab>) {}
+}
+
+/// Average of a 24-bit BMP's pixels (already validated), 0x00RRGGBB
+#[cfg(feature = "led-key-colors")]
+fn average_bmp_color(data: &[u8]) -> Option<u32> {
+    if !data.starts_with(b"BM") || data.len() < 14 {
+        return None;
+    }
+    let pixel_offset = u32::from_le_bytes([data[10], data[11], data[12], data[13]]) as usize;
+    // Row padding is sampled too; at most 3 bytes a row barely moves the mean
+    let pixels = data.get(pixel_offset..)?.chunks_exact(3);
+    let (mut b, mut g, mut r, mut count) = (0u32, 0u32, 0u32, 0u32);
+    for pixel in pixels.step_by(COLOR_SAMPLE_STEP) {
+        b += pixel[0] as u32;
+        g += pixel[1] as u32;
+        r += pixel[2] as u32;
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    Some((r / count) << 16 | (g / count) << 8 | (b / count))
+}
+
+// ===================================================================
+// Hex dump
+// ===================================================================
+
+/// Bytes of each image shown in the dump
+#[cfg(feature = "image-hex-dump")]
+const HEX_DUMP_BYTES: usize = 32;
+
+/// Logs the size and first bytes of every image, for debugging host
+/// software. Logs through the `Display` subsystem at debug level.
+#[cfg(feature = "image-hex-dump")]
+#[derive(Default)]
+pub(crate) struct HexDumpSink;
+
+#[cfg(feature = "image-hex-dump")]
+impl HexDumpSink {
+    fn dump(kind: &str, data: &[u8]) {
+        let head = &data[..data.len().min(HEX_DUMP_BYTES)];
+        log_at!(debug, Subsystem::Display, "  {} {=[u8]:02X}", kind, head);
+    }
+}
+
+#[cfg(feature = "image-hex-dump")]
+impl ImageSink for HexDumpSink {
+    async fn key_image(&mut self, key_id: u8, image: &mut Option<ImageSlab>) {
+        if let Some(data) = image.as_deref() {
+            log_at!(
+                debug,
+                Subsystem::Display,
+                "Image for key {}: {} bytes",
+                key_id,
+                data.len()
+            );
+            Self::dump("image", data);
+        }
+    }
+
+    async fn key_image_chunk(&mut self, key_id: u8, offset: u32, last: bool, data: &ImageChunk) {
+        log_at!(
+            debug,
+            Subsystem::Display,
+            "Image chunk for key {} at {}: {} bytes{}",
+            key_id,
+            offset,
+            data.len(),
+            if last { " (last)" } else { "" }
+        );
+        Self::dump("chunk", data);
+    }
+
+    async fn lcd_image(&mut self, region: LcdRegion, image: &mut Option<ImageSlab>) {
+        if let Some(data) = image.as_deref() {
+            log_at!(
+                debug,
+                Subsystem::Display,
+                "LCD image for {}: {} bytes",
+                region,
+                data.len()
+            );
+            Self::dump("lcd", data);
+        }
+    }
+}
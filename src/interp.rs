@@ -0,0 +1,221 @@
+//! Pixel work on the SIO interpolators
+//!
+//! Each RP2040 core has two interpolators in its SIO block: single-cycle
+//! shift, mask and add units read and written like registers. With
+//! `interp-convert` the display task hands them the two per-pixel jobs of
+//! drawing a key image:
+//!
+//! - RGB888 to RGB565. Gamma and brightness are folded into three 256-entry
+//!   RGB565 tables (`PixelLut`), rebuilt only when the brightness scale or
+//!   gamma curve changes. Interpolator 0 turns one accumulator write into
+//!   the red and green table addresses (lane 1 reads lane 0's accumulator).
+//! - Nearest-neighbour scaling. Interpolator 1 steps a 16.16 source position
+//!   by a fixed increment and returns the source pixel index on every pop.
+//!   The e-paper panel uses it to fit key images into its cells; the TFT
+//!   only scales icons by whole numbers, which is plain pixel repetition.
+//!
+//! The interpolators belong to the core that touches them and nothing else
+//! in the firmware uses them, so the display task configures its core's
+//! pair once with `Interp::claim`. `benchmark` then times both jobs against
+//! the scalar code and records the per-pixel cost in `stats`
+//! (`VENDOR_CMD_GET_CONVERSION_BENCH`).
+
+use core::hint::black_box;
+
+use embassy_rp::pac;
+use embassy_time::Instant;
+
+use crate::{brightness, gamma};
+
+/// RGB565 field masks for 8-bit channels
+const RED_MASK: u8 = 0xF8;
+const GREEN_MASK: u8 = 0xFC;
+
+/// Pixels converted or scaled per benchmark pass
+const BENCH_PIXELS: usize = 4096;
+
+/// Source and destination widths of the scaling benchmark (72 px image into a 50 px cell)
+const BENCH_SCALE: (usize, usize) = (72, 50);
+
+/// Gamma-corrected, dimmed RGB565 fields for every channel value
+pub struct PixelLut {
+    red: [u16; 256],
+    green: [u16; 256],
+    blue: [u16; 256],
+    /// Brightness scale and gamma generation the tables were built for
+    built_for: Option<(u8, u8)>,
+}
+
+impl PixelLut {
+    pub const fn new() -> Self {
+        Self {
+            red: [0; 256],
+            green: [0; 256],
+            blue: [0; 256],
+            built_for: None,
+        }
+    }
+
+    /// Rebuild the tables if `scale` or the gamma curve changed since the last call
+    pub fn prepare(&mut self, scale: u8) {
+        let key = (scale, gamma::generation());
+        if self.built_for == Some(key) {
+            return;
+        }
+        for value in 0..=255u8 {
+            let level = brightness::scale_channel(gamma::correct(value), scale);
+            self.red[value as usize] = ((level & RED_MASK) as u16) << 8;
+            self.green[value as usize] = ((level & GREEN_MASK) as u16) << 3;
+            self.blue[value as usize] = (level >> 3) as u16;
+        }
+        self.built_for = Some(key);
+    }
+}
+
+impl Default for PixelLut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The calling core's interpolator pair, configured for pixel work
+pub struct Interp(());
+
+impl Interp {
+    /// Configure this core's interpolators. Only the core that calls this
+    /// may use the returned handle.
+    pub fn claim() -> Self {
+        let convert = pac::SIO.interp(0);
+        // The accumulator holds the pixel shifted left by one, so each 8-bit
+        // channel lands pre-multiplied by the u16 table stride
+        convert.ctrl_lane0().write(|w| {
+            w.set_shift(0);
+            w.set_mask_lsb(1);
+            w.set_mask_msb(8);
+        });
+        convert.ctrl_lane1().write(|w| {
+            w.set_cross_input(true);
+            w.set_shift(8);
+            w.set_mask_lsb(1);
+            w.set_mask_msb(8);
+        });
+
+        let scale = pac::SIO.interp(1);
+        // Lane 0 adds the raw step on every pop; the full result is the
+        // integer part of the position. Lane 1 stays zero.
+        scale.ctrl_lane0().write(|w| {
+            w.set_add_raw(true);
+            w.set_shift(16);
+            w.set_mask_lsb(0);
+            w.set_mask_msb(15);
+        });
+        scale.ctrl_lane1().write(|w| {
+            w.set_mask_lsb(0);
+            w.set_mask_msb(0);
+        });
+        scale.accum1().write_value(0);
+        scale.base1().write_value(0);
+        scale.base2().write_value(0);
+        Self(())
+    }
+
+    /// Point the red and green lanes at `lut`; call after `PixelLut::prepare`
+    /// and before converting with it
+    pub fn load(&self, lut: &PixelLut) {
+        let convert = pac::SIO.interp(0);
+        convert.base0().write_value(lut.red.as_ptr() as u32);
+        convert.base1().write_value(lut.green.as_ptr() as u32);
+    }
+
+    /// Convert one RGB888 pixel through the tables last passed to `load`
+    #[inline(always)]
+    pub fn rgb565(&self, lut: &PixelLut, pixel: [u8; 3]) -> u16 {
+        let convert = pac::SIO.interp(0);
+        let packed = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], 0]);
+        convert.accum0().write_value(packed << 1);
+        // SAFETY: base0/base1 point at `lut`'s red and green tables (`load`)
+        // and the lanes add an even offset of at most 510 bytes
+        let (red, green) = unsafe {
+            (
+                *(convert.peek_lane0().read() as *const u16),
+                *(convert.peek_lane1().read() as *const u16),
+            )
+        };
+        red | green | lut.blue[pixel[2] as usize]
+    }
+
+    /// Source indices of a `size`-wide row sampled from a `source`-wide one,
+    /// nearest neighbour. Starting another row restarts the stepper.
+    pub fn nearest(&self, source: usize, size: usize) -> Nearest {
+        let scale = pac::SIO.interp(1);
+        scale.accum0().write_value(0);
+        scale
+            .base0()
+            .write_value(((source as u32) << 16) / size.max(1) as u32);
+        Nearest { remaining: size }
+    }
+}
+
+/// Source pixel indices popped from interpolator 1 (`Interp::nearest`)
+pub struct Nearest {
+    remaining: usize,
+}
+
+impl Iterator for Nearest {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(pac::SIO.interp(1).pop_full().read() as usize)
+    }
+}
+
+/// Time scalar and interpolator conversion and scaling over a synthetic key
+/// and record the per-pixel cost. `scalar` is the display's scalar converter.
+pub fn benchmark(interp: &Interp, scalar: impl Fn(u8, [u8; 3]) -> u16) {
+    let mut lut = PixelLut::new();
+    let pixel = |i: usize| [i as u8, (i >> 2) as u8, (i >> 4) as u8];
+
+    let started = Instant::now();
+    for i in 0..BENCH_PIXELS {
+        black_box(scalar(255, pixel(i)));
+    }
+    let convert_scalar = started.elapsed();
+
+    let started = Instant::now();
+    lut.prepare(255);
+    interp.load(&lut);
+    for i in 0..BENCH_PIXELS {
+        black_box(interp.rgb565(&lut, pixel(i)));
+    }
+    let convert_interp = started.elapsed();
+
+    let (source, size) = black_box(BENCH_SCALE);
+    let started = Instant::now();
+    for _ in 0..BENCH_PIXELS / size {
+        for dx in 0..size {
+            black_box(dx * source / size);
+        }
+    }
+    let scale_scalar = started.elapsed();
+
+    let started = Instant::now();
+    for _ in 0..BENCH_PIXELS / size {
+        for sx in interp.nearest(source, size) {
+            black_box(sx);
+        }
+    }
+    let scale_interp = started.elapsed();
+
+    let per_pixel = |us: u64, pixels: usize| (us * 1000 / pixels as u64) as u16;
+    let scaled = BENCH_PIXELS / size * size;
+    crate::stats::conversion_benchmarked(crate::stats::ConversionBench {
+        convert_scalar_ns: per_pixel(convert_scalar.as_micros(), BENCH_PIXELS),
+        convert_interp_ns: per_pixel(convert_interp.as_micros(), BENCH_PIXELS),
+        scale_scalar_ns: per_pixel(scale_scalar.as_micros(), scaled),
+        scale_interp_ns: per_pixel(scale_interp.as_micros(), scaled),
+    });
+}
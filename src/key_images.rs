@@ -0,0 +1,101 @@
+//! Locally stored key images (icon + pressed icon)
+//!
+//! A companion tool can store two images per key in flash through the vendor
+//! report: the icon and a pressed variant. For keys that have both, the
+//! firmware draws the pressed image the moment the key goes down and the icon
+//! when it comes back up, without waiting for a host round-trip. Host images
+//! still draw as usual; they stay until the key is next pressed.
+//!
+//! Images are written in pieces into an erased slot and only become visible
+//! once committed, when a header with the image length is written in front
+//! of them. The image must pass the same checks as host images.
+
+use crate::config::{LOCAL_IMAGE_KEYS, MAX_IMAGE_SIZE};
+use crate::device::DeviceConfig;
+use crate::protocol::image_check::validate_key_image;
+use crate::storage::{self, KEY_IMAGE_HEADER_SIZE};
+use crate::types::KeyImageVariant;
+
+/// Marks a committed slot
+const HEADER_MAGIC: [u8; 2] = *b"KI";
+
+/// Sectors to erase to drop a slot's header
+const HEADER_SECTORS: usize = 1;
+
+/// Why an image could not be stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum StoreError {
+    /// Key, offset or length out of range, or the image failed validation
+    Invalid,
+    /// Flash could not be erased or written
+    Storage,
+}
+
+fn slot(key_id: u8, variant: KeyImageVariant) -> Result<usize, StoreError> {
+    if key_id as usize >= LOCAL_IMAGE_KEYS {
+        return Err(StoreError::Invalid);
+    }
+    Ok(key_id as usize * 2 + variant as usize)
+}
+
+/// Erase a key's slot for a new image
+pub fn begin(key_id: u8, variant: KeyImageVariant) -> Result<(), StoreError> {
+    storage::erase_key_image(slot(key_id, variant)?, None).map_err(|_| StoreError::Storage)
+}
+
+/// Write part of an image, `offset` bytes into it
+pub fn write(
+    key_id: u8,
+    variant: KeyImageVariant,
+    offset: usize,
+    data: &[u8],
+) -> Result<(), StoreError> {
+    let slot = slot(key_id, variant)?;
+    if offset + data.len() > MAX_IMAGE_SIZE {
+        return Err(StoreError::Invalid);
+    }
+    storage::write_key_image(slot, KEY_IMAGE_HEADER_SIZE + offset, data)
+        .map_err(|_| StoreError::Storage)
+}
+
+/// Validate the first `len` written bytes and make them the key's image
+pub fn commit(key_id: u8, variant: KeyImageVariant, len: usize) -> Result<(), StoreError> {
+    let slot = slot(key_id, variant)?;
+    let data = storage::key_image_slot(slot).ok_or(StoreError::Invalid)?;
+    if len > MAX_IMAGE_SIZE {
+        return Err(StoreError::Invalid);
+    }
+    let image = &data[KEY_IMAGE_HEADER_SIZE..KEY_IMAGE_HEADER_SIZE + len];
+    let display = crate::config::get_current_device().display_config();
+    validate_key_image(&display, image).map_err(|_| StoreError::Invalid)?;
+
+    let mut header = [0u8; KEY_IMAGE_HEADER_SIZE];
+    header[0..2].copy_from_slice(&HEADER_MAGIC);
+    header[2..6].copy_from_slice(&(len as u32).to_le_bytes());
+    storage::write_key_image(slot, 0, &header).map_err(|_| StoreError::Storage)
+}
+
+/// Remove both of a key's images
+pub fn clear(key_id: u8) -> Result<(), StoreError> {
+    for variant in [KeyImageVariant::Icon, KeyImageVariant::Pressed] {
+        storage::erase_key_image(slot(key_id, variant)?, Some(HEADER_SECTORS))
+            .map_err(|_| StoreError::Storage)?;
+    }
+    Ok(())
+}
+
+/// A key's committed image, if it has one
+pub fn image(key_id: u8, variant: KeyImageVariant) -> Option<&'static [u8]> {
+    let data = storage::key_image_slot(slot(key_id, variant).ok()?)?;
+    if data[0..2] != HEADER_MAGIC {
+        return None;
+    }
+    let len = u32::from_le_bytes([data[2], data[3], data[4], data[5]]) as usize;
+    data.get(KEY_IMAGE_HEADER_SIZE..KEY_IMAGE_HEADER_SIZE + len.min(MAX_IMAGE_SIZE))
+}
+
+/// Whether a key has both images, so presses should swap between them
+pub fn has_pair(key_id: u8) -> bool {
+    image(key_id, KeyImageVariant::Icon).is_some()
+        && image(key_id, KeyImageVariant::Pressed).is_some()
+}
@@ -0,0 +1,76 @@
+//! On-screen keypad layout for touchscreen builds
+//!
+//! A touch build has no key switches, so the TFT has to look like the deck.
+//! The panel (`KEYPAD_PANEL_WIDTH` x `KEYPAD_PANEL_HEIGHT`) is split into
+//! one cell per key in the device's grid. The host's icons are scaled up by
+//! the largest whole factor that fits a cell and centered in it; the gap
+//! left around them holds the key outline and the press animation, so
+//! neither covers the icon. Touches are resolved against the same cells.
+
+use crate::config::{
+    key_image_size, streamdeck_cols, streamdeck_keys, streamdeck_rows, KEYPAD_PANEL_HEIGHT,
+    KEYPAD_PANEL_WIDTH,
+};
+
+/// Inclusive window on the panel: (x_start, y_start, x_end, y_end)
+pub type Region = (u16, u16, u16, u16);
+
+/// Width and height of one key cell
+fn cell_size() -> (u16, u16) {
+    let cols = streamdeck_cols().max(1) as u16;
+    let rows = streamdeck_rows().max(1) as u16;
+    (KEYPAD_PANEL_WIDTH / cols, KEYPAD_PANEL_HEIGHT / rows)
+}
+
+/// Whole-number factor icons are drawn at (at least 1)
+pub fn icon_scale() -> usize {
+    let (width, height) = cell_size();
+    (width.min(height) as usize / key_image_size().max(1)).max(1)
+}
+
+/// Side of a drawn icon in panel pixels
+fn icon_side() -> u16 {
+    (key_image_size() * icon_scale()) as u16
+}
+
+/// Space between a cell's edge and its icon, on the narrower side
+pub fn gap() -> u16 {
+    let (width, height) = cell_size();
+    width.min(height).saturating_sub(icon_side()) / 2
+}
+
+/// A key's whole cell, or None for a key outside the grid
+pub fn cell(key_id: u8) -> Option<Region> {
+    if key_id as usize >= streamdeck_keys() {
+        return None;
+    }
+    let cols = streamdeck_cols();
+    let (width, height) = cell_size();
+    let x = (key_id as usize % cols) as u16 * width;
+    let y = (key_id as usize / cols) as u16 * height;
+    Some((x, y, x + width - 1, y + height - 1))
+}
+
+/// Where a key's icon is drawn: centered in its cell
+pub fn icon_region(key_id: u8) -> Option<Region> {
+    let (x0, y0, x1, y1) = cell(key_id)?;
+    let side = icon_side();
+    let x = x0 + (x1 - x0 + 1).saturating_sub(side) / 2;
+    let y = y0 + (y1 - y0 + 1).saturating_sub(side) / 2;
+    Some((x, y, x + side - 1, y + side - 1))
+}
+
+/// Key whose cell holds a panel point at least `margin` pixels from the
+/// cell's edge
+pub fn key_at(x: u16, y: u16, margin: u16) -> Option<usize> {
+    let (width, height) = cell_size();
+    let (col, row) = ((x / width) as usize, (y / height) as usize);
+    let (inside_x, inside_y) = (x % width, y % height);
+    let margin = margin.min(width.min(height) / 4);
+    let in_grid = col < streamdeck_cols() && row < streamdeck_rows();
+    let off_edge = inside_x >= margin
+        && inside_x < width - margin
+        && inside_y >= margin
+        && inside_y < height - margin;
+    (in_grid && off_edge).then_some(row * streamdeck_cols() + col)
+}
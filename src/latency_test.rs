@@ -0,0 +1,227 @@
+//! Key-to-report latency self-test (`latency-test`)
+//!
+//! Checks the input path against the 1000 Hz polling claim without a logic
+//! analyzer. A jumper from `LATENCY_TEST_PIN` to a key input lets the
+//! firmware press that key itself: each sample pulls the pin low and times
+//! until the key report carrying the press has been handed to the USB
+//! controller. Scanning, debounce, remapping, gestures and the report queue
+//! all count; the host's next poll of the interrupt endpoint adds at most
+//! another `bInterval` (1 ms) on top.
+//!
+//! The pin is released between samples for `LATENCY_TEST_GAP_MS` plus a
+//! different fraction of a millisecond each time, so the presses land at
+//! every phase of the scan and poll cycles. A sample without a report within
+//! `LATENCY_TEST_TIMEOUT_MS` counts as missed.
+//!
+//! Wiring: the pin only ever pulls low (it floats when released), so it can
+//! go straight onto an active-low direct key input. On a matrix column it
+//! presses every key of that column. The host software sees the presses, and
+//! key reports only go out while it owns the keys: in standalone mode, the
+//! self-test or the rollover test every sample is missed.
+//!
+//! Started through the vendor report (`VENDOR_CMD_START_LATENCY_TEST`); the
+//! result is read back the same way and logged over defmt when the run ends.
+//! GPIO1 is one of the parallel display's data lines and the expansion bus
+//! clock, so builds with `parallel-display` or `i2c-expansion` do not
+//! compile with it (see the feature checks in `hardware`).
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, Ordering};
+use defmt::*;
+use embassy_rp::gpio::{Flex, Pull};
+use embassy_rp::peripherals::PIN_1;
+use embassy_rp::Peri;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+
+use crate::config::{LATENCY_TEST_GAP_MS, LATENCY_TEST_PIN, LATENCY_TEST_TIMEOUT_MS};
+
+/// Phase step between samples; co-prime with 1000 so a run of samples
+/// spreads over the whole millisecond
+const PHASE_STEP_US: u64 = 137;
+
+/// Result of the current or last latency test
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+pub struct LatencyResult {
+    pub active: bool,
+    /// Samples taken so far, missed ones included
+    pub samples: u8,
+    /// Samples without a key report within `LATENCY_TEST_TIMEOUT_MS`
+    pub missed: u8,
+    pub min_us: u32,
+    pub max_us: u32,
+    /// Sum over the reported samples, for the average
+    total_us: u64,
+}
+
+impl LatencyResult {
+    const fn new() -> Self {
+        Self {
+            active: false,
+            samples: 0,
+            missed: 0,
+            min_us: 0,
+            max_us: 0,
+            total_us: 0,
+        }
+    }
+
+    /// Average over the samples that got a report
+    pub fn avg_us(&self) -> u32 {
+        let reported = self.samples - self.missed;
+        if reported == 0 {
+            return 0;
+        }
+        (self.total_us / reported as u64) as u32
+    }
+
+    fn record(&mut self, latency: Option<Duration>) {
+        self.samples = self.samples.saturating_add(1);
+        let Some(latency) = latency else {
+            self.missed = self.missed.saturating_add(1);
+            return;
+        };
+        let us = latency.as_micros().min(u32::MAX as u64) as u32;
+        if self.samples - self.missed == 1 {
+            self.min_us = us;
+        }
+        self.min_us = self.min_us.min(us);
+        self.max_us = self.max_us.max(us);
+        self.total_us += us as u64;
+    }
+
+    /// Serialize for the vendor report (little-endian)
+    pub fn to_bytes(&self) -> [u8; 15] {
+        let mut out = [0u8; 15];
+        out[0] = self.active as u8;
+        out[1] = self.samples;
+        out[2] = self.missed;
+        out[3..7].copy_from_slice(&self.min_us.to_le_bytes());
+        out[7..11].copy_from_slice(&self.avg_us().to_le_bytes());
+        out[11..15].copy_from_slice(&self.max_us.to_le_bytes());
+        out
+    }
+}
+
+/// Set while a press is waiting for its report
+static ARMED: AtomicBool = AtomicBool::new(false);
+/// When the key report of the current press went out
+static REPORTED: Signal<CriticalSectionRawMutex, Instant> = Signal::new();
+/// Requested sample count, 0 to stop
+static START: Signal<CriticalSectionRawMutex, u8> = Signal::new();
+static RESULT: Mutex<CriticalSectionRawMutex, Cell<LatencyResult>> =
+    Mutex::new(Cell::new(LatencyResult::new()));
+static PIN: Mutex<CriticalSectionRawMutex, RefCell<Option<Peri<'static, PIN_1>>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Take ownership of the test pin.
+/// Must be called before the latency test task is spawned.
+pub fn init(pin: Peri<'static, PIN_1>) {
+    PIN.lock(|p| p.replace(Some(pin)));
+}
+
+/// Start a test of `samples` presses, or stop the running one with 0.
+/// Starting again while a test runs ends it and starts over.
+pub fn start(samples: u8) {
+    START.signal(samples);
+}
+
+/// The current or last result
+pub fn result() -> LatencyResult {
+    RESULT.lock(|r| r.get())
+}
+
+/// A key report with a key pressed was handed to the USB controller
+pub fn report_sent() {
+    if ARMED.load(Ordering::Relaxed) {
+        REPORTED.signal(Instant::now());
+    }
+}
+
+/// Press the jumpered key once; the time to its key report, if one came
+async fn press(pin: &mut Flex<'static>) -> Option<Duration> {
+    REPORTED.reset();
+    pin.set_as_output();
+    let pressed_at = Instant::now();
+    ARMED.store(true, Ordering::Relaxed);
+    let reported = with_timeout(
+        Duration::from_millis(LATENCY_TEST_TIMEOUT_MS),
+        REPORTED.wait(),
+    )
+    .await;
+    ARMED.store(false, Ordering::Relaxed);
+    pin.set_as_input();
+    reported
+        .ok()
+        .map(|at| at.saturating_duration_since(pressed_at))
+}
+
+fn finish() {
+    let result = RESULT.lock(|r| {
+        let mut result = r.get();
+        result.active = false;
+        r.set(result);
+        result
+    });
+    info!("=== LATENCY TEST RESULT ===");
+    info!("Samples: {} ({} missed)", result.samples, result.missed);
+    info!(
+        "Key to report: min {} us, avg {} us, max {} us",
+        result.min_us,
+        result.avg_us(),
+        result.max_us
+    );
+}
+
+/// Run latency tests as they are requested; never returns once started
+#[embassy_executor::task]
+pub async fn latency_test_task() {
+    let Some(pin) = PIN.lock(|p| p.take()) else {
+        error!("Latency test pin not initialized");
+        return;
+    };
+    let mut pin = Flex::new(pin);
+    pin.set_pull(Pull::None);
+    // Only ever driven low: released is floating, not high
+    pin.set_low();
+    pin.set_as_input();
+
+    let mut request = None;
+    loop {
+        let samples = match request.take() {
+            Some(samples) => samples,
+            None => START.wait().await,
+        };
+        if samples == 0 {
+            continue;
+        }
+        info!(
+            "Latency test: {} presses on GPIO{}",
+            samples, LATENCY_TEST_PIN
+        );
+        RESULT.lock(|r| {
+            r.set(LatencyResult {
+                active: true,
+                ..LatencyResult::new()
+            })
+        });
+
+        for sample in 0..samples as u64 {
+            let phase = Duration::from_micros(sample * PHASE_STEP_US % 1000);
+            Timer::after(Duration::from_millis(LATENCY_TEST_GAP_MS) + phase).await;
+            if let Some(next) = START.try_take() {
+                request = Some(next);
+                break;
+            }
+            let latency = press(&mut pin).await;
+            RESULT.lock(|r| {
+                let mut result = r.get();
+                result.record(latency);
+                r.set(result);
+            });
+        }
+        finish();
+    }
+}
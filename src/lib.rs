@@ -9,12 +9,16 @@
 //! - StreamDeck Original V2 (15 keys, 72x72px, JPEG)
 //! - StreamDeck XL (32 keys, 96x96px, JPEG)
 //! - StreamDeck Plus (8 keys, 120x120px, JPEG)
+//! - StreamDeck Studio (32 keys, 144x112px, JPEG, 2 dials)
 //!
 //! ## Architecture
-//! - **Multi-core**: USB/Protocol on Core 0, Display/Buttons on Core 1
+//! - **Multi-core**: USB/Protocol on Core 0, Display/Buttons on Core 1, or
+//!   everything on Core 0 (`runtime::run_single_core`)
 //! - **Async**: Embassy framework with async/await
 //! - **Channels**: Lock-free inter-task communication
 //! - **Device Abstraction**: Compile-time device selection and configuration
+//! - **Protocol crate**: device tables and HID report parsing are in
+//!   `productiondeck-protocol`, which builds for the host too
 
 #![no_std]
 
@@ -22,17 +26,75 @@ use embassy_rp::usb::InterruptHandler;
 use embassy_rp::{bind_interrupts, peripherals};
 
 // Export all modules for use by device-specific binaries
+pub mod analog;
+#[cfg(feature = "config-drive")]
+pub mod animations;
+pub mod brightness;
 pub mod buttons;
 pub mod channels;
+pub mod compat;
 pub mod config;
-pub mod device;
+#[cfg(feature = "config-drive")]
+pub mod config_drive;
+pub mod crc;
 pub mod display;
+pub mod effects;
+#[cfg(feature = "epaper-display")]
+pub mod epaper;
+pub mod error;
+#[cfg(feature = "i2c-expansion")]
+pub mod expansion;
+#[cfg(feature = "firmware-update")]
+pub mod firmware_update;
+pub mod gamma;
+pub mod gestures;
+pub mod gfx;
 pub mod hardware;
+pub mod host;
+#[cfg(feature = "hybrid-dials")]
+pub mod hybrid;
+pub mod image_sink;
+#[cfg(feature = "interp-convert")]
+pub mod interp;
+pub mod key_images;
+#[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+pub mod keypad;
+#[cfg(feature = "latency-test")]
+pub mod latency_test;
+pub mod log_backend;
+pub mod logging;
+pub mod memory;
+pub mod night;
+pub mod pages;
+#[cfg(feature = "parallel-display")]
+pub mod parallel_bus;
+#[cfg(feature = "pio-matrix")]
+pub mod pio_matrix;
+pub mod power;
 pub mod protocol;
+pub mod rollover;
+pub mod runtime;
+#[cfg(feature = "i2c-satellite")]
+pub mod satellite;
+#[cfg(feature = "secondary-deck")]
+pub mod secondary_deck;
+pub mod selftest;
+pub mod settings;
+pub mod splash;
+pub mod standalone;
+pub mod stats;
+pub mod status_led;
+pub mod storage;
 pub mod supervisor;
+#[cfg(any(feature = "touch-xpt2046", feature = "touch-ft6236"))]
+pub mod touch;
 pub mod types;
 pub mod usb;
 
+// Device tables and the key image pool live in the protocol crate, which
+// also builds for the host (see protocol/)
+pub use productiondeck_protocol::{device, image_pool};
+
 // USB interrupt binding - shared by all binaries
 bind_interrupts!(pub struct Irqs {
     USBCTRL_IRQ => InterruptHandler<peripherals::USB>;
@@ -0,0 +1,137 @@
+//! defmt log backend
+//!
+//! Where defmt frames go is picked by Cargo feature, so the binaries don't
+//! name a transport themselves:
+//!
+//! | Feature              | Backend                                                  |
+//! |----------------------|----------------------------------------------------------|
+//! | `log-rtt` (default)  | `defmt-rtt`, read with a debug probe                     |
+//! | `log-buffer`         | RAM ring read back through the vendor report (no probe)  |
+//! | neither              | frames are discarded                                     |
+//!
+//! `log-buffer` wins when both are enabled. The buffer holds raw defmt
+//! frames; the host reads them with `VENDOR_CMD_READ_LOG` and decodes them
+//! against the firmware ELF (e.g. piping them into `defmt-print -e`).
+//!
+//! A release image without a probe can drop the log call sites and their
+//! strings altogether by building with `DEFMT_LOG=off` and neither feature.
+
+#[cfg(all(feature = "log-rtt", not(feature = "log-buffer")))]
+use defmt_rtt as _;
+
+/// Read buffered log bytes starting at stream position `from` into `out`.
+/// Returns the position of the first byte copied (later than `from` if
+/// older bytes were overwritten) and the count, or None when this build
+/// does not buffer its log.
+pub fn read(from: u32, out: &mut [u8]) -> Option<(u32, usize)> {
+    #[cfg(feature = "log-buffer")]
+    return Some(buffer::read(from, out));
+    #[cfg(not(feature = "log-buffer"))]
+    {
+        let _ = (from, out);
+        None
+    }
+}
+
+#[cfg(feature = "log-buffer")]
+mod buffer {
+    use core::cell::UnsafeCell;
+    use portable_atomic::{AtomicBool, Ordering};
+
+    use crate::config::LOG_BUFFER_SIZE;
+
+    /// Ring of encoded frames and the total bytes ever written; the ring
+    /// holds stream positions `total - LOG_BUFFER_SIZE .. total`
+    struct Ring {
+        data: [u8; LOG_BUFFER_SIZE],
+        total: u32,
+    }
+
+    struct Shared<T>(UnsafeCell<T>);
+
+    // SAFETY: only accessed inside a critical section, taken in `acquire`
+    // (held until `release`) or in `read`.
+    unsafe impl<T> Sync for Shared<T> {}
+
+    static RING: Shared<Ring> = Shared(UnsafeCell::new(Ring {
+        data: [0; LOG_BUFFER_SIZE],
+        total: 0,
+    }));
+    static ENCODER: Shared<defmt::Encoder> = Shared(UnsafeCell::new(defmt::Encoder::new()));
+    static RESTORE: Shared<critical_section::RestoreState> =
+        Shared(UnsafeCell::new(critical_section::RestoreState::invalid()));
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+
+    fn push(bytes: &[u8]) {
+        // SAFETY: called by the encoder between `acquire` and `release`
+        let ring = unsafe { &mut *RING.0.get() };
+        for &byte in bytes {
+            ring.data[ring.total as usize % LOG_BUFFER_SIZE] = byte;
+            ring.total = ring.total.wrapping_add(1);
+        }
+    }
+
+    #[defmt::global_logger]
+    struct BufferLogger;
+
+    // SAFETY: frames are written inside one critical section, so they can't
+    // interleave across tasks, interrupts or cores
+    unsafe impl defmt::Logger for BufferLogger {
+        fn acquire() {
+            // SAFETY: released in `release`
+            let restore = unsafe { critical_section::acquire() };
+            if TAKEN.load(Ordering::Relaxed) {
+                panic!("defmt logger taken reentrantly");
+            }
+            TAKEN.store(true, Ordering::Relaxed);
+            // SAFETY: inside the critical section just taken
+            unsafe {
+                *RESTORE.0.get() = restore;
+                (*ENCODER.0.get()).start_frame(push);
+            }
+        }
+
+        unsafe fn flush() {}
+
+        unsafe fn release() {
+            (*ENCODER.0.get()).end_frame(push);
+            TAKEN.store(false, Ordering::Relaxed);
+            critical_section::release(*RESTORE.0.get());
+        }
+
+        unsafe fn write(bytes: &[u8]) {
+            (*ENCODER.0.get()).write(bytes, push);
+        }
+    }
+
+    pub(super) fn read(from: u32, out: &mut [u8]) -> (u32, usize) {
+        critical_section::with(|_| {
+            // SAFETY: inside a critical section, so no frame is being written
+            let ring = unsafe { &*RING.0.get() };
+            let oldest = ring.total.saturating_sub(LOG_BUFFER_SIZE as u32);
+            let start = from.clamp(oldest, ring.total);
+            let count = ((ring.total - start) as usize).min(out.len());
+            for (i, slot) in out[..count].iter_mut().enumerate() {
+                *slot = ring.data[(start as usize + i) % LOG_BUFFER_SIZE];
+            }
+            (start, count)
+        })
+    }
+}
+
+/// Discards frames; defmt still needs a logger to link
+#[cfg(not(any(feature = "log-rtt", feature = "log-buffer")))]
+#[defmt::global_logger]
+struct NullLogger;
+
+#[cfg(not(any(feature = "log-rtt", feature = "log-buffer")))]
+// SAFETY: does nothing
+unsafe impl defmt::Logger for NullLogger {
+    fn acquire() {}
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {}
+
+    unsafe fn write(_bytes: &[u8]) {}
+}
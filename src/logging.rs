@@ -0,0 +1,160 @@
+//! Runtime log filtering
+//!
+//! defmt filters at compile time (`DEFMT_LOG`); this adds a per-subsystem
+//! level on top that can be changed at runtime through the vendor report and
+//! is persisted in settings. Hot paths (image traffic, HID reports) log
+//! through `log_at!` / `log_limited!` so they can be silenced or throttled
+//! without rebuilding.
+//!
+//! ```ignore
+//! log_at!(debug, Subsystem::Usb, "Output report: {} bytes", len);
+//! log_limited!(info, Subsystem::Usb, "Image complete for key {}", key_id);
+//! ```
+
+use core::sync::atomic::AtomicU8;
+// portable-atomic: thumbv6m has no native swap/fetch_add
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::config::LOG_RATE_LIMIT_MS;
+use crate::settings;
+
+/// Subsystems with their own log level
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum Subsystem {
+    Usb = 0,
+    Protocol = 1,
+    Display = 2,
+    Input = 3,
+    Power = 4,
+}
+
+/// Number of subsystems (size of the level table)
+pub const SUBSYSTEM_COUNT: usize = 5;
+
+/// Runtime log level; messages above a subsystem's level are dropped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, defmt::Format)]
+#[repr(u8)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => LogLevel::Off,
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            5 => LogLevel::Trace,
+            _ => return None,
+        })
+    }
+}
+
+/// Level of every subsystem at boot
+pub const DEFAULT_LOG_LEVELS: [LogLevel; SUBSYSTEM_COUNT] = [LogLevel::Info; SUBSYSTEM_COUNT];
+
+/// Copy of the levels in settings, read without a critical section on every log call
+static LEVELS: [AtomicU8; SUBSYSTEM_COUNT] =
+    [const { AtomicU8::new(LogLevel::Info as u8) }; SUBSYSTEM_COUNT];
+
+/// Whether a message at `level` from `subsystem` should be logged
+#[inline]
+pub fn enabled(subsystem: Subsystem, level: LogLevel) -> bool {
+    level as u8 <= LEVELS[subsystem as usize].load(Ordering::Relaxed)
+}
+
+/// Apply the levels from settings; call after settings are loaded or changed
+pub fn sync_from_settings() {
+    let levels = settings::with(|s| s.log_levels);
+    for (slot, level) in LEVELS.iter().zip(levels) {
+        slot.store(level as u8, Ordering::Relaxed);
+    }
+}
+
+/// Per call site throttle for `log_limited!`
+pub struct RateLimit {
+    last_ms: AtomicU32,
+    suppressed: AtomicU32,
+}
+
+impl RateLimit {
+    pub const fn new() -> Self {
+        Self {
+            // Far enough in the past that the first message always passes
+            last_ms: AtomicU32::new(u32::MAX / 2),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// Some(messages suppressed since the last one) if a message may be
+    /// logged now, None while throttled
+    pub fn check(&self) -> Option<u32> {
+        let now = embassy_time::Instant::now().as_millis() as u32;
+        let last = self.last_ms.load(Ordering::Relaxed);
+        if now.wrapping_sub(last) < LOG_RATE_LIMIT_MS {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.last_ms.store(now, Ordering::Relaxed);
+        Some(self.suppressed.swap(0, Ordering::Relaxed))
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Log through defmt if the subsystem's runtime level allows it
+#[macro_export]
+macro_rules! log_at {
+    (error, $sub:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($sub, $crate::logging::LogLevel::Error) {
+            defmt::error!($($arg)+);
+        }
+    };
+    (warn, $sub:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($sub, $crate::logging::LogLevel::Warn) {
+            defmt::warn!($($arg)+);
+        }
+    };
+    (info, $sub:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($sub, $crate::logging::LogLevel::Info) {
+            defmt::info!($($arg)+);
+        }
+    };
+    (debug, $sub:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($sub, $crate::logging::LogLevel::Debug) {
+            defmt::debug!($($arg)+);
+        }
+    };
+    (trace, $sub:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($sub, $crate::logging::LogLevel::Trace) {
+            defmt::trace!($($arg)+);
+        }
+    };
+}
+
+/// Like `log_at!`, but at most once per `LOG_RATE_LIMIT_MS` per call site,
+/// followed by a count of the messages dropped in between
+#[macro_export]
+macro_rules! log_limited {
+    ($level:ident, $sub:expr, $($arg:tt)+) => {{
+        static LIMIT: $crate::logging::RateLimit = $crate::logging::RateLimit::new();
+        if let Some(suppressed) = LIMIT.check() {
+            $crate::log_at!($level, $sub, $($arg)+);
+            if suppressed > 0 {
+                $crate::log_at!($level, $sub, "  ({} similar messages suppressed)", suppressed);
+            }
+        }
+    }};
+}
@@ -0,0 +1,109 @@
+//! RAM budget
+//!
+//! Buffers that hold key images or key rows are sized from the device tables
+//! (`DisplayConfig::max_image_size` in the protocol crate's `config`, key
+//! image width here) instead of hand-picked constants, and the large static
+//! allocations are added up against the RP2040's RAM. A device table, slab
+//! count or feature that would not fit fails the build:
+//!
+//! | Allocation            | Size                                       |
+//! |-----------------------|--------------------------------------------|
+//! | Image pool            | `IMAGE_SLAB_COUNT` x `IMAGE_SLAB_SIZE`     |
+//! | Core 1 stack          | `CORE1_STACK_SIZE`                         |
+//! | USB descriptors, EP0  | control buffer, descriptors, fragments     |
+//! | Log ring              | `LOG_BUFFER_SIZE` (`log-buffer`)           |
+//! | E-paper framebuffer   | 1 bit per pixel (`epaper-display`)         |
+//! | Config drive cache    | one flash sector (`config-drive`)          |
+//!
+//! Everything else (core 0 stack, executor task arenas, channels, driver
+//! state) is covered by fixed reserves.
+
+use crate::config::{
+    CORE1_STACK_SIZE, IMAGE_SLAB_COUNT, IMAGE_SLAB_SIZE, USB_CONFIG_DESCRIPTOR_SIZE,
+    USB_CONTROL_BUFFER_SIZE, USB_MAX_OUTPUT_REPORT,
+};
+use crate::device::Device;
+
+/// RP2040 SRAM (`memory.x`)
+pub const RAM_SIZE: usize = 264 * 1024;
+
+/// Kept free for the core 0 stack
+pub const CORE0_STACK_RESERVE: usize = 32 * 1024;
+
+/// Statics not listed in the budget: executor task arenas, channels, USB
+/// and display driver state (about 35 KB in the Mini build)
+pub const OTHER_STATICS_RESERVE: usize = 48 * 1024;
+
+/// Largest key image row of any device, RGB888
+pub const LARGEST_KEY_ROW: usize = largest_key_row();
+
+const fn largest_key_row() -> usize {
+    let mut largest = 0;
+    let mut i = 0;
+    while i < Device::ALL.len() {
+        let row = Device::ALL[i].display().image_width * 3;
+        if row > largest {
+            largest = row;
+        }
+        i += 1;
+    }
+    largest
+}
+
+/// USB device descriptor and BOS buffers (`usb::UsbResources`)
+const USB_DESCRIPTOR_BUFFERS: usize = 256 + 256;
+
+/// Statically allocated buffers other than the image pool
+pub const FIXED_BUFFERS: usize = CORE1_STACK_SIZE
+    + USB_DESCRIPTOR_BUFFERS
+    + USB_CONFIG_DESCRIPTOR_SIZE
+    + USB_CONTROL_BUFFER_SIZE
+    // Interrupt OUT reassembly (`usb::fragments::ReportAssembler`)
+    + USB_MAX_OUTPUT_REPORT
+    + feature_buffers();
+
+const fn feature_buffers() -> usize {
+    let mut size = 0;
+    if cfg!(feature = "log-buffer") {
+        size += crate::config::LOG_BUFFER_SIZE;
+    }
+    #[cfg(feature = "epaper-display")]
+    {
+        size += crate::epaper::FRAMEBUFFER_SIZE;
+    }
+    #[cfg(feature = "config-drive")]
+    {
+        size += embassy_rp::flash::ERASE_SIZE;
+    }
+    size
+}
+
+/// RAM the budgeted buffers take in this build
+pub const STATIC_BUFFERS: usize = IMAGE_SLAB_COUNT * IMAGE_SLAB_SIZE + FIXED_BUFFERS;
+
+/// RAM a build for `device` alone would need for its image pool and fixed buffers
+pub const fn device_ram(device: Device) -> usize {
+    // Streaming-only builds have no slabs (IMAGE_SLAB_SIZE == 0)
+    let slab = if IMAGE_SLAB_SIZE == 0 {
+        0
+    } else {
+        device.display().max_image_size()
+    };
+    IMAGE_SLAB_COUNT * slab + FIXED_BUFFERS
+}
+
+const _: () = {
+    let reserves = CORE0_STACK_RESERVE + OTHER_STATICS_RESERVE;
+    let mut i = 0;
+    while i < Device::ALL.len() {
+        assert!(
+            device_ram(Device::ALL[i]) + reserves <= RAM_SIZE,
+            "a device's image buffers do not fit in RAM"
+        );
+        i += 1;
+    }
+    assert!(
+        STATIC_BUFFERS + reserves <= RAM_SIZE,
+        "static buffers do not fit in RAM; lower IMAGE_SLAB_COUNT or disable features"
+    );
+};
@@ -0,0 +1,177 @@
+//! Night mode
+//!
+//! Dark control rooms want the deck readable without lighting up the desk.
+//! While night mode is on:
+//!
+//! - the backlight PWM duty is capped at `NightMode::backlight_cap`, like a
+//!   supply sag limit (`power::brightness_limit`); the host level is kept
+//!   and comes back when night mode ends, even from a cap of 0%
+//! - the accent LED ring follows the same cap
+//! - the status LED stays dark; the error LED still blinks error codes
+//!
+//! Night mode is switched on or off for good, or follows a daily window
+//! (`NightTrigger::Scheduled`, which may wrap past midnight). The RP2040 has
+//! no battery-backed clock, so a schedule only runs once the host has told
+//! the device the time of day (`VENDOR_CMD_SET_CLOCK`) since power-up;
+//! until then the device stays in day mode. The setup is stored in settings
+//! and changed through the vendor report (`VENDOR_CMD_SET_NIGHT_MODE`).
+//!
+//! There is no beeper or haptic driver in this firmware yet; outputs like
+//! that should stay quiet while `is_active`.
+
+use core::cell::Cell;
+use defmt::*;
+use embassy_futures::select::select;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+// portable-atomic: thumbv6m has no native read-modify-write atomics
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::config::NIGHT_POLL_MS;
+
+/// Minutes in a day; times of day are minutes after midnight
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// When night mode is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum NightTrigger {
+    Off = 0,
+    On = 1,
+    /// Between `start_min` and `end_min`, once the clock is set
+    Scheduled = 2,
+}
+
+impl NightTrigger {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => NightTrigger::Off,
+            1 => NightTrigger::On,
+            2 => NightTrigger::Scheduled,
+            _ => return None,
+        })
+    }
+}
+
+/// Stored night mode setup
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+pub struct NightMode {
+    pub trigger: NightTrigger,
+    /// Highest backlight and ring brightness at night, in percent
+    pub backlight_cap: u8,
+    /// Start of the scheduled window, minutes after midnight
+    pub start_min: u16,
+    /// End of the scheduled window, minutes after midnight
+    pub end_min: u16,
+}
+
+impl NightMode {
+    pub const DEFAULT: Self = Self {
+        trigger: NightTrigger::Off,
+        backlight_cap: 20,
+        start_min: 22 * 60,
+        end_min: 7 * 60,
+    };
+
+    /// Whether the setup is usable as stored or sent
+    pub fn is_valid(&self) -> bool {
+        self.backlight_cap <= 100
+            && self.start_min < MINUTES_PER_DAY
+            && self.end_min < MINUTES_PER_DAY
+    }
+
+    /// Whether `minute` (of the day) falls in the scheduled window
+    fn in_window(&self, minute: u16) -> bool {
+        if self.start_min <= self.end_min {
+            (self.start_min..self.end_min).contains(&minute)
+        } else {
+            minute >= self.start_min || minute < self.end_min
+        }
+    }
+}
+
+/// Time of day the host last set, and when
+static CLOCK: Mutex<CriticalSectionRawMutex, Cell<Option<(Instant, u16)>>> =
+    Mutex::new(Cell::new(None));
+/// Night mode as last applied by the night task
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Wakes the night task when the setup or the clock changes
+static CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// The setup night mode runs
+pub fn current() -> NightMode {
+    crate::settings::with(|s| s.night_mode)
+}
+
+/// Let the night task pick up a changed setup now
+pub fn notify() {
+    CHANGED.signal(());
+}
+
+/// Set the time of day, in minutes after midnight
+pub fn set_clock(minute: u16) {
+    CLOCK.lock(|clock| clock.set(Some((Instant::now(), minute % MINUTES_PER_DAY))));
+    notify();
+}
+
+/// Minutes after midnight now, if the host has set the clock
+pub fn minute_of_day() -> Option<u16> {
+    let (set_at, minute) = CLOCK.lock(|clock| clock.get())?;
+    let elapsed = set_at.elapsed().as_secs() / 60;
+    Some(((minute as u64 + elapsed) % MINUTES_PER_DAY as u64) as u16)
+}
+
+/// Whether night mode is on
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Backlight cap in percent (100 outside night mode)
+pub fn backlight_cap() -> u8 {
+    if is_active() {
+        current().backlight_cap
+    } else {
+        100
+    }
+}
+
+/// Whether the setup puts the device in night mode now
+fn wanted(mode: &NightMode) -> bool {
+    match mode.trigger {
+        NightTrigger::Off => false,
+        NightTrigger::On => true,
+        NightTrigger::Scheduled => minute_of_day().is_some_and(|minute| mode.in_window(minute)),
+    }
+}
+
+/// Follow the night mode setup and schedule; never returns
+#[embassy_executor::task]
+pub async fn night_task() {
+    let mut cap = 100;
+    loop {
+        let mode = current();
+        let active = wanted(&mode);
+        if ACTIVE.swap(active, Ordering::Relaxed) != active {
+            if active {
+                info!("Night mode on, backlight capped at {}%", mode.backlight_cap);
+            } else {
+                info!("Night mode off");
+            }
+            crate::status_led::notify();
+        }
+        // The cap can change while night mode stays on
+        if backlight_cap() != cap {
+            cap = backlight_cap();
+            crate::power::refresh_brightness_limit();
+            crate::effects::notify();
+        }
+
+        select(
+            Timer::after(Duration::from_millis(NIGHT_POLL_MS)),
+            CHANGED.wait(),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,276 @@
+//! Standalone mode pages
+//!
+//! A single layer of shortcuts runs out quickly on a 6 or 15 key deck. In
+//! standalone mode a designated page key cycles through up to
+//! `STANDALONE_PAGES` pages, each with its own shortcut and label per key, so
+//! the device works as a macro pad with folders and no host software.
+//!
+//! Page 0 uses the shortcut table from settings (`VENDOR_CMD_SET_SHORTCUT`);
+//! the other pages' shortcuts and every page's labels live in a page table in
+//! its own flash sector. On page 0 a key's stored icon (`key_images`) is shown
+//! instead of its label when it has one.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{with_timeout, Duration};
+
+use crate::channels::send_display;
+use crate::config::{MAX_KEYS, PAGE_LABEL_LEN, STANDALONE_PAGES};
+use crate::gfx::KeyText;
+use crate::standalone::KeyShortcut;
+use crate::types::{ButtonState, DisplayCommand, KeyImageVariant};
+
+/// Page key value meaning paging is off
+pub const NO_PAGE_KEY: u8 = 0xFF;
+
+/// Label colors (RGB565)
+const LABEL_FG: u16 = 0xFFFF;
+const LABEL_BG: u16 = 0x0000;
+/// Page key colors
+const PAGE_KEY_FG: u16 = 0x0000;
+const PAGE_KEY_BG: u16 = 0xFD20;
+
+/// How long to wait for room in the display queue while drawing a page
+const DISPLAY_SEND_TIMEOUT_MS: u64 = 50;
+
+/// Record magic ("PDPG")
+const PAGES_MAGIC: [u8; 4] = *b"PDPG";
+
+/// Bytes per key entry: modifiers, keycode, label
+const ENTRY_SIZE: usize = 2 + PAGE_LABEL_LEN;
+
+/// Offset of the first entry: magic, page key, page count
+const ENTRIES_OFFSET: usize = 6;
+
+/// Size of the serialized page table in flash
+pub const PAGES_RECORD_SIZE: usize = ENTRIES_OFFSET + STANDALONE_PAGES * MAX_KEYS * ENTRY_SIZE;
+
+/// Shortcut and label of one key on one page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageEntry {
+    /// Ignored on page 0, which uses the settings shortcut table
+    pub shortcut: KeyShortcut,
+    /// ASCII label, zero padded
+    pub label: [u8; PAGE_LABEL_LEN],
+}
+
+impl PageEntry {
+    pub const EMPTY: Self = Self {
+        shortcut: KeyShortcut::NONE,
+        label: [0; PAGE_LABEL_LEN],
+    };
+
+    /// Label bytes up to the padding
+    pub fn label(&self) -> &[u8] {
+        let len = self
+            .label
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(PAGE_LABEL_LEN);
+        &self.label[..len]
+    }
+}
+
+/// Every page's entries plus the paging setup
+#[derive(Clone, Debug)]
+pub struct PageTable {
+    /// Protocol key index that cycles pages (`NO_PAGE_KEY` = paging off)
+    pub page_key: u8,
+    /// Pages in use (1..=STANDALONE_PAGES)
+    pub page_count: u8,
+    pub entries: [[PageEntry; MAX_KEYS]; STANDALONE_PAGES],
+}
+
+impl PageTable {
+    pub const fn new() -> Self {
+        Self {
+            page_key: NO_PAGE_KEY,
+            page_count: 1,
+            entries: [[PageEntry::EMPTY; MAX_KEYS]; STANDALONE_PAGES],
+        }
+    }
+
+    /// Whether a page key is set and there is more than one page
+    pub fn paging_enabled(&self) -> bool {
+        (self.page_key as usize) < MAX_KEYS && self.page_count > 1
+    }
+
+    /// Serialize into a flash record
+    pub fn to_record(&self, record: &mut [u8; PAGES_RECORD_SIZE]) {
+        record.fill(0xFF);
+        record[..4].copy_from_slice(&PAGES_MAGIC);
+        record[4] = self.page_key;
+        record[5] = self.page_count;
+        for (i, entry) in self.entries.iter().flatten().enumerate() {
+            let at = ENTRIES_OFFSET + i * ENTRY_SIZE;
+            record[at] = entry.shortcut.modifiers;
+            record[at + 1] = entry.shortcut.keycode;
+            record[at + 2..at + ENTRY_SIZE].copy_from_slice(&entry.label);
+        }
+    }
+
+    /// Deserialize from a flash record. Returns None for erased or
+    /// unrecognized records.
+    pub fn from_record(record: &[u8; PAGES_RECORD_SIZE]) -> Option<Self> {
+        if record[..4] != PAGES_MAGIC {
+            return None;
+        }
+        let mut table = Self::new();
+        table.page_key = record[4];
+        table.page_count = record[5].clamp(1, STANDALONE_PAGES as u8);
+        for (i, entry) in table.entries.iter_mut().flatten().enumerate() {
+            let at = ENTRIES_OFFSET + i * ENTRY_SIZE;
+            entry.shortcut = KeyShortcut {
+                modifiers: record[at],
+                keycode: record[at + 1],
+            };
+            entry
+                .label
+                .copy_from_slice(&record[at + 2..at + ENTRY_SIZE]);
+            if !entry.label().iter().all(u8::is_ascii_graphic) {
+                entry.label = [0; PAGE_LABEL_LEN];
+            }
+        }
+        Some(table)
+    }
+}
+
+impl Default for PageTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static PAGES: Mutex<CriticalSectionRawMutex, RefCell<PageTable>> =
+    Mutex::new(RefCell::new(PageTable::new()));
+
+/// Read the page table
+pub fn with<R>(f: impl FnOnce(&PageTable) -> R) -> R {
+    PAGES.lock(|pages| f(&pages.borrow()))
+}
+
+/// Modify the page table (in RAM only; call `storage::save_pages` to persist)
+pub fn update<R>(f: impl FnOnce(&mut PageTable) -> R) -> R {
+    PAGES.lock(|pages| f(&mut pages.borrow_mut()))
+}
+
+/// Replace the page table
+pub fn replace(table: PageTable) {
+    PAGES.lock(|pages| *pages.borrow_mut() = table);
+}
+
+/// Page the standalone keyboard is on, for status displays
+static CURRENT_PAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Page the standalone keyboard is on (0 when paging is off)
+pub fn current_page() -> u8 {
+    CURRENT_PAGE.load(Ordering::Relaxed)
+}
+
+/// Page navigation for the standalone keyboard; owned by the input report loop
+pub struct Pager {
+    page: u8,
+    page_key_held: bool,
+    /// Whether the current page is on the display
+    shown: bool,
+}
+
+impl Pager {
+    pub const fn new() -> Self {
+        Self {
+            page: 0,
+            page_key_held: false,
+            shown: false,
+        }
+    }
+
+    /// Handle the page key and return the shortcuts of the current page, with
+    /// the page key itself masked out. Draws the page when it changes.
+    pub async fn update(&mut self, state: &ButtonState) -> [KeyShortcut; MAX_KEYS] {
+        let (page_key, page_count, enabled) =
+            with(|t| (t.page_key as usize, t.page_count, t.paging_enabled()));
+        if !enabled {
+            self.page = 0;
+            CURRENT_PAGE.store(0, Ordering::Relaxed);
+            return crate::settings::with(|s| s.shortcuts);
+        }
+
+        let held = state.is_pressed(page_key);
+        if held && !self.page_key_held {
+            self.page = (self.page + 1) % page_count;
+            self.shown = false;
+        }
+        self.page_key_held = held;
+        self.page = self.page.min(page_count - 1);
+        CURRENT_PAGE.store(self.page, Ordering::Relaxed);
+
+        if !self.shown {
+            self.draw(state.active_count(), page_key).await;
+            self.shown = true;
+        }
+
+        let mut shortcuts = if self.page == 0 {
+            crate::settings::with(|s| s.shortcuts)
+        } else {
+            with(|t| t.entries[self.page as usize].map(|entry| entry.shortcut))
+        };
+        shortcuts[page_key] = KeyShortcut::NONE;
+        shortcuts
+    }
+
+    async fn draw(&self, keys: usize, page_key: usize) {
+        let page = self.page as usize;
+        let entries = with(|t| t.entries[page]);
+        for (key, entry) in entries.iter().enumerate().take(keys) {
+            let key_id = key as u8;
+            let command = if key == page_key {
+                let mut text = KeyText::new();
+                let _ = core::fmt::write(
+                    &mut text,
+                    format_args!("{}/{}", page + 1, with(|t| t.page_count)),
+                );
+                DisplayCommand::DrawText {
+                    key_id,
+                    text,
+                    fg: PAGE_KEY_FG,
+                    bg: PAGE_KEY_BG,
+                }
+            } else if page == 0 && crate::key_images::image(key_id, KeyImageVariant::Icon).is_some()
+            {
+                DisplayCommand::ShowStoredImage {
+                    key_id,
+                    variant: KeyImageVariant::Icon,
+                }
+            } else if entry.label().is_empty() {
+                DisplayCommand::Clear(key_id)
+            } else {
+                let mut text = KeyText::new();
+                for &b in entry.label() {
+                    let _ = text.push(b as char);
+                }
+                DisplayCommand::DrawText {
+                    key_id,
+                    text,
+                    fg: LABEL_FG,
+                    bg: LABEL_BG,
+                }
+            };
+            // Builds without a display consumer must not stall key reports
+            let send = send_display(command);
+            if with_timeout(Duration::from_millis(DISPLAY_SEND_TIMEOUT_MS), send)
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+impl Default for Pager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
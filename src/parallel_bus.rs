@@ -0,0 +1,202 @@
+//! 8080-style parallel panel bus on PIO (`parallel-display` feature)
+//!
+//! Large inexpensive TFTs (3.5"-4", ILI9486/ST7796 class) usually expose an
+//! 8-bit 8080 interface. A PIO1 state machine clocks bytes out of its TX
+//! FIFO: each byte is placed on D0-D7 and latched with a WR pulse. DMA feeds
+//! the FIFO for pixel data, so a whole key image moves without CPU work
+//! per byte. Chip select and data/command stay plain GPIOs, switched only
+//! once the state machine has drained.
+//!
+//! D0-D7 must be consecutive GPIOs starting at `PARALLEL_DATA_BASE_PIN`; WR
+//! is `PARALLEL_WR_PIN`. RD is not used and should be tied high.
+
+use embassy_rp::gpio::Output;
+use embassy_rp::pac;
+use embassy_rp::peripherals::{DMA_CH2, PIO1};
+use embassy_rp::pio::program::{pio_asm, InstructionOperands, OutDestination, SetDestination};
+use embassy_rp::pio::{Common, Config, Pio, ShiftConfig, ShiftDirection, StateMachine};
+use embassy_rp::pio_programs::clock_divider::calculate_pio_clock_divider;
+use embassy_rp::{bind_interrupts, Peri};
+
+use crate::config::{
+    DCS_COLOR_MODE_16BIT, PARALLEL_DATA_BASE_PIN, PARALLEL_PIO_CLOCK_HZ, PARALLEL_WR_PIN,
+};
+use crate::display::PanelDriver;
+
+bind_interrupts!(struct PioIrqs {
+    PIO1_IRQ_0 => embassy_rp::pio::InterruptHandler<PIO1>;
+});
+
+/// GPIO function select for PIO1
+const FUNCSEL_PIO1: u8 = 7;
+
+/// Shorter writes are pushed by the CPU; DMA setup costs more than it saves
+const DMA_MIN_LEN: usize = 16;
+
+/// 8080 write-only bus driven by PIO1 state machine 0 and DMA_CH2
+pub struct Parallel8080Bus {
+    _common: Common<'static, PIO1>,
+    sm: StateMachine<'static, PIO1, 0>,
+    dma: Peri<'static, DMA_CH2>,
+    /// Pads stay configured while PIO drives them
+    _data: [Output<'static>; 8],
+    _wr: Output<'static>,
+    cs: Output<'static>,
+    dc: Output<'static>,
+}
+
+impl Parallel8080Bus {
+    /// Take over the bus pins. `data` must be GPIO `PARALLEL_DATA_BASE_PIN`..+8
+    /// in order and `wr` GPIO `PARALLEL_WR_PIN`.
+    pub fn new(
+        pio: Peri<'static, PIO1>,
+        dma: Peri<'static, DMA_CH2>,
+        data: [Output<'static>; 8],
+        wr: Output<'static>,
+        cs: Output<'static>,
+        dc: Output<'static>,
+    ) -> Self {
+        let Pio {
+            mut common,
+            mut sm0,
+            ..
+        } = Pio::new(pio, PioIrqs);
+
+        let program = pio_asm!(
+            ".wrap_target",
+            "out pins, 8", // next byte onto D0-D7 (autopull)
+            "set pins, 0", // WR low
+            "set pins, 1", // WR high latches the byte
+            ".wrap",
+        );
+        let loaded = common.load_program(&program.program);
+
+        let mut cfg = Config::default();
+        cfg.use_program(&loaded, &[]);
+        cfg.clock_divider = calculate_pio_clock_divider(PARALLEL_PIO_CLOCK_HZ);
+        cfg.shift_out = ShiftConfig {
+            threshold: 8,
+            direction: ShiftDirection::Right,
+            auto_fill: true,
+        };
+        sm0.set_config(&cfg);
+
+        pac::PIO1.sm(0).pinctrl().modify(|w| {
+            w.set_out_base(PARALLEL_DATA_BASE_PIN);
+            w.set_out_count(8);
+            w.set_set_base(PARALLEL_WR_PIN);
+            w.set_set_count(1);
+        });
+
+        for pin in (PARALLEL_DATA_BASE_PIN..PARALLEL_DATA_BASE_PIN + 8).chain([PARALLEL_WR_PIN]) {
+            pac::IO_BANK0
+                .gpio(pin as usize)
+                .ctrl()
+                .modify(|w| w.set_funcsel(FUNCSEL_PIO1));
+        }
+
+        sm0.tx().push(0xFF);
+        // SAFETY: the state machine is disabled; these only set pin directions
+        // and the idle WR level
+        unsafe {
+            sm0.exec_instr(
+                InstructionOperands::PULL {
+                    if_empty: false,
+                    block: true,
+                }
+                .encode(),
+            );
+            sm0.exec_instr(
+                InstructionOperands::OUT {
+                    destination: OutDestination::PINDIRS,
+                    bit_count: 8,
+                }
+                .encode(),
+            );
+            sm0.exec_instr(
+                InstructionOperands::SET {
+                    destination: SetDestination::PINDIRS,
+                    data: 1,
+                }
+                .encode(),
+            );
+            sm0.exec_instr(
+                InstructionOperands::SET {
+                    destination: SetDestination::PINS,
+                    data: 1,
+                }
+                .encode(),
+            );
+        }
+        sm0.set_enable(true);
+
+        defmt::info!(
+            "8080 parallel bus on GPIO{}-{} (WR GPIO{})",
+            PARALLEL_DATA_BASE_PIN,
+            PARALLEL_DATA_BASE_PIN + 7,
+            PARALLEL_WR_PIN
+        );
+
+        Self {
+            _common: common,
+            sm: sm0,
+            dma,
+            _data: data,
+            _wr: wr,
+            cs,
+            dc,
+        }
+    }
+
+    /// Clock out bytes and wait until the last WR pulse is done
+    async fn write(&mut self, bytes: &[u8]) {
+        if bytes.len() >= DMA_MIN_LEN {
+            self.sm
+                .tx()
+                .dma_push(self.dma.reborrow(), bytes, false)
+                .await;
+        } else {
+            for &byte in bytes {
+                self.sm.tx().wait_push(byte as u32).await;
+            }
+        }
+
+        // Clear the stall flag, then wait for the state machine to stall on
+        // the empty FIFO again: the final byte has been latched by then
+        let _ = self.sm.tx().stalled();
+        while !self.sm.tx().stalled() {}
+    }
+}
+
+impl PanelDriver for Parallel8080Bus {
+    const COLOR_MODE_16BIT: u8 = DCS_COLOR_MODE_16BIT;
+
+    fn select(&mut self) {
+        self.cs.set_low();
+    }
+
+    async fn deselect(&mut self) {
+        self.cs.set_high();
+    }
+
+    async fn command(&mut self, command: u8) {
+        self.dc.set_low();
+        self.write(&[command]).await;
+    }
+
+    async fn data(&mut self, data: &[u8]) {
+        self.dc.set_high();
+        self.write(data).await;
+    }
+}
+
+/// Display task for a panel on the 8080 parallel bus
+#[embassy_executor::task]
+pub async fn display_task_8080(
+    bus: Parallel8080Bus,
+    rst: Output<'static>,
+    bl: crate::display::Backlight,
+) {
+    defmt::info!("Display task started (8080 parallel bus)");
+    crate::display::run_display(bus, rst, bl).await
+}
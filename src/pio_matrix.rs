@@ -0,0 +1,308 @@
+//! PIO-driven key matrix scanner (`pio-matrix` feature)
+//!
+//! A PIO0 state machine drives the rows and samples the columns on its own:
+//! for each row it outputs a row pattern (selected row low), waits a fixed
+//! settling time and pushes a sample of all GPIO inputs. Two DMA channels
+//! keep it fed continuously from rings in RAM:
+//!
+//! - `ROW_PATTERNS` -> PIO TX FIFO (read ring)
+//! - PIO RX FIFO -> `SAMPLES` (write ring)
+//!
+//! Both rings are `RING_WORDS` long and start together, so `SAMPLES[r]`
+//! always holds the latest sample taken with row `r` driven. The button task
+//! just reads the snapshot; no CPU time or timers go into settling.
+//!
+//! Row and column pins need not be contiguous: the state machine's OUT and IN
+//! mappings span all 32 GPIOs, and only the row pins are switched to the PIO
+//! function, so OUT data for other bits has no effect.
+
+use core::cell::{RefCell, UnsafeCell};
+use core::sync::atomic::{compiler_fence, Ordering};
+use defmt::*;
+use embassy_rp::dma::Channel as _;
+use embassy_rp::gpio::{Input, Output};
+use embassy_rp::pac;
+use embassy_rp::pac::dma::vals::{DataSize, TreqSel};
+use embassy_rp::peripherals::{DMA_CH0, DMA_CH1, PIO0};
+use embassy_rp::pio::program::{pio_asm, InstructionOperands, OutDestination};
+use embassy_rp::pio::{Common, Config, Pio, ShiftConfig, ShiftDirection, StateMachine};
+use embassy_rp::pio_programs::clock_divider::calculate_pio_clock_divider;
+use embassy_rp::{bind_interrupts, Peri};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use heapless::Vec;
+
+use crate::config::{MAX_KEYS, PIO_MATRIX_CLOCK_HZ};
+
+bind_interrupts!(struct PioIrqs {
+    PIO0_IRQ_0 => embassy_rp::pio::InterruptHandler<PIO0>;
+});
+
+/// Ring length in words; covers up to 4 rows, unused slots idle all rows
+const RING_WORDS: usize = 4;
+/// DMA ring size as log2 of bytes (4 words)
+const RING_SIZE_BITS: u8 = 4;
+
+/// GPIO function select for PIO0
+const FUNCSEL_PIO0: u8 = 6;
+/// DREQ numbers for PIO0 state machine 0
+const DREQ_PIO0_TX0: u8 = 0;
+const DREQ_PIO0_RX0: u8 = 4;
+
+/// Word ring shared with DMA; aligned to its size as ring wrapping requires
+#[repr(C, align(16))]
+struct Ring(UnsafeCell<[u32; RING_WORDS]>);
+
+// SAFETY: `ROW_PATTERNS` is written only before its DMA starts; `SAMPLES` is
+// written only by DMA and read with volatile word reads.
+unsafe impl Sync for Ring {}
+
+static ROW_PATTERNS: Ring = Ring(UnsafeCell::new([0; RING_WORDS]));
+static SAMPLES: Ring = Ring(UnsafeCell::new([u32::MAX; RING_WORDS]));
+
+/// Peripherals claimed at init, handed to the scanner
+struct PioResources {
+    pio: Peri<'static, PIO0>,
+    tx_dma: Peri<'static, DMA_CH0>,
+    rx_dma: Peri<'static, DMA_CH1>,
+}
+
+static RESOURCES: Mutex<CriticalSectionRawMutex, RefCell<Option<PioResources>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Take ownership of PIO0 and the two DMA channels used by the scanner.
+/// Must be called before the PIO button task is spawned.
+pub fn init(
+    pio: Peri<'static, PIO0>,
+    tx_dma: Peri<'static, DMA_CH0>,
+    rx_dma: Peri<'static, DMA_CH1>,
+) {
+    RESOURCES.lock(|r| {
+        *r.borrow_mut() = Some(PioResources {
+            pio,
+            tx_dma,
+            rx_dma,
+        })
+    });
+}
+
+/// Continuously running PIO matrix scan
+pub struct PioMatrix {
+    _common: Common<'static, PIO0>,
+    sm: StateMachine<'static, PIO0, 0>,
+    tx_dma: Peri<'static, DMA_CH0>,
+    rx_dma: Peri<'static, DMA_CH1>,
+    /// GPIO pads stay configured (pull-ups, output enable) while PIO drives them
+    _rows: Vec<Output<'static>, 4>,
+    _cols: Vec<Input<'static>, 32>,
+    row_count: usize,
+    col_masks: Vec<u32, 32>,
+}
+
+impl PioMatrix {
+    /// Start scanning. `row_pins`/`col_pins` are the GPIO numbers of `rows`/`cols`.
+    /// Returns None if `init` was not called.
+    pub fn new(
+        rows: Vec<Output<'static>, 4>,
+        cols: Vec<Input<'static>, 32>,
+        row_pins: &[u8],
+        col_pins: &[u8],
+    ) -> Option<Self> {
+        let resources = RESOURCES.lock(|r| r.borrow_mut().take())?;
+        let Pio {
+            mut common,
+            mut sm0,
+            ..
+        } = Pio::new(resources.pio, PioIrqs);
+
+        let row_pins = &row_pins[..rows.len().min(row_pins.len())];
+        let col_masks = col_pins
+            .iter()
+            .take(cols.len())
+            .map(|&pin| 1u32 << pin)
+            .collect();
+        let row_mask = row_pins.iter().fold(0u32, |mask, &pin| mask | (1 << pin));
+
+        // One pattern per row with that row low; spare slots leave all rows high
+        // SAFETY: the TX DMA is not running yet
+        let patterns = unsafe { &mut *ROW_PATTERNS.0.get() };
+        for (slot, pattern) in patterns.iter_mut().enumerate() {
+            *pattern = match row_pins.get(slot) {
+                Some(&pin) => row_mask & !(1 << pin),
+                None => row_mask,
+            };
+        }
+
+        let program = pio_asm!(
+            ".wrap_target",
+            "out pins, 32", // drive the next row pattern (autopull)
+            "nop [31]",     // let the row settle
+            "in pins, 32",  // sample every GPIO (autopush)
+            ".wrap",
+        );
+        let loaded = common.load_program(&program.program);
+
+        let mut cfg = Config::default();
+        cfg.use_program(&loaded, &[]);
+        cfg.clock_divider = calculate_pio_clock_divider(PIO_MATRIX_CLOCK_HZ);
+        cfg.shift_out = ShiftConfig {
+            threshold: 32,
+            direction: ShiftDirection::Right,
+            auto_fill: true,
+        };
+        cfg.shift_in = cfg.shift_out;
+        sm0.set_config(&cfg);
+
+        // OUT and IN span all GPIOs so the pins need not be contiguous
+        pac::PIO0.sm(0).pinctrl().modify(|w| {
+            w.set_out_base(0);
+            w.set_out_count(32);
+            w.set_in_base(0);
+        });
+
+        // Rows become PIO outputs, idle high
+        for &pin in row_pins {
+            pac::IO_BANK0
+                .gpio(pin as usize)
+                .ctrl()
+                .modify(|w| w.set_funcsel(FUNCSEL_PIO0));
+        }
+        for word in [row_mask, row_mask] {
+            sm0.tx().push(word);
+        }
+        // SAFETY: the state machine is disabled; these only load OSR and set
+        // pin directions/levels
+        unsafe {
+            let pull = InstructionOperands::PULL {
+                if_empty: false,
+                block: true,
+            }
+            .encode();
+            sm0.exec_instr(pull);
+            sm0.exec_instr(
+                InstructionOperands::OUT {
+                    destination: OutDestination::PINDIRS,
+                    bit_count: 32,
+                }
+                .encode(),
+            );
+            sm0.exec_instr(pull);
+            sm0.exec_instr(
+                InstructionOperands::OUT {
+                    destination: OutDestination::PINS,
+                    bit_count: 32,
+                }
+                .encode(),
+            );
+        }
+
+        let mut matrix = Self {
+            _common: common,
+            sm: sm0,
+            tx_dma: resources.tx_dma,
+            rx_dma: resources.rx_dma,
+            _rows: rows,
+            _cols: cols,
+            row_count: row_pins.len(),
+            col_masks,
+        };
+        matrix.start();
+        info!(
+            "PIO matrix scanner running ({}x{})",
+            matrix.col_masks.len(),
+            matrix.row_count
+        );
+        Some(matrix)
+    }
+
+    /// (Re)start the state machine and both DMA rings from row 0
+    fn start(&mut self) {
+        self.sm.set_enable(false);
+
+        // Stop whichever ring is still running before reprogramming it
+        let channels = (1u16 << self.tx_dma.number()) | (1u16 << self.rx_dma.number());
+        pac::DMA.chan_abort().write(|w| w.set_chan_abort(channels));
+        while pac::DMA.chan_abort().read().chan_abort() & channels != 0 {}
+
+        self.sm.clear_fifos();
+        self.sm.restart();
+
+        start_ring(
+            self.rx_dma.regs(),
+            self.rx_dma.number(),
+            pac::PIO0.rxf(0).as_ptr() as u32,
+            SAMPLES.0.get() as u32,
+            DREQ_PIO0_RX0,
+            true,
+        );
+        start_ring(
+            self.tx_dma.regs(),
+            self.tx_dma.number(),
+            ROW_PATTERNS.0.get() as u32,
+            pac::PIO0.txf(0).as_ptr() as u32,
+            DREQ_PIO0_TX0,
+            false,
+        );
+
+        self.sm.set_enable(true);
+    }
+
+    /// Pause or resume scanning (e.g. while the host is asleep)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.sm.set_enable(enabled);
+    }
+
+    /// Latest key states, row-major, true = pressed
+    pub fn snapshot(&mut self) -> [bool; MAX_KEYS] {
+        // The rings run for `u32::MAX` transfers; re-arm once they stop
+        if !self.rx_dma.regs().ctrl_trig().read().busy() {
+            warn!("PIO matrix DMA stopped, restarting");
+            self.start();
+        }
+
+        let mut states = [false; MAX_KEYS];
+        let cols = self.col_masks.len();
+        for row in 0..self.row_count {
+            // SAFETY: aligned word read of memory DMA writes whole words to
+            let sample = unsafe { (SAMPLES.0.get() as *const u32).add(row).read_volatile() };
+            for (col, &mask) in self.col_masks.iter().enumerate() {
+                if let Some(state) = states.get_mut(row * cols + col) {
+                    // Active low with pull-ups
+                    *state = sample & mask == 0;
+                }
+            }
+        }
+        states
+    }
+}
+
+/// Start a word-sized DMA ring between memory and a PIO FIFO.
+/// `ring_write` selects whether the write (RX) or read (TX) side wraps.
+fn start_ring(
+    ch: pac::dma::Channel,
+    number: u8,
+    read: u32,
+    write: u32,
+    dreq: u8,
+    ring_write: bool,
+) {
+    ch.read_addr().write_value(read);
+    ch.write_addr().write_value(write);
+    ch.trans_count().write_value(u32::MAX);
+
+    compiler_fence(Ordering::SeqCst);
+
+    ch.ctrl_trig().write(|w| {
+        w.set_treq_sel(TreqSel::from(dreq));
+        w.set_data_size(DataSize::SIZE_WORD);
+        w.set_incr_read(!ring_write);
+        w.set_incr_write(ring_write);
+        w.set_ring_sel(ring_write);
+        w.set_ring_size(RING_SIZE_BITS);
+        // Chaining to itself disables chaining
+        w.set_chain_to(number);
+        w.set_en(true);
+    });
+
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,210 @@
+//! Power and USB bus state tracking
+//!
+//! The USB device handler records enumeration and suspend state here so that
+//! button scanning, status LEDs and the display can drop into a low-power
+//! state while the host is asleep and resume cleanly on wake.
+//!
+//! It also sequences startup (USB, then panel, then backlight) and watches
+//! the supply voltage and die temperature, limiting the backlight when the
+//! supply sags or the chip runs hot. Night mode (`night`) caps it the same way.
+
+use core::sync::atomic::{AtomicBool, AtomicI16, AtomicU16, AtomicU8, Ordering};
+use defmt::{info, warn};
+use embassy_time::{Duration, Timer};
+
+use crate::channels::try_send_display;
+use crate::config::{
+    SAG_BRIGHTNESS_PERCENT, THERMAL_BRIGHTNESS_PERCENT, THERMAL_RECOVER_DECI_C,
+    THERMAL_THROTTLE_DECI_C, VSYS_POLL_MS, VSYS_RECOVER_MV, VSYS_SAG_MV,
+};
+use crate::types::DisplayCommand;
+
+/// Polling interval used while waiting for the bus to resume
+const RESUME_POLL_INTERVAL_MS: u64 = 10;
+
+static USB_CONFIGURED: AtomicBool = AtomicBool::new(false);
+static USB_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Record whether the host has configured the device
+pub fn set_usb_configured(configured: bool) {
+    USB_CONFIGURED.store(configured, Ordering::Relaxed);
+    crate::status_led::notify();
+}
+
+/// Whether the host has configured the device
+pub fn usb_configured() -> bool {
+    USB_CONFIGURED.load(Ordering::Relaxed)
+}
+
+/// Record whether the USB bus is suspended
+pub fn set_usb_suspended(suspended: bool) {
+    USB_SUSPENDED.store(suspended, Ordering::Relaxed);
+    crate::status_led::notify();
+}
+
+/// Whether the USB bus is suspended (host asleep)
+pub fn usb_suspended() -> bool {
+    USB_SUSPENDED.load(Ordering::Relaxed)
+}
+
+/// Wait until the USB bus leaves suspend
+pub async fn wait_for_resume() {
+    while usb_suspended() {
+        Timer::after(Duration::from_millis(RESUME_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Startup stage. Power consumers are brought up one at a time so the
+/// combined inrush doesn't brown out weak hubs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, defmt::Format)]
+#[repr(u8)]
+pub enum StartupStage {
+    /// Waiting for USB enumeration
+    Usb = 0,
+    /// Panel may initialize
+    Panel = 1,
+    /// Panel is up, backlight ramping
+    Backlight = 2,
+    /// Startup complete
+    Running = 3,
+}
+
+static STARTUP_STAGE: AtomicU8 = AtomicU8::new(StartupStage::Usb as u8);
+
+/// Current startup stage
+pub fn startup_stage() -> StartupStage {
+    match STARTUP_STAGE.load(Ordering::Relaxed) {
+        0 => StartupStage::Usb,
+        1 => StartupStage::Panel,
+        2 => StartupStage::Backlight,
+        _ => StartupStage::Running,
+    }
+}
+
+/// Move startup forward to `stage` (never backwards)
+pub fn advance_startup(stage: StartupStage) {
+    if stage > startup_stage() {
+        info!("Startup stage: {}", stage);
+        STARTUP_STAGE.store(stage as u8, Ordering::Relaxed);
+        crate::status_led::notify();
+    }
+}
+
+/// Wait until startup has reached `stage`
+pub async fn wait_for_stage(stage: StartupStage) {
+    while startup_stage() < stage {
+        Timer::after(Duration::from_millis(RESUME_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Wait until the host has configured the device
+pub async fn wait_for_usb_configured() {
+    while !usb_configured() {
+        Timer::after(Duration::from_millis(RESUME_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Last measured VSYS in millivolts (0 = not measured)
+static VSYS_MV: AtomicU16 = AtomicU16::new(0);
+
+/// Last measured VSYS in millivolts, 0 before the first sample
+pub fn vsys_mv() -> u16 {
+    VSYS_MV.load(Ordering::Relaxed)
+}
+
+/// Last measured die temperature in 0.1 °C (`i16::MIN` = not measured)
+static TEMPERATURE_DECI_C: AtomicI16 = AtomicI16::new(i16::MIN);
+
+/// Backlight limit the monitor wants for supply sag or heat, in percent
+static SUPPLY_LIMIT: AtomicU8 = AtomicU8::new(100);
+
+/// Backlight limit currently applied, in percent
+static BRIGHTNESS_LIMIT: AtomicU8 = AtomicU8::new(100);
+
+/// Last measured die temperature in 0.1 °C, None before the first sample
+pub fn temperature_deci_c() -> Option<i16> {
+    match TEMPERATURE_DECI_C.load(Ordering::Relaxed) {
+        i16::MIN => None,
+        t => Some(t),
+    }
+}
+
+/// Backlight limit currently applied for supply sag, heat or night mode (100 = none)
+pub fn brightness_limit() -> u8 {
+    BRIGHTNESS_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Apply the lowest of the supply and night mode limits to the backlight;
+/// the display caps its PWM duty at it (`Brightness::global_duty`). Never
+/// waits on the display: a limit it had no room for stays pending and goes
+/// out on the next call (the voltage monitor calls every poll).
+pub fn refresh_brightness_limit() {
+    let limit = SUPPLY_LIMIT
+        .load(Ordering::Relaxed)
+        .min(crate::night::backlight_cap());
+    if BRIGHTNESS_LIMIT.load(Ordering::Relaxed) != limit
+        && try_send_display(DisplayCommand::LimitBrightness(limit))
+    {
+        BRIGHTNESS_LIMIT.store(limit, Ordering::Relaxed);
+    }
+}
+
+/// Convert a 12-bit ADC sample of the VSYS/3 divider to millivolts
+fn sample_to_mv(sample: u16) -> u16 {
+    (sample as u32 * 3 * 3300 / 4096) as u16
+}
+
+/// Convert a 12-bit temperature sensor sample to 0.1 °C
+/// (RP2040 datasheet: T = 27 - (V - 0.706) / 0.001721)
+fn sample_to_deci_c(sample: u16) -> i16 {
+    let uv = sample as i32 * 3_300_000 / 4096;
+    (270 - (uv - 706_000) * 10 / 1721) as i16
+}
+
+/// Sample VSYS and the die temperature, limiting the backlight while the
+/// supply sags or the chip is hot.
+///
+/// VBUS has no ADC channel on the Pico; a sagging VBUS shows up on VSYS one
+/// diode drop lower, so VSYS covers both. The ADC is owned by `analog`.
+#[embassy_executor::task]
+pub async fn voltage_monitor_task() {
+    info!("Voltage monitor started");
+
+    let mut sagging = false;
+    let mut hot = false;
+    loop {
+        Timer::after(Duration::from_millis(VSYS_POLL_MS)).await;
+
+        // Hysteresis keeps the backlight from flickering around the thresholds
+        if let Some(mv) = crate::analog::read_vsys().map(sample_to_mv) {
+            VSYS_MV.store(mv, Ordering::Relaxed);
+            if !sagging && mv < VSYS_SAG_MV {
+                warn!("Supply sag: VSYS {} mV, limiting backlight", mv);
+                sagging = true;
+            } else if sagging && mv > VSYS_RECOVER_MV {
+                info!("Supply recovered: VSYS {} mV", mv);
+                sagging = false;
+            }
+        }
+        if let Some(t) = crate::analog::read_temperature().map(sample_to_deci_c) {
+            TEMPERATURE_DECI_C.store(t, Ordering::Relaxed);
+            if !hot && t > THERMAL_THROTTLE_DECI_C {
+                warn!("Die at {} (0.1 C), limiting backlight", t);
+                hot = true;
+            } else if hot && t < THERMAL_RECOVER_DECI_C {
+                info!("Die cooled to {} (0.1 C)", t);
+                hot = false;
+            }
+        }
+
+        let mut limit = 100;
+        if sagging {
+            limit = limit.min(SAG_BRIGHTNESS_PERCENT);
+        }
+        if hot {
+            limit = limit.min(THERMAL_BRIGHTNESS_PERCENT);
+        }
+        SUPPLY_LIMIT.store(limit, Ordering::Relaxed);
+        refresh_brightness_limit();
+    }
+}
@@ -0,0 +1,1281 @@
+//! ProductionDeck vendor feature report
+//!
+//! Configuration commands that are not part of any Elgato protocol use a
+//! dedicated feature report ID (`FEATURE_REPORT_VENDOR`) outside the Elgato
+//! range. The host sends a command with SET_REPORT and reads the result back
+//! with GET_REPORT on the same ID.
+//!
+//! SET_REPORT: `[0xF0, command, payload...]`
+//! GET_REPORT: `[0xF0, command, status, length, payload...]`
+//!
+//! Commands are grouped by the high nibble so a configurator can discover
+//! features by probing for `VENDOR_STATUS_UNKNOWN_COMMAND`:
+//!
+//! | Range | Area                                                |
+//! |-------|-----------------------------------------------------|
+//! | 0x0_  | Serial number, factory reset                        |
+//! | 0x1_  | Key remap                                           |
+//! | 0x2_  | Gestures                                            |
+//! | 0x3_  | Standalone keyboard and pages                       |
+//! | 0x4_  | Self-test, key tests, expansion boards              |
+//! | 0x5_  | Metrics, errors, telemetry, pool use, pixel bench   |
+//! | 0x6_  | Faders                                              |
+//! | 0x7_  | Debounce and stuck keys                             |
+//! | 0x8_  | Logging                                             |
+//! | 0x9_  | Brightness, pin map, personality, rotation, version |
+//! | 0xA_  | Panel gamma                                         |
+//! | 0xB_  | Stored key images (icon + pressed icon)             |
+//! | 0xC_  | Host OS profile and compatibility matrix            |
+//! | 0xD_  | Accent LED ring effects                             |
+//! | 0xE_  | Firmware update (`firmware-update`)                 |
+//!
+//! WebHID pages can also use the settings reports (`SETTINGS_REPORTS`): one
+//! small fixed-size feature report per setting, read with GET_REPORT and
+//! written with SET_REPORT on its own ID, no command step. A write takes the
+//! layout the read returned, so a page can read, edit and write back:
+//!
+//! | ID   | Setting     | Layout after the ID                              |
+//! |------|-------------|--------------------------------------------------|
+//! | 0xF1 | Brightness  | `[percent]`                                      |
+//! | 0xF2 | Personality | `[active PID, stored PID]` u16 LE; writes set stored (0xFFFF = build default) |
+//! | 0xF3 | Key remap   | protocol key index per physical key, `MAX_KEYS`  |
+//! | 0xF4 | Rotation    | `[active, stored]` quarter turns; writes set stored (0xFF = board default) |
+//!
+//! A rejected write fails the control transfer, so `sendFeatureReport`
+//! rejects. These reports are declared in generated descriptors only.
+
+use crate::buttons::{KeyDebounce, StuckKeyPolicy, DEFAULT_DEBOUNCE};
+use crate::channels::{try_send_display, DEVICE_BUS};
+use crate::config::{
+    DISPLAY_MAX_KEY_FPS, FADER_COUNT, FEATURE_REPORT_SETTING_BRIGHTNESS,
+    FEATURE_REPORT_SETTING_PERSONALITY, FEATURE_REPORT_SETTING_REMAP,
+    FEATURE_REPORT_SETTING_ROTATION, FEATURE_REPORT_VENDOR, INPUT_LATENCY_BUDGET_US, LED_RING_LEDS,
+    MAX_KEYS, PAGE_LABEL_LEN, SETTINGS_REPORTS, STANDALONE_PAGES, VENDOR_REPORT_SIZE,
+};
+use crate::device::Device;
+use crate::effects::{IdleEffect, RingEffects};
+use crate::error::Error;
+use crate::gamma::{GAMMA_POINTS, IDENTITY_GAMMA};
+use crate::gestures::{KeyGesture, NO_GESTURES};
+use crate::hardware::HardwareConfig;
+use crate::host::HostOs;
+use crate::key_images::StoreError;
+use crate::logging::{LogLevel, SUBSYSTEM_COUNT};
+use crate::night::{NightMode, NightTrigger, MINUTES_PER_DAY};
+use crate::pages::{self, PageEntry, NO_PAGE_KEY};
+use crate::settings;
+use crate::standalone::{KeyShortcut, DEFAULT_SHORTCUTS};
+use crate::stats::{Pool, PoolUsage};
+use crate::types::{DeviceCommand, DisplayCommand, KeyImageVariant, Rotation, APP_VERSION};
+use defmt::*;
+use heapless::Vec;
+
+/// Read the current USB serial number
+pub const VENDOR_CMD_GET_SERIAL: u8 = 0x01;
+/// Set and persist a serial number override: `[len, ascii...]`
+pub const VENDOR_CMD_SET_SERIAL: u8 = 0x02;
+/// Remove the serial override (revert to the flash unique ID)
+pub const VENDOR_CMD_RESET_SERIAL: u8 = 0x03;
+/// Restore factory settings (pages and stored key images stay)
+pub const VENDOR_CMD_FACTORY_RESET: u8 = 0x04;
+/// Read remap entries: `[offset, count]` -> entries
+pub const VENDOR_CMD_GET_REMAP: u8 = 0x10;
+/// Write and persist remap entries: `[offset, count, entries...]`
+pub const VENDOR_CMD_SET_REMAP: u8 = 0x11;
+/// Restore and persist the identity remap table
+pub const VENDOR_CMD_RESET_REMAP: u8 = 0x12;
+/// Read gesture entries: `[offset, count]` -> `[long, double]` per key
+pub const VENDOR_CMD_GET_GESTURES: u8 = 0x20;
+/// Write and persist one key's gestures: `[key, long, double]` (0xFF = none)
+pub const VENDOR_CMD_SET_GESTURE: u8 = 0x21;
+/// Clear and persist all gestures
+pub const VENDOR_CMD_RESET_GESTURES: u8 = 0x22;
+/// Read standalone shortcuts: `[offset, count]` -> `[modifiers, keycode]` per key
+pub const VENDOR_CMD_GET_SHORTCUTS: u8 = 0x30;
+/// Write and persist one key's shortcut: `[key, modifiers, keycode]`
+pub const VENDOR_CMD_SET_SHORTCUT: u8 = 0x31;
+/// Restore and persist the default shortcuts (F13..F24)
+pub const VENDOR_CMD_RESET_SHORTCUTS: u8 = 0x32;
+/// Read the standalone fallback timeout -> `[seconds]`
+pub const VENDOR_CMD_GET_STANDALONE_TIMEOUT: u8 = 0x33;
+/// Write and persist the standalone fallback timeout: `[seconds]` (0 = never)
+pub const VENDOR_CMD_SET_STANDALONE_TIMEOUT: u8 = 0x34;
+/// Read the standalone paging setup -> `[page key (0xFF = none), page count, max pages]`
+pub const VENDOR_CMD_GET_PAGES: u8 = 0x35;
+/// Set and persist the standalone paging setup: `[page key (0xFF = none), page count]`
+pub const VENDOR_CMD_SET_PAGES: u8 = 0x36;
+/// Read one key of a standalone page: `[page, key]` -> `[modifiers, keycode, label...]`
+pub const VENDOR_CMD_GET_PAGE_ENTRY: u8 = 0x37;
+/// Write and persist one key of a standalone page:
+/// `[page, key, modifiers, keycode, label (ASCII, up to 8)...]`. Page 0's
+/// shortcut is the settings shortcut table.
+pub const VENDOR_CMD_SET_PAGE_ENTRY: u8 = 0x38;
+/// Start the hardware self-test
+pub const VENDOR_CMD_START_SELF_TEST: u8 = 0x40;
+/// Read the self-test result -> `[state, keys, passed_mask (u32 LE)]`
+pub const VENDOR_CMD_GET_SELF_TEST: u8 = 0x41;
+/// Start the key rollover test: `[seconds]` (0 = stop)
+pub const VENDOR_CMD_START_ROLLOVER_TEST: u8 = 0x42;
+/// Read the rollover test result -> `[active, guaranteed keys, max held,
+/// conflicts u16, seen_mask u32, conflict_mask u32]` (LE)
+pub const VENDOR_CMD_GET_ROLLOVER_TEST: u8 = 0x43;
+/// Start the key-to-report latency test (`latency-test`): `[samples]` (0 = stop)
+pub const VENDOR_CMD_START_LATENCY_TEST: u8 = 0x44;
+/// Read the latency test result -> `[active, samples, missed, min_us u32,
+/// avg_us u32, max_us u32]` (LE)
+pub const VENDOR_CMD_GET_LATENCY_TEST: u8 = 0x45;
+/// Read the expansion bus state (`i2c-expansion`) -> `[boards, online mask,
+/// remote keys u32]` (LE)
+pub const VENDOR_CMD_GET_EXPANSION: u8 = 0x46;
+/// Read a metrics snapshot (see `stats::Metrics::to_bytes`)
+pub const VENDOR_CMD_GET_METRICS: u8 = 0x50;
+/// Reset all metrics counters
+pub const VENDOR_CMD_RESET_METRICS: u8 = 0x51;
+/// Read per-kind error counts: `[first kind (default 0)]` -> u16 LE per kind
+/// from `first`, in `Error::kind_index` order, as many as fit the report
+pub const VENDOR_CMD_GET_ERROR_COUNTS: u8 = 0x52;
+/// Read frame pacing -> `[deferred u32, coalesced u32, max fps per key u8]` (LE)
+pub const VENDOR_CMD_GET_FRAME_PACING: u8 = 0x53;
+/// Read image reassembly counters -> `[resyncs u32, evictions u32, rejected u32]` (LE)
+pub const VENDOR_CMD_GET_REASSEMBLY: u8 = 0x54;
+/// Read rolling average image pipeline stage times ->
+/// `[assemble_us u32, queue_us u32, decode_us u32, blit_us u32]` (LE)
+pub const VENDOR_CMD_GET_PIPELINE_TIMING: u8 = 0x55;
+/// Read supply and temperature telemetry ->
+/// `[vsys_mv u16, die temperature i16 (0.1 °C, i16::MIN = unknown), backlight limit %]` (LE)
+pub const VENDOR_CMD_GET_TELEMETRY: u8 = 0x56;
+/// Read button-to-report latency ->
+/// `[average_us u32, worst_us u32, over budget u32, budget_us u32]` (LE)
+pub const VENDOR_CMD_GET_INPUT_LATENCY: u8 = 0x57;
+/// Read image pool and command queue use -> `[in use, peak, capacity]` per
+/// `stats::Pool`, in `Pool` order
+pub const VENDOR_CMD_GET_POOL_USAGE: u8 = 0x58;
+/// Read the pixel path benchmark -> `[convert scalar, convert interp, scale
+/// scalar, scale interp]` in ns per pixel, u16 LE each (0 = not measured;
+/// only `interp-convert` builds measure)
+pub const VENDOR_CMD_GET_CONVERSION_BENCH: u8 = 0x59;
+/// Read debounce timings: `[offset, count]` -> `[press_ms, release_ms, eager]` per key
+pub const VENDOR_CMD_GET_DEBOUNCE: u8 = 0x70;
+/// Set and persist one key's debounce: `[key (0xFF = all), press_ms, release_ms, eager]`
+pub const VENDOR_CMD_SET_DEBOUNCE: u8 = 0x71;
+/// Restore the default debounce for every key
+pub const VENDOR_CMD_RESET_DEBOUNCE: u8 = 0x72;
+/// Read the stuck-key policy and state ->
+/// `[timeout_secs (0 = off), mask, stuck keys u32 LE (physical index bits)]`
+pub const VENDOR_CMD_GET_STUCK_KEYS: u8 = 0x73;
+/// Set and persist the stuck-key policy: `[timeout_secs (0 = off, max 120), mask]`
+pub const VENDOR_CMD_SET_STUCK_KEY_POLICY: u8 = 0x74;
+/// Read runtime log levels -> `[count, level per subsystem...]`
+pub const VENDOR_CMD_GET_LOG_LEVELS: u8 = 0x80;
+/// Set and persist a log level: `[subsystem (0xFF = all), level (0 = off .. 5 = trace)]`
+pub const VENDOR_CMD_SET_LOG_LEVEL: u8 = 0x81;
+/// Read buffered defmt frames (`log-buffer` builds): `[from u32]` ->
+/// `[start u32, bytes...]`; `start` is past `from` when older bytes were lost
+pub const VENDOR_CMD_READ_LOG: u8 = 0x82;
+/// Read the host brightness -> `[percent]`
+pub const VENDOR_CMD_GET_BRIGHTNESS: u8 = 0x90;
+/// Set the brightness of every output: `[percent]` (saved like a host level once it settles)
+pub const VENDOR_CMD_SET_BRIGHTNESS: u8 = 0x91;
+/// Read the board pin map -> `[rows, cols, row pins..., col pins...,
+/// data, clock, cs, dc, rst, backlight, status LED, USB LED, error LED,
+/// display bus (0 = SPI0, 1 = SPI1)]`
+pub const VENDOR_CMD_GET_PIN_MAP: u8 = 0x92;
+/// Read the device personality -> `[active PID (u16 LE), stored PID (u16 LE, 0xFFFF = none)]`
+pub const VENDOR_CMD_GET_PERSONALITY: u8 = 0x93;
+/// Store the device to emulate from the next boot: `[PID (u16 LE), 0xFFFF = build default]`.
+/// Only builds that select the device at runtime honor it.
+pub const VENDOR_CMD_SET_PERSONALITY: u8 = 0x94;
+/// Read the panel rotation -> `[active quarter turns, stored (0xFF = board default)]`
+pub const VENDOR_CMD_GET_ROTATION: u8 = 0x95;
+/// Set and persist the panel rotation: `[quarter turns clockwise, 0xFF = board default]`
+pub const VENDOR_CMD_SET_ROTATION: u8 = 0x96;
+/// Read the firmware version -> `[major, minor, patch, hash length, git hash...]`
+pub const VENDOR_CMD_GET_VERSION: u8 = 0x97;
+/// Read night mode -> `[trigger (0 = off, 1 = on, 2 = scheduled), backlight cap %,
+/// start u16 LE, end u16 LE (minutes after midnight), active, minute of day u16 LE
+/// (0xFFFF = clock not set)]`
+pub const VENDOR_CMD_GET_NIGHT_MODE: u8 = 0x98;
+/// Set and persist night mode: `[trigger, backlight cap %, start u16 LE, end u16 LE]`
+pub const VENDOR_CMD_SET_NIGHT_MODE: u8 = 0x99;
+/// Set the time of day for night mode schedules: `[minutes after midnight u16 LE]`
+/// (not kept across power cycles)
+pub const VENDOR_CMD_SET_CLOCK: u8 = 0x9A;
+/// Read the panel gamma curve -> `[output for input 0, 17, 34 ... 255]` (16 points)
+pub const VENDOR_CMD_GET_GAMMA: u8 = 0xA0;
+/// Set and persist the panel gamma curve: `[16 outputs for inputs 0, 17 ... 255]`
+pub const VENDOR_CMD_SET_GAMMA: u8 = 0xA1;
+/// Remove the gamma curve (uncorrected output)
+pub const VENDOR_CMD_RESET_GAMMA: u8 = 0xA2;
+/// Erase a stored key image slot for upload: `[key, variant (0 = icon, 1 = pressed)]`
+pub const VENDOR_CMD_BEGIN_KEY_IMAGE: u8 = 0xB0;
+/// Write part of a stored key image: `[key, variant, offset u16 LE, data...]`
+pub const VENDOR_CMD_WRITE_KEY_IMAGE: u8 = 0xB1;
+/// Validate and activate an uploaded key image: `[key, variant, length u32 LE]`
+pub const VENDOR_CMD_COMMIT_KEY_IMAGE: u8 = 0xB2;
+/// Remove a key's stored images: `[key]`
+pub const VENDOR_CMD_CLEAR_KEY_IMAGES: u8 = 0xB3;
+/// Read fader positions -> `[count, position (0-255) per fader...]`
+pub const VENDOR_CMD_GET_FADERS: u8 = 0x60;
+/// Report fader movement as Plus dial turns: `[enabled]`
+pub const VENDOR_CMD_SET_FADER_DIALS: u8 = 0x61;
+/// Read the host OS profile -> `[active, stored (0xFF = none)]`
+/// (0 = unknown, 1 = Windows, 2 = macOS, 3 = Linux)
+pub const VENDOR_CMD_GET_HOST_PROFILE: u8 = 0xC0;
+/// Set and persist the host OS profile: `[OS, 0xFF = none]`
+pub const VENDOR_CMD_SET_HOST_PROFILE: u8 = 0xC1;
+/// Clear the host compatibility matrix and start recording
+pub const VENDOR_CMD_START_COMPAT_MATRIX: u8 = 0xC2;
+/// Read the host compatibility matrix -> `[recording, seen (u16 LE), failed (u16 LE),
+/// last unanswered read ID, last unanswered write ID]` (bits: `compat::Probe`)
+pub const VENDOR_CMD_GET_COMPAT_MATRIX: u8 = 0xC3;
+/// Read the LED ring effects -> `[idle (0 = off, 1 = solid, 2 = breathe), R, G, B,
+/// flash on image, flash on error, LEDs fitted (0 = no ring in this build)]`
+pub const VENDOR_CMD_GET_RING_EFFECTS: u8 = 0xD0;
+/// Set and persist the LED ring effects: `[idle, R, G, B, flash on image, flash on error]`
+pub const VENDOR_CMD_SET_RING_EFFECTS: u8 = 0xD1;
+/// Start a firmware upload over the vendor bulk interface: `[size u32 LE, CRC-32 u32 LE]`
+pub const VENDOR_CMD_BEGIN_FIRMWARE: u8 = 0xE0;
+/// Read the firmware upload state -> `[state (0 = idle, 1 = receiving, 2 = staged,
+/// 3 = failed), received u32 LE, size u32 LE, largest image u32 LE]`
+pub const VENDOR_CMD_GET_FIRMWARE_STATUS: u8 = 0xE1;
+/// Check the uploaded firmware and restart to apply it
+pub const VENDOR_CMD_COMMIT_FIRMWARE: u8 = 0xE2;
+
+/// Command completed successfully
+pub const VENDOR_STATUS_OK: u8 = 0x00;
+/// Command not recognized
+pub const VENDOR_STATUS_UNKNOWN_COMMAND: u8 = 0x01;
+/// Command payload invalid
+pub const VENDOR_STATUS_INVALID: u8 = 0x02;
+/// Settings could not be persisted
+pub const VENDOR_STATUS_STORAGE_ERROR: u8 = 0x03;
+
+/// Length after the report ID of settings report `report_id`, if it is one
+pub fn settings_report_len(report_id: u8) -> Option<usize> {
+    SETTINGS_REPORTS
+        .iter()
+        .find(|(id, _)| *id == report_id)
+        .map(|(_, len)| *len)
+}
+
+/// Offset of the response payload within the GET_REPORT reply
+const RESPONSE_PAYLOAD_OFFSET: usize = 4;
+
+/// Vendor report handler holding the response to the last command
+#[derive(Debug)]
+pub struct VendorHandler {
+    device: Device,
+    last_command: u8,
+    status: u8,
+    payload: [u8; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET],
+    payload_len: usize,
+}
+
+impl VendorHandler {
+    pub const fn new(device: Device) -> Self {
+        Self {
+            device,
+            last_command: 0,
+            status: VENDOR_STATUS_OK,
+            payload: [0; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET],
+            payload_len: 0,
+        }
+    }
+
+    /// Handle a vendor SET_REPORT (data includes the report ID)
+    pub fn handle_set_report(&mut self, data: &[u8]) {
+        if data.len() < 2 || data[0] != FEATURE_REPORT_VENDOR {
+            warn!("Malformed vendor report ({} bytes)", data.len());
+            return;
+        }
+
+        let command = data[1];
+        let payload = &data[2..];
+        self.last_command = command;
+        self.payload_len = 0;
+
+        self.status = match command {
+            VENDOR_CMD_GET_SERIAL => {
+                let serial = crate::config::usb_serial();
+                self.set_payload(serial.as_bytes());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_SERIAL => self.set_serial(payload),
+            VENDOR_CMD_RESET_SERIAL => {
+                settings::update(|s| s.serial_override = None);
+                Self::persist()
+            }
+            VENDOR_CMD_FACTORY_RESET => match crate::storage::factory_reset() {
+                Ok(()) => VENDOR_STATUS_OK,
+                Err(e) => {
+                    warn!("Failed to persist settings: {}", e);
+                    VENDOR_STATUS_STORAGE_ERROR
+                }
+            },
+            VENDOR_CMD_GET_REMAP => self.get_remap(payload),
+            VENDOR_CMD_SET_REMAP => self.set_remap(payload),
+            VENDOR_CMD_RESET_REMAP => {
+                settings::update(|s| s.key_remap = settings::IDENTITY_REMAP);
+                Self::persist()
+            }
+            VENDOR_CMD_GET_GESTURES => self.get_gestures(payload),
+            VENDOR_CMD_SET_GESTURE => Self::set_gesture(payload),
+            VENDOR_CMD_RESET_GESTURES => {
+                settings::update(|s| s.gestures = NO_GESTURES);
+                Self::persist()
+            }
+            VENDOR_CMD_GET_SHORTCUTS => self.get_shortcuts(payload),
+            VENDOR_CMD_SET_SHORTCUT => Self::set_shortcut(payload),
+            VENDOR_CMD_RESET_SHORTCUTS => {
+                settings::update(|s| s.shortcuts = DEFAULT_SHORTCUTS);
+                Self::persist()
+            }
+            VENDOR_CMD_GET_STANDALONE_TIMEOUT => {
+                let timeout = settings::with(|s| s.standalone_timeout_secs);
+                self.set_payload(&[timeout]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_STANDALONE_TIMEOUT => match payload.first() {
+                Some(&seconds) => {
+                    settings::update(|s| s.standalone_timeout_secs = seconds);
+                    info!("Standalone timeout set to {} s", seconds);
+                    Self::persist()
+                }
+                None => VENDOR_STATUS_INVALID,
+            },
+            VENDOR_CMD_GET_PAGES => {
+                let (page_key, page_count) = pages::with(|t| (t.page_key, t.page_count));
+                self.set_payload(&[page_key, page_count, STANDALONE_PAGES as u8]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_PAGES => Self::set_pages(payload),
+            VENDOR_CMD_GET_PAGE_ENTRY => self.get_page_entry(payload),
+            VENDOR_CMD_SET_PAGE_ENTRY => Self::set_page_entry(payload),
+            VENDOR_CMD_START_SELF_TEST => {
+                crate::selftest::request();
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_SELF_TEST => {
+                let result = crate::selftest::result();
+                let mask = result.passed_mask.to_le_bytes();
+                self.set_payload(&[
+                    result.state as u8,
+                    result.keys,
+                    mask[0],
+                    mask[1],
+                    mask[2],
+                    mask[3],
+                ]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_START_ROLLOVER_TEST => match payload.first() {
+                Some(&secs) => {
+                    crate::rollover::start(secs);
+                    VENDOR_STATUS_OK
+                }
+                None => VENDOR_STATUS_INVALID,
+            },
+            VENDOR_CMD_GET_ROLLOVER_TEST => {
+                self.set_payload(&crate::rollover::result().to_bytes());
+                VENDOR_STATUS_OK
+            }
+            #[cfg(feature = "latency-test")]
+            VENDOR_CMD_START_LATENCY_TEST => match payload.first() {
+                Some(&samples) => {
+                    crate::latency_test::start(samples);
+                    VENDOR_STATUS_OK
+                }
+                None => VENDOR_STATUS_INVALID,
+            },
+            #[cfg(feature = "latency-test")]
+            VENDOR_CMD_GET_LATENCY_TEST => {
+                self.set_payload(&crate::latency_test::result().to_bytes());
+                VENDOR_STATUS_OK
+            }
+            #[cfg(feature = "i2c-expansion")]
+            VENDOR_CMD_GET_EXPANSION => {
+                self.set_payload(&crate::expansion::to_bytes());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_METRICS => {
+                self.set_payload(&crate::stats::snapshot().to_bytes());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_RESET_METRICS => {
+                crate::stats::reset();
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_ERROR_COUNTS => self.get_error_counts(payload),
+            VENDOR_CMD_GET_FRAME_PACING => {
+                let (deferred, coalesced) = crate::stats::frame_pacing();
+                let mut payload = [0u8; 9];
+                payload[0..4].copy_from_slice(&deferred.to_le_bytes());
+                payload[4..8].copy_from_slice(&coalesced.to_le_bytes());
+                payload[8] = DISPLAY_MAX_KEY_FPS;
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_REASSEMBLY => {
+                let (resyncs, evictions, rejected) = crate::stats::image_reassembly();
+                let mut payload = [0u8; 12];
+                payload[0..4].copy_from_slice(&resyncs.to_le_bytes());
+                payload[4..8].copy_from_slice(&evictions.to_le_bytes());
+                payload[8..12].copy_from_slice(&rejected.to_le_bytes());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_PIPELINE_TIMING => {
+                self.set_payload(&crate::stats::pipeline_timing().to_bytes());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_INPUT_LATENCY => {
+                let (average, worst, late) = crate::stats::input_latency();
+                let mut payload = [0u8; 16];
+                payload[0..4].copy_from_slice(&average.to_le_bytes());
+                payload[4..8].copy_from_slice(&worst.to_le_bytes());
+                payload[8..12].copy_from_slice(&late.to_le_bytes());
+                payload[12..16].copy_from_slice(&INPUT_LATENCY_BUDGET_US.to_le_bytes());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_POOL_USAGE => {
+                let mut payload = [0u8; Pool::COUNT * PoolUsage::SIZE];
+                for (out, usage) in payload
+                    .chunks_exact_mut(PoolUsage::SIZE)
+                    .zip(crate::stats::pool_usage())
+                {
+                    out.copy_from_slice(&usage.to_bytes());
+                }
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_CONVERSION_BENCH => {
+                self.set_payload(&crate::stats::conversion_bench().to_bytes());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_TELEMETRY => {
+                let temperature = crate::power::temperature_deci_c().unwrap_or(i16::MIN);
+                let mut payload = [0u8; 5];
+                payload[0..2].copy_from_slice(&crate::power::vsys_mv().to_le_bytes());
+                payload[2..4].copy_from_slice(&temperature.to_le_bytes());
+                payload[4] = crate::power::brightness_limit();
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_BEGIN_KEY_IMAGE => Self::begin_key_image(payload),
+            VENDOR_CMD_WRITE_KEY_IMAGE => Self::write_key_image(payload),
+            VENDOR_CMD_COMMIT_KEY_IMAGE => Self::commit_key_image(payload),
+            VENDOR_CMD_CLEAR_KEY_IMAGES => match payload.first() {
+                Some(&key) => Self::store_status(crate::key_images::clear(key)),
+                None => VENDOR_STATUS_INVALID,
+            },
+            VENDOR_CMD_GET_DEBOUNCE => self.get_debounce(payload),
+            VENDOR_CMD_SET_DEBOUNCE => Self::set_debounce(payload),
+            VENDOR_CMD_RESET_DEBOUNCE => {
+                settings::update(|s| s.debounce = DEFAULT_DEBOUNCE);
+                Self::persist()
+            }
+            VENDOR_CMD_GET_STUCK_KEYS => {
+                let policy = settings::with(|s| s.stuck_key);
+                let mut payload = [0u8; 6];
+                payload[0] = policy.timeout_secs;
+                payload[1] = policy.mask as u8;
+                payload[2..6].copy_from_slice(&crate::buttons::stuck_keys().to_le_bytes());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_STUCK_KEY_POLICY => Self::set_stuck_key_policy(payload),
+            VENDOR_CMD_GET_FADERS => {
+                let mut payload = [0u8; 1 + FADER_COUNT];
+                payload[0] = FADER_COUNT as u8;
+                payload[1..].copy_from_slice(&crate::analog::fader_positions());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_FADER_DIALS => match payload.first() {
+                Some(&enabled) => {
+                    crate::analog::set_dial_mapping(enabled != 0);
+                    VENDOR_STATUS_OK
+                }
+                None => VENDOR_STATUS_INVALID,
+            },
+            VENDOR_CMD_GET_LOG_LEVELS => {
+                let mut payload = [0u8; 1 + SUBSYSTEM_COUNT];
+                payload[0] = SUBSYSTEM_COUNT as u8;
+                let levels = settings::with(|s| s.log_levels);
+                for (slot, level) in payload[1..].iter_mut().zip(levels) {
+                    *slot = level as u8;
+                }
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_LOG_LEVEL => Self::set_log_level(payload),
+            VENDOR_CMD_READ_LOG => self.read_log(payload),
+            VENDOR_CMD_GET_BRIGHTNESS => {
+                self.set_payload(&[crate::brightness::host_percent()]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_BRIGHTNESS => Self::set_brightness(payload),
+            VENDOR_CMD_GET_PIN_MAP => self.get_pin_map(),
+            VENDOR_CMD_GET_PERSONALITY => {
+                self.set_payload(&self.personality());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_PERSONALITY => Self::set_personality(payload),
+            VENDOR_CMD_GET_ROTATION => {
+                self.set_payload(&Self::rotation());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_ROTATION => Self::set_rotation(payload),
+            VENDOR_CMD_GET_VERSION => {
+                let mut payload = [0u8; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET];
+                let hash = APP_VERSION.git_hash.as_bytes();
+                let hash_len = hash.len().min(payload.len() - 4);
+                payload[..4].copy_from_slice(&[
+                    APP_VERSION.major,
+                    APP_VERSION.minor,
+                    APP_VERSION.patch,
+                    hash_len as u8,
+                ]);
+                payload[4..4 + hash_len].copy_from_slice(&hash[..hash_len]);
+                self.set_payload(&payload[..4 + hash_len]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_NIGHT_MODE => {
+                let night = crate::night::current();
+                let mut payload = [0u8; 9];
+                payload[0] = night.trigger as u8;
+                payload[1] = night.backlight_cap;
+                payload[2..4].copy_from_slice(&night.start_min.to_le_bytes());
+                payload[4..6].copy_from_slice(&night.end_min.to_le_bytes());
+                payload[6] = crate::night::is_active() as u8;
+                let minute = crate::night::minute_of_day().unwrap_or(0xFFFF);
+                payload[7..9].copy_from_slice(&minute.to_le_bytes());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_NIGHT_MODE => Self::set_night_mode(payload),
+            VENDOR_CMD_SET_CLOCK => match *payload {
+                [lo, hi, ..] if u16::from_le_bytes([lo, hi]) < MINUTES_PER_DAY => {
+                    crate::night::set_clock(u16::from_le_bytes([lo, hi]));
+                    VENDOR_STATUS_OK
+                }
+                _ => VENDOR_STATUS_INVALID,
+            },
+            VENDOR_CMD_GET_GAMMA => {
+                let points = settings::with(|s| s.gamma).unwrap_or(IDENTITY_GAMMA);
+                self.set_payload(&points);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_GAMMA => Self::set_gamma(payload),
+            VENDOR_CMD_RESET_GAMMA => {
+                settings::update(|s| s.gamma = None);
+                crate::gamma::sync_from_settings();
+                Self::persist()
+            }
+            VENDOR_CMD_GET_HOST_PROFILE => {
+                let stored = settings::with(|s| s.host_os).map_or(0xFF, |os| os as u8);
+                self.set_payload(&[crate::host::current() as u8, stored]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_HOST_PROFILE => Self::set_host_profile(payload),
+            VENDOR_CMD_START_COMPAT_MATRIX => {
+                crate::compat::start();
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_COMPAT_MATRIX => {
+                self.set_payload(&crate::compat::snapshot());
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_GET_RING_EFFECTS => {
+                let effects = crate::effects::current();
+                let [r, g, b] = effects.color;
+                let leds = if cfg!(feature = "led-ring") {
+                    LED_RING_LEDS as u8
+                } else {
+                    0
+                };
+                self.set_payload(&[
+                    effects.idle as u8,
+                    r,
+                    g,
+                    b,
+                    effects.flash_activity as u8,
+                    effects.flash_error as u8,
+                    leds,
+                ]);
+                VENDOR_STATUS_OK
+            }
+            VENDOR_CMD_SET_RING_EFFECTS => Self::set_ring_effects(payload),
+            #[cfg(feature = "firmware-update")]
+            VENDOR_CMD_BEGIN_FIRMWARE => Self::begin_firmware(payload),
+            #[cfg(feature = "firmware-update")]
+            VENDOR_CMD_GET_FIRMWARE_STATUS => {
+                let (state, received, size) = crate::firmware_update::status();
+                let mut payload = [0u8; 13];
+                payload[0] = state as u8;
+                payload[1..5].copy_from_slice(&(received as u32).to_le_bytes());
+                payload[5..9].copy_from_slice(&(size as u32).to_le_bytes());
+                payload[9..13]
+                    .copy_from_slice(&(crate::storage::FIRMWARE_SLOT_SIZE as u32).to_le_bytes());
+                self.set_payload(&payload);
+                VENDOR_STATUS_OK
+            }
+            #[cfg(feature = "firmware-update")]
+            VENDOR_CMD_COMMIT_FIRMWARE => Self::update_status(crate::firmware_update::commit()),
+            _ => VENDOR_STATUS_UNKNOWN_COMMAND,
+        };
+
+        debug!("Vendor command 0x{:02X} -> status {}", command, self.status);
+    }
+
+    /// Build the vendor GET_REPORT reply for the last command
+    pub fn get_report(&self, buf: &mut [u8]) -> Option<usize> {
+        let total_len = VENDOR_REPORT_SIZE.min(buf.len());
+        if total_len < RESPONSE_PAYLOAD_OFFSET {
+            return None;
+        }
+        buf.iter_mut().take(total_len).for_each(|b| *b = 0);
+        buf[0] = FEATURE_REPORT_VENDOR;
+        buf[1] = self.last_command;
+        buf[2] = self.status;
+        buf[3] = self.payload_len as u8;
+
+        let end = (RESPONSE_PAYLOAD_OFFSET + self.payload_len).min(total_len);
+        buf[RESPONSE_PAYLOAD_OFFSET..end]
+            .copy_from_slice(&self.payload[..end - RESPONSE_PAYLOAD_OFFSET]);
+        Some(total_len)
+    }
+
+    /// Build a settings report reply (`SETTINGS_REPORTS`)
+    pub fn get_settings_report(&self, report_id: u8, buf: &mut [u8]) -> Option<usize> {
+        let len = settings_report_len(report_id)?;
+        if buf.len() < 1 + len {
+            return None;
+        }
+        buf[0] = report_id;
+        let out = &mut buf[1..1 + len];
+        match report_id {
+            FEATURE_REPORT_SETTING_BRIGHTNESS => out[0] = crate::brightness::host_percent(),
+            FEATURE_REPORT_SETTING_PERSONALITY => out.copy_from_slice(&self.personality()),
+            FEATURE_REPORT_SETTING_REMAP => {
+                out.copy_from_slice(&settings::with(|s| s.key_remap));
+            }
+            FEATURE_REPORT_SETTING_ROTATION => out.copy_from_slice(&Self::rotation()),
+            _ => return None,
+        }
+        Some(1 + len)
+    }
+
+    /// Apply a settings report write (data includes the report ID).
+    /// Returns false if the value was rejected or could not be saved.
+    pub fn set_settings_report(&mut self, report_id: u8, data: &[u8]) -> bool {
+        let Some(len) = settings_report_len(report_id) else {
+            return false;
+        };
+        let Some(value) = data.get(1..1 + len) else {
+            warn!(
+                "Settings report 0x{:02X} too short ({} bytes)",
+                report_id,
+                data.len()
+            );
+            return false;
+        };
+        let status = match report_id {
+            FEATURE_REPORT_SETTING_BRIGHTNESS => Self::set_brightness(value),
+            FEATURE_REPORT_SETTING_PERSONALITY => Self::set_personality(&value[2..]),
+            FEATURE_REPORT_SETTING_REMAP => {
+                let mut payload = [0u8; 2 + MAX_KEYS];
+                payload[1] = MAX_KEYS as u8;
+                payload[2..].copy_from_slice(value);
+                self.set_remap(&payload)
+            }
+            FEATURE_REPORT_SETTING_ROTATION => Self::set_rotation(&value[1..]),
+            _ => VENDOR_STATUS_UNKNOWN_COMMAND,
+        };
+        status == VENDOR_STATUS_OK
+    }
+
+    fn set_payload(&mut self, data: &[u8]) {
+        let len = data.len().min(self.payload.len());
+        self.payload[..len].copy_from_slice(&data[..len]);
+        self.payload_len = len;
+    }
+
+    fn read_log(&mut self, payload: &[u8]) -> u8 {
+        let Some(from) = payload.first_chunk::<4>() else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let mut out = [0u8; 28];
+        let Some((start, count)) =
+            crate::log_backend::read(u32::from_le_bytes(*from), &mut out[4..])
+        else {
+            return VENDOR_STATUS_UNKNOWN_COMMAND;
+        };
+        out[..4].copy_from_slice(&start.to_le_bytes());
+        self.set_payload(&out[..4 + count]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_serial(&mut self, payload: &[u8]) -> u8 {
+        let Some((&len, rest)) = payload.split_first() else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let len = len as usize;
+        if len > rest.len() {
+            return VENDOR_STATUS_INVALID;
+        }
+        match settings::parse_serial(&rest[..len]) {
+            Some(serial) => {
+                info!(
+                    "Serial override set to {} (applies after reconnect)",
+                    serial.as_str()
+                );
+                settings::update(|s| s.serial_override = Some(serial));
+                Self::persist()
+            }
+            None => VENDOR_STATUS_INVALID,
+        }
+    }
+
+    fn get_remap(&mut self, payload: &[u8]) -> u8 {
+        let [offset, count, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (offset, count) = (offset as usize, count as usize);
+        if offset >= MAX_KEYS || count > self.payload.len() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let end = (offset + count).min(MAX_KEYS);
+        let remap = settings::with(|s| s.key_remap);
+        self.set_payload(&remap[offset..end]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_remap(&mut self, payload: &[u8]) -> u8 {
+        let [offset, count, ref entries @ ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (offset, count) = (offset as usize, count as usize);
+        // Targets are logical keys, so they must exist on the emulated device
+        let key_count = crate::config::streamdeck_keys();
+        if offset + count > MAX_KEYS
+            || count > entries.len()
+            || entries[..count].iter().any(|&k| k as usize >= key_count)
+        {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| {
+            s.key_remap[offset..offset + count].copy_from_slice(&entries[..count])
+        });
+        info!("Key remap updated ({} entries from {})", count, offset);
+        Self::persist()
+    }
+
+    fn get_gestures(&mut self, payload: &[u8]) -> u8 {
+        let [offset, count, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (offset, count) = (offset as usize, count as usize);
+        if offset >= MAX_KEYS || count * 2 > self.payload.len() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let end = (offset + count).min(MAX_KEYS);
+        let gestures = settings::with(|s| s.gestures);
+        let mut entries = [0u8; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET];
+        for (i, gesture) in gestures[offset..end].iter().enumerate() {
+            entries[i * 2] = gesture.long_press;
+            entries[i * 2 + 1] = gesture.double_press;
+        }
+        self.set_payload(&entries[..(end - offset) * 2]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_gesture(payload: &[u8]) -> u8 {
+        let [key, long_press, double_press, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let gesture = KeyGesture {
+            long_press,
+            double_press,
+        };
+        // Gestures fire on logical keys and tap logical keys, both of which
+        // must exist on the emulated device
+        let key_count = crate::config::streamdeck_keys();
+        if key as usize >= key_count || !gesture.is_valid(key_count) {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| s.gestures[key as usize] = gesture);
+        info!(
+            "Key {} gestures: long={} double={}",
+            key, long_press, double_press
+        );
+        Self::persist()
+    }
+
+    fn get_shortcuts(&mut self, payload: &[u8]) -> u8 {
+        let [offset, count, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (offset, count) = (offset as usize, count as usize);
+        if offset >= MAX_KEYS || count * 2 > self.payload.len() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let end = (offset + count).min(MAX_KEYS);
+        let shortcuts = settings::with(|s| s.shortcuts);
+        let mut entries = [0u8; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET];
+        for (i, shortcut) in shortcuts[offset..end].iter().enumerate() {
+            entries[i * 2] = shortcut.modifiers;
+            entries[i * 2 + 1] = shortcut.keycode;
+        }
+        self.set_payload(&entries[..(end - offset) * 2]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_shortcut(payload: &[u8]) -> u8 {
+        let [key, modifiers, keycode, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        if key as usize >= MAX_KEYS {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| s.shortcuts[key as usize] = KeyShortcut { modifiers, keycode });
+        info!(
+            "Key {} shortcut: modifiers=0x{:02X} keycode=0x{:02X}",
+            key, modifiers, keycode
+        );
+        Self::persist()
+    }
+
+    fn set_pages(payload: &[u8]) -> u8 {
+        let [page_key, page_count, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let key_valid = page_key == NO_PAGE_KEY || (page_key as usize) < MAX_KEYS;
+        if !key_valid || page_count == 0 || page_count as usize > STANDALONE_PAGES {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        pages::update(|t| {
+            t.page_key = page_key;
+            t.page_count = page_count;
+        });
+        info!("Standalone pages: key={} count={}", page_key, page_count);
+        Self::persist_pages()
+    }
+
+    fn get_page_entry(&mut self, payload: &[u8]) -> u8 {
+        let [page, key, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (page, key) = (page as usize, key as usize);
+        if page >= STANDALONE_PAGES || key >= MAX_KEYS {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let mut entry = pages::with(|t| t.entries[page][key]);
+        if page == 0 {
+            entry.shortcut = settings::with(|s| s.shortcuts[key]);
+        }
+        let mut out = [0u8; 2 + PAGE_LABEL_LEN];
+        out[0] = entry.shortcut.modifiers;
+        out[1] = entry.shortcut.keycode;
+        let label = entry.label();
+        out[2..2 + label.len()].copy_from_slice(label);
+        self.set_payload(&out[..2 + label.len()]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_page_entry(payload: &[u8]) -> u8 {
+        let [page, key, modifiers, keycode, ref label @ ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (page, key) = (page as usize, key as usize);
+        let label = &label[..label.iter().position(|&b| b == 0).unwrap_or(label.len())];
+        if page >= STANDALONE_PAGES
+            || key >= MAX_KEYS
+            || label.len() > PAGE_LABEL_LEN
+            || !label.iter().all(u8::is_ascii_graphic)
+        {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let shortcut = KeyShortcut { modifiers, keycode };
+        let mut entry = PageEntry {
+            shortcut,
+            label: [0; PAGE_LABEL_LEN],
+        };
+        entry.label[..label.len()].copy_from_slice(label);
+        pages::update(|t| t.entries[page][key] = entry);
+        info!(
+            "Page {} key {} shortcut: modifiers=0x{:02X} keycode=0x{:02X}",
+            page, key, modifiers, keycode
+        );
+        if page == 0 {
+            settings::update(|s| s.shortcuts[key] = shortcut);
+            let status = Self::persist();
+            if status != VENDOR_STATUS_OK {
+                return status;
+            }
+        }
+        Self::persist_pages()
+    }
+
+    fn get_debounce(&mut self, payload: &[u8]) -> u8 {
+        let [offset, count, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let (offset, count) = (offset as usize, count as usize);
+        if offset >= MAX_KEYS || count * 3 > self.payload.len() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        let end = (offset + count).min(MAX_KEYS);
+        let debounce = settings::with(|s| s.debounce);
+        let mut entries = [0u8; VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET];
+        for (entry, key) in entries.chunks_exact_mut(3).zip(&debounce[offset..end]) {
+            entry.copy_from_slice(&[key.press_ms, key.release_ms, key.eager as u8]);
+        }
+        self.set_payload(&entries[..(end - offset) * 3]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_debounce(payload: &[u8]) -> u8 {
+        let [key, press_ms, release_ms, eager, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let debounce = KeyDebounce {
+            press_ms,
+            release_ms,
+            eager: eager != 0,
+        };
+        if (key as usize >= MAX_KEYS && key != 0xFF) || !debounce.is_valid() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| match s.debounce.get_mut(key as usize) {
+            Some(slot) => *slot = debounce,
+            None => s.debounce = [debounce; MAX_KEYS],
+        });
+        info!(
+            "Key {} debounce: press={}ms release={}ms eager={}",
+            key, press_ms, release_ms, debounce.eager
+        );
+        Self::persist()
+    }
+
+    fn get_error_counts(&mut self, payload: &[u8]) -> u8 {
+        let first = payload.first().copied().unwrap_or(0) as usize;
+        if first >= Error::KIND_COUNT {
+            return VENDOR_STATUS_INVALID;
+        }
+        let mut reply = [0u8; Error::KIND_COUNT * 2];
+        for (out, count) in reply
+            .chunks_exact_mut(2)
+            .zip(&crate::stats::error_counts()[first..])
+        {
+            out.copy_from_slice(&((*count).min(u16::MAX as u32) as u16).to_le_bytes());
+        }
+        // Kinds past the report size are read by asking again from there
+        self.set_payload(&reply[..(Error::KIND_COUNT - first) * 2]);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_stuck_key_policy(payload: &[u8]) -> u8 {
+        let [timeout_secs, mask, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let policy = StuckKeyPolicy {
+            timeout_secs,
+            mask: mask != 0,
+        };
+        if !policy.is_valid() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| s.stuck_key = policy);
+        info!("Stuck key timeout {} s, mask={}", timeout_secs, policy.mask);
+        Self::persist()
+    }
+
+    fn set_ring_effects(payload: &[u8]) -> u8 {
+        let [idle, r, g, b, flash_activity, flash_error, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(idle) = IdleEffect::from_u8(idle) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let effects = RingEffects {
+            idle,
+            color: [r, g, b],
+            flash_activity: flash_activity != 0,
+            flash_error: flash_error != 0,
+        };
+
+        settings::update(|s| s.ring_effects = effects);
+        crate::effects::notify();
+        info!("LED ring effects: {}", effects);
+        Self::persist()
+    }
+
+    fn set_night_mode(payload: &[u8]) -> u8 {
+        let [trigger, backlight_cap, start_lo, start_hi, end_lo, end_hi, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(trigger) = NightTrigger::from_u8(trigger) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let night = NightMode {
+            trigger,
+            backlight_cap,
+            start_min: u16::from_le_bytes([start_lo, start_hi]),
+            end_min: u16::from_le_bytes([end_lo, end_hi]),
+        };
+        if !night.is_valid() {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| s.night_mode = night);
+        crate::night::notify();
+        info!("Night mode: {}", night);
+        Self::persist()
+    }
+
+    fn set_log_level(payload: &[u8]) -> u8 {
+        let [subsystem, level, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(level) = LogLevel::from_u8(level) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        if subsystem as usize >= SUBSYSTEM_COUNT && subsystem != 0xFF {
+            return VENDOR_STATUS_INVALID;
+        }
+
+        settings::update(|s| match s.log_levels.get_mut(subsystem as usize) {
+            Some(slot) => *slot = level,
+            None => s.log_levels = [level; SUBSYSTEM_COUNT],
+        });
+        crate::logging::sync_from_settings();
+        info!("Subsystem {} log level: {}", subsystem, level);
+        Self::persist()
+    }
+
+    fn get_pin_map(&mut self) -> u8 {
+        let hw = HardwareConfig::for_device(self.device);
+        let (rows, cols) = (hw.button_pins.row_pins, hw.button_pins.col_pins);
+        let d = &hw.display_pins;
+        let leds = &hw.led_pins;
+
+        let mut payload: Vec<u8, { VENDOR_REPORT_SIZE - RESPONSE_PAYLOAD_OFFSET }> = Vec::new();
+        let fits = payload
+            .extend_from_slice(&[rows.len() as u8, cols.len() as u8])
+            .is_ok()
+            && payload.extend_from_slice(rows).is_ok()
+            && payload.extend_from_slice(cols).is_ok()
+            && payload
+                .extend_from_slice(&[d.data, d.clock, d.cs, d.dc, d.rst, d.backlight])
+                .is_ok()
+            && payload
+                .extend_from_slice(&[leds.status, leds.usb, leds.error])
+                .is_ok()
+            && payload.push(d.bus.id()).is_ok();
+        if !fits {
+            return VENDOR_STATUS_INVALID;
+        }
+        self.set_payload(&payload);
+        VENDOR_STATUS_OK
+    }
+
+    fn set_brightness(payload: &[u8]) -> u8 {
+        match payload.first() {
+            Some(&percent) if percent <= 100 => {
+                crate::brightness::set_host_percent(percent);
+                DEVICE_BUS
+                    .immediate_publisher()
+                    .publish_immediate(DeviceCommand::SetBrightness(percent));
+                VENDOR_STATUS_OK
+            }
+            _ => VENDOR_STATUS_INVALID,
+        }
+    }
+
+    /// `[active PID, stored PID (0xFFFF = none)]`, u16 LE each
+    fn personality(&self) -> [u8; 4] {
+        let stored = settings::with(|s| s.personality_pid).unwrap_or(0xFFFF);
+        let mut reply = [0u8; 4];
+        reply[..2].copy_from_slice(&self.device.pid().to_le_bytes());
+        reply[2..].copy_from_slice(&stored.to_le_bytes());
+        reply
+    }
+
+    fn set_personality(payload: &[u8]) -> u8 {
+        let [lo, hi, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let pid = match u16::from_le_bytes([lo, hi]) {
+            0xFFFF => None,
+            pid if Device::from_pid(pid).is_some() => Some(pid),
+            _ => return VENDOR_STATUS_INVALID,
+        };
+
+        settings::update(|s| s.personality_pid = pid);
+        info!("Device personality set to {} (applies after reboot)", pid);
+        Self::persist()
+    }
+
+    /// `[active quarter turns, stored (0xFF = board default)]`
+    fn rotation() -> [u8; 2] {
+        let stored = settings::with(|s| s.display_rotation).map_or(0xFF, |r| r as u8);
+        [crate::hardware::display_rotation() as u8, stored]
+    }
+
+    fn set_rotation(payload: &[u8]) -> u8 {
+        let rotation = match payload.first() {
+            Some(0xFF) => None,
+            Some(&value) => match Rotation::from_u8(value) {
+                Some(rotation) => Some(rotation),
+                None => return VENDOR_STATUS_INVALID,
+            },
+            None => return VENDOR_STATUS_INVALID,
+        };
+
+        settings::update(|s| s.display_rotation = rotation);
+        let active = crate::hardware::display_rotation();
+        if !try_send_display(DisplayCommand::SetRotation(active)) {
+            warn!("Display busy, rotation {} applies after reboot", active);
+        }
+        Self::persist()
+    }
+
+    fn set_host_profile(payload: &[u8]) -> u8 {
+        let os = match payload.first() {
+            Some(0xFF) => None,
+            Some(&value) => match HostOs::from_u8(value) {
+                Some(os) => Some(os),
+                None => return VENDOR_STATUS_INVALID,
+            },
+            None => return VENDOR_STATUS_INVALID,
+        };
+
+        settings::update(|s| s.host_os = os);
+        info!("Host profile: {}", crate::host::current());
+        Self::persist()
+    }
+
+    fn set_gamma(payload: &[u8]) -> u8 {
+        let Some(points) = payload.get(..GAMMA_POINTS) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let mut curve = [0u8; GAMMA_POINTS];
+        curve.copy_from_slice(points);
+
+        settings::update(|s| s.gamma = Some(curve));
+        crate::gamma::sync_from_settings();
+        info!("Gamma curve set: {}", curve);
+        Self::persist()
+    }
+
+    fn begin_key_image(payload: &[u8]) -> u8 {
+        let [key, variant, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(variant) = KeyImageVariant::from_u8(variant) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        info!("Uploading stored {} image for key {}", variant, key);
+        Self::store_status(crate::key_images::begin(key, variant))
+    }
+
+    fn write_key_image(payload: &[u8]) -> u8 {
+        let [key, variant, offset_lo, offset_hi, ref data @ ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(variant) = KeyImageVariant::from_u8(variant) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let offset = u16::from_le_bytes([offset_lo, offset_hi]) as usize;
+        Self::store_status(crate::key_images::write(key, variant, offset, data))
+    }
+
+    fn commit_key_image(payload: &[u8]) -> u8 {
+        let [key, variant, l0, l1, l2, l3, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let Some(variant) = KeyImageVariant::from_u8(variant) else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let len = u32::from_le_bytes([l0, l1, l2, l3]) as usize;
+        let status = Self::store_status(crate::key_images::commit(key, variant, len));
+        if status == VENDOR_STATUS_OK {
+            info!("Stored {} image for key {} ({} bytes)", variant, key, len);
+        }
+        status
+    }
+
+    #[cfg(feature = "firmware-update")]
+    fn begin_firmware(payload: &[u8]) -> u8 {
+        let [s0, s1, s2, s3, c0, c1, c2, c3, ..] = *payload else {
+            return VENDOR_STATUS_INVALID;
+        };
+        let size = u32::from_le_bytes([s0, s1, s2, s3]) as usize;
+        let crc = u32::from_le_bytes([c0, c1, c2, c3]);
+        Self::update_status(crate::firmware_update::begin(size, crc))
+    }
+
+    #[cfg(feature = "firmware-update")]
+    fn update_status(result: Result<(), crate::firmware_update::UpdateError>) -> u8 {
+        use crate::firmware_update::UpdateError;
+        match result {
+            Ok(()) => VENDOR_STATUS_OK,
+            Err(UpdateError::Invalid) => VENDOR_STATUS_INVALID,
+            Err(UpdateError::Storage) => {
+                warn!("Failed to stage firmware update");
+                VENDOR_STATUS_STORAGE_ERROR
+            }
+        }
+    }
+
+    fn store_status(result: Result<(), StoreError>) -> u8 {
+        match result {
+            Ok(()) => VENDOR_STATUS_OK,
+            Err(StoreError::Invalid) => VENDOR_STATUS_INVALID,
+            Err(e) => {
+                warn!("Failed to store key image: {}", e);
+                VENDOR_STATUS_STORAGE_ERROR
+            }
+        }
+    }
+
+    fn persist_pages() -> u8 {
+        match crate::storage::save_pages() {
+            Ok(()) => VENDOR_STATUS_OK,
+            Err(e) => {
+                warn!("Failed to persist standalone pages: {}", e);
+                VENDOR_STATUS_STORAGE_ERROR
+            }
+        }
+    }
+
+    fn persist() -> u8 {
+        match crate::storage::save_settings() {
+            Ok(()) => VENDOR_STATUS_OK,
+            Err(e) => {
+                warn!("Failed to persist settings: {}", e);
+                VENDOR_STATUS_STORAGE_ERROR
+            }
+        }
+    }
+}
+
+impl Default for VendorHandler {
+    fn default() -> Self {
+        Self::new(crate::config::get_current_device())
+    }
+}
@@ -0,0 +1,201 @@
+//! Key rollover test
+//!
+//! Diagnostic mode for checking a build's wiring under multi-key presses
+//! before blaming the firmware. Started for a number of seconds by a vendor
+//! report command; while it runs, key presses go to the test instead of the
+//! host, and every raw scan (before debounce and ghost suppression) is
+//! recorded: which keys answered, the most held at once, and every
+//! combination the wiring can't represent.
+//!
+//! A matrix without diodes can't tell three keys on the corners of a
+//! rectangle from all four, so it only guarantees 2-key rollover; such
+//! combinations are counted as conflicts. With diodes (`MATRIX_HAS_DIODES`),
+//! or with direct wiring, every combination is representable. Results are
+//! logged over defmt and kept for the vendor report.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use defmt::*;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant};
+
+use crate::config::{MATRIX_HAS_DIODES, MAX_KEYS};
+
+/// Longest test that can be requested
+pub const MAX_TEST_SECS: u8 = 120;
+
+/// Result of the current or last rollover test
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+pub struct RolloverResult {
+    pub active: bool,
+    /// Keys the wiring can hold in any combination and still report exactly
+    pub guaranteed: u8,
+    /// Most keys seen held at once
+    pub max_held: u8,
+    /// Times an unrepresentable combination was pressed
+    pub conflicts: u16,
+    /// Bit per key: seen pressed during the test
+    pub seen_mask: u32,
+    /// Keys of the last unrepresentable combination
+    pub conflict_mask: u32,
+}
+
+impl RolloverResult {
+    const fn new() -> Self {
+        Self {
+            active: false,
+            guaranteed: 0,
+            max_held: 0,
+            conflicts: 0,
+            seen_mask: 0,
+            conflict_mask: 0,
+        }
+    }
+
+    /// Serialize for the vendor report (little-endian)
+    pub fn to_bytes(&self) -> [u8; 13] {
+        let mut out = [0u8; 13];
+        out[0] = self.active as u8;
+        out[1] = self.guaranteed;
+        out[2] = self.max_held;
+        out[3..5].copy_from_slice(&self.conflicts.to_le_bytes());
+        out[5..9].copy_from_slice(&self.seen_mask.to_le_bytes());
+        out[9..13].copy_from_slice(&self.conflict_mask.to_le_bytes());
+        out
+    }
+}
+
+/// Test state shared by the vendor report and the key scan
+struct RolloverTest {
+    result: RolloverResult,
+    ends_at: Instant,
+    /// Whether the previous scan held an unrepresentable combination
+    in_conflict: bool,
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static TEST: Mutex<CriticalSectionRawMutex, RefCell<RolloverTest>> =
+    Mutex::new(RefCell::new(RolloverTest {
+        result: RolloverResult::new(),
+        ends_at: Instant::from_ticks(0),
+        in_conflict: false,
+    }));
+
+/// Start a test of `secs` seconds (capped at `MAX_TEST_SECS`), or stop the
+/// running one with 0
+pub fn start(secs: u8) {
+    if secs == 0 {
+        TEST.lock(|test| finish(&mut test.borrow_mut()));
+        return;
+    }
+    let secs = secs.min(MAX_TEST_SECS);
+    info!("Rollover test: hold key combinations for {} s", secs);
+    TEST.lock(|test| {
+        *test.borrow_mut() = RolloverTest {
+            result: RolloverResult {
+                active: true,
+                ..RolloverResult::new()
+            },
+            ends_at: Instant::now() + Duration::from_secs(secs as u64),
+            in_conflict: false,
+        };
+    });
+    ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Whether the test currently owns the keys
+pub fn is_active() -> bool {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return false;
+    }
+    TEST.lock(|test| {
+        let mut test = test.borrow_mut();
+        if Instant::now() >= test.ends_at {
+            finish(&mut test);
+        }
+        test.result.active
+    })
+}
+
+/// Current or last result
+pub fn result() -> RolloverResult {
+    is_active();
+    TEST.lock(|test| test.borrow().result)
+}
+
+/// Record one raw scan of a `rows` x `cols` key matrix (direct wiring: one
+/// row). Returns whether the test is running, in which case the keys should
+/// not be reported to the host.
+pub fn record(raw: &[bool; MAX_KEYS], rows: usize, cols: usize) -> bool {
+    if !is_active() {
+        return false;
+    }
+    let keys = (rows * cols).min(MAX_KEYS);
+    let mask = raw[..keys]
+        .iter()
+        .enumerate()
+        .filter(|(_, &pressed)| pressed)
+        .fold(0u32, |mask, (key, _)| mask | 1 << key);
+
+    TEST.lock(|test| {
+        let mut test = test.borrow_mut();
+        let result = &mut test.result;
+        result.guaranteed = guaranteed_rollover(rows, cols);
+        result.seen_mask |= mask;
+        result.max_held = result.max_held.max(mask.count_ones() as u8);
+
+        let conflict = !MATRIX_HAS_DIODES && is_ambiguous(mask, rows, cols);
+        if conflict && !test.in_conflict {
+            test.result.conflicts = test.result.conflicts.saturating_add(1);
+            test.result.conflict_mask = mask;
+            warn!(
+                "Rollover test: keys 0x{:08X} read the same as another combination",
+                mask
+            );
+        }
+        test.in_conflict = conflict;
+    });
+    true
+}
+
+/// Keys that can be held in any combination without ghosting
+fn guaranteed_rollover(rows: usize, cols: usize) -> u8 {
+    if MATRIX_HAS_DIODES || rows < 2 || cols < 2 {
+        (rows * cols).min(MAX_KEYS) as u8
+    } else {
+        2
+    }
+}
+
+/// Whether two rows share two or more pressed columns, the reading a
+/// diode-less matrix gives for both three and four corners of a rectangle
+fn is_ambiguous(mask: u32, rows: usize, cols: usize) -> bool {
+    let row_mask = 1u32
+        .checked_shl(cols as u32)
+        .map_or(u32::MAX, |bit| bit - 1);
+    let row_bits = |row: usize| (mask >> (row * cols)) & row_mask;
+    (0..rows).any(|a| (a + 1..rows).any(|b| (row_bits(a) & row_bits(b)).count_ones() >= 2))
+}
+
+fn finish(test: &mut RolloverTest) {
+    ACTIVE.store(false, Ordering::Relaxed);
+    if !test.result.active {
+        return;
+    }
+    test.result.active = false;
+    let result = &test.result;
+    info!("========================================");
+    info!(
+        "Rollover test done: up to {} keys held, {} guaranteed by the wiring",
+        result.max_held, result.guaranteed
+    );
+    info!("Keys seen: 0x{:08X}", result.seen_mask);
+    if result.conflicts > 0 {
+        warn!(
+            "{} unrepresentable combinations, last 0x{:08X}; add diodes for full rollover",
+            result.conflicts, result.conflict_mask
+        );
+    }
+    info!("========================================");
+}
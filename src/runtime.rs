@@ -0,0 +1,149 @@
+//! Firmware entry points
+//!
+//! A device binary only picks its `Device` and hands over to one of these:
+//!
+//! - `run_single_core`: one executor on core 0 runs every task. Simplest to
+//!   debug and leaves core 1 parked; the display task's time slicing
+//!   (`DISPLAY_TIME_SLICE_US`) keeps key reports flowing during blits.
+//! - `run_dual_core`: core 0 runs USB, buttons and supervision, core 1 gets
+//!   its own executor for display and image work.
+//!
+//! Binaries built for dual core can be switched to a single executor with
+//! the `single-core` feature. Both apply a staged firmware update
+//! (`firmware-update`) before starting anything else.
+
+use defmt::*;
+use embassy_executor::{Executor, Spawner};
+use embassy_rp::multicore::{spawn_core1, Stack};
+use embassy_rp::peripherals::CORE1;
+use embassy_rp::Peripherals;
+use static_cell::StaticCell;
+
+use crate::config::CORE1_STACK_SIZE;
+use crate::device::{Device, DeviceConfig};
+use crate::hardware;
+use crate::supervisor::AppSupervisor;
+
+static mut CORE1_STACK: Stack<CORE1_STACK_SIZE> = Stack::new();
+static EXECUTOR0: StaticCell<Executor> = StaticCell::new();
+static EXECUTOR1: StaticCell<Executor> = StaticCell::new();
+
+/// Run the firmware for `device` with every task on core 0
+pub fn run_single_core(device: Device) -> ! {
+    let p = embassy_rp::init(Default::default());
+    #[cfg(feature = "firmware-update")]
+    crate::firmware_update::apply_staged();
+
+    let executor0 = EXECUTOR0.init(Executor::new());
+    executor0.run(|spawner| {
+        unwrap!(spawner.spawn(core0_task(spawner, p, device, false)));
+    })
+}
+
+/// Run the firmware for `device` with display and image work on core 1
+pub fn run_dual_core(device: Device) -> ! {
+    let p = embassy_rp::init(Default::default());
+    #[cfg(feature = "firmware-update")]
+    crate::firmware_update::apply_staged();
+    // SAFETY: the hardware layer hands out every other peripheral but never
+    // touches CORE1, so this is its only owner
+    let core1 = unsafe { CORE1::steal() };
+
+    spawn_core1(
+        core1,
+        unsafe { &mut *core::ptr::addr_of_mut!(CORE1_STACK) },
+        move || {
+            let executor1 = EXECUTOR1.init(Executor::new());
+            executor1.run(|spawner| {
+                unwrap!(spawner.spawn(core1_task(spawner, device)));
+            })
+        },
+    );
+
+    let executor0 = EXECUTOR0.init(Executor::new());
+    executor0.run(|spawner| {
+        unwrap!(spawner.spawn(core0_task(spawner, p, device, true)));
+    })
+}
+
+/// Core 0: bring up the hardware tasks, then run the supervisor. With
+/// `dual_core` the display task is left to core 1.
+#[embassy_executor::task]
+async fn core0_task(spawner: Spawner, p: Peripherals, device: Device, dual_core: bool) {
+    let mut supervisor = AppSupervisor::new_for_device(device);
+    supervisor.print_startup_banner();
+
+    let init = if dual_core {
+        hardware::init_hardware_tasks_core0(&spawner, p, device).await
+    } else {
+        hardware::init_hardware_tasks_for_device(&spawner, p, device).await
+    };
+    match init {
+        Ok(()) => {
+            info!("{} firmware initialized successfully", device.device_name());
+            supervisor.print_init_success();
+        }
+        Err(e) => {
+            error!("Failed to spawn hardware tasks: {:?}", e);
+            core::panic!("Hardware initialization failed");
+        }
+    }
+
+    supervisor.run().await;
+}
+
+/// Core 1: display and image processing tasks
+#[embassy_executor::task]
+async fn core1_task(spawner: Spawner, device: Device) {
+    info!("Core 1: Starting image processing and display tasks");
+    if let Err(e) = hardware::init_hardware_tasks_core1(&spawner, device).await {
+        error!("Core 1: Failed to spawn image processing tasks: {:?}", e);
+        core::panic!("Image processing initialization failed");
+    }
+}
+
+/// Build-time checks of `device` against the enabled features; `firmware_main!`
+/// evaluates this in a const so an unsupported binary does not compile
+pub const fn check_device(device: Device) {
+    #[cfg(feature = "secondary-deck")]
+    core::assert!(
+        crate::secondary_deck::fits(device),
+        "secondary-deck needs a 2x3 or 2x4 key matrix (Mini, Module 6, Plus)"
+    );
+    let _ = device;
+}
+
+/// Entry point of a device binary:
+///
+/// ```ignore
+/// #![no_std]
+/// #![no_main]
+///
+/// productiondeck::firmware_main!(Device::Xl);
+/// ```
+///
+/// Runs `run_dual_core` (or `run_single_core` with the `single-core`
+/// feature). `firmware_main!(Device::Xl, single_core)` always runs on one
+/// executor. Also links the panic handler.
+#[macro_export]
+macro_rules! firmware_main {
+    (Device::$device:ident) => {
+        $crate::firmware_main!(@entry $device, not(feature = "single-core"));
+    };
+    (Device::$device:ident, single_core) => {
+        $crate::firmware_main!(@entry $device, any());
+    };
+    (@entry $device:ident, $dual_core:meta) => {
+        use panic_halt as _;
+
+        #[cortex_m_rt::entry]
+        fn main() -> ! {
+            const DEVICE: $crate::device::Device = $crate::device::Device::$device;
+            const _: () = $crate::runtime::check_device(DEVICE);
+            #[cfg($dual_core)]
+            $crate::runtime::run_dual_core(DEVICE);
+            #[cfg(not($dual_core))]
+            $crate::runtime::run_single_core(DEVICE)
+        }
+    };
+}
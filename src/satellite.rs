@@ -0,0 +1,96 @@
+//! Expansion satellite (`i2c-satellite`)
+//!
+//! The other end of `i2c-expansion`: built with this feature, the board
+//! answers a main unit's polls as an I2C target on the expansion bus
+//! (`EXPANSION_SDA_PIN` / `EXPANSION_SCL_PIN`) at `SATELLITE_I2C_ADDRESS`,
+//! so a panel of keys can be built from a second RP2040 running this
+//! firmware. Every read gets the satellite answer of
+//! `protocol::expansion`, filled from the debounced physical keys before
+//! remap and gestures; the main unit maps them into its own layout
+//! (`HardwareConfig::expansion_boards`).
+//!
+//! The board keeps running as a deck of its own, so it can also be plugged
+//! into a host. The bus takes I2C0 on GPIO0/1, which the main unit's
+//! `i2c-expansion`, the parallel display, the FT6236 touch panel and the
+//! latency test also want; builds with any of them do not compile with it.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use defmt::*;
+use embassy_rp::bind_interrupts;
+use embassy_rp::i2c::InterruptHandler;
+use embassy_rp::i2c_slave::{self, Command, I2cSlave};
+use embassy_rp::peripherals::{I2C0, PIN_0, PIN_1};
+use embassy_rp::Peri;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+
+use crate::config::{EXPANSION_SCL_PIN, EXPANSION_SDA_PIN, SATELLITE_I2C_ADDRESS};
+use crate::protocol::expansion::encode_satellite_report;
+
+bind_interrupts!(struct I2cIrqs {
+    I2C0_IRQ => InterruptHandler<I2C0>;
+});
+
+/// Answer bytes past the report, for a main unit that reads too far
+const FILL_BYTE: u8 = 0xFF;
+
+/// Bus claimed at init, handed to the answering task
+static TARGET: Mutex<CriticalSectionRawMutex, RefCell<Option<I2cSlave<'static, I2C0>>>> =
+    Mutex::new(RefCell::new(None));
+/// Keys this board reports
+static KEYS: AtomicU8 = AtomicU8::new(0);
+/// Debounced physical keys pressed, by key index
+static LOCAL_KEYS: AtomicU32 = AtomicU32::new(0);
+
+/// Set up the satellite bus for a board with `keys` keys.
+/// Must be called before the satellite task is spawned.
+pub fn init(
+    i2c: Peri<'static, I2C0>,
+    sda: Peri<'static, PIN_0>,
+    scl: Peri<'static, PIN_1>,
+    keys: usize,
+) {
+    KEYS.store(keys.min(32) as u8, Ordering::Relaxed);
+    let mut config = i2c_slave::Config::default();
+    config.addr = SATELLITE_I2C_ADDRESS as u16;
+    config.general_call = false;
+    let target = I2cSlave::new(i2c, scl, sda, I2cIrqs, config);
+    TARGET.lock(|t| t.replace(Some(target)));
+}
+
+/// Record the debounced physical key state; called by the key scan
+pub fn set_local_keys(pressed: u32) {
+    LOCAL_KEYS.store(pressed, Ordering::Relaxed);
+}
+
+/// Answer the main unit's reads; never returns once started
+#[embassy_executor::task]
+pub async fn satellite_task() {
+    let Some(mut target) = TARGET.lock(|t| t.borrow_mut().take()) else {
+        error!("Satellite bus not initialized");
+        return;
+    };
+    info!(
+        "Expansion satellite at 0x{:02X} on SDA GPIO{}, SCL GPIO{}",
+        SATELLITE_I2C_ADDRESS, EXPANSION_SDA_PIN, EXPANSION_SCL_PIN
+    );
+
+    // Writes carry nothing for a satellite; they are taken and dropped
+    let mut request = [0u8; 8];
+    loop {
+        match target.listen(&mut request).await {
+            Ok(Command::Read) | Ok(Command::WriteRead(_)) => {
+                let answer = encode_satellite_report(
+                    KEYS.load(Ordering::Relaxed),
+                    LOCAL_KEYS.load(Ordering::Relaxed),
+                );
+                if let Err(e) = target.respond_and_fill(&answer, FILL_BYTE).await {
+                    warn!("Satellite answer failed: {:?}", e);
+                }
+            }
+            Ok(Command::Write(_)) | Ok(Command::GeneralCall(_)) => {}
+            Err(e) => warn!("Satellite bus error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Secondary deck (`secondary-deck`)
+//!
+//! Some builds put a second control beside the main deck on the same RP2040,
+//! such as a foot pedal next to a 6-key deck. The secondary deck is a logical
+//! device of its own:
+//!
+//! - its keys are wired straight to `SECONDARY_DECK_PINS` (active low) and
+//!   scanned and debounced here, independent of the main deck's layout
+//! - key states travel on `SECONDARY_BUTTON_CHANNEL`, never on
+//!   `BUTTON_CHANNEL`, so they never show up as keys of the emulated StreamDeck
+//! - the USB device gets a second HID interface for it, with its own report
+//!   descriptor and report ID (`SECONDARY_REPORT_ID`)
+//!
+//! The interface uses a vendor usage page, so the StreamDeck software, which
+//! opens the Elgato collection, keeps seeing only the main deck; companion
+//! tools read the secondary deck from its own interface. Its keys skip the
+//! main deck's pipeline: no remap, gestures, pages or standalone shortcuts,
+//! and it has no display.
+//!
+//! The pins are matrix pins of the larger layouts and the touch panels, so
+//! the secondary deck only fits beside a 2x3 or 2x4 key matrix (Mini,
+//! Module 6, Plus) without a touch panel. Other devices fail to build with
+//! it (`fits`, checked by `firmware_main!`), as do touch builds.
+
+use core::cell::RefCell;
+use defmt::*;
+use embassy_rp::gpio::Input;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::channels::SECONDARY_BUTTON_CHANNEL;
+use crate::config::{
+    BUTTON_DEBOUNCE_MS, BUTTON_SCAN_RATE_HZ, SECONDARY_DECK_KEYS, SECONDARY_DECK_PINS,
+};
+use crate::device::Device;
+use crate::types::ButtonState;
+
+/// Report ID of the secondary deck's key report, outside every Elgato report ID
+pub const SECONDARY_REPORT_ID: u8 = 0x21;
+
+/// Key report: report ID, then one byte per key (1 = pressed)
+pub const SECONDARY_REPORT_SIZE: usize = 1 + SECONDARY_DECK_KEYS;
+
+/// Vendor collection with one button byte per key
+pub const SECONDARY_REPORT_DESCRIPTOR: &[u8] = &[
+    0x06, 0x00, 0xff, // Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x01, // Usage (0x01)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x21, // Report ID (SECONDARY_REPORT_ID)
+    0x05, 0x09, // Usage Page (Button)
+    0x19, 0x01, // Usage Minimum (Button 1)
+    0x29, 0x03, // Usage Maximum (Button SECONDARY_DECK_KEYS)
+    0x15, 0x00, // Logical Minimum (0)
+    0x25, 0x01, // Logical Maximum (1)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x03, // Report Count (SECONDARY_DECK_KEYS)
+    0x81, 0x02, // Input (Data,Var,Abs)
+    0xc0, // End Collection
+];
+
+// The descriptor spells out the report ID and key count
+const _: () = core::assert!(SECONDARY_REPORT_DESCRIPTOR[8] == SECONDARY_REPORT_ID);
+const _: () = core::assert!(SECONDARY_REPORT_DESCRIPTOR[14] as usize == SECONDARY_DECK_KEYS);
+const _: () = core::assert!(SECONDARY_REPORT_DESCRIPTOR[22] as usize == SECONDARY_DECK_KEYS);
+
+/// Whether `device`'s key matrix leaves `SECONDARY_DECK_PINS` free
+pub const fn fits(device: Device) -> bool {
+    matches!(device.layout().matrix(), (2, 3) | (2, 4))
+}
+
+/// Key inputs claimed at init, handed to the scan task
+static INPUTS: Mutex<
+    CriticalSectionRawMutex,
+    RefCell<Option<[Input<'static>; SECONDARY_DECK_KEYS]>>,
+> = Mutex::new(RefCell::new(None));
+
+/// Take the key inputs.
+/// Must be called before the secondary deck task is spawned.
+pub fn init(inputs: [Input<'static>; SECONDARY_DECK_KEYS]) {
+    INPUTS.lock(|i| i.replace(Some(inputs)));
+}
+
+/// Build the key report for `state`; returns its length
+pub fn format_report(state: &ButtonState, report: &mut [u8; SECONDARY_REPORT_SIZE]) -> usize {
+    report[0] = SECONDARY_REPORT_ID;
+    for (key, byte) in report[1..].iter_mut().enumerate() {
+        *byte = state.is_pressed(key) as u8;
+    }
+    SECONDARY_REPORT_SIZE
+}
+
+/// Scan and debounce the secondary deck's keys; never returns once started
+#[embassy_executor::task]
+pub async fn secondary_deck_task() {
+    let Some(inputs) = INPUTS.lock(|i| i.take()) else {
+        warn!("Secondary deck not wired beside this layout, its interface stays idle");
+        return;
+    };
+    info!(
+        "Secondary deck: {} keys on GPIO {}",
+        SECONDARY_DECK_KEYS, SECONDARY_DECK_PINS
+    );
+
+    let debounce = Duration::from_millis(BUTTON_DEBOUNCE_MS as u64);
+    let scan_interval = Duration::from_millis(1000 / BUTTON_SCAN_RATE_HZ);
+    let sender = SECONDARY_BUTTON_CHANNEL.sender();
+    let mut state = ButtonState::new(SECONDARY_DECK_KEYS);
+    // Raw level per key and since when it has read that way
+    let mut raw = [(false, Instant::now()); SECONDARY_DECK_KEYS];
+
+    loop {
+        // Stop scanning while the host is asleep
+        if crate::power::usb_suspended() {
+            crate::power::wait_for_resume().await;
+        }
+
+        let now = Instant::now();
+        state.changed = false;
+        for (key, input) in inputs.iter().enumerate() {
+            let pressed = input.is_low();
+            if pressed != raw[key].0 {
+                raw[key] = (pressed, now);
+            }
+            // A level counts once it has held for the debounce time
+            if pressed != state.is_pressed(key) && now - raw[key].1 >= debounce {
+                state.set_button(key, pressed);
+            }
+        }
+
+        if state.changed {
+            state.scanned_at = now;
+            sender.send(state).await;
+        }
+
+        Timer::after(scan_interval).await;
+    }
+}
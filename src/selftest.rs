@@ -0,0 +1,252 @@
+//! Hardware self-test
+//!
+//! Bring-up aid for DIY builds. Triggered by holding key 0 while the device
+//! boots or by a vendor report command, it:
+//! 1. cycles solid colors and color bars across every key region,
+//! 2. walks the keys in order, showing the number of the key to press and
+//!    marking it green (pressed) or red (timed out / another key answered instead),
+//! 3. logs a summary over defmt and keeps it for the vendor report.
+//!
+//! While the test runs, key presses go to the test instead of the host and
+//! bypass the remap and gesture layers so the physical wiring is checked.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use defmt::*;
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Timer};
+
+use crate::channels::send_display;
+use crate::config::MAX_KEYS;
+use crate::device::{Device, DeviceConfig};
+use crate::gfx;
+use crate::types::{ButtonState, DisplayCommand};
+
+/// Hold time of each solid color / color bar step
+const PATTERN_STEP_MS: u64 = 500;
+
+/// Time allowed for each key press during the button walk
+const KEY_PRESS_TIMEOUT_MS: u64 = 10_000;
+
+/// How long to wait for the display to accept a pattern command
+const DISPLAY_SEND_TIMEOUT_MS: u64 = 50;
+
+/// How long the boot-time check watches key 0 after startup
+pub const BOOT_TRIGGER_WINDOW_MS: u64 = 500;
+
+// RGB565 colors used by the test
+const COLOR_RED: u16 = 0xF800;
+const COLOR_GREEN: u16 = 0x07E0;
+const COLOR_BLUE: u16 = 0x001F;
+const COLOR_WHITE: u16 = 0xFFFF;
+const COLOR_YELLOW: u16 = 0xFFE0;
+const COLOR_BLACK: u16 = 0x0000;
+
+/// Self-test progress as reported over the vendor report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum SelfTestState {
+    NotRun = 0,
+    Running = 1,
+    Passed = 2,
+    Failed = 3,
+}
+
+/// Result of the last self-test run
+#[derive(Clone, Copy, Debug)]
+pub struct SelfTestResult {
+    pub state: SelfTestState,
+    /// Number of keys tested
+    pub keys: u8,
+    /// Bit per key: set when the key answered correctly
+    pub passed_mask: u32,
+}
+
+impl SelfTestResult {
+    const fn new() -> Self {
+        Self {
+            state: SelfTestState::NotRun,
+            keys: 0,
+            passed_mask: 0,
+        }
+    }
+}
+
+static REQUEST: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static BUTTONS: Signal<CriticalSectionRawMutex, ButtonState> = Signal::new();
+static RESULT: Mutex<CriticalSectionRawMutex, RefCell<SelfTestResult>> =
+    Mutex::new(RefCell::new(SelfTestResult::new()));
+
+/// Ask the supervisor to run the self-test
+pub fn request() {
+    if !is_active() {
+        info!("Self-test requested");
+        REQUEST.signal(());
+    }
+}
+
+/// Wait for a self-test request
+pub async fn wait_request() {
+    REQUEST.wait().await
+}
+
+/// Whether the self-test currently owns the keys
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Hand a key state change to the running self-test.
+/// Returns false when no test is running and the state should go to the host.
+pub fn capture(state: &ButtonState) -> bool {
+    if !is_active() {
+        return false;
+    }
+    BUTTONS.signal(*state);
+    true
+}
+
+/// Result of the last run
+pub fn result() -> SelfTestResult {
+    RESULT.lock(|r| *r.borrow())
+}
+
+fn set_result(result: SelfTestResult) {
+    RESULT.lock(|r| *r.borrow_mut() = result);
+}
+
+/// Display commands are best-effort: builds without a display consumer still
+/// run the button walk and report results
+async fn display(command: DisplayCommand) {
+    let send = send_display(command);
+    if with_timeout(Duration::from_millis(DISPLAY_SEND_TIMEOUT_MS), send)
+        .await
+        .is_err()
+    {
+        debug!("Self-test: display not responding, pattern step dropped");
+    }
+}
+
+async fn fill_all(keys: u8, color: u16) {
+    for key_id in 0..keys {
+        display(DisplayCommand::FillKey { key_id, color }).await;
+    }
+}
+
+/// Run the full self-test
+pub async fn run(device: Device) {
+    let keys = (device.button_layout().total_keys as u8).min(MAX_KEYS as u8);
+
+    info!("========================================");
+    info!("Self-test started ({} keys)", keys);
+    info!("========================================");
+
+    ACTIVE.store(true, Ordering::Relaxed);
+    BUTTONS.reset();
+    set_result(SelfTestResult {
+        state: SelfTestState::Running,
+        keys,
+        passed_mask: 0,
+    });
+
+    // Display patterns
+    info!("Self-test: display patterns");
+    for color in [COLOR_RED, COLOR_GREEN, COLOR_BLUE, COLOR_WHITE] {
+        fill_all(keys, color).await;
+        Timer::after(Duration::from_millis(PATTERN_STEP_MS)).await;
+    }
+    for key_id in 0..keys {
+        display(DisplayCommand::ColorBars(key_id)).await;
+    }
+    Timer::after(Duration::from_millis(PATTERN_STEP_MS * 2)).await;
+    display(DisplayCommand::ClearAll).await;
+
+    // Button walk
+    info!("Self-test: press each key in order");
+    let mut passed_mask = 0u32;
+    let mut held = 0u32;
+    for key_id in 0..keys {
+        display(DisplayCommand::DrawText {
+            key_id,
+            text: gfx::key_number(key_id),
+            fg: COLOR_BLACK,
+            bg: COLOR_YELLOW,
+        })
+        .await;
+        info!("Self-test: press key {}", key_id);
+
+        let ok = wait_for_key(key_id, &mut held).await;
+        if ok {
+            passed_mask |= 1 << key_id;
+        }
+        display(DisplayCommand::FillKey {
+            key_id,
+            color: if ok { COLOR_GREEN } else { COLOR_RED },
+        })
+        .await;
+    }
+
+    // Summary
+    let passed = passed_mask.count_ones() as u8;
+    let state = if passed == keys {
+        SelfTestState::Passed
+    } else {
+        SelfTestState::Failed
+    };
+    info!("========================================");
+    info!(
+        "Self-test {:?}: {}/{} keys OK (mask 0x{:08X})",
+        state, passed, keys, passed_mask
+    );
+    for key_id in (0..keys).filter(|&k| passed_mask & (1 << k) == 0) {
+        warn!("Self-test: key {} failed", key_id);
+    }
+    info!("========================================");
+
+    set_result(SelfTestResult {
+        state,
+        keys,
+        passed_mask,
+    });
+
+    Timer::after(Duration::from_millis(PATTERN_STEP_MS * 4)).await;
+    display(DisplayCommand::ClearAll).await;
+    ACTIVE.store(false, Ordering::Relaxed);
+}
+
+/// Wait for `key_id` to be pressed. Returns false on timeout or when a
+/// different key is pressed first (likely miswired row/column).
+/// `held` tracks keys still down from earlier steps (bitmask) so only new
+/// presses count.
+async fn wait_for_key(key_id: u8, held: &mut u32) -> bool {
+    let timeout = Timer::after(Duration::from_millis(KEY_PRESS_TIMEOUT_MS));
+    let mut timeout = core::pin::pin!(timeout);
+
+    loop {
+        match select(BUTTONS.wait(), &mut timeout).await {
+            Either::First(state) => {
+                let pressed = state.pressed_keys().find(|&k| *held & (1 << k) == 0);
+                *held = state.pressed_mask();
+                match pressed {
+                    Some(k) if k == key_id as usize => {
+                        info!("Self-test: key {} OK", key_id);
+                        return true;
+                    }
+                    Some(k) => {
+                        warn!("Self-test: expected key {}, got key {}", key_id, k);
+                        return false;
+                    }
+                    // Release events
+                    None => continue,
+                }
+            }
+            Either::Second(()) => {
+                warn!("Self-test: key {} timed out", key_id);
+                return false;
+            }
+        }
+    }
+}
@@ -0,0 +1,496 @@
+//! Persistent user settings
+//!
+//! Settings are kept in RAM behind a critical-section mutex so both cores can
+//! read them, and are serialized into a small record that `storage` writes to
+//! the last flash sector.
+//!
+//! The record ends in a trailer: the layout version (`SETTINGS_VERSION`) and
+//! a CRC-32 over everything before it. Fields added in spare bytes read as
+//! erased (default) from older records and need no new version; a version
+//! bump is for fields that move or change meaning, with a step in `migrate`
+//! that rewrites the old layout. A record from an older layout is migrated
+//! at boot and saved back; a damaged record or one from newer firmware is
+//! ignored (defaults) and left in flash until settings are next saved.
+
+use crate::buttons::{KeyDebounce, StuckKeyPolicy, DEFAULT_DEBOUNCE};
+use crate::config::MAX_KEYS;
+use crate::device::Device;
+use crate::effects::{IdleEffect, RingEffects};
+use crate::gamma::GAMMA_POINTS;
+use crate::gestures::{KeyGesture, NO_GESTURES};
+use crate::host::HostOs;
+use crate::logging::{LogLevel, DEFAULT_LOG_LEVELS, SUBSYSTEM_COUNT};
+use crate::night::{NightMode, NightTrigger};
+use crate::standalone::{KeyShortcut, DEFAULT_SHORTCUTS, DEFAULT_STANDALONE_TIMEOUT_SECS};
+use crate::types::Rotation;
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use heapless::String;
+
+/// Maximum length of a user-provided serial number
+pub const MAX_SERIAL_LEN: usize = 16;
+
+/// Serial number string type
+pub type SerialString = String<MAX_SERIAL_LEN>;
+
+/// Size of the serialized settings record in flash
+/// Grown from 256 bytes; older records read the extra bytes as erased.
+pub const SETTINGS_RECORD_SIZE: usize = 512;
+
+/// Record magic ("PDCK")
+const SETTINGS_MAGIC: [u8; 4] = *b"PDCK";
+
+/// Layout version this firmware writes. Version 1 records predate the
+/// trailer: it reads as erased.
+pub const SETTINGS_VERSION: u8 = 2;
+
+/// Offset of the layout version, in front of the CRC
+const VERSION_OFFSET: usize = SETTINGS_RECORD_SIZE - 5;
+
+/// Offset of the CRC-32 (u32 LE) over the record before it
+const CRC_OFFSET: usize = SETTINGS_RECORD_SIZE - 4;
+
+/// Offset of the display rotation, in the spare bytes after the serial
+const ROTATION_OFFSET: usize = 5 + MAX_SERIAL_LEN;
+
+/// Offset of the key remap table within the record
+const REMAP_OFFSET: usize = 24;
+
+/// Offset of the gesture table (long, double per key) within the record.
+/// Records written before gestures existed read back as erased (0xFF = none).
+const GESTURE_OFFSET: usize = REMAP_OFFSET + MAX_KEYS;
+
+/// Offset of the standalone shortcut table (modifiers, keycode per key)
+const SHORTCUT_OFFSET: usize = GESTURE_OFFSET + MAX_KEYS * 2;
+
+/// Offset of the standalone fallback timeout
+const STANDALONE_TIMEOUT_OFFSET: usize = SHORTCUT_OFFSET + MAX_KEYS * 2;
+
+/// Offset of the debounce table (press ms, release ms | eager << 7 per key)
+const DEBOUNCE_OFFSET: usize = STANDALONE_TIMEOUT_OFFSET + 1;
+
+/// Eager-press flag in the debounce release byte
+const DEBOUNCE_EAGER: u8 = 0x80;
+
+/// Offset of the runtime log levels (one byte per subsystem)
+const LOG_LEVEL_OFFSET: usize = DEBOUNCE_OFFSET + MAX_KEYS * 2;
+
+/// Offset of the device personality override (USB PID, u16 LE)
+const PERSONALITY_OFFSET: usize = LOG_LEVEL_OFFSET + SUBSYSTEM_COUNT;
+
+/// Offset of the gamma curve (present flag, then `GAMMA_POINTS` values)
+const GAMMA_OFFSET: usize = PERSONALITY_OFFSET + 2;
+
+/// Gamma present flag (anything else, e.g. erased, means no curve)
+const GAMMA_PRESENT: u8 = 0x01;
+
+/// Offset of the host OS profile (erased = none, generic behavior)
+const HOST_OS_OFFSET: usize = GAMMA_OFFSET + 1 + GAMMA_POINTS;
+
+/// Offset of the last host brightness in percent (erased = build default)
+const BRIGHTNESS_OFFSET: usize = HOST_OS_OFFSET + 1;
+
+/// Offset of the stuck-key policy (timeout seconds | mask << 7; erased = default)
+const STUCK_KEY_OFFSET: usize = BRIGHTNESS_OFFSET + 1;
+
+/// Mask flag in the stuck-key byte
+const STUCK_KEY_MASK: u8 = 0x80;
+
+/// Offset of the LED ring effects (idle effect, R, G, B, flash flags; erased = default)
+const RING_EFFECTS_OFFSET: usize = STUCK_KEY_OFFSET + 1;
+
+/// Flash flags in the ring effects record
+const RING_FLASH_ACTIVITY: u8 = 0x01;
+const RING_FLASH_ERROR: u8 = 0x02;
+
+/// Offset of the night mode setup (trigger, backlight cap, start u16 LE,
+/// end u16 LE; erased = default)
+const NIGHT_MODE_OFFSET: usize = RING_EFFECTS_OFFSET + 5;
+
+const _: () = assert!(ROTATION_OFFSET < REMAP_OFFSET);
+const _: () = assert!(NIGHT_MODE_OFFSET + 5 < VERSION_OFFSET);
+
+/// Erased flash value, used as "not stored" for fields added after the first layout
+const ERASED: u8 = 0xFF;
+
+/// Why a stored settings record was not loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum RecordError {
+    /// Erased or not a settings record
+    Missing,
+    /// CRC mismatch
+    Damaged,
+    /// Written by newer firmware with a layout this one does not know
+    Newer(u8),
+}
+
+/// Identity key remap table (physical index == protocol index)
+pub const IDENTITY_REMAP: [u8; MAX_KEYS] = identity_remap();
+
+const fn identity_remap() -> [u8; MAX_KEYS] {
+    let mut table = [0u8; MAX_KEYS];
+    let mut i = 0;
+    while i < MAX_KEYS {
+        table[i] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+/// User settings persisted across power cycles
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Serial number override (None = derive from flash unique ID)
+    pub serial_override: Option<SerialString>,
+    /// Physical key index -> protocol key index
+    pub key_remap: [u8; MAX_KEYS],
+    /// Long-press / double-press virtual keys per protocol key index
+    pub gestures: [KeyGesture; MAX_KEYS],
+    /// Keyboard shortcut per protocol key index for standalone mode
+    pub shortcuts: [KeyShortcut; MAX_KEYS],
+    /// Seconds without Elgato traffic before standalone mode (0 = never)
+    pub standalone_timeout_secs: u8,
+    /// Debounce timing per physical key index
+    pub debounce: [KeyDebounce; MAX_KEYS],
+    /// Runtime log level per `logging::Subsystem`
+    pub log_levels: [LogLevel; SUBSYSTEM_COUNT],
+    /// Device to emulate on runtime-selected builds, by USB PID (None = build default)
+    pub personality_pid: Option<u16>,
+    /// Panel orientation (None = board profile default)
+    pub display_rotation: Option<Rotation>,
+    /// Panel gamma curve (None = uncorrected)
+    pub gamma: Option<[u8; GAMMA_POINTS]>,
+    /// Host OS quirk profile (None = generic behavior)
+    pub host_os: Option<HostOs>,
+    /// Last brightness the host set, restored at power-up (None = build default)
+    pub brightness: Option<u8>,
+    /// When a held key counts as stuck, and whether it is then masked
+    pub stuck_key: StuckKeyPolicy,
+    /// Accent LED ring effects
+    pub ring_effects: RingEffects,
+    /// Night mode trigger, schedule and backlight cap
+    pub night_mode: NightMode,
+}
+
+impl Settings {
+    /// Factory default settings
+    pub const fn new() -> Self {
+        Self {
+            serial_override: None,
+            key_remap: IDENTITY_REMAP,
+            gestures: NO_GESTURES,
+            shortcuts: DEFAULT_SHORTCUTS,
+            standalone_timeout_secs: DEFAULT_STANDALONE_TIMEOUT_SECS,
+            debounce: DEFAULT_DEBOUNCE,
+            log_levels: DEFAULT_LOG_LEVELS,
+            personality_pid: None,
+            display_rotation: None,
+            gamma: None,
+            host_os: None,
+            brightness: None,
+            stuck_key: StuckKeyPolicy::DEFAULT,
+            ring_effects: RingEffects::DEFAULT,
+            night_mode: NightMode::DEFAULT,
+        }
+    }
+
+    /// Serialize settings into a flash record
+    pub fn to_record(&self, record: &mut [u8; SETTINGS_RECORD_SIZE]) {
+        record.fill(0xFF);
+        record[..4].copy_from_slice(&SETTINGS_MAGIC);
+
+        match &self.serial_override {
+            Some(serial) => {
+                record[4] = serial.len() as u8;
+                record[5..5 + serial.len()].copy_from_slice(serial.as_bytes());
+            }
+            None => record[4] = 0,
+        }
+
+        if let Some(rotation) = self.display_rotation {
+            record[ROTATION_OFFSET] = rotation as u8;
+        }
+
+        record[REMAP_OFFSET..REMAP_OFFSET + MAX_KEYS].copy_from_slice(&self.key_remap);
+
+        for (i, gesture) in self.gestures.iter().enumerate() {
+            record[GESTURE_OFFSET + i * 2] = gesture.long_press;
+            record[GESTURE_OFFSET + i * 2 + 1] = gesture.double_press;
+        }
+
+        for (i, shortcut) in self.shortcuts.iter().enumerate() {
+            record[SHORTCUT_OFFSET + i * 2] = shortcut.modifiers;
+            record[SHORTCUT_OFFSET + i * 2 + 1] = shortcut.keycode;
+        }
+
+        record[STANDALONE_TIMEOUT_OFFSET] = self.standalone_timeout_secs;
+
+        for (i, debounce) in self.debounce.iter().enumerate() {
+            record[DEBOUNCE_OFFSET + i * 2] = debounce.press_ms;
+            record[DEBOUNCE_OFFSET + i * 2 + 1] =
+                debounce.release_ms | if debounce.eager { DEBOUNCE_EAGER } else { 0 };
+        }
+
+        for (i, &level) in self.log_levels.iter().enumerate() {
+            record[LOG_LEVEL_OFFSET + i] = level as u8;
+        }
+
+        if let Some(points) = &self.gamma {
+            record[GAMMA_OFFSET] = GAMMA_PRESENT;
+            record[GAMMA_OFFSET + 1..GAMMA_OFFSET + 1 + GAMMA_POINTS].copy_from_slice(points);
+        }
+
+        if let Some(pid) = self.personality_pid {
+            record[PERSONALITY_OFFSET..PERSONALITY_OFFSET + 2].copy_from_slice(&pid.to_le_bytes());
+        }
+
+        if let Some(os) = self.host_os {
+            record[HOST_OS_OFFSET] = os as u8;
+        }
+
+        if let Some(percent) = self.brightness {
+            record[BRIGHTNESS_OFFSET] = percent;
+        }
+
+        record[STUCK_KEY_OFFSET] = self.stuck_key.timeout_secs
+            | if self.stuck_key.mask {
+                STUCK_KEY_MASK
+            } else {
+                0
+            };
+
+        let ring = &self.ring_effects;
+        let mut flags = 0;
+        if ring.flash_activity {
+            flags |= RING_FLASH_ACTIVITY;
+        }
+        if ring.flash_error {
+            flags |= RING_FLASH_ERROR;
+        }
+        record[RING_EFFECTS_OFFSET] = ring.idle as u8;
+        record[RING_EFFECTS_OFFSET + 1..RING_EFFECTS_OFFSET + 4].copy_from_slice(&ring.color);
+        record[RING_EFFECTS_OFFSET + 4] = flags;
+
+        let night = &self.night_mode;
+        record[NIGHT_MODE_OFFSET] = night.trigger as u8;
+        record[NIGHT_MODE_OFFSET + 1] = night.backlight_cap;
+        record[NIGHT_MODE_OFFSET + 2..NIGHT_MODE_OFFSET + 4]
+            .copy_from_slice(&night.start_min.to_le_bytes());
+        record[NIGHT_MODE_OFFSET + 4..NIGHT_MODE_OFFSET + 6]
+            .copy_from_slice(&night.end_min.to_le_bytes());
+
+        record[VERSION_OFFSET] = SETTINGS_VERSION;
+        let crc = crate::crc::crc32(&record[..CRC_OFFSET]);
+        record[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Deserialize settings from a flash record, migrating older layouts.
+    /// Also returns the layout version the record was written with.
+    pub fn from_record(record: &[u8; SETTINGS_RECORD_SIZE]) -> Result<(Self, u8), RecordError> {
+        if record[..4] != SETTINGS_MAGIC {
+            return Err(RecordError::Missing);
+        }
+
+        let version = match record[VERSION_OFFSET] {
+            ERASED => 1,
+            0 => return Err(RecordError::Damaged),
+            version => version,
+        };
+        if version > SETTINGS_VERSION {
+            return Err(RecordError::Newer(version));
+        }
+        if version >= 2 {
+            let stored = u32::from_le_bytes([
+                record[CRC_OFFSET],
+                record[CRC_OFFSET + 1],
+                record[CRC_OFFSET + 2],
+                record[CRC_OFFSET + 3],
+            ]);
+            if crate::crc::crc32(&record[..CRC_OFFSET]) != stored {
+                return Err(RecordError::Damaged);
+            }
+        }
+        let mut record = *record;
+        migrate(&mut record, version);
+
+        Ok((Self::parse(&record), version))
+    }
+
+    /// Read the fields of a current-layout record
+    fn parse(record: &[u8; SETTINGS_RECORD_SIZE]) -> Self {
+        let mut settings = Self::new();
+
+        let serial_len = record[4] as usize;
+        if serial_len > 0 {
+            // An unusable serial falls back to the flash unique ID
+            settings.serial_override = parse_serial(&record[5..5 + serial_len.min(MAX_SERIAL_LEN)]);
+        }
+
+        settings.display_rotation = Rotation::from_u8(record[ROTATION_OFFSET]);
+
+        // Fall back to identity if the table is missing or contains invalid entries
+        let remap = &record[REMAP_OFFSET..REMAP_OFFSET + MAX_KEYS];
+        if remap.iter().all(|&k| (k as usize) < MAX_KEYS) {
+            settings.key_remap.copy_from_slice(remap);
+        }
+
+        for (i, gesture) in settings.gestures.iter_mut().enumerate() {
+            let entry = KeyGesture {
+                long_press: record[GESTURE_OFFSET + i * 2],
+                double_press: record[GESTURE_OFFSET + i * 2 + 1],
+            };
+            if entry.is_valid(MAX_KEYS) {
+                *gesture = entry;
+            }
+        }
+
+        for (i, shortcut) in settings.shortcuts.iter_mut().enumerate() {
+            let (modifiers, keycode) = (
+                record[SHORTCUT_OFFSET + i * 2],
+                record[SHORTCUT_OFFSET + i * 2 + 1],
+            );
+            if (modifiers, keycode) != (ERASED, ERASED) {
+                *shortcut = KeyShortcut { modifiers, keycode };
+            }
+        }
+
+        if record[STANDALONE_TIMEOUT_OFFSET] != ERASED {
+            settings.standalone_timeout_secs = record[STANDALONE_TIMEOUT_OFFSET];
+        }
+
+        for (i, debounce) in settings.debounce.iter_mut().enumerate() {
+            let (press, release) = (
+                record[DEBOUNCE_OFFSET + i * 2],
+                record[DEBOUNCE_OFFSET + i * 2 + 1],
+            );
+            if (press, release) != (ERASED, ERASED) {
+                *debounce = KeyDebounce {
+                    press_ms: press,
+                    release_ms: release & !DEBOUNCE_EAGER,
+                    eager: release & DEBOUNCE_EAGER != 0,
+                };
+            }
+        }
+
+        for (i, level) in settings.log_levels.iter_mut().enumerate() {
+            if let Some(stored) = LogLevel::from_u8(record[LOG_LEVEL_OFFSET + i]) {
+                *level = stored;
+            }
+        }
+
+        let pid = u16::from_le_bytes([record[PERSONALITY_OFFSET], record[PERSONALITY_OFFSET + 1]]);
+        if Device::from_pid(pid).is_some() {
+            settings.personality_pid = Some(pid);
+        }
+
+        if record[GAMMA_OFFSET] == GAMMA_PRESENT {
+            let mut points = [0u8; GAMMA_POINTS];
+            points.copy_from_slice(&record[GAMMA_OFFSET + 1..GAMMA_OFFSET + 1 + GAMMA_POINTS]);
+            settings.gamma = Some(points);
+        }
+
+        settings.host_os = HostOs::from_u8(record[HOST_OS_OFFSET]);
+
+        if record[BRIGHTNESS_OFFSET] <= 100 {
+            settings.brightness = Some(record[BRIGHTNESS_OFFSET]);
+        }
+
+        let stuck_key = StuckKeyPolicy {
+            timeout_secs: record[STUCK_KEY_OFFSET] & !STUCK_KEY_MASK,
+            mask: record[STUCK_KEY_OFFSET] & STUCK_KEY_MASK != 0,
+        };
+        if stuck_key.is_valid() {
+            settings.stuck_key = stuck_key;
+        }
+
+        if let Some(idle) = IdleEffect::from_u8(record[RING_EFFECTS_OFFSET]) {
+            let flags = record[RING_EFFECTS_OFFSET + 4];
+            settings.ring_effects = RingEffects {
+                idle,
+                color: [
+                    record[RING_EFFECTS_OFFSET + 1],
+                    record[RING_EFFECTS_OFFSET + 2],
+                    record[RING_EFFECTS_OFFSET + 3],
+                ],
+                flash_activity: flags & RING_FLASH_ACTIVITY != 0,
+                flash_error: flags & RING_FLASH_ERROR != 0,
+            };
+        }
+
+        if let Some(trigger) = NightTrigger::from_u8(record[NIGHT_MODE_OFFSET]) {
+            let night = NightMode {
+                trigger,
+                backlight_cap: record[NIGHT_MODE_OFFSET + 1],
+                start_min: u16::from_le_bytes([
+                    record[NIGHT_MODE_OFFSET + 2],
+                    record[NIGHT_MODE_OFFSET + 3],
+                ]),
+                end_min: u16::from_le_bytes([
+                    record[NIGHT_MODE_OFFSET + 4],
+                    record[NIGHT_MODE_OFFSET + 5],
+                ]),
+            };
+            if night.is_valid() {
+                settings.night_mode = night;
+            }
+        }
+
+        settings
+    }
+}
+
+/// Layout steps: `MIGRATIONS[n]` rewrites a version n + 1 record as version n + 2
+const MIGRATIONS: [fn(&mut [u8; SETTINGS_RECORD_SIZE]); SETTINGS_VERSION as usize - 1] =
+    [migrate_v1_to_v2];
+
+/// Rewrite a record from layout `version` into the current layout
+fn migrate(record: &mut [u8; SETTINGS_RECORD_SIZE], version: u8) {
+    for step in &MIGRATIONS[version as usize - 1..] {
+        step(record);
+    }
+}
+
+/// Version 2 added the trailer in bytes no field used; nothing moves
+fn migrate_v1_to_v2(_record: &mut [u8; SETTINGS_RECORD_SIZE]) {}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Validate and convert raw bytes into a serial number.
+/// Only printable ASCII without spaces is accepted, as hosts use the serial as an identifier.
+pub fn parse_serial(bytes: &[u8]) -> Option<SerialString> {
+    if bytes.is_empty() || bytes.len() > MAX_SERIAL_LEN {
+        return None;
+    }
+    if !bytes.iter().all(|b| b.is_ascii_graphic()) {
+        return None;
+    }
+
+    let mut serial = SerialString::new();
+    for &b in bytes {
+        serial.push(b as char).ok()?;
+    }
+    Some(serial)
+}
+
+static SETTINGS: Mutex<CriticalSectionRawMutex, RefCell<Settings>> =
+    Mutex::new(RefCell::new(Settings::new()));
+
+/// Read the current settings
+pub fn with<R>(f: impl FnOnce(&Settings) -> R) -> R {
+    SETTINGS.lock(|settings| f(&settings.borrow()))
+}
+
+/// Modify the current settings (in RAM only; call `storage::save_settings` to persist)
+pub fn update<R>(f: impl FnOnce(&mut Settings) -> R) -> R {
+    SETTINGS.lock(|settings| f(&mut settings.borrow_mut()))
+}
+
+/// Replace the current settings
+pub fn replace(new_settings: Settings) {
+    SETTINGS.lock(|settings| *settings.borrow_mut() = new_settings);
+}
@@ -0,0 +1,100 @@
+//! Startup splash and "waiting for host" screen
+//!
+//! Blank panels look like dead hardware, so at boot the keys show the
+//! firmware name, version and device personality, followed by a waiting
+//! message that stays up until the host uploads its first key image.
+
+use defmt::*;
+use embassy_time::{with_timeout, Duration, Timer};
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::channels::send_display;
+use crate::device::{Device, DeviceConfig};
+use crate::gfx::KeyText;
+use crate::types::{DisplayCommand, APP_VERSION};
+
+/// How long the splash stays up before switching to the waiting screen
+const SPLASH_DURATION_MS: u64 = 2000;
+
+/// How long to wait for the display to accept a command
+const DISPLAY_SEND_TIMEOUT_MS: u64 = 50;
+
+const COLOR_FG: u16 = 0xFFFF;
+const COLOR_BG: u16 = 0x0000;
+const COLOR_ACCENT: u16 = 0x041F;
+
+/// portable-atomic: thumbv6m has no native swap
+static WAITING_FOR_HOST: AtomicBool = AtomicBool::new(false);
+
+/// Consume the waiting state on the first host image. Returns true exactly
+/// once, when the waiting screen must be cleared before drawing the image.
+pub fn host_image_received() -> bool {
+    WAITING_FOR_HOST.swap(false, Ordering::Relaxed)
+}
+
+async fn draw_text(key_id: u8, text: &str, fg: u16, bg: u16) {
+    let mut key_text = KeyText::new();
+    // Words go on separate lines so long names fit narrow keys
+    for c in text.chars() {
+        let _ = key_text.push(if c == ' ' { '\n' } else { c });
+    }
+
+    let command = DisplayCommand::DrawText {
+        key_id,
+        text: key_text,
+        fg,
+        bg,
+    };
+    // Builds without a display consumer must not block here
+    let send = send_display(command);
+    if with_timeout(Duration::from_millis(DISPLAY_SEND_TIMEOUT_MS), send)
+        .await
+        .is_err()
+    {
+        debug!("Splash: display not responding");
+    }
+}
+
+/// Show the splash, then the waiting screen. Returns immediately if the host
+/// has already sent images.
+pub async fn show(device: Device) {
+    let keys = device.button_layout().total_keys as u8;
+    WAITING_FOR_HOST.store(true, Ordering::Relaxed);
+
+    // Splash: name, version, personality on the first keys
+    let mut version = KeyText::new();
+    let _ = version.push('v');
+    let _ = version.push_str(APP_VERSION.as_string());
+    let lines = [
+        ("Production Deck", COLOR_ACCENT),
+        (version.as_str(), COLOR_FG),
+        (device.device_name(), COLOR_FG),
+    ];
+    for key_id in 0..keys {
+        if !WAITING_FOR_HOST.load(Ordering::Relaxed) {
+            return;
+        }
+        let (text, fg) = lines
+            .get(key_id as usize)
+            .copied()
+            .unwrap_or(("", COLOR_FG));
+        draw_text(key_id, text, fg, COLOR_BG).await;
+    }
+
+    Timer::after(Duration::from_millis(SPLASH_DURATION_MS)).await;
+
+    info!("Waiting for StreamDeck software...");
+    for key_id in 0..keys {
+        // Never draw over images the host has already sent
+        if !WAITING_FOR_HOST.load(Ordering::Relaxed) {
+            return;
+        }
+        let text = match key_id {
+            0 => "Waiting for",
+            1 => "StreamDeck",
+            2 => "software...",
+            _ => "",
+        };
+        draw_text(key_id, text, COLOR_FG, COLOR_BG).await;
+    }
+}
@@ -0,0 +1,219 @@
+//! Standalone keyboard mode
+//!
+//! Without StreamDeck software on the host the device would do nothing. With
+//! the `standalone-keyboard` feature the device also exposes a keyboard HID
+//! interface, and when no Elgato protocol traffic has been seen for
+//! `standalone_timeout_secs` after enumeration each key sends a configurable
+//! keyboard shortcut instead. A page key can switch between several
+//! shortcut layers (see `pages`).
+//!
+//! The StreamDeck software only talks to the device when something changes, so
+//! a quiet host does not mean the software is gone. Once Elgato traffic has
+//! been seen the device stays in host mode until the next bus reset.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use embassy_time::Instant;
+
+use crate::config::MAX_KEYS;
+use crate::types::ButtonState;
+
+/// Keyboard report size: `[modifiers, reserved, keycodes(6)]` (boot layout)
+pub const KEYBOARD_REPORT_SIZE: usize = 8;
+
+/// Maximum simultaneous non-modifier keys in a boot keyboard report
+const KEYBOARD_ROLLOVER: usize = 6;
+
+/// HID usage of F13; default shortcuts run F13..F24 on the first 12 keys
+const KEYCODE_F13: u8 = 0x68;
+
+/// Default fallback timeout in seconds
+pub const DEFAULT_STANDALONE_TIMEOUT_SECS: u8 = 10;
+
+/// Boot-compatible keyboard report descriptor (no LED output report)
+pub const KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xa1, 0x01, // Collection (Application)
+    0x05, 0x07, // Usage Page (Keyboard/Keypad)
+    0x19, 0xe0, // Usage Minimum (Left Control)
+    0x29, 0xe7, // Usage Maximum (Right GUI)
+    0x15, 0x00, // Logical Minimum (0)
+    0x25, 0x01, // Logical Maximum (1)
+    0x75, 0x01, // Report Size (1)
+    0x95, 0x08, // Report Count (8)
+    0x81, 0x02, // Input (Data,Var,Abs) - Modifiers
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x01, // Report Count (1)
+    0x81, 0x01, // Input (Const) - Reserved
+    0x19, 0x00, // Usage Minimum (0)
+    0x29, 0xff, // Usage Maximum (255)
+    0x15, 0x00, // Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x06, // Report Count (6)
+    0x81, 0x00, // Input (Data,Array,Abs) - Keycodes
+    0xc0, // End Collection
+];
+
+/// Keyboard shortcut sent by one key in standalone mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyShortcut {
+    /// HID modifier bits (Ctrl/Shift/Alt/GUI)
+    pub modifiers: u8,
+    /// HID keyboard usage (0 = none)
+    pub keycode: u8,
+}
+
+impl KeyShortcut {
+    pub const NONE: Self = Self {
+        modifiers: 0,
+        keycode: 0,
+    };
+}
+
+/// Default shortcut table: F13..F24 on the first 12 keys
+pub const DEFAULT_SHORTCUTS: [KeyShortcut; MAX_KEYS] = default_shortcuts();
+
+const fn default_shortcuts() -> [KeyShortcut; MAX_KEYS] {
+    let mut table = [KeyShortcut::NONE; MAX_KEYS];
+    let mut i = 0;
+    while i < 12 {
+        table[i] = KeyShortcut {
+            modifiers: 0,
+            keycode: KEYCODE_F13 + i as u8,
+        };
+        i += 1;
+    }
+    table
+}
+
+/// HID modifier bits by name, for text configuration
+const MODIFIER_NAMES: [(&str, u8); 4] = [
+    ("CTRL", 0x01),
+    ("SHIFT", 0x02),
+    ("ALT", 0x04),
+    ("GUI", 0x08),
+];
+
+/// HID usages of named non-letter keys, for text configuration
+const KEY_NAMES: [(&str, u8); 9] = [
+    ("ENTER", 0x28),
+    ("ESC", 0x29),
+    ("BACKSPACE", 0x2A),
+    ("TAB", 0x2B),
+    ("SPACE", 0x2C),
+    ("RIGHT", 0x4F),
+    ("LEFT", 0x50),
+    ("DOWN", 0x51),
+    ("UP", 0x52),
+];
+
+/// Parse a shortcut written as `MOD+MOD+KEY` (e.g. `CTRL+SHIFT+F13`).
+/// Keys are letters, digits, `F1`..`F24`, the names in `KEY_NAMES`, or a
+/// raw usage as `0xNN`; `NONE` clears the key. Case-insensitive.
+pub fn parse_shortcut(text: &str) -> Option<KeyShortcut> {
+    if text.eq_ignore_ascii_case("NONE") {
+        return Some(KeyShortcut::NONE);
+    }
+    let mut parts = text.rsplit('+');
+    let key = parts.next()?;
+    let mut modifiers = 0;
+    for name in parts {
+        let (_, bit) = MODIFIER_NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))?;
+        modifiers |= bit;
+    }
+    Some(KeyShortcut {
+        modifiers,
+        keycode: parse_keycode(key)?,
+    })
+}
+
+fn parse_keycode(key: &str) -> Option<u8> {
+    if let Some(hex) = key.strip_prefix("0x").or_else(|| key.strip_prefix("0X")) {
+        return u8::from_str_radix(hex, 16).ok();
+    }
+    if let [c] = key.as_bytes() {
+        return match c.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => Some(0x04 + (c - b'A')),
+            c @ b'1'..=b'9' => Some(0x1E + (c - b'1')),
+            b'0' => Some(0x27),
+            _ => None,
+        };
+    }
+    if let Some(n) = key
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u8>().ok())
+    {
+        return match n {
+            1..=12 => Some(0x3A + n - 1),
+            13..=24 => Some(KEYCODE_F13 + n - 13),
+            _ => None,
+        };
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|&(_, code)| code)
+}
+
+// ===================================================================
+// Host Activity Tracking
+// ===================================================================
+
+static HOST_SEEN: AtomicBool = AtomicBool::new(false);
+static ENUMERATED_AT_SECS: AtomicU32 = AtomicU32::new(0);
+
+/// Record Elgato protocol traffic from the host
+pub fn note_host_activity() {
+    if !HOST_SEEN.load(Ordering::Relaxed) {
+        HOST_SEEN.store(true, Ordering::Relaxed);
+        crate::status_led::notify();
+    }
+}
+
+/// Whether StreamDeck software has talked to the device since enumeration
+pub fn host_seen() -> bool {
+    HOST_SEEN.load(Ordering::Relaxed)
+}
+
+/// Restart the fallback timeout (bus reset / new configuration)
+pub fn reset_host_activity() {
+    HOST_SEEN.store(false, Ordering::Relaxed);
+    ENUMERATED_AT_SECS.store(Instant::now().as_secs() as u32, Ordering::Relaxed);
+    crate::status_led::notify();
+}
+
+/// Whether key presses should be sent as keyboard shortcuts
+pub fn is_active() -> bool {
+    if HOST_SEEN.load(Ordering::Relaxed) {
+        return false;
+    }
+    let timeout = crate::settings::with(|s| s.standalone_timeout_secs) as u32;
+    if timeout == 0 {
+        return false;
+    }
+    let now = Instant::now().as_secs() as u32;
+    now.wrapping_sub(ENUMERATED_AT_SECS.load(Ordering::Relaxed)) >= timeout
+}
+
+/// Build a keyboard report for the pressed keys (first six keycodes win)
+pub fn build_keyboard_report(
+    state: &ButtonState,
+    shortcuts: &[KeyShortcut; MAX_KEYS],
+    report: &mut [u8; KEYBOARD_REPORT_SIZE],
+) {
+    report.fill(0);
+    let mut slot = 0;
+    for (key, shortcut) in shortcuts.iter().enumerate().take(state.active_count()) {
+        if !state.is_pressed(key) || shortcut.keycode == 0 {
+            continue;
+        }
+        report[0] |= shortcut.modifiers;
+        if slot < KEYBOARD_ROLLOVER && !report[2..2 + slot].contains(&shortcut.keycode) {
+            report[2 + slot] = shortcut.keycode;
+            slot += 1;
+        }
+    }
+}
@@ -0,0 +1,445 @@
+//! Runtime metrics and health counters
+//!
+//! Tasks on either core bump these counters as they work; the vendor report
+//! (`VENDOR_CMD_GET_METRICS`) serializes a snapshot for host-side diagnostics.
+//! Counters wrap rather than saturate.
+//!
+//! Peak use of the image pool and the command queues is kept as well
+//! (`pool_usage`), so a device profile's slab count and channel depths can
+//! be tuned from what a real session needed.
+//!
+//! Builds with `interp-convert` also keep the per-pixel cost of the scalar
+//! and interpolator pixel paths, measured once when the display starts
+//! (`interp::benchmark`). `reset` leaves that measurement alone.
+
+use embassy_time::Instant;
+// portable-atomic: thumbv6m has no native read-modify-write atomics
+use portable_atomic::{AtomicU16, AtomicU32, AtomicU8, Ordering};
+
+use crate::channels::{DISPLAY_CHANNEL, DISPLAY_CONTROL_CHANNEL, USB_COMMAND_CHANNEL};
+use crate::config::{IMAGE_SLAB_COUNT, INPUT_LATENCY_BUDGET_US};
+use crate::error::Error;
+use crate::image_pool::ImageSlab;
+
+/// No error recorded
+pub const ERROR_NONE: u8 = 0x00;
+
+/// Stages timed in `PipelineTiming`
+const PIPELINE_STAGES: usize = 4;
+
+/// Weight of a new sample in the pipeline rolling averages, as a shift (1/8)
+const PIPELINE_AVERAGE_SHIFT: u32 = 3;
+
+/// Paths timed in `ConversionBench`
+const CONVERSION_PATHS: usize = 4;
+
+/// Size of a serialized metrics snapshot
+pub const METRICS_SIZE: usize = 27;
+
+static PACKETS_DROPPED: AtomicU32 = AtomicU32::new(0);
+static COMMANDS_DROPPED: AtomicU32 = AtomicU32::new(0);
+static IMAGES_RECEIVED: AtomicU32 = AtomicU32::new(0);
+static IMAGES_DISPLAYED: AtomicU32 = AtomicU32::new(0);
+static DISPLAY_TIME_US: AtomicU32 = AtomicU32::new(0);
+static BUTTON_EVENTS: AtomicU32 = AtomicU32::new(0);
+static FRAMES_DEFERRED: AtomicU32 = AtomicU32::new(0);
+static FRAMES_COALESCED: AtomicU32 = AtomicU32::new(0);
+static IMAGES_REJECTED: AtomicU32 = AtomicU32::new(0);
+static INPUT_LATENCY_US: AtomicU32 = AtomicU32::new(0);
+static INPUT_LATENCY_MAX_US: AtomicU32 = AtomicU32::new(0);
+static INPUT_LATE_REPORTS: AtomicU32 = AtomicU32::new(0);
+static PIPELINE_US: [AtomicU32; PIPELINE_STAGES] = [const { AtomicU32::new(0) }; PIPELINE_STAGES];
+static LAST_ERROR: AtomicU8 = AtomicU8::new(ERROR_NONE);
+static ERROR_COUNTS: [AtomicU32; Error::KIND_COUNT] =
+    [const { AtomicU32::new(0) }; Error::KIND_COUNT];
+static POOL_PEAKS: [AtomicU8; Pool::COUNT] = [const { AtomicU8::new(0) }; Pool::COUNT];
+static CONVERSION_NS: [AtomicU16; CONVERSION_PATHS] =
+    [const { AtomicU16::new(0) }; CONVERSION_PATHS];
+
+/// A USB packet was lost (read/write error or unusable report)
+pub fn packet_dropped() {
+    PACKETS_DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A command could not be queued because the channel was full
+pub fn command_dropped() {
+    COMMANDS_DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A complete key image was reassembled from USB
+pub fn image_received() {
+    IMAGES_RECEIVED.fetch_add(1, Ordering::Relaxed);
+    crate::effects::activity();
+}
+
+/// A key image was decoded and written to the panel
+pub fn image_displayed(elapsed_us: u32) {
+    IMAGES_DISPLAYED.fetch_add(1, Ordering::Relaxed);
+    DISPLAY_TIME_US.fetch_add(elapsed_us, Ordering::Relaxed);
+}
+
+/// A key image arrived within its key's frame interval and had to wait
+pub fn frame_deferred() {
+    FRAMES_DEFERRED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A waiting key image was replaced by a newer one before it was drawn
+pub fn frame_coalesced() {
+    FRAMES_COALESCED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Frame pacing counters: (deferred, coalesced)
+pub fn frame_pacing() -> (u32, u32) {
+    (
+        FRAMES_DEFERRED.load(Ordering::Relaxed),
+        FRAMES_COALESCED.load(Ordering::Relaxed),
+    )
+}
+
+/// A completed key image failed validation and was not displayed
+pub fn image_rejected() {
+    IMAGES_REJECTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Image reassembly counters: (resyncs, evictions, rejected). Resyncs and
+/// evictions are counted by the reassembly contexts (`protocol::assembly`).
+pub fn image_reassembly() -> (u32, u32, u32) {
+    let (resyncs, evictions) = crate::protocol::assembly::counters();
+    (resyncs, evictions, IMAGES_REJECTED.load(Ordering::Relaxed))
+}
+
+/// Time a key image spent in each stage of the USB -> panel pipeline
+#[derive(Clone, Copy, Debug, Default, defmt::Format)]
+pub struct PipelineTiming {
+    /// First page received -> last page received
+    pub assemble_us: u32,
+    /// Last page received -> display task starts drawing (includes frame pacing)
+    pub queue_us: u32,
+    /// Header handling and pixel conversion
+    pub decode_us: u32,
+    /// Panel bus writes
+    pub blit_us: u32,
+}
+
+impl PipelineTiming {
+    fn stages(&self) -> [u32; PIPELINE_STAGES] {
+        [
+            self.assemble_us,
+            self.queue_us,
+            self.decode_us,
+            self.blit_us,
+        ]
+    }
+
+    /// Serialize as `[assemble_us u32, queue_us u32, decode_us u32, blit_us u32]` (LE)
+    pub fn to_bytes(&self) -> [u8; PIPELINE_STAGES * 4] {
+        let mut out = [0u8; PIPELINE_STAGES * 4];
+        for (chunk, value) in out.chunks_exact_mut(4).zip(self.stages()) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Fold one displayed image's stage times into the rolling averages. Only the
+/// display task calls this, so plain load/store is enough.
+pub fn image_pipeline(timing: &PipelineTiming) {
+    for (average, sample) in PIPELINE_US.iter().zip(timing.stages()) {
+        let old = average.load(Ordering::Relaxed);
+        let new = if old == 0 {
+            sample
+        } else {
+            // old + (sample - old) / 8, in i64 to keep the sign
+            (old as i64 + ((sample as i64 - old as i64) >> PIPELINE_AVERAGE_SHIFT)) as u32
+        };
+        average.store(new, Ordering::Relaxed);
+    }
+}
+
+/// Rolling average of each pipeline stage
+pub fn pipeline_timing() -> PipelineTiming {
+    let [assemble_us, queue_us, decode_us, blit_us] =
+        core::array::from_fn(|i| PIPELINE_US[i].load(Ordering::Relaxed));
+    PipelineTiming {
+        assemble_us,
+        queue_us,
+        decode_us,
+        blit_us,
+    }
+}
+
+/// A key state report was produced
+pub fn button_event() {
+    BUTTON_EVENTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A key report reached the host `latency_us` after its scan. Only the input
+/// report task calls this, so plain load/store is enough for the average.
+pub fn input_reported(latency_us: u32) {
+    let old = INPUT_LATENCY_US.load(Ordering::Relaxed);
+    let new = if old == 0 {
+        latency_us
+    } else {
+        (old as i64 + ((latency_us as i64 - old as i64) >> PIPELINE_AVERAGE_SHIFT)) as u32
+    };
+    INPUT_LATENCY_US.store(new, Ordering::Relaxed);
+    INPUT_LATENCY_MAX_US.fetch_max(latency_us, Ordering::Relaxed);
+    if latency_us > INPUT_LATENCY_BUDGET_US {
+        INPUT_LATE_REPORTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Button-to-report latency: (rolling average us, worst us, reports over
+/// `INPUT_LATENCY_BUDGET_US`)
+pub fn input_latency() -> (u32, u32, u32) {
+    (
+        INPUT_LATENCY_US.load(Ordering::Relaxed),
+        INPUT_LATENCY_MAX_US.load(Ordering::Relaxed),
+        INPUT_LATE_REPORTS.load(Ordering::Relaxed),
+    )
+}
+
+/// Count an error and remember it as the most recent one
+pub fn record_error(error: impl Into<Error>) {
+    let error = error.into();
+    ERROR_COUNTS[error.kind_index()].fetch_add(1, Ordering::Relaxed);
+    LAST_ERROR.store(error.code(), Ordering::Relaxed);
+    crate::status_led::error(error);
+    crate::effects::error();
+}
+
+/// Per-kind error counts, indexed by `Error::kind_index`
+pub fn error_counts() -> [u32; Error::KIND_COUNT] {
+    core::array::from_fn(|i| ERROR_COUNTS[i].load(Ordering::Relaxed))
+}
+
+/// Buffers whose peak use is tracked, in serialization order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum Pool {
+    /// Key image slabs (`image_pool`)
+    ImageSlabs = 0,
+    /// `USB_COMMAND_CHANNEL`
+    UsbCommands = 1,
+    /// `DISPLAY_CHANNEL`
+    DisplayCommands = 2,
+    /// `DISPLAY_CONTROL_CHANNEL`
+    DisplayControl = 3,
+}
+
+impl Pool {
+    pub const COUNT: usize = 4;
+    pub const ALL: [Pool; Pool::COUNT] = [
+        Pool::ImageSlabs,
+        Pool::UsbCommands,
+        Pool::DisplayCommands,
+        Pool::DisplayControl,
+    ];
+
+    /// Entries the pool holds
+    pub const fn capacity(self) -> usize {
+        match self {
+            Pool::ImageSlabs => IMAGE_SLAB_COUNT,
+            Pool::UsbCommands => USB_COMMAND_CHANNEL.capacity(),
+            Pool::DisplayCommands => DISPLAY_CHANNEL.capacity(),
+            Pool::DisplayControl => DISPLAY_CONTROL_CHANNEL.capacity(),
+        }
+    }
+
+    /// Entries taken right now
+    pub fn in_use(self) -> usize {
+        match self {
+            Pool::ImageSlabs => IMAGE_SLAB_COUNT - ImageSlab::free_count(),
+            Pool::UsbCommands => USB_COMMAND_CHANNEL.len(),
+            Pool::DisplayCommands => DISPLAY_CHANNEL.len(),
+            Pool::DisplayControl => DISPLAY_CONTROL_CHANNEL.len(),
+        }
+    }
+
+    /// Most entries taken at once. The image pool keeps its own peak.
+    pub fn peak(self) -> usize {
+        match self {
+            Pool::ImageSlabs => ImageSlab::peak_in_use(),
+            _ => POOL_PEAKS[self as usize].load(Ordering::Relaxed) as usize,
+        }
+    }
+}
+
+/// `used` entries of `pool` were taken at once. Queues are sampled by their
+/// consumer on each receive (the entry received plus those still waiting).
+pub fn pool_used(pool: Pool, used: usize) {
+    POOL_PEAKS[pool as usize].fetch_max(used.min(u8::MAX as usize) as u8, Ordering::Relaxed);
+}
+
+/// Current and peak use of one pool
+#[derive(Clone, Copy, Debug, defmt::Format)]
+pub struct PoolUsage {
+    pub pool: Pool,
+    pub in_use: u8,
+    pub peak: u8,
+    pub capacity: u8,
+}
+
+impl PoolUsage {
+    /// Serialized size: `[in use, peak, capacity]`
+    pub const SIZE: usize = 3;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        [self.in_use, self.peak, self.capacity]
+    }
+}
+
+/// Use of every tracked pool, in `Pool` order
+pub fn pool_usage() -> [PoolUsage; Pool::COUNT] {
+    Pool::ALL.map(|pool| PoolUsage {
+        pool,
+        in_use: pool.in_use() as u8,
+        peak: pool.peak().min(u8::MAX as usize) as u8,
+        capacity: pool.capacity() as u8,
+    })
+}
+
+/// Per-pixel cost of the display's pixel paths, in nanoseconds (0 = not measured)
+#[derive(Clone, Copy, Debug, Default, defmt::Format)]
+pub struct ConversionBench {
+    /// RGB888 -> RGB565 with per-channel gamma and brightness
+    pub convert_scalar_ns: u16,
+    /// RGB888 -> RGB565 through the interpolator-addressed tables
+    pub convert_interp_ns: u16,
+    /// Nearest-neighbour source index by division
+    pub scale_scalar_ns: u16,
+    /// Nearest-neighbour source index stepped by the interpolator
+    pub scale_interp_ns: u16,
+}
+
+impl ConversionBench {
+    fn paths(&self) -> [u16; CONVERSION_PATHS] {
+        [
+            self.convert_scalar_ns,
+            self.convert_interp_ns,
+            self.scale_scalar_ns,
+            self.scale_interp_ns,
+        ]
+    }
+
+    /// Serialize as `[convert scalar, convert interp, scale scalar, scale interp]` (u16 LE each)
+    pub fn to_bytes(&self) -> [u8; CONVERSION_PATHS * 2] {
+        let mut out = [0u8; CONVERSION_PATHS * 2];
+        for (chunk, value) in out.chunks_exact_mut(2).zip(self.paths()) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Record a pixel path benchmark
+pub fn conversion_benchmarked(bench: ConversionBench) {
+    for (slot, value) in CONVERSION_NS.iter().zip(bench.paths()) {
+        slot.store(value, Ordering::Relaxed);
+    }
+}
+
+/// Last pixel path benchmark
+pub fn conversion_bench() -> ConversionBench {
+    let [convert_scalar_ns, convert_interp_ns, scale_scalar_ns, scale_interp_ns] =
+        core::array::from_fn(|i| CONVERSION_NS[i].load(Ordering::Relaxed));
+    ConversionBench {
+        convert_scalar_ns,
+        convert_interp_ns,
+        scale_scalar_ns,
+        scale_interp_ns,
+    }
+}
+
+/// Reset all counters (uptime is unaffected)
+pub fn reset() {
+    for counter in [
+        &PACKETS_DROPPED,
+        &COMMANDS_DROPPED,
+        &IMAGES_RECEIVED,
+        &IMAGES_DISPLAYED,
+        &DISPLAY_TIME_US,
+        &BUTTON_EVENTS,
+        &FRAMES_DEFERRED,
+        &FRAMES_COALESCED,
+        &IMAGES_REJECTED,
+        &INPUT_LATENCY_US,
+        &INPUT_LATENCY_MAX_US,
+        &INPUT_LATE_REPORTS,
+    ]
+    .into_iter()
+    .chain(PIPELINE_US.iter())
+    .chain(ERROR_COUNTS.iter())
+    {
+        counter.store(0, Ordering::Relaxed);
+    }
+    for peak in POOL_PEAKS.iter() {
+        peak.store(0, Ordering::Relaxed);
+    }
+    ImageSlab::reset_peak();
+    crate::protocol::assembly::reset_counters();
+    LAST_ERROR.store(ERROR_NONE, Ordering::Relaxed);
+}
+
+/// Point-in-time copy of all metrics
+#[derive(Clone, Copy, Debug, defmt::Format)]
+pub struct Metrics {
+    pub uptime_secs: u32,
+    /// Free slots in the USB command queue
+    pub usb_queue_free: u8,
+    /// Free slots in the display command queue
+    pub display_queue_free: u8,
+    pub packets_dropped: u32,
+    pub commands_dropped: u32,
+    pub images_received: u32,
+    pub images_displayed: u32,
+    /// Average decode + panel write time per image
+    pub avg_display_us: u32,
+    pub button_events: u32,
+    pub last_error: u8,
+}
+
+impl Metrics {
+    /// Serialize as little-endian fields:
+    /// `[uptime u32, usb_free u8, display_free u8, packets_dropped u16,
+    ///   commands_dropped u16, images_received u32, images_displayed u32,
+    ///   avg_display_us u32, button_events u32, last_error u8]`
+    pub fn to_bytes(&self) -> [u8; METRICS_SIZE] {
+        let mut out = [0u8; METRICS_SIZE];
+        out[0..4].copy_from_slice(&self.uptime_secs.to_le_bytes());
+        out[4] = self.usb_queue_free;
+        out[5] = self.display_queue_free;
+        out[6..8]
+            .copy_from_slice(&(self.packets_dropped.min(u16::MAX as u32) as u16).to_le_bytes());
+        out[8..10]
+            .copy_from_slice(&(self.commands_dropped.min(u16::MAX as u32) as u16).to_le_bytes());
+        out[10..14].copy_from_slice(&self.images_received.to_le_bytes());
+        out[14..18].copy_from_slice(&self.images_displayed.to_le_bytes());
+        out[18..22].copy_from_slice(&self.avg_display_us.to_le_bytes());
+        out[22..26].copy_from_slice(&self.button_events.to_le_bytes());
+        out[26] = self.last_error;
+        out
+    }
+}
+
+/// Take a snapshot of all metrics
+pub fn snapshot() -> Metrics {
+    let images_displayed = IMAGES_DISPLAYED.load(Ordering::Relaxed);
+    let avg_display_us = DISPLAY_TIME_US
+        .load(Ordering::Relaxed)
+        .checked_div(images_displayed)
+        .unwrap_or(0);
+
+    Metrics {
+        uptime_secs: Instant::now().as_secs() as u32,
+        usb_queue_free: USB_COMMAND_CHANNEL.free_capacity() as u8,
+        display_queue_free: DISPLAY_CHANNEL.free_capacity() as u8,
+        packets_dropped: PACKETS_DROPPED.load(Ordering::Relaxed),
+        commands_dropped: COMMANDS_DROPPED.load(Ordering::Relaxed),
+        images_received: IMAGES_RECEIVED.load(Ordering::Relaxed),
+        images_displayed,
+        avg_display_us,
+        button_events: BUTTON_EVENTS.load(Ordering::Relaxed),
+        last_error: LAST_ERROR.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,189 @@
+//! Status LED patterns
+//!
+//! Field debugging without a console: the status LED shows how far the
+//! device got with the host, and the error LED blinks a code for the last
+//! error, for `STATUS_ERROR_HOLD_MS` after it happened.
+//!
+//! | State        | Status LED                       | Error LED                      |
+//! |--------------|----------------------------------|--------------------------------|
+//! | `Booting`    | fast blink (5 Hz)                | off                            |
+//! | `Enumerated` | short blink every second         | off                            |
+//! | `HostActive` | on, short gap every second       | off                            |
+//! | `Suspended`  | off                              | off                            |
+//! | `Error(n)`   | off                              | n blinks, pause                |
+//!
+//! Error blink counts follow the error code's category: 1 protocol,
+//! 2 display, 3 USB, 4 input. A host reset clears the error and is
+//! acknowledged with three quick blinks of the status LED. A stuck key
+//! (`buttons::stuck_keys`) keeps the input code blinking until it is released.
+//! Night mode (`night`) keeps the status LED dark; errors still blink.
+//!
+//! The state is derived from startup, USB and host activity tracking, which
+//! call `notify` so the LEDs react at once rather than at the end of the
+//! current blink.
+
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use defmt::*;
+use embassy_futures::select::{select3, Either3};
+use embassy_rp::gpio::Output;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::channels::{next_device_command, subscribe_device_bus};
+use crate::config::STATUS_ERROR_HOLD_MS;
+use crate::error::Error;
+use crate::error::InputError;
+use crate::power::{self, StartupStage};
+use crate::types::DeviceCommand;
+
+/// What the LEDs are showing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Format)]
+pub enum LedState {
+    /// Startup not finished or USB not configured
+    Booting,
+    /// Configured, but no StreamDeck software talking yet
+    Enumerated,
+    /// StreamDeck software is sending reports
+    HostActive,
+    /// Host asleep
+    Suspended,
+    /// Recent error; error LED blinks this many times
+    Error(u8),
+}
+
+/// One step of a pattern: LED levels and how long they hold
+#[derive(Clone, Copy)]
+struct Step {
+    status: bool,
+    error: bool,
+    ms: u16,
+}
+
+const fn step(status: bool, error: bool, ms: u16) -> Step {
+    Step { status, error, ms }
+}
+
+const BOOTING: &[Step] = &[step(true, false, 100), step(false, false, 100)];
+const ENUMERATED: &[Step] = &[step(true, false, 100), step(false, false, 900)];
+const HOST_ACTIVE: &[Step] = &[step(true, false, 900), step(false, false, 100)];
+const SUSPENDED: &[Step] = &[step(false, false, 1000)];
+const ERROR_BLINK: &[Step] = &[step(false, true, 200), step(false, false, 200)];
+const ERROR_PAUSE: Step = step(false, false, 1000);
+
+/// Wakes the LED task when an input to the state changes
+static CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+/// Blink count of the last error, 0 = none
+static ERROR_BLINKS: AtomicU8 = AtomicU8::new(0);
+static ERROR_AT_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Let the LEDs pick up a state change now rather than after the current step
+pub fn notify() {
+    CHANGED.signal(());
+}
+
+/// Show an error on the error LED
+pub fn error(error: Error) {
+    ERROR_BLINKS.store(error.code() >> 4, Ordering::Relaxed);
+    ERROR_AT_MS.store(Instant::now().as_millis() as u32, Ordering::Relaxed);
+    notify();
+}
+
+/// Current LED state
+pub fn state() -> LedState {
+    let blinks = ERROR_BLINKS.load(Ordering::Relaxed);
+    let since_error =
+        (Instant::now().as_millis() as u32).wrapping_sub(ERROR_AT_MS.load(Ordering::Relaxed));
+    if blinks > 0 && since_error < STATUS_ERROR_HOLD_MS {
+        return LedState::Error(blinks);
+    }
+    if crate::buttons::stuck_keys() != 0 {
+        return LedState::Error(InputError::StuckKey as u8 >> 4);
+    }
+    if power::usb_suspended() {
+        LedState::Suspended
+    } else if power::startup_stage() < StartupStage::Running || !power::usb_configured() {
+        LedState::Booting
+    } else if crate::standalone::host_seen() {
+        LedState::HostActive
+    } else {
+        LedState::Enumerated
+    }
+}
+
+fn pattern(state: LedState) -> &'static [Step] {
+    match state {
+        LedState::Booting => BOOTING,
+        LedState::Enumerated => ENUMERATED,
+        LedState::HostActive => HOST_ACTIVE,
+        LedState::Suspended => SUSPENDED,
+        LedState::Error(_) => ERROR_BLINK,
+    }
+}
+
+/// Steps of one pass through a state's pattern
+fn steps(state: LedState) -> impl Iterator<Item = Step> {
+    let repeats = match state {
+        LedState::Error(blinks) => blinks as usize,
+        _ => 1,
+    };
+    let pause = matches!(state, LedState::Error(_)).then_some(ERROR_PAUSE);
+    pattern(state)
+        .iter()
+        .copied()
+        .cycle()
+        .take(pattern(state).len() * repeats)
+        .chain(pause)
+}
+
+/// Drive both LEDs; never returns
+pub async fn run(mut status_led: Output<'static>, mut error_led: Output<'static>) -> ! {
+    let mut device_bus = subscribe_device_bus();
+    // Host brightness 0 turns the status LED off like the backlight
+    let mut lit = true;
+
+    loop {
+        let current = state();
+        for step in steps(current) {
+            status_led.set_level((step.status && lit && !crate::night::is_active()).into());
+            error_led.set_level(step.error.into());
+            let hold = Timer::after(Duration::from_millis(step.ms as u64));
+            match select3(hold, CHANGED.wait(), next_device_command(&mut device_bus)).await {
+                Either3::First(()) => {}
+                Either3::Second(()) => {
+                    if state() != current {
+                        debug!("Status LEDs: {}", state());
+                        break;
+                    }
+                }
+                Either3::Third(command) => {
+                    match command {
+                        DeviceCommand::Reset => {
+                            ERROR_BLINKS.store(0, Ordering::Relaxed);
+                            acknowledge_reset(&mut status_led, &mut error_led, lit).await;
+                        }
+                        DeviceCommand::SetBrightness(percent) => lit = percent > 0,
+                        DeviceCommand::Sleep | DeviceCommand::Wake => {}
+                    }
+                    debug!("Status LEDs: {}", command);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Three quick blinks acknowledge a host reset
+async fn acknowledge_reset(
+    status_led: &mut Output<'static>,
+    error_led: &mut Output<'static>,
+    lit: bool,
+) {
+    error_led.set_low();
+    for _ in 0..3 {
+        status_led.set_level(lit.into());
+        Timer::after(Duration::from_millis(60)).await;
+        status_led.set_low();
+        Timer::after(Duration::from_millis(60)).await;
+    }
+}
@@ -0,0 +1,322 @@
+//! Flash-backed persistent storage
+//!
+//! Owns the RP2040 flash peripheral, reads the flash unique ID used to derive
+//! a per-unit serial number, and stores the settings record in the last
+//! 4 KB sector of the 2 MB flash. Locally stored key images (`key_images`)
+//! take the slots just below it, the standalone page table (`pages`) the
+//! sector below those, and the USB configuration drive (`config-drive`) the
+//! region below that. A firmware update (`firmware-update`) is staged below
+//! the configuration drive, with a header sector under it; the running
+//! firmware keeps the flash below that.
+
+use core::cell::RefCell;
+use defmt::*;
+use embassy_rp::flash::{Blocking, Flash, ERASE_SIZE, FLASH_BASE};
+use embassy_rp::{peripherals, Peri};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+
+use crate::config::{LOCAL_IMAGE_KEYS, MAX_IMAGE_SIZE};
+use crate::pages::{self, PageTable, PAGES_RECORD_SIZE};
+use crate::settings::{
+    self, RecordError, SerialString, Settings, SETTINGS_RECORD_SIZE, SETTINGS_VERSION,
+};
+
+/// Total flash size on the Raspberry Pi Pico
+pub const FLASH_SIZE: usize = 2 * 1024 * 1024;
+
+/// Offset of the settings sector (last sector of flash)
+pub const SETTINGS_OFFSET: u32 = (FLASH_SIZE - ERASE_SIZE) as u32;
+
+/// Header in front of each stored key image
+pub const KEY_IMAGE_HEADER_SIZE: usize = 8;
+
+/// Flash reserved per stored key image, in whole erase sectors
+pub const KEY_IMAGE_SLOT_SIZE: usize =
+    (KEY_IMAGE_HEADER_SIZE + MAX_IMAGE_SIZE).next_multiple_of(ERASE_SIZE);
+
+/// Stored key image slots: an icon and a pressed icon per key
+pub const KEY_IMAGE_SLOTS: usize = LOCAL_IMAGE_KEYS * 2;
+
+/// Offset of the first key image slot, just below the settings sector
+pub const KEY_IMAGES_OFFSET: u32 = SETTINGS_OFFSET - (KEY_IMAGE_SLOTS * KEY_IMAGE_SLOT_SIZE) as u32;
+
+/// Offset of the standalone page table sector, below the key images
+pub const PAGES_OFFSET: u32 = KEY_IMAGES_OFFSET - ERASE_SIZE as u32;
+
+const _: () = core::assert!(PAGES_RECORD_SIZE <= ERASE_SIZE);
+
+/// Size of the USB configuration drive volume
+pub const CONFIG_DRIVE_SIZE: usize = 512 * 1024;
+
+/// Offset of the configuration drive volume, below the page table
+pub const CONFIG_DRIVE_OFFSET: u32 = PAGES_OFFSET - CONFIG_DRIVE_SIZE as u32;
+
+/// Largest firmware image, boot2 included: half of the flash below the
+/// configuration drive and the staging header, in whole sectors
+pub const FIRMWARE_SLOT_SIZE: usize =
+    (CONFIG_DRIVE_OFFSET as usize - ERASE_SIZE) / 2 / ERASE_SIZE * ERASE_SIZE;
+
+/// Offset of a staged firmware image, just below the configuration drive
+pub const FIRMWARE_STAGING_OFFSET: u32 = CONFIG_DRIVE_OFFSET - FIRMWARE_SLOT_SIZE as u32;
+
+/// Offset of the staged firmware header sector, below the staged image
+pub const FIRMWARE_HEADER_OFFSET: u32 = FIRMWARE_STAGING_OFFSET - ERASE_SIZE as u32;
+
+type FlashDriver = Flash<'static, peripherals::FLASH, Blocking, FLASH_SIZE>;
+
+static FLASH: Mutex<CriticalSectionRawMutex, RefCell<Option<FlashDriver>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Serial number derived from the flash unique ID
+static UNIQUE_SERIAL: Mutex<CriticalSectionRawMutex, RefCell<Option<SerialString>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Take ownership of the flash peripheral, derive the unit serial and load settings.
+/// Must be called before the USB task starts so the serial descriptor is correct.
+pub fn init(flash: Peri<'static, peripherals::FLASH>) {
+    let mut driver = FlashDriver::new_blocking(flash);
+
+    // Unique ID -> 12 uppercase hex characters (matches Elgato serial length)
+    let mut uid = [0u8; 8];
+    match driver.blocking_unique_id(&mut uid) {
+        Ok(()) => {
+            let serial = serial_from_unique_id(&uid);
+            info!("Flash unique ID serial: {}", serial.as_str());
+            UNIQUE_SERIAL.lock(|s| *s.borrow_mut() = Some(serial));
+        }
+        Err(e) => {
+            warn!("Failed to read flash unique ID: {:?}", e);
+        }
+    }
+
+    // Load persisted settings
+    let mut record = [0u8; SETTINGS_RECORD_SIZE];
+    let mut migrated = false;
+    match driver.blocking_read(SETTINGS_OFFSET, &mut record) {
+        Ok(()) => match Settings::from_record(&record) {
+            Ok((loaded, version)) => {
+                info!("Loaded settings from flash (layout {})", version);
+                settings::replace(loaded);
+                sync_settings();
+                migrated = version < SETTINGS_VERSION;
+            }
+            Err(RecordError::Missing) => info!("No stored settings, using defaults"),
+            Err(e) => warn!("Stored settings unusable ({}), using defaults", e),
+        },
+        Err(e) => warn!("Failed to read settings: {:?}", e),
+    }
+
+    // Load the standalone page table
+    let mut record = [0u8; PAGES_RECORD_SIZE];
+    match driver.blocking_read(PAGES_OFFSET, &mut record) {
+        Ok(()) => {
+            if let Some(table) = PageTable::from_record(&record) {
+                info!("Loaded standalone pages from flash");
+                pages::replace(table);
+            }
+        }
+        Err(e) => warn!("Failed to read standalone pages: {:?}", e),
+    }
+
+    FLASH.lock(|f| *f.borrow_mut() = Some(driver));
+
+    if migrated {
+        info!("Settings migrated to layout {}", SETTINGS_VERSION);
+        if let Err(e) = save_settings() {
+            warn!("Failed to save migrated settings: {}", e);
+        }
+    }
+}
+
+/// Apply loaded or reset settings to the modules that cache them
+fn sync_settings() {
+    crate::logging::sync_from_settings();
+    crate::gamma::sync_from_settings();
+    crate::brightness::sync_from_settings();
+    crate::effects::notify();
+    crate::night::notify();
+}
+
+/// Serial number derived from the flash unique ID, if storage is initialized
+pub fn unique_serial() -> Option<SerialString> {
+    UNIQUE_SERIAL.lock(|s| s.borrow().clone())
+}
+
+/// Write the current settings to flash
+pub fn save_settings() -> Result<(), &'static str> {
+    let mut record = [0u8; SETTINGS_RECORD_SIZE];
+    settings::with(|s| s.to_record(&mut record));
+
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_erase(SETTINGS_OFFSET, SETTINGS_OFFSET + ERASE_SIZE as u32)
+            .map_err(|_| "Flash erase failed")?;
+        driver
+            .blocking_write(SETTINGS_OFFSET, &record)
+            .map_err(|_| "Flash write failed")?;
+        Ok(())
+    })?;
+
+    info!("Settings saved to flash");
+    Ok(())
+}
+
+/// Restore factory settings in RAM and flash. Standalone pages and stored
+/// key images stay. The serial, personality and rotation take effect at the
+/// next power-up, as when they are changed.
+pub fn factory_reset() -> Result<(), &'static str> {
+    settings::replace(Settings::new());
+    sync_settings();
+    info!("Settings reset to factory defaults");
+    save_settings()
+}
+
+/// Write the standalone page table to flash
+pub fn save_pages() -> Result<(), &'static str> {
+    let mut record = [0u8; PAGES_RECORD_SIZE];
+    pages::with(|t| t.to_record(&mut record));
+
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_erase(PAGES_OFFSET, PAGES_OFFSET + ERASE_SIZE as u32)
+            .map_err(|_| "Flash erase failed")?;
+        driver
+            .blocking_write(PAGES_OFFSET, &record)
+            .map_err(|_| "Flash write failed")?;
+        Ok(())
+    })?;
+
+    info!("Standalone pages saved to flash");
+    Ok(())
+}
+
+/// The configuration drive volume as mapped by XIP
+pub fn config_drive() -> &'static [u8] {
+    let address = FLASH_BASE as usize + CONFIG_DRIVE_OFFSET as usize;
+    // SAFETY: the volume lies inside the memory-mapped flash and is only
+    // changed through `write_config_drive_sector`
+    unsafe { core::slice::from_raw_parts(address as *const u8, CONFIG_DRIVE_SIZE) }
+}
+
+/// Replace one erase sector of the configuration drive volume
+pub fn write_config_drive_sector(
+    sector: usize,
+    data: &[u8; ERASE_SIZE],
+) -> Result<(), &'static str> {
+    if sector >= CONFIG_DRIVE_SIZE / ERASE_SIZE {
+        return Err("Invalid config drive sector");
+    }
+    let start = CONFIG_DRIVE_OFFSET + (sector * ERASE_SIZE) as u32;
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_erase(start, start + ERASE_SIZE as u32)
+            .map_err(|_| "Flash erase failed")?;
+        driver
+            .blocking_write(start, data)
+            .map_err(|_| "Flash write failed")
+    })
+}
+
+/// Erase one sector of the firmware staging area; sector 0 is the header,
+/// the image starts at sector 1
+pub fn erase_firmware_sector(sector: usize) -> Result<(), &'static str> {
+    if sector > FIRMWARE_SLOT_SIZE / ERASE_SIZE {
+        return Err("Invalid firmware sector");
+    }
+    let start = FIRMWARE_HEADER_OFFSET + (sector * ERASE_SIZE) as u32;
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_erase(start, start + ERASE_SIZE as u32)
+            .map_err(|_| "Flash erase failed")
+    })
+}
+
+/// Program bytes into the erased firmware staging area, `offset` bytes
+/// from the start of the header sector
+pub fn write_firmware(offset: usize, data: &[u8]) -> Result<(), &'static str> {
+    if offset + data.len() > ERASE_SIZE + FIRMWARE_SLOT_SIZE {
+        return Err("Invalid firmware offset");
+    }
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_write(FIRMWARE_HEADER_OFFSET + offset as u32, data)
+            .map_err(|_| "Flash write failed")
+    })
+}
+
+/// The firmware staging area as mapped by XIP, header sector first
+pub fn firmware_staging() -> &'static [u8] {
+    let address = FLASH_BASE as usize + FIRMWARE_HEADER_OFFSET as usize;
+    // SAFETY: the staging area lies inside the memory-mapped flash and is
+    // only changed through `erase_firmware_sector` and `write_firmware`
+    unsafe { core::slice::from_raw_parts(address as *const u8, ERASE_SIZE + FIRMWARE_SLOT_SIZE) }
+}
+
+fn key_image_offset(slot: usize) -> u32 {
+    KEY_IMAGES_OFFSET + (slot * KEY_IMAGE_SLOT_SIZE) as u32
+}
+
+/// Erase a key image slot; `sectors` limits how much (None = the whole slot)
+pub fn erase_key_image(slot: usize, sectors: Option<usize>) -> Result<(), &'static str> {
+    if slot >= KEY_IMAGE_SLOTS {
+        return Err("Invalid key image slot");
+    }
+    let start = key_image_offset(slot);
+    let len = sectors.map_or(KEY_IMAGE_SLOT_SIZE, |n| {
+        (n * ERASE_SIZE).min(KEY_IMAGE_SLOT_SIZE)
+    });
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_erase(start, start + len as u32)
+            .map_err(|_| "Flash erase failed")
+    })
+}
+
+/// Program bytes into an erased key image slot at `offset`
+pub fn write_key_image(slot: usize, offset: usize, data: &[u8]) -> Result<(), &'static str> {
+    if slot >= KEY_IMAGE_SLOTS || offset + data.len() > KEY_IMAGE_SLOT_SIZE {
+        return Err("Invalid key image slot");
+    }
+    FLASH.lock(|f| {
+        let mut f = f.borrow_mut();
+        let driver = f.as_mut().ok_or("Storage not initialized")?;
+        driver
+            .blocking_write(key_image_offset(slot) + offset as u32, data)
+            .map_err(|_| "Flash write failed")
+    })
+}
+
+/// A key image slot as mapped by XIP. Its contents change under the caller
+/// if the slot is rewritten, which only costs a garbled draw.
+pub fn key_image_slot(slot: usize) -> Option<&'static [u8]> {
+    if slot >= KEY_IMAGE_SLOTS {
+        return None;
+    }
+    let address = FLASH_BASE as usize + key_image_offset(slot) as usize;
+    // SAFETY: the slot lies inside the memory-mapped flash and is never
+    // handed out as mutable memory
+    Some(unsafe { core::slice::from_raw_parts(address as *const u8, KEY_IMAGE_SLOT_SIZE) })
+}
+
+fn serial_from_unique_id(uid: &[u8; 8]) -> SerialString {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut serial = SerialString::new();
+    for &byte in &uid[2..] {
+        let _ = serial.push(HEX[(byte >> 4) as usize] as char);
+        let _ = serial.push(HEX[(byte & 0x0F) as usize] as char);
+    }
+    serial
+}
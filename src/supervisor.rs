@@ -3,17 +3,26 @@
 //! This module provides application-level supervision, monitoring,
 //! and lifecycle management functionality.
 
+use crate::channels::{send_display, try_send_display};
 use crate::config;
 use crate::device::{Device, DeviceConfig};
-use crate::types::APP_VERSION;
+use crate::gfx::KeyText;
+use crate::power::{self, StartupStage};
+use crate::types::{DisplayCommand, APP_VERSION};
 use defmt::*;
-use embassy_time::{Duration, Timer};
+use embassy_futures::select::{select3, Either3};
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+
+/// How long to wait for the display to accept a command
+const DISPLAY_SEND_TIMEOUT_MS: u64 = 50;
 
 /// Application supervisor responsible for monitoring and lifecycle management
 pub struct AppSupervisor {
     device: Device,
     uptime_seconds: u32,
     last_heartbeat: u32,
+    /// Status line last drawn on the info bar, None when the host owns it
+    info_bar: Option<KeyText>,
 }
 
 impl AppSupervisor {
@@ -28,6 +37,7 @@ impl AppSupervisor {
             device,
             uptime_seconds: 0,
             last_heartbeat: 0,
+            info_bar: None,
         }
     }
 
@@ -39,7 +49,15 @@ impl AppSupervisor {
         let display = device.display_config();
 
         info!("========================================");
-        info!("ProductionDeck v{}", APP_VERSION.as_string());
+        info!(
+            "ProductionDeck v{} ({})",
+            APP_VERSION.as_string(),
+            APP_VERSION.git_hash
+        );
+        info!(
+            "Firmware version reported to host: {}",
+            APP_VERSION.firmware
+        );
         info!("Open Source StreamDeck Alternative");
         info!("========================================");
         info!("Hardware: RP2040 (Raspberry Pi Pico)");
@@ -75,9 +93,34 @@ impl AppSupervisor {
     pub async fn run(&mut self) {
         info!("Application supervisor started");
 
+        self.staged_power_up().await;
+        crate::splash::show(self.device).await;
+
+        let mut next_tick = Instant::now() + Duration::from_secs(10);
+
         loop {
-            // Wait for 10 seconds
-            Timer::after(Duration::from_secs(10)).await;
+            // Wait for 10 seconds, running the self-test if one is requested
+            // and refreshing the info bar meanwhile
+            let refresh = Timer::after(Duration::from_millis(config::INFO_BAR_REFRESH_MS));
+            match select3(
+                Timer::at(next_tick),
+                crate::selftest::wait_request(),
+                refresh,
+            )
+            .await
+            {
+                Either3::First(()) => {}
+                Either3::Second(()) => {
+                    crate::selftest::run(self.device).await;
+                    continue;
+                }
+                Either3::Third(()) => {
+                    self.update_info_bar();
+                    crate::brightness::save_if_settled();
+                    continue;
+                }
+            }
+            next_tick += Duration::from_secs(10);
             self.uptime_seconds += 10;
 
             // Print status every 60 seconds (6 iterations)
@@ -88,6 +131,106 @@ impl AppSupervisor {
         }
     }
 
+    /// Bring up USB, the panel and the backlight one after another so their
+    /// inrush currents don't add up on weak hubs
+    async fn staged_power_up(&self) {
+        // USB first; bounded so the deck still starts without a host
+        if with_timeout(
+            Duration::from_millis(config::POWER_UP_USB_TIMEOUT_MS),
+            power::wait_for_usb_configured(),
+        )
+        .await
+        .is_err()
+        {
+            info!("USB not configured yet, continuing startup");
+        }
+
+        // Then the panel
+        power::advance_startup(StartupStage::Panel);
+        if with_timeout(
+            Duration::from_millis(config::POWER_UP_PANEL_TIMEOUT_MS),
+            power::wait_for_stage(StartupStage::Backlight),
+        )
+        .await
+        .is_err()
+        {
+            info!("Panel not reported ready, ramping backlight anyway");
+        }
+
+        // Then the backlight, ramped from dark to the stored host level.
+        // Startup completes even if the display never takes the steps.
+        let target = crate::brightness::host_percent() as u16;
+        let steps = config::BACKLIGHT_RAMP_STEPS as u16;
+        for step in 1..=steps {
+            let send = send_display(DisplayCommand::SetBrightness((target * step / steps) as u8));
+            if with_timeout(Duration::from_millis(DISPLAY_SEND_TIMEOUT_MS), send)
+                .await
+                .is_err()
+            {
+                warn!("Display not taking the backlight ramp, skipping it");
+                break;
+            }
+            Timer::after(Duration::from_millis(config::BACKLIGHT_RAMP_STEP_MS)).await;
+        }
+        power::advance_startup(StartupStage::Running);
+    }
+
+    /// Draw the local status strip while the deck runs standalone, on
+    /// devices that have an info bar. Only changes are sent to the display.
+    fn update_info_bar(&mut self) {
+        let Some(region) = self.device.info_bar() else {
+            return;
+        };
+        if !(cfg!(feature = "standalone-keyboard") && crate::standalone::is_active()) {
+            // The host draws the strip; redraw in full when standalone resumes
+            self.info_bar = None;
+            return;
+        }
+
+        let text = Self::info_bar_text();
+        if self.info_bar.as_ref() == Some(&text) {
+            return;
+        }
+        // Cosmetic: try again on the next refresh rather than wait on the display
+        let command = DisplayCommand::DrawInfoBar {
+            region,
+            text: text.clone(),
+        };
+        if try_send_display(command) {
+            self.info_bar = Some(text);
+        }
+    }
+
+    /// Uptime clock, USB state, brightness and the active page
+    fn info_bar_text() -> KeyText {
+        let minutes = Instant::now().as_secs() / 60;
+        let usb = if !power::usb_configured() {
+            "NO USB"
+        } else if power::usb_suspended() {
+            "USB SLEEP"
+        } else {
+            "USB"
+        };
+        let brightness = crate::brightness::host_percent().min(power::brightness_limit());
+
+        let mut text = KeyText::new();
+        let _ = core::fmt::write(
+            &mut text,
+            format_args!(
+                "{}:{:02}  {}  {}%",
+                minutes / 60,
+                minutes % 60,
+                usb,
+                brightness
+            ),
+        );
+        if let Some(count) = crate::pages::with(|t| t.paging_enabled().then_some(t.page_count)) {
+            let page = crate::pages::current_page() + 1;
+            let _ = core::fmt::write(&mut text, format_args!("  P{}/{}", page, count));
+        }
+        text
+    }
+
     /// Print current application status
     fn print_status(&self) {
         let minutes = self.uptime_seconds / 60;
@@ -100,7 +243,28 @@ impl AppSupervisor {
             info!("Status: Uptime {}m", minutes);
         }
 
-        // TODO: Add memory usage, task health, etc.
+        let limit = power::brightness_limit();
+        match power::temperature_deci_c() {
+            Some(t) => info!(
+                "Supply: VSYS {} mV, die {}.{} C, backlight limit {}%",
+                power::vsys_mv(),
+                t / 10,
+                (t % 10).unsigned_abs(),
+                limit
+            ),
+            None => info!(
+                "Supply: VSYS {} mV, backlight limit {}%",
+                power::vsys_mv(),
+                limit
+            ),
+        }
+        info!("Metrics: {}", crate::stats::snapshot());
+        for usage in crate::stats::pool_usage() {
+            info!(
+                "Pool {}: {}/{} in use, peak {}",
+                usage.pool, usage.in_use, usage.capacity, usage.peak
+            );
+        }
     }
 
     /// Get current uptime in seconds
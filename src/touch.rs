@@ -0,0 +1,219 @@
+//! Touchscreen key input
+//!
+//! For builds with a touch panel over the TFT and no key switches. The touch
+//! controller is polled and each touched point is mapped to the key region
+//! under it, so the panel reads like a set of direct-wired keys and goes
+//! through the same debounce, remap and gesture pipeline to `BUTTON_CHANNEL`
+//! (`buttons::button_task_touch`). The controller is picked by feature:
+//!
+//! | Feature         | Controller            | Wiring                                        |
+//! |-----------------|-----------------------|-----------------------------------------------|
+//! | `touch-xpt2046` | XPT2046 (resistive)   | SPI1: SCK 10, MOSI 11, MISO 12, CS 13, IRQ 16 |
+//! | `touch-ft6236`  | FT6236 (capacitive)   | I2C0: SDA 12, SCL 13                          |
+//!
+//! Both use key matrix pins, which a zero-switch build leaves free.
+//! `touch-ft6236` wins when both are enabled. Resistive panels report raw
+//! 12-bit readings and need the `TOUCH_RAW_*` calibration; the FT6236
+//! reports panel pixels. Points are resolved against the on-screen keypad's
+//! cells (`keypad`); touches within `TOUCH_EDGE_MARGIN` of a cell's edge are
+//! ignored so a finger between two keys presses neither.
+
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use heapless::Vec;
+
+use crate::config::{MAX_KEYS, TOUCH_EDGE_MARGIN, TOUCH_SWAP_XY};
+
+/// Most simultaneous touches any supported controller reports
+pub const MAX_TOUCH_POINTS: usize = 2;
+
+/// Touched points in panel pixels
+pub type TouchPoints = Vec<(u16, u16), MAX_TOUCH_POINTS>;
+
+/// A touch controller
+pub trait TouchController {
+    /// Current touches in panel pixels; empty when nothing is touched or
+    /// the controller didn't answer
+    fn read(&mut self) -> TouchPoints;
+}
+
+#[cfg(feature = "touch-ft6236")]
+pub type Touch = ft6236::Ft6236;
+#[cfg(all(feature = "touch-xpt2046", not(feature = "touch-ft6236")))]
+pub type Touch = xpt2046::Xpt2046;
+
+/// Controller set up by `init`, taken by the touch button task
+static TOUCH: Mutex<CriticalSectionRawMutex, RefCell<Option<Touch>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Hand over the controller. Must be called before the touch button task runs.
+pub fn init(touch: Touch) {
+    TOUCH.lock(|t| *t.borrow_mut() = Some(touch));
+}
+
+/// Take the controller set up by `init`
+pub fn take() -> Option<Touch> {
+    TOUCH.lock(|t| t.borrow_mut().take())
+}
+
+/// Key under a touched point, or None outside the keypad or too close to a
+/// key's edge
+pub fn key_at(x: u16, y: u16) -> Option<usize> {
+    let (x, y) = if TOUCH_SWAP_XY { (y, x) } else { (x, y) };
+    crate::keypad::key_at(x, y, TOUCH_EDGE_MARGIN)
+}
+
+/// Poll the controller once: the keys under the current touches
+pub fn scan(touch: &mut impl TouchController) -> [bool; MAX_KEYS] {
+    let mut keys = [false; MAX_KEYS];
+    for (x, y) in touch.read() {
+        if let Some(key) = key_at(x, y).filter(|&key| key < MAX_KEYS) {
+            keys[key] = true;
+        }
+    }
+    keys
+}
+
+// ===================================================================
+// XPT2046 (resistive, SPI)
+// ===================================================================
+
+#[cfg(feature = "touch-xpt2046")]
+pub mod xpt2046 {
+    use embassy_rp::gpio::{Input, Output};
+    use embassy_rp::peripherals::SPI1;
+    use embassy_rp::spi::{Blocking, Spi};
+
+    use super::{TouchController, TouchPoints};
+    use crate::config::{
+        display_total_height, display_total_width, TOUCH_RAW_X_MAX, TOUCH_RAW_X_MIN,
+        TOUCH_RAW_Y_MAX, TOUCH_RAW_Y_MIN,
+    };
+
+    /// Control bytes: start bit, channel, 12-bit, differential, power-down
+    /// between conversions
+    const READ_X: u8 = 0xD0;
+    const READ_Y: u8 = 0x90;
+    /// Samples averaged per axis; resistive readings are noisy
+    const SAMPLES: u32 = 4;
+
+    pub struct Xpt2046 {
+        spi: Spi<'static, SPI1, Blocking>,
+        cs: Output<'static>,
+        /// PENIRQ, pulled low by the controller while the panel is pressed
+        irq: Input<'static>,
+    }
+
+    impl Xpt2046 {
+        pub fn new(
+            spi: Spi<'static, SPI1, Blocking>,
+            cs: Output<'static>,
+            irq: Input<'static>,
+        ) -> Self {
+            Self { spi, cs, irq }
+        }
+
+        /// One 12-bit conversion
+        fn sample(&mut self, command: u8) -> Option<u16> {
+            let mut rx = [0u8; 3];
+            self.spi.blocking_transfer(&mut rx, &[command, 0, 0]).ok()?;
+            Some((u16::from_be_bytes([rx[1], rx[2]]) >> 3) & 0x0FFF)
+        }
+
+        fn average(&mut self, command: u8) -> Option<u16> {
+            let mut sum = 0u32;
+            for _ in 0..SAMPLES {
+                sum += self.sample(command)? as u32;
+            }
+            Some((sum / SAMPLES) as u16)
+        }
+    }
+
+    /// Scale a raw reading between its calibrated ends to 0..pixels
+    fn scale(raw: u16, min: u16, max: u16, pixels: usize) -> u16 {
+        let span = max.abs_diff(min).max(1) as u32;
+        let offset = if max >= min {
+            raw.clamp(min, max) - min
+        } else {
+            // Axis runs the other way on this panel
+            min - raw.clamp(max, min)
+        };
+        (offset as u32 * pixels as u32 / span).min(pixels as u32 - 1) as u16
+    }
+
+    impl TouchController for Xpt2046 {
+        fn read(&mut self) -> TouchPoints {
+            let mut points = TouchPoints::new();
+            if self.irq.is_high() {
+                return points;
+            }
+            self.cs.set_low();
+            let raw = self.average(READ_X).zip(self.average(READ_Y));
+            self.cs.set_high();
+            // Released while sampling: the readings are garbage
+            if let (Some((x, y)), true) = (raw, self.irq.is_low()) {
+                let _ = points.push((
+                    scale(x, TOUCH_RAW_X_MIN, TOUCH_RAW_X_MAX, display_total_width()),
+                    scale(y, TOUCH_RAW_Y_MIN, TOUCH_RAW_Y_MAX, display_total_height()),
+                ));
+            }
+            points
+        }
+    }
+}
+
+// ===================================================================
+// FT6236 (capacitive, I2C)
+// ===================================================================
+
+#[cfg(feature = "touch-ft6236")]
+pub mod ft6236 {
+    use embassy_rp::i2c::{Blocking, I2c};
+    use embassy_rp::peripherals::I2C0;
+
+    use super::{TouchController, TouchPoints, MAX_TOUCH_POINTS};
+
+    /// 7-bit bus address
+    const ADDRESS: u8 = 0x38;
+    /// TD_STATUS: number of touches; the touch records follow
+    const REG_TD_STATUS: u8 = 0x02;
+    /// Bytes per touch record (XH, XL, YH, YL, weight, misc)
+    const RECORD_LEN: usize = 6;
+    /// Event flag in XH: 1 = lift-up
+    const EVENT_LIFT_UP: u8 = 1;
+
+    pub struct Ft6236 {
+        i2c: I2c<'static, I2C0, Blocking>,
+    }
+
+    impl Ft6236 {
+        pub fn new(i2c: I2c<'static, I2C0, Blocking>) -> Self {
+            Self { i2c }
+        }
+    }
+
+    impl TouchController for Ft6236 {
+        fn read(&mut self) -> TouchPoints {
+            let mut points = TouchPoints::new();
+            let mut regs = [0u8; 1 + RECORD_LEN * MAX_TOUCH_POINTS];
+            if self
+                .i2c
+                .blocking_write_read(ADDRESS, &[REG_TD_STATUS], &mut regs)
+                .is_err()
+            {
+                return points;
+            }
+            let count = (regs[0] & 0x0F) as usize;
+            for record in regs[1..].chunks_exact(RECORD_LEN).take(count) {
+                if record[0] >> 6 == EVENT_LIFT_UP {
+                    continue;
+                }
+                let x = u16::from_be_bytes([record[0] & 0x0F, record[1]]);
+                let y = u16::from_be_bytes([record[2] & 0x0F, record[3]]);
+                let _ = points.push((x, y));
+            }
+            points
+        }
+    }
+}
@@ -3,67 +3,218 @@
 //! This module contains shared types, enums, and structures that are used
 //! by multiple modules in the application.
 
-use crate::config::IMAGE_BUFFER_SIZE;
+use crate::config::{MAX_KEYS, RLE_MAX_RUNS, STREAM_CHUNK_SIZE};
+use crate::gfx::KeyText;
+use crate::image_pool::ImageSlab;
+use embassy_time::Instant;
 use heapless::Vec;
+pub use productiondeck_protocol::types::{
+    EncoderEvent, LcdRegion, Rotation, Screen, TouchEvent, TouchKind, PLUS_DIAL_COUNT,
+};
+
+/// Which of a key's locally stored images to show (see `key_images`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum KeyImageVariant {
+    /// Shown while the key is released
+    Icon = 0,
+    /// Shown while the key is held
+    Pressed = 1,
+}
+
+impl KeyImageVariant {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => KeyImageVariant::Icon,
+            1 => KeyImageVariant::Pressed,
+            _ => return None,
+        })
+    }
+}
+
+/// Part of a streamed key image
+pub type ImageChunk = Vec<u8, STREAM_CHUNK_SIZE>;
+
+/// A run of identical RGB888 pixels (same byte order as the image)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RleRun {
+    pub color: [u8; 3],
+    pub len: u16,
+}
+
+/// Key image as runs of identical pixels in row order. Icons with large flat
+/// areas fit in a few runs: they travel without an image slab and each run
+/// is converted to the panel format once instead of per pixel.
+#[derive(Clone, Debug, Default)]
+pub struct RleImage {
+    runs: Vec<RleRun, RLE_MAX_RUNS>,
+}
+
+// Carried inline in `DisplayCommand`; must not grow it past a stream chunk
+const _: () = assert!(core::mem::size_of::<RleImage>() <= core::mem::size_of::<ImageChunk>());
+
+impl RleImage {
+    /// Encode RGB888 pixels; None when they need more than `RLE_MAX_RUNS` runs
+    pub fn encode(rgb: &[u8]) -> Option<Self> {
+        let mut runs: Vec<RleRun, RLE_MAX_RUNS> = Vec::new();
+        for pixel in rgb.chunks_exact(3) {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            match runs.last_mut() {
+                Some(run) if run.color == color && run.len < u16::MAX => run.len += 1,
+                _ => runs.push(RleRun { color, len: 1 }).ok()?,
+            }
+        }
+        Some(Self { runs })
+    }
+
+    pub fn runs(&self) -> &[RleRun] {
+        &self.runs
+    }
+
+    /// Number of pixels the runs expand to
+    pub fn pixel_count(&self) -> usize {
+        self.runs.iter().map(|run| run.len as usize).sum()
+    }
+
+    /// The pixels in row order
+    pub fn pixels(&self) -> impl Iterator<Item = [u8; 3]> + '_ {
+        self.runs
+            .iter()
+            .flat_map(|run| core::iter::repeat_n(run.color, run.len as usize))
+    }
+}
 
 /// Button state structure for communicating button presses between tasks
 #[derive(Clone, Copy, Debug, defmt::Format)]
 pub struct ButtonState {
-    /// Array of button states - true if pressed, false if released
-    /// Using fixed size for compatibility across all devices
-    pub buttons: [bool; 32], // Max buttons for any StreamDeck device (XL has 32)
+    /// Pressed buttons, bit n for button n
+    pressed: u32,
     /// Whether any button state has changed since last report
     pub changed: bool,
     /// Number of active buttons for this device
-    pub active_count: usize,
+    active_count: u8,
+    /// When the scan that produced this state finished, for the
+    /// button-to-report latency metric
+    pub scanned_at: Instant,
 }
 
+const _: () = core::assert!(MAX_KEYS <= u32::BITS as usize);
+
 impl ButtonState {
     /// Create new button state with all buttons released
     pub fn new(active_count: usize) -> Self {
         Self {
-            buttons: [false; 32],
+            pressed: 0,
             changed: false,
-            active_count: active_count.min(32),
+            active_count: active_count.min(MAX_KEYS) as u8,
+            scanned_at: Instant::now(),
         }
     }
 
+    /// Number of active buttons for this device
+    pub fn active_count(&self) -> usize {
+        self.active_count as usize
+    }
+
     /// Check if a specific button is pressed
     pub fn is_pressed(&self, button_index: usize) -> bool {
-        if button_index < self.active_count {
-            self.buttons[button_index]
-        } else {
-            false
-        }
+        button_index < self.active_count() && self.pressed & (1 << button_index) != 0
     }
 
     /// Set button state and mark as changed if different
     pub fn set_button(&mut self, button_index: usize, pressed: bool) {
-        if button_index < self.active_count && self.buttons[button_index] != pressed {
-            self.buttons[button_index] = pressed;
+        if button_index < self.active_count() && self.is_pressed(button_index) != pressed {
+            self.pressed ^= 1 << button_index;
+            self.changed = true;
+        }
+    }
+
+    /// Pressed buttons as a bitmask, bit n for button n
+    pub fn pressed_mask(&self) -> u32 {
+        self.pressed
+    }
+
+    /// Replace the pressed buttons; bits past the active count are dropped
+    pub fn set_pressed_mask(&mut self, mask: u32) {
+        let active = match self.active_count() {
+            32 => u32::MAX,
+            count => (1 << count) - 1,
+        };
+        if self.pressed != mask & active {
+            self.pressed = mask & active;
             self.changed = true;
         }
     }
+
+    /// Indices of the pressed buttons, in order
+    pub fn pressed_keys(&self) -> impl Iterator<Item = usize> {
+        let pressed = self.pressed;
+        (0..self.active_count()).filter(move |&key| pressed & (1 << key) != 0)
+    }
+
+    /// One flag per button, for code that works on key arrays
+    pub fn to_array(&self) -> [bool; MAX_KEYS] {
+        core::array::from_fn(|key| self.is_pressed(key))
+    }
+}
+
+/// A single key transition, as published on `KEY_EVENTS` for local layers
+/// (gestures, press feedback, stored images) that care about edges and press
+/// durations rather than the whole key state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct KeyEvent {
+    /// Protocol key index, after the remap and before gestures
+    pub key: u8,
+    pub pressed: bool,
+    /// Scan time of the transition
+    pub timestamp: Instant,
+}
+
+/// Device-wide commands broadcast to every output subsystem (display, LEDs,
+/// haptics) present in the build
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum DeviceCommand {
+    /// Host reset: clear every output to its idle state
+    Reset,
+    /// Set output brightness (0-100%)
+    SetBrightness(u8),
+    /// Host went to sleep, outputs off
+    Sleep,
+    /// Host woke up, restore outputs
+    Wake,
 }
 
 /// USB commands that can be sent from the HID handler to other tasks
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum UsbCommand {
     /// Reset device to initial state
     Reset,
     /// Set display brightness (0-100%)
     SetBrightness(u8),
+    /// USB bus suspended by the host
+    Suspend,
+    /// USB bus resumed from suspend
+    Resume,
     /// Image data received for a specific key
-    ImageData {
+    ImageData { key_id: u8, data: ImageSlab },
+    /// Streamed image data for a specific key, starting at `offset` in the image
+    ImageChunk {
         key_id: u8,
-        #[allow(clippy::large_enum_variant)]
-        data: Vec<u8, IMAGE_BUFFER_SIZE>,
+        offset: u32,
+        last: bool,
+        data: ImageChunk,
     },
+    /// Image data received for a window of the touch strip LCD
+    LcdImage { region: LcdRegion, data: ImageSlab },
+    /// Put one screen to sleep or wake it (Plus/Neo)
+    SetScreenSleep { screen: Screen, sleep: bool },
+    /// Set the brightness of one screen (0-100%, Plus/Neo)
+    SetScreenBrightness { screen: Screen, value: u8 },
 }
 
 /// Display commands for controlling the display subsystem
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum DisplayCommand {
     /// Clear a specific key display
@@ -72,19 +223,95 @@ pub enum DisplayCommand {
     ClearAll,
     /// Set display brightness (0-100%)
     SetBrightness(u8),
+    /// Override brightness for a single key (0-100%, >100 clears the override)
+    SetKeyBrightness { key_id: u8, value: u8 },
+    /// Put the panel to sleep (backlight off, controller in sleep mode)
+    Sleep,
+    /// Wake the panel and restore the previous brightness
+    Wake,
+    /// Fill a key region with a solid RGB565 color
+    FillKey { key_id: u8, color: u16 },
+    /// Draw a run-length encoded key image (RGB888 runs, dimmed like images)
+    DrawRle { key_id: u8, image: RleImage },
+    /// Draw vertical color bars across a key region (test pattern)
+    ColorBars(u8),
+    /// Render centered text on a key (RGB565 foreground/background)
+    DrawText {
+        key_id: u8,
+        text: KeyText,
+        fg: u16,
+        bg: u16,
+    },
     /// Display an image on a specific key
-    DisplayImage {
+    DisplayImage { key_id: u8, data: ImageSlab },
+    /// Draw the next part of a streamed image on a specific key
+    DisplayImageChunk {
+        key_id: u8,
+        offset: u32,
+        last: bool,
+        data: ImageChunk,
+    },
+    /// Display an image in a window of the touch strip LCD
+    DisplayLcdImage { region: LcdRegion, data: ImageSlab },
+    /// Cap the backlight (0-100%) while the supply voltage sags; 100 lifts the cap
+    LimitBrightness(u8),
+    /// Briefly highlight a key that was just pressed (`key-feedback`)
+    HighlightKey(u8),
+    /// Change the panel orientation; the panel is cleared
+    SetRotation(Rotation),
+    /// Blank one screen, or wake it; the panel sleeps once every screen does
+    SetScreenSleep { screen: Screen, sleep: bool },
+    /// Set the brightness of one screen (0-100%); `Keys` is the global level
+    SetScreenBrightness { screen: Screen, value: u8 },
+    /// Draw one of a key's locally stored images (press feedback)
+    ShowStoredImage {
         key_id: u8,
-        #[allow(clippy::large_enum_variant)]
-        data: Vec<u8, IMAGE_BUFFER_SIZE>,
+        variant: KeyImageVariant,
     },
+    /// Render a status line centered in a window of the touch strip / info bar
+    DrawInfoBar { region: LcdRegion, text: KeyText },
+}
+
+impl DisplayCommand {
+    /// Brightness, clearing, power and orientation changes travel on the
+    /// control channel, ahead of queued image traffic
+    pub fn is_control(&self) -> bool {
+        matches!(
+            self,
+            DisplayCommand::SetBrightness(_)
+                | DisplayCommand::SetKeyBrightness { .. }
+                | DisplayCommand::ClearAll
+                | DisplayCommand::Sleep
+                | DisplayCommand::Wake
+                | DisplayCommand::LimitBrightness(_)
+                | DisplayCommand::SetRotation(_)
+                | DisplayCommand::SetScreenSleep { .. }
+                | DisplayCommand::SetScreenBrightness { .. }
+        )
+    }
 }
 
-/// Application version information
+impl From<DeviceCommand> for DisplayCommand {
+    fn from(command: DeviceCommand) -> Self {
+        match command {
+            DeviceCommand::Reset => DisplayCommand::ClearAll,
+            DeviceCommand::SetBrightness(value) => DisplayCommand::SetBrightness(value),
+            DeviceCommand::Sleep => DisplayCommand::Sleep,
+            DeviceCommand::Wake => DisplayCommand::Wake,
+        }
+    }
+}
+
+/// Application version information, generated at build time from the
+/// Cargo package version and the git commit (see `build.rs`)
 pub struct AppVersion {
     pub major: u8,
     pub minor: u8,
     pub patch: u8,
+    /// Short commit hash, "-dirty" suffixed for uncommitted builds
+    pub git_hash: &'static str,
+    /// Version in the Elgato firmware format ("M.mm.ppp")
+    pub firmware: &'static str,
 }
 
 impl AppVersion {
@@ -93,14 +320,31 @@ impl AppVersion {
             major,
             minor,
             patch,
+            git_hash: env!("PRODUCTIONDECK_GIT_HASH"),
+            firmware: env!("PRODUCTIONDECK_FIRMWARE_VERSION"),
         }
     }
 
     pub fn as_string(&self) -> &'static str {
-        // For embedded, use a simple compile-time string
-        "0.1.0"
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+/// Parse a decimal version component at compile time
+const fn parse_version_part(part: &str) -> u8 {
+    let bytes = part.as_bytes();
+    let mut value = 0u8;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0');
+        i += 1;
     }
+    value
 }
 
 /// Current application version
-pub const APP_VERSION: AppVersion = AppVersion::new(0, 1, 0);
+pub const APP_VERSION: AppVersion = AppVersion::new(
+    parse_version_part(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_PATCH")),
+);
@@ -0,0 +1,458 @@
+//! USB device setup and IN-side endpoints
+//!
+//! Device descriptor configuration, the bus-state handler, and the loops
+//! that write to the host: HID input reports (keys, dials, touch strip), the
+//! optional standalone keyboard interface, the optional secondary deck
+//! interface and the optional vendor bulk interface.
+
+use defmt::*;
+use embassy_futures::select::{select, select4, Either, Either4};
+use embassy_time::Timer;
+use embassy_usb::{Config, Handler, UsbVersion};
+use static_cell::StaticCell;
+
+use super::handler::{
+    expect_reopen, idle_rate, set_last_key_report, with_protocol, RESEND_KEY_STATE,
+};
+use super::HidIn;
+#[cfg(feature = "standalone-keyboard")]
+use super::KeyboardIn;
+#[cfg(feature = "secondary-deck")]
+use super::SecondaryIn;
+use crate::channels::{BUTTON_CHANNEL, ENCODER_CHANNEL, TOUCH_CHANNEL, USB_COMMAND_CHANNEL};
+use crate::compat::{self, Probe};
+use crate::config;
+use crate::device::{Device, DeviceConfig};
+use crate::error::UsbError;
+use crate::log_at;
+#[cfg(feature = "vendor-bulk")]
+use crate::log_limited;
+use crate::logging::Subsystem;
+#[cfg(feature = "proto-v2")]
+use crate::protocol::plus;
+use crate::settings::SerialString;
+use crate::types::UsbCommand;
+
+// ===================================================================
+// USB Configuration
+// ===================================================================
+
+/// Serial number string for the USB descriptor, fixed for the lifetime of the connection
+static USB_SERIAL_STRING: StaticCell<SerialString> = StaticCell::new();
+
+pub(super) fn create_usb_config_for_device(device: Device) -> Config<'static> {
+    let usb_config_data = device.usb_config();
+    let mut usb_config = Config::new(usb_config_data.vid, usb_config_data.pid);
+    usb_config.manufacturer = Some(usb_config_data.manufacturer);
+    usb_config.product = Some(usb_config_data.product_name);
+    let serial = USB_SERIAL_STRING.init(config::usb_serial());
+    info!("USB serial number: {}", serial.as_str());
+    usb_config.serial_number = Some(serial.as_str());
+    usb_config.max_power = 100; // 200mA (matches real StreamDeck devices)
+    usb_config.max_packet_size_0 = 64;
+    usb_config.device_class = 0x00; // Interface-defined (HID class will be set in interface)
+    usb_config.device_sub_class = 0x00;
+    usb_config.device_protocol = 0x00;
+    usb_config.composite_with_iads = false;
+    // USB 2.1 tells the host to read the BOS descriptor; only WebUSB builds
+    // put anything in it, HID-only builds report 2.0 like Elgato hardware
+    usb_config.bcd_usb = if cfg!(feature = "webusb") {
+        UsbVersion::TwoOne
+    } else {
+        UsbVersion::Two
+    };
+
+    // Extra interfaces turn the device into a composite device
+    #[cfg(any(
+        feature = "vendor-bulk",
+        feature = "standalone-keyboard",
+        feature = "secondary-deck"
+    ))]
+    {
+        usb_config.device_class = 0xEF; // Miscellaneous (IAD)
+        usb_config.device_sub_class = 0x02;
+        usb_config.device_protocol = 0x01;
+        usb_config.composite_with_iads = true;
+    }
+
+    // Set device version to match real StreamDeck devices
+    usb_config.device_release = config::USB_BCD_DEVICE;
+
+    usb_config
+}
+
+// ===================================================================
+// USB Device State Handler
+// ===================================================================
+
+/// Tracks bus-level state changes (enumeration, suspend/resume) and forwards
+/// them to the rest of the firmware
+pub(super) struct UsbDeviceHandler {
+    usb_command_sender: embassy_sync::channel::Sender<
+        'static,
+        embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+        UsbCommand,
+        4,
+    >,
+}
+
+impl UsbDeviceHandler {
+    pub(super) fn new() -> Self {
+        Self {
+            usb_command_sender: USB_COMMAND_CHANNEL.sender(),
+        }
+    }
+}
+
+impl Handler for UsbDeviceHandler {
+    fn enabled(&mut self, enabled: bool) {
+        info!("USB {}", if enabled { "enabled" } else { "disabled" });
+        if !enabled {
+            crate::power::set_usb_configured(false);
+        }
+    }
+
+    fn reset(&mut self) {
+        info!("USB bus reset");
+        crate::power::set_usb_configured(false);
+        super::handler::reset_idle_rate();
+        crate::standalone::reset_host_activity();
+        expect_reopen();
+    }
+
+    fn configured(&mut self, configured: bool) {
+        info!("USB configured: {}", configured);
+        crate::power::set_usb_configured(configured);
+        if configured {
+            crate::standalone::reset_host_activity();
+            expect_reopen();
+            // Re-enumeration after startup: show the stored host level right
+            // away. At power-up the backlight ramp brings it up instead.
+            if crate::power::startup_stage() == crate::power::StartupStage::Running {
+                let percent = crate::brightness::host_percent();
+                if self
+                    .usb_command_sender
+                    .try_send(UsbCommand::SetBrightness(percent))
+                    .is_err()
+                {
+                    crate::stats::command_dropped();
+                }
+            }
+        }
+    }
+
+    fn suspended(&mut self, suspended: bool) {
+        if suspended == crate::power::usb_suspended() {
+            return;
+        }
+        crate::power::set_usb_suspended(suspended);
+        if suspended {
+            info!("USB suspended, entering low-power state");
+            if self
+                .usb_command_sender
+                .try_send(UsbCommand::Suspend)
+                .is_err()
+            {
+                crate::stats::command_dropped();
+            }
+        } else {
+            info!("USB resumed, leaving low-power state");
+            // Host software may have restarted while the bus slept
+            expect_reopen();
+            if self
+                .usb_command_sender
+                .try_send(UsbCommand::Resume)
+                .is_err()
+            {
+                crate::stats::command_dropped();
+            }
+        }
+    }
+}
+
+// ===================================================================
+// HID Input Reports
+// ===================================================================
+
+/// Send one input report on the HID interrupt IN endpoint; false if it was lost
+async fn send_input_report(writer: &mut HidIn, report: &[u8]) -> bool {
+    if report.is_empty() {
+        return false;
+    }
+    let sent = match writer.write(report).await {
+        Ok(()) => {
+            log_at!(
+                trace,
+                Subsystem::Usb,
+                "Input report sent ({} bytes)",
+                report.len()
+            );
+            true
+        }
+        Err(e) => {
+            warn!("Failed to send input report: {:?}", e);
+            crate::stats::packet_dropped();
+            crate::stats::record_error(UsbError::WriteFailed);
+            false
+        }
+    };
+    // Some hosts merge reports sent back to back
+    let gap = crate::host::quirks().min_report_gap_ms;
+    if gap > 0 {
+        Timer::after_millis(gap as u64).await;
+    }
+    sent
+}
+/// Forward key, dial and touch events to the host as input reports; never
+/// returns. In standalone mode keys go out on the keyboard interface instead.
+/// When the host set an idle rate the last key report is repeated at it.
+pub(super) async fn run_input_reports(
+    device: Device,
+    mut writer: HidIn,
+    #[cfg(feature = "standalone-keyboard")] mut keyboard: KeyboardIn,
+) {
+    let receiver = BUTTON_CHANNEL.receiver();
+    #[cfg(feature = "standalone-keyboard")]
+    let mut keyboard_report = [0u8; crate::standalone::KEYBOARD_REPORT_SIZE];
+    #[cfg(feature = "standalone-keyboard")]
+    let mut pager = crate::pages::Pager::new();
+    // Last key report sent, repeated when the idle rate runs out. Starts
+    // as all released so GET_REPORT(Input) has an answer before any press.
+    let mut last_report = [0u8; config::USB_MAX_INPUT_REPORT];
+    let mut last_len = format_key_report(device, &[false; config::MAX_KEYS], &mut last_report);
+    set_last_key_report(&last_report[..last_len]);
+    // Keys as last scanned, reported in full when a new host session opens
+    let mut buttons = [false; config::MAX_KEYS];
+
+    loop {
+        let idle = async {
+            let repeat = async {
+                match idle_rate() {
+                    Some(rate) => Timer::after(rate).await,
+                    None => core::future::pending().await,
+                }
+            };
+            select(repeat, RESEND_KEY_STATE.wait()).await
+        };
+        let button_state = match select4(
+            receiver.receive(),
+            ENCODER_CHANNEL.receive(),
+            TOUCH_CHANNEL.receive(),
+            idle,
+        )
+        .await
+        {
+            Either4::First(state) => state,
+            // Dial and touch strip events (StreamDeck Plus, Studio)
+            #[cfg(feature = "proto-v2")]
+            Either4::Second(event) => {
+                let mut report = [0u8; plus::DIAL_REPORT_SIZE];
+                let len = plus::format_encoder_report(&event, device.dial_count(), &mut report);
+                send_input_report(&mut writer, &report[..len]).await;
+                continue;
+            }
+            #[cfg(feature = "proto-v2")]
+            Either4::Third(event) => {
+                let mut report = [0u8; plus::TOUCH_REPORT_SIZE];
+                let len = plus::format_touch_report(&event, &mut report);
+                send_input_report(&mut writer, &report[..len]).await;
+                continue;
+            }
+            // No Plus or Studio in this build, so nothing reports them
+            #[cfg(not(feature = "proto-v2"))]
+            Either4::Second(_) | Either4::Third(_) => continue,
+            Either4::Fourth(wake) => {
+                let standalone =
+                    cfg!(feature = "standalone-keyboard") && crate::standalone::is_active();
+                if !standalone {
+                    if let Either::Second(()) = wake {
+                        last_len = format_key_report(device, &buttons, &mut last_report);
+                        set_last_key_report(&last_report[..last_len]);
+                    }
+                    send_input_report(&mut writer, &last_report[..last_len]).await;
+                }
+                continue;
+            }
+        };
+        buttons = button_state.to_array();
+
+        // Standalone mode: keys become keyboard shortcuts
+        #[cfg(feature = "standalone-keyboard")]
+        {
+            let standalone = crate::standalone::is_active();
+            if standalone || keyboard_report.iter().any(|&b| b != 0) {
+                let mut state = button_state;
+                let shortcuts = if standalone {
+                    pager.update(&state).await
+                } else {
+                    // Host software appeared: release anything still held
+                    state.set_pressed_mask(0);
+                    crate::settings::with(|s| s.shortcuts)
+                };
+                crate::standalone::build_keyboard_report(&state, &shortcuts, &mut keyboard_report);
+                if let Err(e) = keyboard.write(&keyboard_report).await {
+                    warn!("Failed to send keyboard report: {:?}", e);
+                    crate::stats::packet_dropped();
+                }
+            }
+            if standalone {
+                continue;
+            }
+        }
+
+        if button_state.changed {
+            last_len = format_key_report(device, &buttons, &mut last_report);
+            set_last_key_report(&last_report[..last_len]);
+            if send_input_report(&mut writer, &last_report[..last_len]).await {
+                let latency = button_state.scanned_at.elapsed().as_micros();
+                crate::stats::input_reported(latency.min(u32::MAX as u64) as u32);
+                #[cfg(feature = "latency-test")]
+                if button_state.pressed_mask() != 0 {
+                    crate::latency_test::report_sent();
+                }
+                compat::note(Probe::KeyReport);
+            } else {
+                compat::fail(Probe::KeyReport);
+            }
+        }
+    }
+}
+
+/// Build the key report for `buttons` (physical order); returns its length
+fn format_key_report(device: Device, buttons: &[bool], report: &mut [u8]) -> usize {
+    let layout = device.button_layout();
+    let len = with_protocol(|protocol| {
+        let button_mapping =
+            protocol.map_buttons(buttons, layout.cols, layout.rows, layout.left_to_right);
+        protocol.format_button_report(&button_mapping, report)
+    })
+    .unwrap_or(0)
+    .min(device.input_report_size());
+    if len > 0 && crate::host::quirks().pad_input_reports {
+        report[len..device.input_report_size()].fill(0);
+        return device.input_report_size();
+    }
+    len
+}
+
+// ===================================================================
+// Secondary Deck Interface
+// ===================================================================
+
+/// Forward the secondary deck's keys to its own interface; never returns
+#[cfg(feature = "secondary-deck")]
+pub(super) async fn run_secondary_reports(mut writer: SecondaryIn) {
+    use crate::secondary_deck::{format_report, SECONDARY_REPORT_SIZE};
+
+    let receiver = crate::channels::SECONDARY_BUTTON_CHANNEL.receiver();
+    let mut report = [0u8; SECONDARY_REPORT_SIZE];
+    loop {
+        let state = receiver.receive().await;
+        let len = format_report(&state, &mut report);
+        if let Err(e) = writer.write(&report[..len]).await {
+            warn!("Failed to send secondary deck report: {:?}", e);
+            crate::stats::packet_dropped();
+            crate::stats::record_error(UsbError::WriteFailed);
+        }
+    }
+}
+
+// ===================================================================
+// Vendor Bulk Interface
+// ===================================================================
+
+/// Receive images on the vendor bulk OUT endpoint and acknowledge them on bulk IN
+#[cfg(feature = "vendor-bulk")]
+pub(super) async fn vendor_bulk_loop(
+    mut ep_in: impl embassy_usb::driver::EndpointIn,
+    mut ep_out: impl embassy_usb::driver::EndpointOut,
+) {
+    use crate::protocol::bulk::{self, BulkAssembler, BulkResult};
+
+    let mut assembler = BulkAssembler::new();
+    let mut packet = [0u8; 64];
+    // Whether the firmware transfer in progress has been stored so far
+    #[cfg(feature = "firmware-update")]
+    let mut firmware_ok = true;
+
+    let transfers = async {
+        loop {
+            ep_out.wait_enabled().await;
+            info!("Vendor bulk interface enabled");
+
+            loop {
+                let n = match ep_out.read(&mut packet).await {
+                    Ok(n) => n,
+                    Err(e) => {
+                        warn!("Vendor bulk read error: {:?}", e);
+                        crate::stats::packet_dropped();
+                        break;
+                    }
+                };
+
+                let reply = match assembler.feed(&packet[..n]) {
+                    BulkResult::Pending => continue,
+                    BulkResult::KeyImageComplete { key_id, mut image } => {
+                        log_limited!(
+                            info,
+                            Subsystem::Usb,
+                            "Bulk image complete for key {} ({} bytes)",
+                            key_id,
+                            image.len()
+                        );
+                        image.mark_assembled();
+                        crate::stats::image_received();
+                        let command = UsbCommand::ImageData {
+                            key_id,
+                            data: image,
+                        };
+                        if USB_COMMAND_CHANNEL.try_send(command).is_err() {
+                            crate::stats::command_dropped();
+                        }
+                        bulk::status_reply(bulk::BULK_STATUS_OK, key_id)
+                    }
+                    #[cfg(feature = "firmware-update")]
+                    BulkResult::FirmwareData { range, complete } => {
+                        if firmware_ok && !range.is_empty() {
+                            firmware_ok = crate::firmware_update::write(&packet[range]).is_ok();
+                        }
+                        if !complete {
+                            continue;
+                        }
+                        let status = if firmware_ok {
+                            bulk::BULK_STATUS_OK
+                        } else {
+                            warn!("Bulk firmware transfer rejected");
+                            bulk::BULK_STATUS_FIRMWARE_REJECTED
+                        };
+                        firmware_ok = true;
+                        bulk::status_reply(status, 0)
+                    }
+                    BulkResult::Rejected { key_id, status } => {
+                        warn!(
+                            "Bulk transfer rejected for key {}: status {}",
+                            key_id, status
+                        );
+                        bulk::status_reply(status, key_id)
+                    }
+                };
+
+                if let Err(e) = ep_in.write(&reply).await {
+                    warn!("Vendor bulk write error: {:?}", e);
+                    crate::stats::packet_dropped();
+                }
+            }
+        }
+    };
+
+    #[cfg(not(feature = "firmware-update"))]
+    transfers.await;
+
+    // A committed firmware update restarts the device from here
+    #[cfg(feature = "firmware-update")]
+    {
+        embassy_futures::select::select(transfers, crate::firmware_update::wait_restart()).await;
+        info!("Restarting to apply the firmware update");
+        cortex_m::peripheral::SCB::sys_reset();
+    }
+}
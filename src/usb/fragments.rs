@@ -0,0 +1,68 @@
+//! Output report reassembly from interrupt OUT packets
+//!
+//! Elgato hosts write 1 KB output reports, but a full-speed endpoint moves
+//! at most `USB_HID_PACKET_SIZE` bytes per packet, so a report arrives as a
+//! run of packets. The protocol parsers expect whole reports; this layer
+//! gathers packets until the report's expected length is reached, or until
+//! the host ends it early with a short (or zero-length) packet.
+//!
+//! The expected length is taken from the report's first byte: the profile's
+//! `output_report_size` for the image report ID, one byte less when the
+//! host's HID stack stripped the ID from the wire.
+
+use crate::config::{OUTPUT_REPORT_IMAGE, USB_HID_PACKET_SIZE, USB_MAX_OUTPUT_REPORT};
+use crate::device::{Device, DeviceConfig};
+
+/// Gathers packets into one output report at a time
+pub(super) struct ReportAssembler {
+    device: Device,
+    buf: [u8; USB_MAX_OUTPUT_REPORT],
+    len: usize,
+    /// Length the report being gathered ends at, set by its first packet
+    expected: usize,
+}
+
+impl ReportAssembler {
+    pub(super) const fn new(device: Device) -> Self {
+        Self {
+            device,
+            buf: [0; USB_MAX_OUTPUT_REPORT],
+            len: 0,
+            expected: 0,
+        }
+    }
+
+    /// Add one packet. Returns the report once it is complete.
+    pub(super) fn push(&mut self, packet: &[u8]) -> Option<&[u8]> {
+        if self.len == 0 {
+            // A zero-length packet between reports carries nothing
+            let &first = packet.first()?;
+            self.expected = expected_len(self.device, first);
+        }
+
+        let take = packet.len().min(self.expected - self.len);
+        self.buf[self.len..self.len + take].copy_from_slice(&packet[..take]);
+        self.len += take;
+        if packet.len() == USB_HID_PACKET_SIZE && self.len < self.expected {
+            return None;
+        }
+
+        let len = core::mem::take(&mut self.len);
+        Some(&self.buf[..len])
+    }
+
+    /// Drop a partly gathered report (e.g. after a read error)
+    pub(super) fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Length of an output report starting with `first`
+fn expected_len(device: Device, first: u8) -> usize {
+    let size = device.output_report_size().min(USB_MAX_OUTPUT_REPORT);
+    if first == OUTPUT_REPORT_IMAGE {
+        size
+    } else {
+        size - 1
+    }
+}
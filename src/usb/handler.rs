@@ -0,0 +1,590 @@
+//! HID request handling
+//!
+//! The protocol handler is shared by the two paths a host uses to talk to
+//! the HID interface: control transfers (GET/SET_REPORT, handled
+//! synchronously by `StreamDeckHidHandler`) and the interrupt OUT endpoint
+//! (read by `run_out_reader`). Output reports from both go through
+//! `receive_output_report` into one image assembler, so pages of an image
+//! may arrive on either; they differ only in how they queue the resulting
+//! `UsbCommand`s for the router (the control pipe cannot wait).
+//!
+//! A new host session (StreamDeck software restarted, or back from suspend)
+//! is recognized by its identity probe and gets a clean protocol state and a
+//! full key report (`note_identity_read`).
+//!
+//! The control pipe also carries the HID class housekeeping requests.
+//! embassy-usb answers GET_PROTOCOL and SET_PROTOCOL itself (report protocol
+//! only; this is not a boot device); SET_IDLE and GET_IDLE land here and set
+//! how often unchanged key reports are repeated (`idle_rate`).
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt::*;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+use embassy_usb::class::hid::{ReportId, RequestHandler};
+use embassy_usb::control::OutResponse;
+use heapless::Vec;
+
+use super::fragments::ReportAssembler;
+use super::HidOut;
+use crate::channels::USB_COMMAND_CHANNEL;
+use crate::compat::{self, Probe};
+use crate::config;
+use crate::device::Device;
+use crate::error::UsbError;
+use crate::logging::Subsystem;
+use crate::protocol::feature::{self, FeatureContent, UnitInfo};
+use crate::protocol::module::ModuleSetCommand;
+use crate::protocol::vendor::{self, VendorHandler};
+use crate::protocol::{OutputReportResult, ProtocolHandler};
+use crate::types::{UsbCommand, APP_VERSION};
+use crate::{log_at, log_limited};
+
+// ===================================================================
+// Shared Protocol State
+// ===================================================================
+
+/// The one protocol handler for the HID interface. Image reports may arrive
+/// on the control pipe (SET_REPORT) or the interrupt OUT endpoint, and both
+/// must feed the same reassembly state. Installed by `install_protocol`; the
+/// blocking mutex lets the synchronous `RequestHandler` callbacks use it too.
+static PROTOCOL_HANDLER: Mutex<CriticalSectionRawMutex, RefCell<Option<ProtocolHandler>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Run `f` on the shared protocol handler. Never hold the result across an
+/// await that other USB paths depend on.
+pub(super) fn with_protocol<R>(f: impl FnOnce(&mut ProtocolHandler) -> R) -> Option<R> {
+    PROTOCOL_HANDLER.lock(|handler| handler.borrow_mut().as_mut().map(f))
+}
+
+/// Make `protocol` the handler for both report paths
+pub(super) fn install_protocol(protocol: ProtocolHandler) {
+    PROTOCOL_HANDLER.lock(|handler| *handler.borrow_mut() = Some(protocol));
+}
+
+/// Last key report sent on the interrupt IN endpoint, for hosts that poll
+/// key state with GET_REPORT(Input)
+static LAST_KEY_REPORT: Mutex<
+    CriticalSectionRawMutex,
+    RefCell<Vec<u8, { config::USB_MAX_INPUT_REPORT }>>,
+> = Mutex::new(RefCell::new(Vec::new()));
+
+/// Remember the key report just sent
+pub(super) fn set_last_key_report(report: &[u8]) {
+    LAST_KEY_REPORT.lock(|last| {
+        let mut last = last.borrow_mut();
+        last.clear();
+        // Bounded: reports are built in a buffer of the same capacity
+        let _ = last.extend_from_slice(report);
+    });
+}
+
+// ===================================================================
+// Idle Rate
+// ===================================================================
+
+/// Input report idle rate set by the host with SET_IDLE, in ms. `u32::MAX`
+/// is indefinite: reports are only sent on change, the HID default for
+/// devices other than boot keyboards.
+static IDLE_RATE_MS: AtomicU32 = AtomicU32::new(u32::MAX);
+
+/// How long an unchanged key report may go unrepeated, or None to send
+/// reports only on change
+pub(super) fn idle_rate() -> Option<Duration> {
+    match IDLE_RATE_MS.load(Ordering::Relaxed) {
+        u32::MAX => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Back to the power-on idle rate; a new host negotiates its own
+pub(super) fn reset_idle_rate() {
+    IDLE_RATE_MS.store(u32::MAX, Ordering::Relaxed);
+}
+
+// ===================================================================
+// Host Sessions
+// ===================================================================
+
+/// When the host last read the serial or firmware version; None once a bus
+/// reset, configuration or resume means the next read opens a new session
+static LAST_IDENTITY_READ: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+
+/// Asks the input report task to send the full key state
+pub(super) static RESEND_KEY_STATE: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Take the next identity read as a new host session
+pub(super) fn expect_reopen() {
+    LAST_IDENTITY_READ.lock(|last| last.set(None));
+}
+
+/// StreamDeck software reads the serial and firmware version when it opens
+/// the device, and not again while it runs. A read after a quiet gap, or the
+/// first after a resume, is a new session: images the last one left half
+/// sent are dropped, and the keys are reported in full since the new
+/// software never saw the state they are in.
+fn note_identity_read() {
+    let now = Instant::now();
+    let reopened = LAST_IDENTITY_READ.lock(|last| {
+        let gap = Duration::from_millis(config::HOST_REOPEN_GAP_MS);
+        let reopened = last.get().is_none_or(|at| now - at >= gap);
+        last.set(Some(now));
+        reopened
+    });
+    if reopened {
+        info!("Host session opened; resetting protocol state");
+        with_protocol(|protocol| protocol.reset());
+        RESEND_KEY_STATE.signal(());
+    }
+}
+
+// ===================================================================
+// HID Request Handler
+// ===================================================================
+
+/// Control-pipe side of the HID interface: feature reports and SET_REPORT images
+pub(super) struct StreamDeckHidHandler {
+    vendor_handler: VendorHandler,
+    usb_command_sender: embassy_sync::channel::Sender<
+        'static,
+        embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+        UsbCommand,
+        4,
+    >,
+}
+
+impl StreamDeckHidHandler {
+    pub(super) fn new(device: Device) -> Self {
+        Self {
+            vendor_handler: VendorHandler::new(device),
+            usb_command_sender: USB_COMMAND_CHANNEL.sender(),
+        }
+    }
+}
+
+impl RequestHandler for StreamDeckHidHandler {
+    fn get_report(&mut self, id: ReportId, buf: &mut [u8]) -> Option<usize> {
+        log_limited!(
+            info,
+            Subsystem::Usb,
+            "HID Get Report: ID={:?}, buf_len={}",
+            id,
+            buf.len()
+        );
+
+        match id {
+            ReportId::In(_) => {
+                // Same bytes the interrupt IN endpoint last carried
+                let len = LAST_KEY_REPORT.lock(|last| {
+                    let last = last.borrow();
+                    let len = last.len().min(buf.len());
+                    buf[..len].copy_from_slice(&last[..len]);
+                    len
+                });
+                if len == 0 {
+                    compat::fail(Probe::InputReportRead);
+                    return None;
+                }
+                compat::note(Probe::InputReportRead);
+                Some(len)
+            }
+            ReportId::Feature(config::FEATURE_REPORT_VENDOR) => self.vendor_handler.get_report(buf),
+            ReportId::Feature(report_id) if vendor::settings_report_len(report_id).is_some() => {
+                self.vendor_handler.get_settings_report(report_id, buf)
+            }
+            ReportId::Feature(report_id) => {
+                crate::standalone::note_host_activity();
+                let serial = config::usb_serial();
+                let unit = UnitInfo {
+                    firmware_version: APP_VERSION.firmware.as_bytes(),
+                    serial: serial.as_bytes(),
+                    idle_time_secs: config::get_idle_time_seconds(),
+                    brightness: crate::brightness::host_percent(),
+                };
+                // Delegate fully to protocol handler; no fallback here
+                let len =
+                    with_protocol(|protocol| protocol.get_feature_report(report_id, &unit, buf))
+                        .flatten();
+                if len.is_none() {
+                    compat::unanswered_read(report_id);
+                    return None;
+                }
+                let table = with_protocol(|protocol| feature::table_for(protocol.version()));
+                if let Some(layout) = table.and_then(|table| feature::lookup(table, report_id)) {
+                    compat::note(match layout.content {
+                        FeatureContent::Serial => Probe::SerialRead,
+                        FeatureContent::FirmwareVersion | FeatureContent::FixedVersion(_) => {
+                            Probe::FirmwareRead
+                        }
+                        FeatureContent::IdleTime => Probe::IdleTimeRead,
+                        FeatureContent::Brightness => Probe::Brightness,
+                        FeatureContent::Zeroed => Probe::OtherFeatureRead,
+                    });
+                }
+                if table.is_some_and(|table| feature::is_identity(table, report_id)) {
+                    note_identity_read();
+                }
+                len
+            }
+            _ => None,
+        }
+    }
+
+    fn set_report(&mut self, id: ReportId, data: &[u8]) -> OutResponse {
+        log_limited!(
+            info,
+            Subsystem::Usb,
+            "HID Set Report: ID={:?}, len={}",
+            id,
+            data.len()
+        );
+
+        match id {
+            ReportId::Feature(config::FEATURE_REPORT_VENDOR) => {
+                self.vendor_handler.handle_set_report(data);
+            }
+            ReportId::Feature(report_id) if vendor::settings_report_len(report_id).is_some() => {
+                return if self.vendor_handler.set_settings_report(report_id, data) {
+                    OutResponse::Accepted
+                } else {
+                    OutResponse::Rejected
+                };
+            }
+            ReportId::Feature(report_id) => {
+                crate::standalone::note_host_activity();
+                let command =
+                    with_protocol(|protocol| protocol.handle_feature_report(report_id, data))
+                        .flatten();
+                match command {
+                    Some(ModuleSetCommand::Reset) => compat::note(Probe::Reset),
+                    Some(ModuleSetCommand::SetBrightness { .. }) => compat::note(Probe::Brightness),
+                    Some(_) => compat::note(Probe::OtherFeatureWrite),
+                    None => compat::unanswered_write(report_id),
+                }
+                if let Some(command) = command {
+                    match command {
+                        ModuleSetCommand::Reset => {
+                            info!("Processing reset command");
+                            // Partial images from before the reset must not
+                            // complete with pages sent after it
+                            with_protocol(|protocol| protocol.reset());
+                            if self.usb_command_sender.try_send(UsbCommand::Reset).is_err() {
+                                crate::stats::command_dropped();
+                            }
+                        }
+                        ModuleSetCommand::SetBrightness { value } => {
+                            info!("Processing brightness command: {}%", value);
+                            if self
+                                .usb_command_sender
+                                .try_send(UsbCommand::SetBrightness(value))
+                                .is_err()
+                            {
+                                crate::stats::command_dropped();
+                            }
+                        }
+                        ModuleSetCommand::SetScreenSleep { screen, sleep } => {
+                            info!("Processing screen sleep command: {} -> {}", screen, sleep);
+                            if self
+                                .usb_command_sender
+                                .try_send(UsbCommand::SetScreenSleep { screen, sleep })
+                                .is_err()
+                            {
+                                crate::stats::command_dropped();
+                            }
+                        }
+                        ModuleSetCommand::SetScreenBrightness { screen, value } => {
+                            info!(
+                                "Processing screen brightness command: {} {}%",
+                                screen, value
+                            );
+                            if self
+                                .usb_command_sender
+                                .try_send(UsbCommand::SetScreenBrightness { screen, value })
+                                .is_err()
+                            {
+                                crate::stats::command_dropped();
+                            }
+                        }
+                        ModuleSetCommand::SetIdleTime { seconds } => {
+                            crate::config::set_idle_time_seconds(seconds);
+                            info!("Set idle time to {} seconds", seconds);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ReportId::Out(_) => match receive_output_report(OutPipe::Control, data) {
+                Received::Command(command) => {
+                    if self.usb_command_sender.try_send(command).is_err() {
+                        crate::stats::command_dropped();
+                    }
+                }
+                Received::Chunk {
+                    key_id,
+                    offset,
+                    payload,
+                    last,
+                } => {
+                    // Control transfers can't wait for queue space; a dropped
+                    // chunk leaves a gap the display detects
+                    for command in image_chunk_commands(key_id, offset, &data[payload], last) {
+                        if self.usb_command_sender.try_send(command).is_err() {
+                            crate::stats::command_dropped();
+                        }
+                    }
+                }
+                Received::Nothing => {}
+            },
+            _ => {}
+        }
+
+        OutResponse::Accepted
+    }
+
+    fn get_idle_ms(&mut self, id: Option<ReportId>) -> Option<u32> {
+        // One rate covers every input report; rejecting this made some hosts
+        // retry during enumeration
+        let rate = IDLE_RATE_MS.load(Ordering::Relaxed);
+        log_at!(
+            debug,
+            Subsystem::Usb,
+            "HID Get Idle: ID={:?} -> {} ms",
+            id,
+            rate
+        );
+        Some(rate)
+    }
+
+    fn set_idle_ms(&mut self, id: Option<ReportId>, duration_ms: u32) {
+        log_at!(
+            debug,
+            Subsystem::Usb,
+            "HID Set Idle: ID={:?}, {} ms",
+            id,
+            duration_ms
+        );
+        IDLE_RATE_MS.store(duration_ms, Ordering::Relaxed);
+    }
+}
+
+// ===================================================================
+// Output Reports
+// ===================================================================
+
+/// Pipe an output report arrived on
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+enum OutPipe {
+    /// SET_REPORT on the control pipe
+    Control,
+    /// Interrupt OUT endpoint
+    Interrupt,
+}
+
+impl OutPipe {
+    fn probe(self) -> Probe {
+        match self {
+            OutPipe::Control => Probe::ImageControl,
+            OutPipe::Interrupt => Probe::ImageInterrupt,
+        }
+    }
+
+    /// Whether the host profile takes images from this pipe
+    fn accepted(self) -> bool {
+        let path = crate::host::quirks().image_path;
+        match self {
+            OutPipe::Control => path.accepts_control(),
+            OutPipe::Interrupt => path.accepts_interrupt(),
+        }
+    }
+}
+
+/// What an output report produced, for the pipe's caller to queue
+#[allow(clippy::large_enum_variant)]
+enum Received {
+    /// A finished image
+    Command(UsbCommand),
+    /// A streamed page; `payload` is the range of the report holding it
+    Chunk {
+        key_id: u8,
+        offset: usize,
+        payload: core::ops::Range<usize>,
+        last: bool,
+    },
+    Nothing,
+}
+
+/// Feed one output report to the shared protocol handler. Both pipes come
+/// through here, so a host that moves between them mid-image continues the
+/// same reassembly context instead of corrupting a second one.
+fn receive_output_report(pipe: OutPipe, data: &[u8]) -> Received {
+    crate::standalone::note_host_activity();
+    if !pipe.accepted() {
+        debug!("Output report on {} pipe ignored (host profile)", pipe);
+        compat::fail(pipe.probe());
+        return Received::Nothing;
+    }
+
+    log_at!(
+        debug,
+        Subsystem::Usb,
+        "USB Output Report ({}): {} bytes received",
+        pipe,
+        data.len()
+    );
+    if data.len() >= 8 {
+        log_at!(
+            trace,
+            Subsystem::Usb,
+            "Header: [{:02X}, {:02X}, {:02X}, {:02X}, {:02X}, {:02X}, {:02X}, {:02X}]",
+            data[0],
+            data[1],
+            data[2],
+            data[3],
+            data[4],
+            data[5],
+            data[6],
+            data[7]
+        );
+    }
+
+    let result = with_protocol(|protocol| protocol.parse_output_report(data))
+        .unwrap_or(OutputReportResult::Unhandled);
+    match result {
+        OutputReportResult::KeyImageComplete { key_id, mut image } => {
+            log_limited!(
+                info,
+                Subsystem::Usb,
+                "Image complete for key {} ({} bytes)",
+                key_id,
+                image.len()
+            );
+            image.mark_assembled();
+            crate::stats::image_received();
+            compat::note(pipe.probe());
+            Received::Command(UsbCommand::ImageData {
+                key_id,
+                data: image,
+            })
+        }
+        OutputReportResult::KeyImageChunk {
+            key_id,
+            offset,
+            payload,
+            last,
+        } => {
+            if last {
+                crate::stats::image_received();
+                compat::note(pipe.probe());
+            }
+            Received::Chunk {
+                key_id,
+                offset,
+                payload,
+                last,
+            }
+        }
+        OutputReportResult::LcdImageComplete { region, image } => {
+            log_limited!(
+                info,
+                Subsystem::Usb,
+                "LCD image complete for {} ({} bytes)",
+                region,
+                image.len()
+            );
+            Received::Command(UsbCommand::LcdImage {
+                region,
+                data: image,
+            })
+        }
+        OutputReportResult::FullScreenImageChunk => {
+            debug!("Full screen image chunk received (not assembled)");
+            Received::Nothing
+        }
+        OutputReportResult::BootLogoImageChunk => {
+            debug!("Boot logo image chunk received (not assembled)");
+            Received::Nothing
+        }
+        OutputReportResult::Unhandled => {
+            debug!("Unhandled output report");
+            Received::Nothing
+        }
+        OutputReportResult::Error(e) => {
+            warn!("Output report rejected: {}", e);
+            crate::stats::packet_dropped();
+            crate::stats::record_error(e);
+            compat::fail(pipe.probe());
+            Received::Nothing
+        }
+    }
+}
+
+/// Split a streamed image page into display-sized chunk commands.
+/// Always yields at least one command so `last` reaches the display.
+fn image_chunk_commands(
+    key_id: u8,
+    offset: usize,
+    payload: &[u8],
+    last: bool,
+) -> impl Iterator<Item = UsbCommand> + '_ {
+    let chunk_count = payload.len().div_ceil(config::STREAM_CHUNK_SIZE).max(1);
+    (0..chunk_count).map(move |i| {
+        let start = i * config::STREAM_CHUNK_SIZE;
+        let end = (start + config::STREAM_CHUNK_SIZE).min(payload.len());
+        let mut data = crate::types::ImageChunk::new();
+        // Chunk bounds never exceed the capacity
+        let _ = data.extend_from_slice(&payload[start..end]);
+        UsbCommand::ImageChunk {
+            key_id,
+            offset: (offset + start) as u32,
+            last: last && i + 1 == chunk_count,
+            data,
+        }
+    })
+}
+
+// ===================================================================
+// Interrupt OUT Endpoint
+// ===================================================================
+
+/// Read output reports from the interrupt OUT endpoint; never returns.
+/// Unlike the control pipe this may wait for queue space, so whole images
+/// and streamed chunks are never dropped here.
+pub(super) async fn run_out_reader(device: Device, mut reader: HidOut) {
+    let mut reports = ReportAssembler::new(device);
+    let mut packet = [0u8; config::USB_HID_PACKET_SIZE];
+    loop {
+        let n = match reader.read(&mut packet).await {
+            Ok(n) => n,
+            Err(e) => {
+                warn!("HID OUT read error: {:?}", e);
+                crate::stats::packet_dropped();
+                crate::stats::record_error(UsbError::ReadFailed);
+                reports.clear();
+                continue;
+            }
+        };
+        let Some(data) = reports.push(&packet[..n]) else {
+            continue;
+        };
+
+        match receive_output_report(OutPipe::Interrupt, data) {
+            Received::Command(command) => USB_COMMAND_CHANNEL.send(command).await,
+            Received::Chunk {
+                key_id,
+                offset,
+                payload,
+                last,
+            } => {
+                for command in image_chunk_commands(key_id, offset, &data[payload], last) {
+                    USB_COMMAND_CHANNEL.send(command).await;
+                }
+            }
+            Received::Nothing => {}
+        }
+    }
+}
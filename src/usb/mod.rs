@@ -0,0 +1,293 @@
+//! USB HID implementation for StreamDeck compatibility
+//!
+//! This module implements a flexible USB HID protocol that supports multiple
+//! StreamDeck device types through device abstraction and protocol handlers.
+//!
+//! - `device`: descriptor configuration, bus state, and the IN-side loops
+//!   (input reports, standalone keyboard, secondary deck, vendor bulk)
+//! - `handler`: the shared protocol handler, control-pipe requests and the
+//!   interrupt OUT reader
+//! - `fragments`: gathers interrupt OUT packets into whole output reports
+//! - `router`: turns queued `UsbCommand`s into device-bus and display work
+//! - `webusb`: WebUSB and WinUSB descriptors for the vendor interface, and
+//!   the vendor report over control requests (`webusb`)
+//! - `msc`: mass-storage interface used instead of all of the above when the
+//!   device starts as a configuration drive (`config-drive`)
+//!
+//! `usb_task_impl` only builds the device and runs these side by side.
+
+mod device;
+mod fragments;
+mod handler;
+#[cfg(feature = "config-drive")]
+mod msc;
+mod router;
+#[cfg(feature = "webusb")]
+mod webusb;
+
+use defmt::*;
+use embassy_rp::gpio::Output;
+use embassy_rp::peripherals;
+use embassy_rp::usb::Driver;
+use embassy_time::{Duration, Timer};
+use embassy_usb::class::hid::{Config as HidConfig, HidReader, HidReaderWriter, HidWriter, State};
+use embassy_usb::Builder;
+use static_cell::StaticCell;
+
+use self::device::{create_usb_config_for_device, UsbDeviceHandler};
+use self::handler::StreamDeckHidHandler;
+use crate::config;
+use crate::device::{Device, DeviceConfig};
+use crate::protocol::descriptor::ReportDescriptor;
+use crate::protocol::ProtocolHandler;
+
+/// The RP2040 USB driver every endpoint is built on
+type UsbDriver = Driver<'static, peripherals::USB>;
+
+/// HID input side; sized for the largest profile, each device sends
+/// `DeviceConfig::input_report_size` at most
+type HidIn = HidWriter<'static, UsbDriver, { config::USB_MAX_INPUT_REPORT }>;
+
+/// HID interrupt OUT side, read one packet at a time; reports are
+/// reassembled to `DeviceConfig::output_report_size` by the handler
+type HidOut = HidReader<'static, UsbDriver, { config::USB_HID_PACKET_SIZE }>;
+
+/// Boot keyboard interface for standalone mode
+#[cfg(feature = "standalone-keyboard")]
+type KeyboardIn = HidWriter<'static, UsbDriver, { crate::standalone::KEYBOARD_REPORT_SIZE }>;
+
+/// HID interface of the secondary deck
+#[cfg(feature = "secondary-deck")]
+type SecondaryIn = HidWriter<'static, UsbDriver, { crate::secondary_deck::SECONDARY_REPORT_SIZE }>;
+
+// ===================================================================
+// USB Task Implementation
+// ===================================================================
+
+/// Buffers and class state the USB stack borrows for as long as it runs
+struct UsbResources {
+    device_descriptor: [u8; 256],
+    config_descriptor: [u8; config::USB_CONFIG_DESCRIPTOR_SIZE],
+    bos_descriptor: [u8; 256],
+    control_buf: [u8; config::USB_CONTROL_BUFFER_SIZE],
+    request_handler: StreamDeckHidHandler,
+    hid_state: State<'static>,
+    #[cfg(feature = "standalone-keyboard")]
+    keyboard_state: State<'static>,
+    #[cfg(feature = "secondary-deck")]
+    secondary_state: State<'static>,
+}
+
+/// Claimed by the first USB task to start; there is only one USB peripheral
+static USB_RESOURCES: StaticCell<UsbResources> = StaticCell::new();
+
+#[embassy_executor::task]
+pub async fn usb_task(driver: UsbDriver, usb_led: Output<'static>) {
+    usb_task_impl(driver, usb_led, config::get_current_device()).await
+}
+
+#[embassy_executor::task]
+pub async fn usb_task_for_device(driver: UsbDriver, usb_led: Output<'static>, device: Device) {
+    usb_task_impl(driver, usb_led, device).await
+}
+
+async fn usb_task_impl(driver: UsbDriver, mut usb_led: Output<'static>, device: Device) {
+    #[cfg(feature = "config-drive")]
+    if crate::config_drive::boot_requested().await {
+        msc::run(driver, usb_led).await;
+        return;
+    }
+
+    let Some(resources) = USB_RESOURCES.try_init(UsbResources {
+        device_descriptor: [0; 256],
+        config_descriptor: [0; config::USB_CONFIG_DESCRIPTOR_SIZE],
+        bos_descriptor: [0; 256],
+        control_buf: [0; config::USB_CONTROL_BUFFER_SIZE],
+        request_handler: StreamDeckHidHandler::new(device),
+        hid_state: State::new(),
+        #[cfg(feature = "standalone-keyboard")]
+        keyboard_state: State::new(),
+        #[cfg(feature = "secondary-deck")]
+        secondary_state: State::new(),
+    }) else {
+        error!("USB task already running, not starting another");
+        return;
+    };
+    let UsbResources {
+        device_descriptor,
+        config_descriptor,
+        bos_descriptor,
+        control_buf,
+        request_handler,
+        hid_state,
+        #[cfg(feature = "standalone-keyboard")]
+        keyboard_state,
+        #[cfg(feature = "secondary-deck")]
+        secondary_state,
+    } = resources;
+
+    info!("USB task started");
+
+    info!("USB HID device: {}", device.device_name());
+    info!("Protocol: {:?}", device.usb_config().protocol);
+    info!(
+        "Button layout: {}x{} ({} keys)",
+        device.button_layout().cols,
+        device.button_layout().rows,
+        device.button_layout().total_keys
+    );
+
+    // Create USB configuration for specific device
+    let usb_config = create_usb_config_for_device(device);
+
+    // Create USB builder
+    let mut builder = Builder::new(
+        driver,
+        usb_config,
+        device_descriptor,
+        config_descriptor,
+        bos_descriptor,
+        control_buf,
+    );
+
+    // Shared protocol state for the control pipe and interrupt endpoints
+    let protocol_handler = ProtocolHandler::for_device(device);
+    static HID_DESCRIPTOR: StaticCell<ReportDescriptor> = StaticCell::new();
+    let generated_descriptor: &'static [u8] =
+        HID_DESCRIPTOR.init(protocol_handler.hid_descriptor(device.button_layout().total_keys));
+    #[cfg(feature = "elgato-exact-descriptors")]
+    let hid_descriptor = match crate::protocol::elgato_descriptors::exact_descriptor(device) {
+        Some(exact) => {
+            info!("Using byte-exact Elgato report descriptor");
+            exact
+        }
+        None => {
+            warn!(
+                "No byte-exact descriptor for {}, using generated descriptor",
+                device.device_name()
+            );
+            generated_descriptor
+        }
+    };
+    #[cfg(not(feature = "elgato-exact-descriptors"))]
+    let hid_descriptor = generated_descriptor;
+    handler::install_protocol(protocol_handler);
+
+    // Register bus state handler for suspend/resume tracking
+    static DEVICE_HANDLER: StaticCell<UsbDeviceHandler> = StaticCell::new();
+    builder.handler(DEVICE_HANDLER.init(UsbDeviceHandler::new()));
+
+    let hid_config = HidConfig {
+        report_descriptor: hid_descriptor,
+        request_handler: Some(request_handler),
+        poll_ms: device.usb_config().poll_ms,
+        max_packet_size: config::USB_HID_PACKET_SIZE as u16, // RP2040 USB hardware limitation
+    };
+
+    info!(
+        "HID configuration created with report descriptor size: {} bytes, polling every {} ms",
+        hid_descriptor.len(),
+        hid_config.poll_ms
+    );
+    info!(
+        "HID report sizes: {} bytes in, {} bytes out",
+        device.input_report_size(),
+        device.output_report_size()
+    );
+
+    let hid = HidReaderWriter::<
+        _,
+        { config::USB_HID_PACKET_SIZE },
+        { config::USB_MAX_INPUT_REPORT },
+    >::new(&mut builder, hid_state, hid_config);
+
+    // Optional keyboard interface for standalone mode
+    #[cfg(feature = "standalone-keyboard")]
+    let keyboard = {
+        let keyboard_config = HidConfig {
+            report_descriptor: crate::standalone::KEYBOARD_REPORT_DESCRIPTOR,
+            request_handler: None,
+            poll_ms: 10,
+            max_packet_size: crate::standalone::KEYBOARD_REPORT_SIZE as u16,
+        };
+        KeyboardIn::new(&mut builder, keyboard_state, keyboard_config)
+    };
+
+    // Optional second logical deck with its own report descriptor
+    #[cfg(feature = "secondary-deck")]
+    let secondary = {
+        let secondary_config = HidConfig {
+            report_descriptor: crate::secondary_deck::SECONDARY_REPORT_DESCRIPTOR,
+            request_handler: None,
+            poll_ms: device.usb_config().poll_ms,
+            max_packet_size: crate::secondary_deck::SECONDARY_REPORT_SIZE as u16,
+        };
+        SecondaryIn::new(&mut builder, secondary_state, secondary_config)
+    };
+
+    // Optional vendor-specific bulk interface for companion host tools
+    #[cfg(feature = "vendor-bulk")]
+    let (bulk_in, bulk_out) = {
+        #[cfg(feature = "webusb")]
+        webusb::describe_device(&mut builder);
+        let mut function = builder.function(0xFF, 0x00, 0x00);
+        #[cfg(feature = "webusb")]
+        webusb::bind_winusb(&mut function);
+        let mut interface = function.interface();
+        #[cfg(feature = "webusb")]
+        let number = interface.interface_number();
+        let mut alt = interface.alt_setting(0xFF, 0x00, 0x00, None);
+        #[cfg(feature = "webusb")]
+        webusb::advertise(&mut alt);
+        let ep_out = alt.endpoint_bulk_out(None, 64);
+        let ep_in = alt.endpoint_bulk_in(None, 64);
+        drop(function);
+        #[cfg(feature = "webusb")]
+        builder.handler(webusb::handler(device, number));
+        (ep_in, ep_out)
+    };
+
+    // Build USB device
+    let mut usb = builder.build();
+
+    // Split HID into reader and writer
+    let (reader, writer) = hid.split();
+
+    let usb_fut = usb.run();
+    let command_fut = router::run_command_router();
+    #[cfg(feature = "secondary-deck")]
+    let secondary_fut = device::run_secondary_reports(secondary);
+    #[cfg(not(feature = "secondary-deck"))]
+    let secondary_fut = async {};
+    let io_fut = embassy_futures::join::join3(
+        device::run_input_reports(
+            device,
+            writer,
+            #[cfg(feature = "standalone-keyboard")]
+            keyboard,
+        ),
+        handler::run_out_reader(device, reader),
+        secondary_fut,
+    );
+
+    // USB status LED control: lit while configured, dark while suspended
+    let led_fut = async {
+        info!("USB LED task started");
+        loop {
+            if crate::power::usb_configured() && !crate::power::usb_suspended() {
+                usb_led.set_high();
+            } else {
+                usb_led.set_low();
+            }
+            Timer::after(Duration::from_millis(100)).await;
+        }
+    };
+
+    #[cfg(feature = "vendor-bulk")]
+    let bulk_fut = device::vendor_bulk_loop(bulk_in, bulk_out);
+    #[cfg(not(feature = "vendor-bulk"))]
+    let bulk_fut = async {};
+
+    // Run all futures concurrently
+    embassy_futures::join::join5(usb_fut, command_fut, io_fut, led_fut, bulk_fut).await;
+}
@@ -0,0 +1,450 @@
+//! USB mass-storage interface for the configuration drive (`config-drive`)
+//!
+//! Bulk-only transport with the handful of SCSI commands hosts use for a
+//! small removable disk. Blocks come from `config_drive::Volume`; ejecting
+//! the medium flushes it, imports its contents and restarts the device as a
+//! StreamDeck.
+
+use defmt::*;
+use embassy_rp::gpio::Output;
+use embassy_time::{with_timeout, Duration, Timer};
+use embassy_usb::control::{InResponse, OutResponse, Recipient, Request, RequestType};
+use embassy_usb::driver::{EndpointIn, EndpointOut};
+use embassy_usb::{Builder, Config, Handler};
+use static_cell::StaticCell;
+
+use super::UsbDriver;
+use crate::config;
+use crate::config_drive::{self, Volume, BLOCK_COUNT, BLOCK_SIZE};
+
+/// Bulk endpoint packet size (full speed)
+const PACKET_SIZE: usize = 64;
+
+/// Command and status wrapper sizes and signatures
+const CBW_SIZE: usize = 31;
+const CBW_SIGNATURE: [u8; 4] = *b"USBC";
+const CSW_SIGNATURE: [u8; 4] = *b"USBS";
+
+/// CSW status values
+const CSW_PASSED: u8 = 0x00;
+const CSW_FAILED: u8 = 0x01;
+
+/// Class requests on the interface
+const REQ_GET_MAX_LUN: u8 = 0xFE;
+const REQ_BULK_ONLY_RESET: u8 = 0xFF;
+
+/// How long the host may stay idle before cached writes go to flash
+const IDLE_FLUSH_MS: u64 = 1000;
+
+/// How long to keep the bus up after an eject so the status reaches the host
+const EJECT_RESET_DELAY_MS: u64 = 100;
+
+/// SCSI operation codes
+const SCSI_TEST_UNIT_READY: u8 = 0x00;
+const SCSI_REQUEST_SENSE: u8 = 0x03;
+const SCSI_INQUIRY: u8 = 0x12;
+const SCSI_MODE_SENSE_6: u8 = 0x1A;
+const SCSI_START_STOP_UNIT: u8 = 0x1B;
+const SCSI_PREVENT_ALLOW_REMOVAL: u8 = 0x1E;
+const SCSI_READ_FORMAT_CAPACITIES: u8 = 0x23;
+const SCSI_READ_CAPACITY_10: u8 = 0x25;
+const SCSI_READ_10: u8 = 0x28;
+const SCSI_WRITE_10: u8 = 0x2A;
+const SCSI_VERIFY_10: u8 = 0x2F;
+const SCSI_SYNCHRONIZE_CACHE_10: u8 = 0x35;
+const SCSI_MODE_SENSE_10: u8 = 0x5A;
+
+/// Sense key and additional sense code of a failed command
+#[derive(Clone, Copy)]
+struct Sense {
+    key: u8,
+    asc: u8,
+}
+
+impl Sense {
+    const NONE: Self = Self {
+        key: 0x00,
+        asc: 0x00,
+    };
+    const NOT_PRESENT: Self = Self {
+        key: 0x02,
+        asc: 0x3A,
+    };
+    const MEDIUM_ERROR: Self = Self {
+        key: 0x03,
+        asc: 0x0C,
+    };
+    const INVALID_COMMAND: Self = Self {
+        key: 0x05,
+        asc: 0x20,
+    };
+    const OUT_OF_RANGE: Self = Self {
+        key: 0x05,
+        asc: 0x21,
+    };
+    const INVALID_FIELD: Self = Self {
+        key: 0x05,
+        asc: 0x24,
+    };
+}
+
+/// Buffers the configuration drive's USB stack borrows while it runs
+struct MscResources {
+    device_descriptor: [u8; 256],
+    config_descriptor: [u8; 128],
+    bos_descriptor: [u8; 256],
+    control_buf: [u8; 64],
+    handler: MscControlHandler,
+    volume: Volume,
+}
+
+static MSC_RESOURCES: StaticCell<MscResources> = StaticCell::new();
+
+/// Run the device as the configuration drive until it is ejected, then reset
+pub(super) async fn run(driver: UsbDriver, mut usb_led: Output<'static>) {
+    let Some(resources) = MSC_RESOURCES.try_init(MscResources {
+        device_descriptor: [0; 256],
+        config_descriptor: [0; 128],
+        bos_descriptor: [0; 256],
+        control_buf: [0; 64],
+        handler: MscControlHandler { interface: 0 },
+        volume: Volume::new(),
+    }) else {
+        error!("Configuration drive already running");
+        return;
+    };
+    let MscResources {
+        device_descriptor,
+        config_descriptor,
+        bos_descriptor,
+        control_buf,
+        handler,
+        volume,
+    } = resources;
+
+    if let Err(e) = config_drive::ensure_formatted(volume) {
+        error!("Configuration drive could not be formatted: {}", e);
+    }
+
+    let mut usb_config = Config::new(config::CONFIG_DRIVE_VID, config::CONFIG_DRIVE_PID);
+    usb_config.manufacturer = Some(config::usb_manufacturer());
+    usb_config.product = Some(config::CONFIG_DRIVE_PRODUCT);
+    usb_config.max_power = 100;
+    usb_config.max_packet_size_0 = 64;
+
+    let mut builder = Builder::new(
+        driver,
+        usb_config,
+        device_descriptor,
+        config_descriptor,
+        bos_descriptor,
+        control_buf,
+    );
+
+    // Mass storage, SCSI transparent command set, bulk-only transport
+    let (ep_in, ep_out) = {
+        let mut function = builder.function(0x08, 0x06, 0x50);
+        let mut interface = function.interface();
+        handler.interface = interface.interface_number().0;
+        let mut alt = interface.alt_setting(0x08, 0x06, 0x50, None);
+        let ep_out = alt.endpoint_bulk_out(None, PACKET_SIZE as u16);
+        let ep_in = alt.endpoint_bulk_in(None, PACKET_SIZE as u16);
+        (ep_in, ep_out)
+    };
+    builder.handler(handler);
+
+    let mut usb = builder.build();
+    info!("USB configuration drive started ({} blocks)", BLOCK_COUNT);
+    usb_led.set_high();
+
+    let mut target = ScsiTarget {
+        volume,
+        ep_in,
+        ep_out,
+        sense: Sense::NONE,
+        ejected: false,
+    };
+    let serve = async {
+        target.run().await;
+        // Ejected: let the host collect the command status before the bus goes away
+        Timer::after(Duration::from_millis(EJECT_RESET_DELAY_MS)).await;
+    };
+    embassy_futures::select::select(usb.run(), serve).await;
+
+    config_drive::import();
+    info!("Configuration imported, restarting");
+    cortex_m::peripheral::SCB::sys_reset();
+}
+
+/// Answers the bulk-only transport's class requests
+struct MscControlHandler {
+    interface: u8,
+}
+
+impl MscControlHandler {
+    fn is_ours(&self, req: &Request) -> bool {
+        req.request_type == RequestType::Class
+            && req.recipient == Recipient::Interface
+            && req.index == self.interface as u16
+    }
+}
+
+impl Handler for MscControlHandler {
+    fn control_out(&mut self, req: Request, _data: &[u8]) -> Option<OutResponse> {
+        if !self.is_ours(&req) {
+            return None;
+        }
+        match req.request {
+            REQ_BULK_ONLY_RESET => Some(OutResponse::Accepted),
+            _ => Some(OutResponse::Rejected),
+        }
+    }
+
+    fn control_in<'a>(&'a mut self, req: Request, buf: &'a mut [u8]) -> Option<InResponse<'a>> {
+        if !self.is_ours(&req) {
+            return None;
+        }
+        match req.request {
+            REQ_GET_MAX_LUN => {
+                buf[0] = 0; // A single logical unit
+                Some(InResponse::Accepted(&buf[..1]))
+            }
+            _ => Some(InResponse::Rejected),
+        }
+    }
+}
+
+/// The single logical unit behind the bulk endpoints
+struct ScsiTarget<'d, I: EndpointIn, O: EndpointOut> {
+    volume: &'d mut Volume,
+    ep_in: I,
+    ep_out: O,
+    /// Sense data of the last failed command, for REQUEST SENSE
+    sense: Sense,
+    ejected: bool,
+}
+
+impl<I: EndpointIn, O: EndpointOut> ScsiTarget<'_, I, O> {
+    /// Serve commands until the medium is ejected
+    async fn run(&mut self) {
+        let mut cbw = [0u8; PACKET_SIZE];
+        loop {
+            self.ep_out.wait_enabled().await;
+            loop {
+                let read = with_timeout(
+                    Duration::from_millis(IDLE_FLUSH_MS),
+                    self.ep_out.read(&mut cbw),
+                )
+                .await;
+                let n = match read {
+                    Ok(Ok(n)) => n,
+                    Ok(Err(e)) => {
+                        warn!("Mass storage read error: {:?}", e);
+                        break;
+                    }
+                    Err(_) => {
+                        if let Err(e) = self.volume.flush() {
+                            warn!("Configuration drive flush failed: {}", e);
+                        }
+                        continue;
+                    }
+                };
+                if n != CBW_SIZE || cbw[0..4] != CBW_SIGNATURE {
+                    warn!("Ignoring invalid command block wrapper ({} bytes)", n);
+                    continue;
+                }
+
+                let tag = [cbw[4], cbw[5], cbw[6], cbw[7]];
+                let length = u32::from_le_bytes([cbw[8], cbw[9], cbw[10], cbw[11]]) as usize;
+                let data_in = cbw[12] & 0x80 != 0;
+                let mut cb = [0u8; 16];
+                cb.copy_from_slice(&cbw[15..31]);
+
+                let (passed, transferred) = self.execute(&cb, length, data_in).await;
+                let residue = (length - transferred) as u32;
+
+                let mut csw = [0u8; 13];
+                csw[0..4].copy_from_slice(&CSW_SIGNATURE);
+                csw[4..8].copy_from_slice(&tag);
+                csw[8..12].copy_from_slice(&residue.to_le_bytes());
+                csw[12] = if passed { CSW_PASSED } else { CSW_FAILED };
+                if let Err(e) = self.ep_in.write(&csw).await {
+                    warn!("Mass storage status write error: {:?}", e);
+                    break;
+                }
+                if self.ejected {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Run one command; returns whether it passed and the data bytes moved
+    async fn execute(&mut self, cb: &[u8; 16], length: usize, data_in: bool) -> (bool, usize) {
+        let result = self.command(cb, length).await;
+        let transferred = match result {
+            Ok(transferred) => {
+                self.sense = Sense::NONE;
+                transferred
+            }
+            Err((sense, transferred)) => {
+                self.sense = sense;
+                transferred
+            }
+        };
+        // Finish a data phase the command did not complete
+        if transferred < length {
+            if data_in {
+                if transferred % PACKET_SIZE == 0 {
+                    let _ = self.ep_in.write(&[]).await;
+                }
+            } else {
+                self.discard(length - transferred).await;
+            }
+        }
+        (result.is_ok(), transferred)
+    }
+
+    /// Returns the data bytes moved, or the failure sense and bytes moved
+    async fn command(&mut self, cb: &[u8; 16], length: usize) -> Result<usize, (Sense, usize)> {
+        if self.ejected && cb[0] != SCSI_REQUEST_SENSE && cb[0] != SCSI_INQUIRY {
+            return Err((Sense::NOT_PRESENT, 0));
+        }
+        match cb[0] {
+            SCSI_TEST_UNIT_READY | SCSI_PREVENT_ALLOW_REMOVAL | SCSI_VERIFY_10 => Ok(0),
+            SCSI_REQUEST_SENSE => {
+                let mut sense = [0u8; 18];
+                sense[0] = 0x70; // Current error, fixed format
+                sense[2] = self.sense.key;
+                sense[7] = 10; // Additional sense length
+                sense[12] = self.sense.asc;
+                self.send(&sense, length).await
+            }
+            SCSI_INQUIRY => {
+                if cb[1] & 0x01 != 0 {
+                    return Err((Sense::INVALID_FIELD, 0)); // No vital product data pages
+                }
+                let mut inquiry = [0u8; 36];
+                inquiry[1] = 0x80; // Removable
+                inquiry[2] = 0x04; // SPC-2
+                inquiry[3] = 0x02; // Response data format
+                inquiry[4] = 31; // Additional length
+                inquiry[8..16].copy_from_slice(b"PRODDECK");
+                inquiry[16..32].copy_from_slice(b"Config Drive    ");
+                inquiry[32..36].copy_from_slice(b"1.0 ");
+                self.send(&inquiry, length).await
+            }
+            SCSI_MODE_SENSE_6 => self.send(&[3, 0, 0, 0], length).await,
+            SCSI_MODE_SENSE_10 => self.send(&[0, 6, 0, 0, 0, 0, 0, 0], length).await,
+            SCSI_START_STOP_UNIT => {
+                let (load_eject, start) = (cb[4] & 0x02 != 0, cb[4] & 0x01 != 0);
+                if load_eject && !start {
+                    info!("Configuration drive ejected");
+                    self.volume.flush().map_err(|_| (Sense::MEDIUM_ERROR, 0))?;
+                    self.ejected = true;
+                }
+                Ok(0)
+            }
+            SCSI_SYNCHRONIZE_CACHE_10 => {
+                self.volume.flush().map_err(|_| (Sense::MEDIUM_ERROR, 0))?;
+                Ok(0)
+            }
+            SCSI_READ_FORMAT_CAPACITIES => {
+                let mut capacities = [0u8; 12];
+                capacities[3] = 8; // Capacity list length
+                capacities[4..8].copy_from_slice(&(BLOCK_COUNT as u32).to_be_bytes());
+                capacities[8] = 0x02; // Formatted media
+                capacities[9..12].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes()[1..]);
+                self.send(&capacities, length).await
+            }
+            SCSI_READ_CAPACITY_10 => {
+                let mut capacity = [0u8; 8];
+                capacity[0..4].copy_from_slice(&(BLOCK_COUNT as u32 - 1).to_be_bytes());
+                capacity[4..8].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+                self.send(&capacity, length).await
+            }
+            SCSI_READ_10 => {
+                let (lba, count) = block_range(cb)?;
+                let mut block = [0u8; BLOCK_SIZE];
+                let mut transferred = 0;
+                for lba in lba..lba + count {
+                    if transferred + BLOCK_SIZE > length {
+                        return Err((Sense::INVALID_FIELD, transferred));
+                    }
+                    self.volume
+                        .read_block(lba, &mut block)
+                        .map_err(|_| (Sense::MEDIUM_ERROR, transferred))?;
+                    for packet in block.chunks(PACKET_SIZE) {
+                        self.ep_in
+                            .write(packet)
+                            .await
+                            .map_err(|_| (Sense::MEDIUM_ERROR, transferred))?;
+                        transferred += packet.len();
+                    }
+                }
+                Ok(transferred)
+            }
+            SCSI_WRITE_10 => {
+                let (lba, count) = block_range(cb)?;
+                let mut block = [0u8; BLOCK_SIZE];
+                let mut transferred = 0;
+                for lba in lba..lba + count {
+                    if transferred + BLOCK_SIZE > length {
+                        return Err((Sense::INVALID_FIELD, transferred));
+                    }
+                    let mut filled = 0;
+                    while filled < BLOCK_SIZE {
+                        let n = self
+                            .ep_out
+                            .read(&mut block[filled..filled + PACKET_SIZE])
+                            .await
+                            .map_err(|_| (Sense::MEDIUM_ERROR, transferred + filled))?;
+                        filled += n;
+                    }
+                    transferred += BLOCK_SIZE;
+                    self.volume
+                        .write_block(lba, &block)
+                        .map_err(|_| (Sense::MEDIUM_ERROR, transferred))?;
+                }
+                Ok(transferred)
+            }
+            opcode => {
+                debug!("Unsupported SCSI command 0x{:02X}", opcode);
+                Err((Sense::INVALID_COMMAND, 0))
+            }
+        }
+    }
+
+    /// Send a response, cut to the host's allocation length
+    async fn send(&mut self, data: &[u8], length: usize) -> Result<usize, (Sense, usize)> {
+        let data = &data[..data.len().min(length)];
+        for packet in data.chunks(PACKET_SIZE) {
+            self.ep_in
+                .write(packet)
+                .await
+                .map_err(|_| (Sense::MEDIUM_ERROR, 0))?;
+        }
+        Ok(data.len())
+    }
+
+    /// Read and drop OUT data the command did not take
+    async fn discard(&mut self, mut remaining: usize) {
+        let mut packet = [0u8; PACKET_SIZE];
+        while remaining > 0 {
+            match self.ep_out.read(&mut packet).await {
+                Ok(n) if n > 0 => remaining = remaining.saturating_sub(n),
+                _ => return,
+            }
+        }
+    }
+}
+
+/// First block and block count of a READ(10)/WRITE(10), checked against the volume
+fn block_range(cb: &[u8; 16]) -> Result<(usize, usize), (Sense, usize)> {
+    let lba = u32::from_be_bytes([cb[2], cb[3], cb[4], cb[5]]) as usize;
+    let count = u16::from_be_bytes([cb[7], cb[8]]) as usize;
+    if lba + count > BLOCK_COUNT {
+        return Err((Sense::OUT_OF_RANGE, 0));
+    }
+    Ok((lba, count))
+}
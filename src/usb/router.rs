@@ -0,0 +1,98 @@
+//! USB command router
+//!
+//! Everything the USB side decides to do (reset, brightness, images, power
+//! state) arrives as a `UsbCommand` on `USB_COMMAND_CHANNEL`, from either the
+//! control pipe or the endpoint loops. The router is the only place that
+//! turns those into work for the rest of the firmware: device-wide commands
+//! go on `DEVICE_BUS`, images go to the build's image sinks (the display,
+//! by default) once they pass validation.
+
+use defmt::*;
+
+use crate::channels::{send_display, DEVICE_BUS, USB_COMMAND_CHANNEL};
+use crate::device::DeviceConfig;
+use crate::image_sink::{self, ImageSink};
+use crate::log_at;
+use crate::logging::Subsystem;
+use crate::protocol::image_check::validate_key_image;
+use crate::stats::Pool;
+use crate::types::{DeviceCommand, DisplayCommand, Screen, UsbCommand};
+
+/// Route queued USB commands to the device bus and display; never returns
+pub(super) async fn run_command_router() {
+    info!("USB command processor started");
+    let receiver = USB_COMMAND_CHANNEL.receiver();
+    // Device-wide commands go to every output subsystem, not just the display
+    let device_bus = DEVICE_BUS.immediate_publisher();
+    let mut sinks = image_sink::sinks();
+    loop {
+        let command = receiver.receive().await;
+        crate::stats::pool_used(Pool::UsbCommands, receiver.len() + 1);
+        match command {
+            UsbCommand::Reset => {
+                info!("Processing reset command");
+                device_bus.publish_immediate(DeviceCommand::Reset);
+            }
+            UsbCommand::SetBrightness(brightness) => {
+                info!("Processing brightness command: {}%", brightness);
+                crate::brightness::set_host_percent(brightness);
+                device_bus.publish_immediate(DeviceCommand::SetBrightness(brightness));
+            }
+            UsbCommand::SetScreenSleep { screen, sleep } => {
+                send_display(DisplayCommand::SetScreenSleep { screen, sleep }).await;
+            }
+            UsbCommand::SetScreenBrightness {
+                screen: Screen::Keys,
+                value,
+            } => {
+                // The key grid level is the brightness every output follows
+                info!("Processing brightness command: {}%", value);
+                crate::brightness::set_host_percent(value);
+                device_bus.publish_immediate(DeviceCommand::SetBrightness(value));
+            }
+            UsbCommand::SetScreenBrightness { screen, value } => {
+                send_display(DisplayCommand::SetScreenBrightness { screen, value }).await;
+            }
+            UsbCommand::Suspend => {
+                device_bus.publish_immediate(DeviceCommand::Sleep);
+            }
+            UsbCommand::Resume => {
+                device_bus.publish_immediate(DeviceCommand::Wake);
+            }
+            UsbCommand::ImageData { key_id, data } => {
+                log_at!(
+                    debug,
+                    Subsystem::Usb,
+                    "Processing image data for key {} ({} bytes)",
+                    key_id,
+                    data.len()
+                );
+                let display = crate::config::get_current_device().display_config();
+                if let Err(reason) = validate_key_image(&display, &data) {
+                    warn!("Rejected image for key {}: {}", key_id, reason);
+                    crate::stats::image_rejected();
+                    continue;
+                }
+                sinks.key_image(key_id, &mut Some(data)).await;
+            }
+            UsbCommand::ImageChunk {
+                key_id,
+                offset,
+                last,
+                data,
+            } => {
+                sinks.key_image_chunk(key_id, offset, last, &data).await;
+            }
+            UsbCommand::LcdImage { region, data } => {
+                log_at!(
+                    debug,
+                    Subsystem::Usb,
+                    "Processing LCD image for {} ({} bytes)",
+                    region,
+                    data.len()
+                );
+                sinks.lcd_image(region, &mut Some(data)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! WebUSB access to the vendor interface (`webusb`)
+//!
+//! Browsers only open devices that describe themselves for it, so this adds
+//! to the vendor bulk interface:
+//!
+//! - a WebUSB platform capability in the BOS descriptor, with a landing page
+//!   (`WEBUSB_LANDING_URL`) Chrome offers when the device is plugged in
+//! - an MS OS 2.0 descriptor set binding WinUSB to the vendor interface, so
+//!   Windows needs no driver install either
+//! - vendor control requests on that interface carrying the vendor feature
+//!   report, so a web configurator reaches every `VENDOR_CMD_*` without WebHID:
+//!
+//! | Request                       | Data                                          |
+//! |-------------------------------|-----------------------------------------------|
+//! | OUT `VENDOR_REQUEST_COMMAND`  | vendor SET_REPORT (`[0xF0, cmd, payload...]`) |
+//! | IN `VENDOR_REQUEST_RESPONSE`  | vendor GET_REPORT reply to the last command   |
+//!
+//! The HID interfaces stay as they are; the StreamDeck software never sees
+//! any of this.
+
+use defmt::*;
+use embassy_usb::control::{InResponse, OutResponse, Recipient, Request, RequestType};
+use embassy_usb::descriptor::capability_type;
+use embassy_usb::msos::{self, windows_version};
+use embassy_usb::types::InterfaceNumber;
+use embassy_usb::{Builder, FunctionBuilder, Handler, InterfaceAltBuilder};
+use static_cell::StaticCell;
+
+use super::UsbDriver;
+use crate::config::WEBUSB_LANDING_URL;
+use crate::device::Device;
+use crate::protocol::vendor::VendorHandler;
+
+/// bRequest of the WebUSB GET_URL request (`bVendorCode` in the capability)
+const WEBUSB_VENDOR_CODE: u8 = 0x01;
+/// bRequest Windows uses to read the MS OS 2.0 descriptor set
+const MSOS_VENDOR_CODE: u8 = 0x02;
+/// Vendor report command, host to device
+pub const VENDOR_REQUEST_COMMAND: u8 = 0x10;
+/// Vendor report response, device to host
+pub const VENDOR_REQUEST_RESPONSE: u8 = 0x11;
+
+/// wIndex of a WebUSB GET_URL request
+const GET_URL: u16 = 0x02;
+/// URL descriptor type
+const URL_DESCRIPTOR: u8 = 0x03;
+/// iLandingPage: the URL index GET_URL asks for
+const LANDING_PAGE_INDEX: u8 = 1;
+
+/// Device interface GUID the WinUSB binding registers for the vendor interface
+const DEVICE_INTERFACE_GUID: &str = "{6B2E1A3C-5F4D-4E8B-9C7A-2D1F0E3B4A59}";
+
+// A URL descriptor holds at most 255 bytes, scheme stripped
+const _: () = core::assert!(WEBUSB_LANDING_URL.len() <= 252 + "https://".len());
+
+/// WebUSB platform capability UUID 3408b638-09a9-47a0-8bfd-a0768815b665, little endian
+const WEBUSB_UUID: [u8; 16] = [
+    0x38, 0xB6, 0x08, 0x34, 0xA9, 0x09, 0xA0, 0x47, 0x8B, 0xFD, 0xA0, 0x76, 0x88, 0x15, 0xB6, 0x65,
+];
+
+/// Announce the MS OS 2.0 descriptor set; call before adding any function
+pub(super) fn describe_device(builder: &mut Builder<'static, UsbDriver>) {
+    builder.msos_descriptor(windows_version::WIN8_1, MSOS_VENDOR_CODE);
+}
+
+/// Bind WinUSB to the vendor function
+pub(super) fn bind_winusb(function: &mut FunctionBuilder<'_, 'static, UsbDriver>) {
+    function.msos_feature(msos::CompatibleIdFeatureDescriptor::new("WINUSB", ""));
+    function.msos_feature(msos::RegistryPropertyFeatureDescriptor::new(
+        "DeviceInterfaceGUIDs",
+        msos::PropertyData::RegMultiSz(&[DEVICE_INTERFACE_GUID]),
+    ));
+}
+
+/// Add the WebUSB platform capability to the vendor interface
+pub(super) fn advertise(alt: &mut InterfaceAltBuilder<'_, 'static, UsbDriver>) {
+    let mut capability = [0u8; 21];
+    // capability[0] is bReserved
+    capability[1..17].copy_from_slice(&WEBUSB_UUID);
+    // bcdVersion 1.0
+    capability[17..19].copy_from_slice(&0x0100u16.to_le_bytes());
+    capability[19] = WEBUSB_VENDOR_CODE;
+    capability[20] = LANDING_PAGE_INDEX;
+    alt.bos_capability(capability_type::PLATFORM, &capability);
+}
+
+/// Answers GET_URL and the vendor report requests on the vendor interface
+pub(super) struct WebUsbHandler {
+    interface: InterfaceNumber,
+    vendor_handler: VendorHandler,
+    url: [u8; 3 + WEBUSB_LANDING_URL.len()],
+}
+
+static HANDLER: StaticCell<WebUsbHandler> = StaticCell::new();
+
+/// Control handler for the vendor interface `interface`; register it with the builder
+pub(super) fn handler(device: Device, interface: InterfaceNumber) -> &'static mut WebUsbHandler {
+    let (scheme, host_path) = match WEBUSB_LANDING_URL.strip_prefix("https://") {
+        Some(rest) => (1, rest),
+        None => (0, WEBUSB_LANDING_URL.trim_start_matches("http://")),
+    };
+    let mut url = [0u8; 3 + WEBUSB_LANDING_URL.len()];
+    let len = 3 + host_path.len();
+    url[0] = len as u8;
+    url[1] = URL_DESCRIPTOR;
+    url[2] = scheme;
+    url[3..len].copy_from_slice(host_path.as_bytes());
+    info!("WebUSB landing page: {}", WEBUSB_LANDING_URL);
+
+    HANDLER.init(WebUsbHandler {
+        interface,
+        vendor_handler: VendorHandler::new(device),
+        url,
+    })
+}
+
+impl WebUsbHandler {
+    /// Whether `req` is a vendor request addressed to our interface
+    fn for_interface(&self, req: &Request) -> bool {
+        req.request_type == RequestType::Vendor
+            && req.recipient == Recipient::Interface
+            && req.index as u8 == u8::from(self.interface)
+    }
+}
+
+impl Handler for WebUsbHandler {
+    fn control_out(&mut self, req: Request, data: &[u8]) -> Option<OutResponse> {
+        if !self.for_interface(&req) || req.request != VENDOR_REQUEST_COMMAND {
+            return None;
+        }
+        self.vendor_handler.handle_set_report(data);
+        Some(OutResponse::Accepted)
+    }
+
+    fn control_in<'a>(&'a mut self, req: Request, buf: &'a mut [u8]) -> Option<InResponse<'a>> {
+        if req.request_type == RequestType::Vendor
+            && req.recipient == Recipient::Device
+            && req.request == WEBUSB_VENDOR_CODE
+            && req.index == GET_URL
+        {
+            if req.value != LANDING_PAGE_INDEX as u16 {
+                return Some(InResponse::Rejected);
+            }
+            let len = self.url[0] as usize;
+            return Some(InResponse::Accepted(&self.url[..len]));
+        }
+
+        if !self.for_interface(&req) || req.request != VENDOR_REQUEST_RESPONSE {
+            return None;
+        }
+        match self.vendor_handler.get_report(buf) {
+            Some(len) => Some(InResponse::Accepted(&buf[..len])),
+            None => Some(InResponse::Rejected),
+        }
+    }
+}